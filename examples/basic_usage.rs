@@ -28,8 +28,8 @@ async fn main() -> Result<(), SonarError> {
     };
 
     // Check streamer mode status
-    let is_streamer_mode = sonar.is_streamer_mode().await?;
-    println!("🎮 Streamer mode: {}", if is_streamer_mode { "Enabled" } else { "Disabled" });
+    let mode = sonar.get_mode().await?;
+    println!("🎮 Mode: {}", mode);
 
     // Get current volume data
     println!("\n📊 Getting current volume data...");
@@ -2,7 +2,7 @@
 //!
 //! This example demonstrates the blocking API which doesn't require async/await.
 
-use steelseries_sonar::{BlockingSonar, SonarError, CHANNEL_NAMES};
+use steelseries_sonar::{BlockingSonar, Mode, SonarError, StreamerSlider, CHANNEL_NAMES};
 
 fn main() -> Result<(), SonarError> {
     println!("SteelSeries Sonar Blocking API Example");
@@ -10,7 +10,7 @@ fn main() -> Result<(), SonarError> {
 
     // Create a new blocking Sonar client
     println!("🔌 Connecting to SteelSeries Sonar (blocking)...");
-    let mut sonar = match BlockingSonar::new() {
+    let sonar = match BlockingSonar::new() {
         Ok(sonar) => {
             println!("✅ Successfully connected to SteelSeries Sonar!");
             sonar
@@ -23,8 +23,8 @@ fn main() -> Result<(), SonarError> {
     };
 
     // Check streamer mode status
-    let is_streamer_mode = sonar.is_streamer_mode()?;
-    println!("🎮 Streamer mode: {}", if is_streamer_mode { "Enabled" } else { "Disabled" });
+    let mode = sonar.get_mode()?;
+    println!("🎮 Mode: {}", mode);
 
     // Get current volume data
     println!("\n📊 Getting current volume data...");
@@ -77,25 +77,25 @@ fn main() -> Result<(), SonarError> {
     println!("✅ Chat mix updated");
 
     // Streamer mode toggle demonstration (if currently in classic mode)
-    if !is_streamer_mode {
+    if mode == Mode::Classic {
         println!("\n🎮 Streamer Mode Toggle Demo:");
         
         println!("Enabling streamer mode...");
-        sonar.set_streamer_mode(true)?;
+        sonar.set_mode(Mode::Streamer)?;
         println!("✅ Streamer mode enabled");
 
         // Show how to use streamer sliders
         println!("Setting game volume for streaming slider...");
-        sonar.set_volume("game", 0.7, Some("streaming"))?;
+        sonar.set_volume("game", 0.7, Some(StreamerSlider::Streaming))?;
         
         println!("Setting game volume for monitoring slider...");
-        sonar.set_volume("game", 0.5, Some("monitoring"))?;
+        sonar.set_volume("game", 0.5, Some(StreamerSlider::Monitoring))?;
         
         println!("✅ Different volumes set for streaming and monitoring");
 
         // Switch back to classic mode
         println!("Switching back to classic mode...");
-        sonar.set_streamer_mode(false)?;
+        sonar.set_mode(Mode::Classic)?;
         println!("✅ Back to classic mode");
     }
 
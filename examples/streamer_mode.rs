@@ -5,7 +5,7 @@
 //! - Using different sliders (streaming vs monitoring)
 //! - Toggling between classic and streamer modes
 
-use steelseries_sonar::{Sonar, SonarError, STREAMER_SLIDER_NAMES};
+use steelseries_sonar::{Mode, Sonar, SonarError, StreamerSlider};
 
 #[tokio::main]
 async fn main() -> Result<(), SonarError> {
@@ -14,23 +14,23 @@ async fn main() -> Result<(), SonarError> {
 
     // Create a new Sonar client
     println!("🔌 Connecting to SteelSeries Sonar...");
-    let mut sonar = Sonar::new().await?;
+    let sonar = Sonar::new().await?;
     println!("✅ Connected!");
 
     // Check current mode
-    let current_mode = sonar.is_streamer_mode().await?;
-    println!("Current mode: {}", if current_mode { "Streamer" } else { "Classic" });
+    let current_mode = sonar.get_mode().await?;
+    println!("Current mode: {}", current_mode);
 
     // Enable streamer mode if not already enabled
-    if !current_mode {
+    if current_mode == Mode::Classic {
         println!("\n🎮 Enabling streamer mode...");
-        sonar.set_streamer_mode(true).await?;
+        sonar.set_mode(Mode::Streamer).await?;
         println!("✅ Streamer mode enabled!");
     }
 
     // Show available sliders
     println!("\n📊 Available streamer sliders:");
-    for slider in STREAMER_SLIDER_NAMES {
+    for slider in StreamerSlider::ALL {
         println!("  • {}", slider);
     }
 
@@ -39,10 +39,10 @@ async fn main() -> Result<(), SonarError> {
     
     // Set different volumes for streaming and monitoring
     println!("Setting game volume for streaming slider to 80%...");
-    sonar.set_volume("game", 0.8, Some("streaming")).await?;
+    sonar.set_volume("game", 0.8, Some(StreamerSlider::Streaming)).await?;
     
     println!("Setting game volume for monitoring slider to 60%...");
-    sonar.set_volume("game", 0.6, Some("monitoring")).await?;
+    sonar.set_volume("game", 0.6, Some(StreamerSlider::Monitoring)).await?;
     
     println!("✅ Different volumes set for each slider!");
 
@@ -51,10 +51,10 @@ async fn main() -> Result<(), SonarError> {
     
     // Mute chat capture for streaming but keep it for monitoring
     println!("Muting chat capture for streaming slider...");
-    sonar.mute_channel("chatCapture", true, Some("streaming")).await?;
+    sonar.mute_channel("chatCapture", true, Some(StreamerSlider::Streaming)).await?;
     
     println!("Keeping chat capture unmuted for monitoring slider...");
-    sonar.mute_channel("chatCapture", false, Some("monitoring")).await?;
+    sonar.mute_channel("chatCapture", false, Some(StreamerSlider::Monitoring)).await?;
     
     println!("✅ Chat capture muted for stream but audible for monitoring!");
 
@@ -67,7 +67,7 @@ async fn main() -> Result<(), SonarError> {
     println!("\n🔄 Mode Switching Demo:");
     
     println!("Switching back to classic mode...");
-    sonar.set_streamer_mode(false).await?;
+    sonar.set_mode(Mode::Classic).await?;
     println!("✅ Now in classic mode");
 
     // Show how volume control works in classic mode
@@ -77,13 +77,13 @@ async fn main() -> Result<(), SonarError> {
 
     // Switch back to streamer mode for final demo
     println!("\nSwitching back to streamer mode...");
-    sonar.set_streamer_mode(true).await?;
+    sonar.set_mode(Mode::Streamer).await?;
 
     // Reset volumes
     println!("\n🔄 Resetting volumes for demo cleanup...");
-    sonar.set_volume("game", 0.5, Some("streaming")).await?;
-    sonar.set_volume("game", 0.5, Some("monitoring")).await?;
-    sonar.mute_channel("chatCapture", false, Some("streaming")).await?;
+    sonar.set_volume("game", 0.5, Some(StreamerSlider::Streaming)).await?;
+    sonar.set_volume("game", 0.5, Some(StreamerSlider::Monitoring)).await?;
+    sonar.mute_channel("chatCapture", false, Some(StreamerSlider::Streaming)).await?;
     
     println!("✅ Demo completed!");
     println!("\n💡 Key takeaways:");
@@ -0,0 +1,55 @@
+/*
+ * C bindings for steelseries-sonar, built with the `ffi` feature:
+ *
+ *     cargo rustc --lib --release --features ffi --crate-type cdylib
+ *
+ * Functions return SONAR_OK or a SONAR_ERROR_* code; sonar_last_error()
+ * describes the last failure on the calling thread. Maintained by hand;
+ * keep in sync with src/ffi.rs (checked by test_header_declares_exports).
+ */
+
+#ifndef STEELSERIES_SONAR_H
+#define STEELSERIES_SONAR_H
+
+#include <stdbool.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define SONAR_OK 0
+#define SONAR_ERROR_INVALID_ARGUMENT 1
+#define SONAR_ERROR_NOT_RUNNING 2
+#define SONAR_ERROR_CHANNEL_NOT_FOUND 3
+#define SONAR_ERROR_INVALID_VOLUME 4
+#define SONAR_ERROR_CHAT_MIX_UNAVAILABLE 5
+#define SONAR_ERROR_TIMEOUT 6
+#define SONAR_ERROR_OTHER 7
+#define SONAR_ERROR_INTERNAL 8
+
+#define SONAR_SLIDER_DEFAULT 0
+#define SONAR_SLIDER_STREAMING 1
+#define SONAR_SLIDER_MONITORING 2
+
+/* A connected client. Safe to use from several threads at once. */
+typedef struct SonarClient SonarClient;
+
+int32_t sonar_new(SonarClient **out);
+int32_t sonar_connect_to(const char *address, SonarClient **out);
+void sonar_free(SonarClient *sonar);
+
+/* Channels: "master", "game", "chatRender", "media", "aux", "chatCapture". */
+int32_t sonar_set_volume(const SonarClient *sonar, const char *channel, double volume, int32_t streamer_slider);
+int32_t sonar_get_volume(const SonarClient *sonar, const char *channel, int32_t streamer_slider, double *out);
+int32_t sonar_mute(const SonarClient *sonar, const char *channel, bool muted, int32_t streamer_slider);
+int32_t sonar_is_muted(const SonarClient *sonar, const char *channel, int32_t streamer_slider, bool *out);
+int32_t sonar_set_chat_mix(const SonarClient *sonar, double mix_volume);
+
+const char *sonar_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* STEELSERIES_SONAR_H */
@@ -0,0 +1,122 @@
+//! Concurrent execution of several mixer operations.
+
+use crate::error::Result;
+use crate::sonar::{Sonar, StreamerSlider};
+use futures_util::future::join_all;
+use serde_json::Value;
+
+/// Operations queued with [`Sonar::batch`] and sent concurrently.
+///
+/// ```no_run
+/// # async fn example(sonar: steelseries_sonar::Sonar) {
+/// let results = sonar
+///     .batch()
+///     .set_volume("game", 0.8, None)
+///     .set_volume("media", 0.3, None)
+///     .mute_channel("chatCapture", true, None)
+///     .execute()
+///     .await;
+/// for result in results {
+///     if let Err(error) = result {
+///         eprintln!("{}", error);
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Debug)]
+#[must_use = "a batch does nothing until executed"]
+pub struct Batch<'a> {
+    sonar: &'a Sonar,
+    operations: Vec<Operation>,
+}
+
+#[derive(Debug, Clone)]
+enum Operation {
+    Volume {
+        channel: String,
+        volume: f64,
+        slider: Option<StreamerSlider>,
+    },
+    Mute {
+        channel: String,
+        muted: bool,
+        slider: Option<StreamerSlider>,
+    },
+    ChatMix(f64),
+    Redirection {
+        channel: String,
+        device_id: String,
+    },
+}
+
+impl<'a> Batch<'a> {
+    pub(crate) fn new(sonar: &'a Sonar) -> Self {
+        Self {
+            sonar,
+            operations: Vec::new(),
+        }
+    }
+
+    /// Queue [`Sonar::set_volume`].
+    pub fn set_volume(mut self, channel: &str, volume: f64, streamer_slider: Option<StreamerSlider>) -> Self {
+        self.operations.push(Operation::Volume {
+            channel: channel.to_string(),
+            volume,
+            slider: streamer_slider,
+        });
+        self
+    }
+
+    /// Queue [`Sonar::mute_channel`].
+    pub fn mute_channel(mut self, channel: &str, muted: bool, streamer_slider: Option<StreamerSlider>) -> Self {
+        self.operations.push(Operation::Mute {
+            channel: channel.to_string(),
+            muted,
+            slider: streamer_slider,
+        });
+        self
+    }
+
+    /// Queue [`Sonar::set_chat_mix`].
+    pub fn set_chat_mix(mut self, mix_volume: f64) -> Self {
+        self.operations.push(Operation::ChatMix(mix_volume));
+        self
+    }
+
+    /// Queue [`Sonar::set_redirection`].
+    pub fn set_redirection(mut self, channel: &str, device_id: &str) -> Self {
+        self.operations.push(Operation::Redirection {
+            channel: channel.to_string(),
+            device_id: device_id.to_string(),
+        });
+        self
+    }
+
+    /// Number of queued operations.
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    /// Whether no operations are queued.
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    /// Send all queued operations concurrently and wait for them to finish.
+    ///
+    /// Returns one result per operation, in the order they were queued. A
+    /// failing operation does not affect the others.
+    pub async fn execute(self) -> Vec<Result<Value>> {
+        let sonar = self.sonar;
+        join_all(self.operations.iter().map(|operation| run(sonar, operation))).await
+    }
+}
+
+async fn run(sonar: &Sonar, operation: &Operation) -> Result<Value> {
+    match operation {
+        Operation::Volume { channel, volume, slider } => sonar.set_volume(channel, *volume, *slider).await,
+        Operation::Mute { channel, muted, slider } => sonar.mute_channel(channel, *muted, *slider).await,
+        Operation::ChatMix(mix_volume) => sonar.set_chat_mix(*mix_volume).await,
+        Operation::Redirection { channel, device_id } => sonar.set_redirection(channel, device_id).await,
+    }
+}
@@ -0,0 +1,338 @@
+//! Command line interface for SteelSeries Sonar.
+//!
+//! ```text
+//! sonar-cli volume game 0.5
+//! sonar-cli volume game 50 --percent
+//! sonar-cli mute chatCapture
+//! sonar-cli chatmix -0.3
+//! sonar-cli mode stream
+//! sonar-cli status
+//! sonar-cli watch --json
+//! sonar-cli profile save gaming
+//! sonar-cli profile diff podcast
+//! sonar-cli daemon
+//! sonar-cli completions powershell
+//! sonar-cli man --out-dir man/
+//! ```
+
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+use steelseries_sonar::{ipc, list_profiles, ConfigFile, IpcServer, ProfileStore, Result, Sonar, StreamerSlider};
+
+#[derive(Debug, Parser)]
+#[command(name = "sonar-cli", version, about = "Control SteelSeries Sonar from the command line")]
+struct Cli {
+    /// Sonar web server address, skipping discovery
+    #[arg(long, global = true, env = "STEELSERIES_SONAR_ADDRESS")]
+    address: Option<String>,
+
+    /// Path to SteelSeries Engine's coreProps.json
+    #[arg(long, global = true, env = "STEELSERIES_SONAR_COREPROPS")]
+    core_props: Option<PathBuf>,
+
+    /// Config file to read instead of the default sonar.toml
+    #[arg(long, global = true, env = "STEELSERIES_SONAR_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Streamer slider to use in streamer mode
+    #[arg(long, global = true, value_enum)]
+    slider: Option<Slider>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Print or set the volume of a channel (0.0 to 1.0)
+    Volume {
+        /// Channel name or alias from the config file
+        channel: String,
+        volume: Option<f64>,
+        /// Print and set the volume as a percentage (0 to 100)
+        #[arg(long)]
+        percent: bool,
+    },
+    /// Mute a channel
+    Mute {
+        /// Channel name or alias from the config file
+        channel: String,
+    },
+    /// Unmute a channel
+    Unmute {
+        /// Channel name or alias from the config file
+        channel: String,
+    },
+    /// Toggle the mute state of a channel
+    Toggle {
+        /// Channel name or alias from the config file
+        channel: String,
+    },
+    /// Print or set the chat mix balance (-1.0 game to 1.0 chat)
+    #[command(name = "chatmix")]
+    ChatMix {
+        #[arg(allow_negative_numbers = true)]
+        balance: Option<f64>,
+    },
+    /// Print or switch the mode
+    Mode { mode: Option<Mode> },
+    /// Print the mode, chat mix and the volume of every channel
+    Status,
+    /// Print every change to the mixer as it happens, until stopped
+    Watch {
+        /// Print one JSON object per line
+        #[arg(long)]
+        json: bool,
+        /// Time between two polls, in milliseconds
+        #[arg(long, default_value_t = 250)]
+        interval: u64,
+    },
+    /// Share the connection with other local processes until stopped
+    Daemon {
+        /// Socket path, or pipe name on Windows
+        #[arg(long)]
+        endpoint: Option<String>,
+    },
+    /// Save, apply and compare mixer profiles
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommand,
+    },
+    /// Print the completion script of a shell
+    Completions { shell: Shell },
+    /// Print the man page, or write the pages of every command to a directory
+    Man {
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ProfileCommand {
+    /// Save the current mixer state under a name
+    Save { name: String },
+    /// Apply a saved profile, restoring the previous state if it fails
+    Apply { name: String },
+    /// Print the names of the saved profiles
+    List,
+    /// Print what applying a profile would change
+    Diff { name: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Slider {
+    Streaming,
+    Monitoring,
+}
+
+impl From<Slider> for StreamerSlider {
+    fn from(slider: Slider) -> Self {
+        match slider {
+            Slider::Streaming => StreamerSlider::Streaming,
+            Slider::Monitoring => StreamerSlider::Monitoring,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Mode {
+    Classic,
+    Stream,
+}
+
+impl From<Mode> for steelseries_sonar::Mode {
+    fn from(mode: Mode) -> Self {
+        match mode {
+            Mode::Classic => steelseries_sonar::Mode::Classic,
+            Mode::Stream => steelseries_sonar::Mode::Streamer,
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    // Generated files and the profile list need no connection to Sonar.
+    match &cli.command {
+        Command::Profile { command: ProfileCommand::List } => {
+            for name in list_profiles()? {
+                println!("{}", name);
+            }
+            return Ok(());
+        }
+        Command::Completions { shell } => {
+            clap_complete::generate(*shell, &mut Cli::command(), "sonar-cli", &mut std::io::stdout());
+            return Ok(());
+        }
+        Command::Man { out_dir: Some(out_dir) } => {
+            std::fs::create_dir_all(out_dir)?;
+            clap_mangen::generate_to(Cli::command(), out_dir)?;
+            return Ok(());
+        }
+        Command::Man { out_dir: None } => {
+            clap_mangen::Man::new(Cli::command()).render(&mut std::io::stdout())?;
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    let mut builder = Sonar::builder();
+    if let Some(address) = &cli.address {
+        builder = builder.web_server_address(address);
+    }
+    if let Some(path) = &cli.core_props {
+        builder = builder.core_props_path(path);
+    }
+    if let Some(path) = cli.config.clone().or_else(|| ConfigFile::default_path().filter(|path| path.exists())) {
+        builder = builder.config_file(path);
+    }
+    let sonar = builder.build().await?;
+    let slider = cli.slider.map(StreamerSlider::from);
+
+    match cli.command {
+        Command::Volume { channel, volume: Some(volume), percent: false } => {
+            sonar.set_volume(&channel, volume, slider).await?;
+        }
+        Command::Volume { channel, volume: Some(volume), percent: true } => {
+            sonar.set_volume(&channel, volume / 100.0, slider).await?;
+        }
+        Command::Volume { channel, volume: None, percent } => {
+            let state = sonar.get_channel_state(&channel, slider).await?;
+            if percent {
+                println!("{}%", state.percent());
+            } else {
+                println!("{:.2}", state.volume);
+            }
+        }
+        Command::Mute { channel } => {
+            sonar.mute_channel(&channel, true, slider).await?;
+        }
+        Command::Unmute { channel } => {
+            sonar.mute_channel(&channel, false, slider).await?;
+        }
+        Command::Toggle { channel } => {
+            let muted = sonar.toggle_mute(&channel, slider).await?;
+            println!("{}", if muted { "muted" } else { "unmuted" });
+        }
+        Command::ChatMix { balance: Some(balance) } => {
+            sonar.set_chat_mix(balance).await?;
+        }
+        Command::ChatMix { balance: None } => {
+            let data = sonar.get_chat_mix_data().await?;
+            println!("{:.2}", data["balance"].as_f64().unwrap_or_default());
+        }
+        Command::Mode { mode: Some(mode) } => {
+            sonar.set_mode(mode.into()).await?;
+        }
+        Command::Mode { mode: None } => {
+            println!("{}", sonar.get_mode().await?);
+        }
+        Command::Status => print_status(&sonar).await?,
+        Command::Watch { json, interval } => {
+            let mut watcher = sonar.watch(Duration::from_millis(interval));
+            while let Some(event) = watcher.recv().await {
+                if json {
+                    println!("{}", serde_json::to_string(&event)?);
+                } else {
+                    println!("{}", event);
+                }
+            }
+        }
+        Command::Daemon { endpoint } => {
+            let endpoint = endpoint.unwrap_or_else(ipc::default_endpoint);
+            let server = IpcServer::bind(sonar, &endpoint)?;
+            eprintln!("listening on {}", endpoint);
+            server.run().await?;
+        }
+        Command::Profile { command } => run_profile(&sonar, command).await?,
+        Command::Completions { .. } | Command::Man { .. } => {}
+    }
+    Ok(())
+}
+
+async fn run_profile(sonar: &Sonar, command: ProfileCommand) -> Result<()> {
+    match command {
+        ProfileCommand::Save { name } => sonar.save_profile(&name).await?,
+        ProfileCommand::Apply { name } => sonar.load_profile(&name).await?,
+        ProfileCommand::Diff { name } => {
+            let profile = ProfileStore::open_default()?.load(&name)?;
+            for change in sonar.export_state().await?.diff(&profile) {
+                println!("{}", change);
+            }
+        }
+        ProfileCommand::List => {}
+    }
+    Ok(())
+}
+
+async fn print_status(sonar: &Sonar) -> Result<()> {
+    print!("{}", sonar.get_full_state().await?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        let cli = Cli::try_parse_from(["sonar-cli", "chatmix", "-0.3"]).unwrap();
+        assert!(matches!(cli.command, Command::ChatMix { balance: Some(balance) } if balance == -0.3));
+
+        let cli = Cli::try_parse_from(["sonar-cli", "volume", "game", "0.5", "--slider", "monitoring"]).unwrap();
+        assert!(matches!(cli.command, Command::Volume { volume: Some(volume), .. } if volume == 0.5));
+        assert_eq!(cli.slider, Some(Slider::Monitoring));
+
+        let cli = Cli::try_parse_from(["sonar-cli", "volume", "game", "50", "--percent"]).unwrap();
+        assert!(matches!(cli.command, Command::Volume { percent: true, .. }));
+
+        let cli = Cli::try_parse_from(["sonar-cli", "mode", "stream"]).unwrap();
+        assert!(matches!(cli.command, Command::Mode { mode: Some(Mode::Stream) }));
+
+        let cli = Cli::try_parse_from(["sonar-cli", "watch", "--json", "--interval", "100"]).unwrap();
+        assert!(matches!(cli.command, Command::Watch { json: true, interval: 100 }));
+
+        let cli = Cli::try_parse_from(["sonar-cli", "daemon", "--endpoint", "/tmp/sonar.sock"]).unwrap();
+        assert!(matches!(cli.command, Command::Daemon { endpoint: Some(_) }));
+
+        let cli = Cli::try_parse_from(["sonar-cli", "profile", "diff", "podcast"]).unwrap();
+        assert!(matches!(cli.command, Command::Profile { command: ProfileCommand::Diff { name } } if name == "podcast"));
+
+        let cli = Cli::try_parse_from(["sonar-cli", "completions", "powershell"]).unwrap();
+        assert!(matches!(cli.command, Command::Completions { shell: Shell::PowerShell }));
+
+        // Aliases are resolved by the client, using the config file
+        let cli = Cli::try_parse_from(["sonar-cli", "mute", "voice", "--config", "sonar.toml"]).unwrap();
+        assert!(matches!(cli.command, Command::Mute { channel } if channel == "voice"));
+        assert_eq!(cli.config, Some(PathBuf::from("sonar.toml")));
+    }
+
+    #[test]
+    fn test_generated_files() {
+        let mut completions = Vec::new();
+        clap_complete::generate(Shell::Bash, &mut Cli::command(), "sonar-cli", &mut completions);
+        assert!(String::from_utf8_lossy(&completions).contains("chatmix"));
+
+        let mut man = Vec::new();
+        clap_mangen::Man::new(Cli::command()).render(&mut man).unwrap();
+        assert!(String::from_utf8_lossy(&man).contains(".TH sonar-cli"));
+    }
+}
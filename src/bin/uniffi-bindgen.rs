@@ -0,0 +1,12 @@
+//! Generates the Kotlin and Swift sources for the `uniffi` feature from the
+//! built library:
+//!
+//! ```text
+//! cargo build --release --features uniffi
+//! cargo run --features uniffi-bindgen --bin uniffi-bindgen -- generate \
+//!     --library target/release/libsteelseries_sonar.so --language kotlin --out-dir bindings
+//! ```
+
+fn main() {
+    uniffi::uniffi_bindgen_main();
+}
@@ -0,0 +1,117 @@
+//! UniFFI bindings of [`BlockingSonar`] for Kotlin and Swift, e.g. companion
+//! apps controlling Sonar through a port forwarded over the LAN.
+//!
+//! Generate the foreign-language sources with the `uniffi-bindgen` binary.
+//! Calls block, so apps should make them off their UI thread.
+
+use crate::blocking::BlockingSonar;
+use crate::error::SonarError;
+use crate::sonar::{Mode, StreamerSlider};
+use crate::volume::VolumeState;
+use std::sync::Arc;
+
+/// Errors surfaced to foreign code, with the message of the [`SonarError`].
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+#[uniffi(flat_error)]
+pub enum SonarBindingError {
+    /// SteelSeries GG or Sonar is not installed, enabled or running.
+    #[error("{0}")]
+    NotRunning(String),
+    #[error("{0}")]
+    ChannelNotFound(String),
+    /// A volume or chat mix value was out of range.
+    #[error("{0}")]
+    InvalidVolume(String),
+    #[error("{0}")]
+    ChatMixUnavailable(String),
+    #[error("{0}")]
+    Timeout(String),
+    #[error("{0}")]
+    Other(String),
+}
+
+impl From<SonarError> for SonarBindingError {
+    fn from(error: SonarError) -> Self {
+        let message = error.to_string();
+        match error {
+            SonarError::EnginePathNotFound
+            | SonarError::ServerNotAccessible(_)
+            | SonarError::SonarNotEnabled
+            | SonarError::ServerNotReady
+            | SonarError::ServerNotRunning
+            | SonarError::WebServerAddressNotFound => Self::NotRunning(message),
+            SonarError::ChannelNotFound(_) => Self::ChannelNotFound(message),
+            SonarError::InvalidVolume(_) | SonarError::InvalidVolumePercent(_) | SonarError::InvalidMixVolume(_) => {
+                Self::InvalidVolume(message)
+            }
+            SonarError::ChatMixUnavailable => Self::ChatMixUnavailable(message),
+            SonarError::Timeout => Self::Timeout(message),
+            error if error.is_connect() => Self::NotRunning(message),
+            _ => Self::Other(message),
+        }
+    }
+}
+
+type Result<T> = std::result::Result<T, SonarBindingError>;
+
+/// The Sonar client as seen from Kotlin and Swift.
+#[derive(Debug, uniffi::Object)]
+pub struct Sonar {
+    sonar: BlockingSonar,
+}
+
+#[uniffi::export]
+impl Sonar {
+    /// Discover SteelSeries GG on this machine and connect to Sonar.
+    #[uniffi::constructor]
+    pub fn new() -> Result<Arc<Self>> {
+        Ok(Arc::new(Self { sonar: BlockingSonar::new()? }))
+    }
+
+    /// Connect to the Sonar web server at `address`, e.g. a port forwarded
+    /// from the PC running SteelSeries GG.
+    #[uniffi::constructor]
+    pub fn connect_to(address: String) -> Result<Arc<Self>> {
+        Ok(Arc::new(Self { sonar: BlockingSonar::connect_to(address)? }))
+    }
+
+    pub fn get_mode(&self) -> Result<Mode> {
+        Ok(self.sonar.get_mode()?)
+    }
+
+    pub fn set_mode(&self, mode: Mode) -> Result<Mode> {
+        Ok(self.sonar.set_mode(mode)?)
+    }
+
+    pub fn channel_state(&self, channel: String, streamer_slider: Option<StreamerSlider>) -> Result<VolumeState> {
+        Ok(self.sonar.get_channel_state(&channel, streamer_slider)?)
+    }
+
+    pub fn set_volume(&self, channel: String, volume: f64, streamer_slider: Option<StreamerSlider>) -> Result<()> {
+        self.sonar.set_volume(&channel, volume, streamer_slider)?;
+        Ok(())
+    }
+
+    pub fn mute_channel(&self, channel: String, muted: bool, streamer_slider: Option<StreamerSlider>) -> Result<()> {
+        self.sonar.mute_channel(&channel, muted, streamer_slider)?;
+        Ok(())
+    }
+
+    pub fn set_chat_mix(&self, mix_volume: f64) -> Result<()> {
+        self.sonar.set_chat_mix(mix_volume)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binding_errors() {
+        assert!(matches!(SonarError::ServerNotRunning.into(), SonarBindingError::NotRunning(_)));
+        let error = SonarBindingError::from(SonarError::InvalidVolume(2.0));
+        assert!(matches!(error, SonarBindingError::InvalidVolume(_)));
+        assert_eq!(error.to_string(), SonarError::InvalidVolume(2.0).to_string());
+    }
+}
@@ -3,28 +3,72 @@
 //! This module provides a blocking interface for users who prefer synchronous operations
 //! or need to use the library in non-async contexts.
 
+use crate::builder::{BlockingSonarBuilder, ClientOptions};
+use crate::capabilities::{Capabilities, PROBE_PATHS};
+use crate::capture::CapturedRequest;
+use crate::client_core::{check_mix_volume, check_volume, raw_body, raw_path, ClientCore};
+use crate::configs::{NewConfig, SonarConfig};
+use crate::devices::AudioDevice;
+use crate::endpoint::{api_path, api_path_with_query};
+use crate::eq::{apply_band_update, EqBandUpdate, Equalizer};
 use crate::error::{Result, SonarError};
-use reqwest::blocking::Client;
+use crate::group::ChannelGroup;
+use crate::orchestration::{client_orchestration, try_join_in_turn};
+use crate::processes::find_processes;
+use crate::routing::{channel_device, check_capture_device, ChannelApps, ClassicRedirection, StreamRedirection, CAPTURE_CHANNEL};
+use crate::state_cache::Setting;
+#[cfg(feature = "metrics")]
+use crate::telemetry;
+use crate::sonar::{chat_mix_enabled, Channel, Mode, StreamerSlider};
+use crate::transport::{Backend, BlockingTransport, Method, TransportResponse};
+use crate::volume::{percent_to_volume, volume_to_percent, VolumeSettings, VolumeState};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use serde_json::Value;
 use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Instant;
 
 /// Blocking version of the SteelSeries Sonar API client.
-#[derive(Debug)]
+///
+/// Cloning is cheap: clones share the HTTP connection pool, the resolved
+/// server address and the current mode, so a mode switched through one
+/// clone applies to all of them.
+#[derive(Debug, Clone)]
 pub struct BlockingSonar {
-    client: Client,
-    web_server_address: String,
-    streamer_mode: bool,
-    volume_path: String,
+    client: BlockingTransport,
+    core: ClientCore,
+    /// Set once discovery and mode detection succeeded.
+    connected: Arc<Mutex<bool>>,
 }
 
 impl BlockingSonar {
     /// Create a new blocking Sonar client with default settings.
     ///
+    /// The `STEELSERIES_SONAR_COREPROPS` and `STEELSERIES_SONAR_ADDRESS`
+    /// environment variables override discovery, see
+    /// [`BlockingSonarBuilder::from_env`].
+    ///
     /// # Errors
     ///
     /// Returns an error if the SteelSeries Engine is not found or accessible.
     pub fn new() -> Result<Self> {
-        Self::with_config(None, None)
+        BlockingSonarBuilder::from_env().build()
+    }
+
+    /// Create a builder to configure a new blocking Sonar client.
+    pub fn builder() -> BlockingSonarBuilder {
+        BlockingSonarBuilder::new()
+    }
+
+    /// Connect directly to a known Sonar web server address, skipping
+    /// coreProps.json and Engine discovery.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the streamer mode cannot be detected.
+    pub fn connect_to(address: impl AsRef<str>) -> Result<Self> {
+        Self::builder().web_server_address(address).build()
     }
 
     /// Create a new blocking Sonar client with custom configuration.
@@ -33,238 +77,726 @@ impl BlockingSonar {
     ///
     /// * `app_data_path` - Custom path to the coreProps.json file
     /// * `streamer_mode` - Whether to use streamer mode (if None, will be auto-detected)
+    #[deprecated(note = "use `BlockingSonar::builder()` instead")]
     pub fn with_config(app_data_path: Option<&Path>, streamer_mode: Option<bool>) -> Result<Self> {
-        let client = Client::builder()
-            .danger_accept_invalid_certs(true)
-            .build()?;
-
-        let app_data_path = app_data_path.unwrap_or_else(|| {
-            #[cfg(target_os = "windows")]
-            {
-                Path::new("C:\\ProgramData\\SteelSeries\\SteelSeries Engine 3\\coreProps.json")
-            }
-            #[cfg(not(target_os = "windows"))]
-            {
-                Path::new("/tmp/coreProps.json") // Placeholder
-            }
-        });
+        Self::from_options(ClientOptions {
+            core_props_path: app_data_path.map(Path::to_path_buf),
+            streamer_mode,
+            ..ClientOptions::default()
+        }, Backend::default())
+    }
 
-        let base_url = Self::load_base_url(app_data_path)?;
-        let web_server_address = Self::load_server_address(&client, &base_url)?;
+    /// Create a client without any I/O. Discovery and mode detection happen
+    /// on the first request, so the client can be created before SteelSeries
+    /// GG is running.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP client cannot be created.
+    pub fn lazy() -> Result<Self> {
+        BlockingSonarBuilder::from_env().build_lazy()
+    }
 
-        let detected_streamer_mode = match streamer_mode {
-            Some(mode) => mode,
-            None => Self::is_streamer_mode_internal(&client, &web_server_address)?,
-        };
+    pub(crate) fn from_options(options: ClientOptions, backend: Backend) -> Result<Self> {
+        let sonar = Self::unconnected(options, backend)?;
+        sonar.ensure_connected()?;
+        Ok(sonar)
+    }
 
-        let volume_path = if detected_streamer_mode {
-            "/volumeSettings/streamer".to_string()
-        } else {
-            "/volumeSettings/classic".to_string()
-        };
+    /// Set up a client that connects on its first request.
+    pub(crate) fn unconnected(mut options: ClientOptions, backend: Backend) -> Result<Self> {
+        options.load_config_file()?;
+        let client = BlockingTransport::new(backend, &options)?;
 
         Ok(Self {
             client,
-            web_server_address,
-            streamer_mode: detected_streamer_mode,
-            volume_path,
+            core: ClientCore::new(options)?,
+            connected: Arc::default(),
         })
     }
 
-    /// Check if streamer mode is currently enabled.
-    pub fn is_streamer_mode(&self) -> Result<bool> {
-        Self::is_streamer_mode_internal(&self.client, &self.web_server_address)
+    /// Connect unless already connected. Concurrent requests wait for the
+    /// first one to connect.
+    fn ensure_connected(&self) -> Result<()> {
+        let mut connected = self.connected.lock().unwrap_or_else(PoisonError::into_inner);
+        if !*connected {
+            self.establish()?;
+            *connected = true;
+        }
+        Ok(())
     }
 
-    fn is_streamer_mode_internal(client: &Client, web_server_address: &str) -> Result<bool> {
-        let url = format!("{}/mode/", web_server_address);
-        let response = client.get(&url).send()?;
-        
-        if !response.status().is_success() {
-            return Err(SonarError::ServerNotAccessible(response.status().as_u16()));
+    /// Discover the server addresses and detect the mode.
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "sonar.connect", level = "debug", skip_all, err(Display)))]
+    fn establish(&self) -> Result<()> {
+        if let Some(core_props_path) = &self.core.core_props_path {
+            let addresses = Self::discover(&self.client, core_props_path, &self.core.options)?;
+            self.core.set_addresses(addresses);
         }
 
-        let mode: String = response.json()?;
-        Ok(mode == "stream")
+        let detected_streamer_mode = match self.core.options.streamer_mode {
+            Some(mode) => mode,
+            None => {
+                let mode: String = self.send_connected(Method::Get, "/mode/", None)?.json()?;
+                mode.parse::<Mode>()?.is_streamer()
+            }
+        };
+        self.core.use_mode(detected_streamer_mode);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            web_server_address = %self.web_server_address(),
+            streamer_mode = detected_streamer_mode,
+            "connected to Sonar"
+        );
+        Ok(())
     }
 
-    /// Set streamer mode on or off.
-    pub fn set_streamer_mode(&mut self, streamer_mode: bool) -> Result<bool> {
-        let mode = if streamer_mode { "stream" } else { "classic" };
-        let url = format!("{}/mode/{}", self.web_server_address, mode);
-        
-        let response = self.client.put(&url).send()?;
-        
-        if !response.status().is_success() {
-            return Err(SonarError::ServerNotAccessible(response.status().as_u16()));
-        }
+    /// Connect now unless already connected, so that a
+    /// [lazy](Self::lazy) client reports a missing SteelSeries GG up front.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the SteelSeries Engine is not found or accessible.
+    pub fn connect(&self) -> Result<()> {
+        self.ensure_connected()
+    }
 
-        let new_mode: String = response.json()?;
-        self.streamer_mode = new_mode == "stream";
-        
-        self.volume_path = if self.streamer_mode {
-            "/volumeSettings/streamer".to_string()
-        } else {
-            "/volumeSettings/classic".to_string()
-        };
+    /// Connect and open a connection to the Sonar web server, so that the
+    /// first real request, e.g. from a mute hotkey, does not wait for the
+    /// TLS handshake. Sends one cheap request.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the SteelSeries Engine or the Sonar web server is
+    /// not accessible.
+    pub fn warm_up(&self) -> Result<()> {
+        self.ensure_connected()?;
+        let _: String = self.get_json("/mode/")?;
+        Ok(())
+    }
+
+    /// Run discovery and mode detection again, e.g. after SteelSeries GG
+    /// restarted.
+    ///
+    /// Unlike [`reconnect`](Self::reconnect), this resolves the Sonar
+    /// sub-app the way building the client did, including the configured
+    /// [`enable_sonar`](crate::BlockingSonarBuilder::enable_sonar) and ready timeout, and
+    /// re-detects the mode unless it was set on the builder. Options, clones
+    /// and watchers are kept.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the SteelSeries Engine is not found or accessible.
+    pub fn refresh(&self) -> Result<()> {
+        let mut connected = self.connected.lock().unwrap_or_else(PoisonError::into_inner);
+        self.establish()?;
+        self.core.clear_cache();
+        *connected = true;
+        Ok(())
+    }
+
+    /// Re-read coreProps.json and re-resolve the Sonar web server address.
+    ///
+    /// SteelSeries GG picks a new port for the web server whenever it
+    /// restarts. Requests that fail to connect already trigger this
+    /// automatically unless disabled with
+    /// [`auto_reconnect`](crate::BlockingSonarBuilder::auto_reconnect).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the SteelSeries Engine is not found or accessible.
+    pub fn reconnect(&self) -> Result<()> {
+        self.refresh_addresses()?;
+        Ok(())
+    }
+
+    /// Get the current mode.
+    pub fn get_mode(&self) -> Result<Mode> {
+        let mode: String = self.get_json("/mode/")?;
+        mode.parse()
+    }
+
+    /// Switch between classic and streamer mode.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The mode to switch to
+    ///
+    /// # Returns
+    ///
+    /// Returns the new mode as reported by the server.
+    pub fn set_mode(&self, mode: Mode) -> Result<Mode> {
+        let new_mode: String = self.put_json(&format!("/mode/{}", mode))?;
+        let new_mode: Mode = new_mode.parse()?;
+        self.core.use_mode(new_mode.is_streamer());
+        Ok(new_mode)
+    }
+
+    /// Check if streamer mode is currently enabled.
+    #[deprecated(note = "use `get_mode` instead")]
+    pub fn is_streamer_mode(&self) -> Result<bool> {
+        Ok(self.get_mode()?.is_streamer())
+    }
+
+    /// Set streamer mode on or off, returning the new streamer mode state.
+    #[deprecated(note = "use `set_mode` instead")]
+    pub fn set_streamer_mode(&self, streamer_mode: bool) -> Result<bool> {
+        Ok(self.set_mode(Mode::from_streamer_mode(streamer_mode))?.is_streamer())
+    }
 
-        Ok(self.streamer_mode)
+    fn streamer_mode(&self) -> bool {
+        self.core.streamer_mode()
+    }
+
+    /// Send a volume or mute change in `mode`, unless the state cache knows
+    /// it to be in effect already.
+    fn write_setting(&self, mode: Mode, channel: &str, streamer_slider: Option<StreamerSlider>, setting: Setting) -> Result<Value> {
+        let (setting, path) = self.core.setting_path(mode, channel, streamer_slider, setting)?;
+        let Some(path) = path else {
+            return Ok(Value::Null);
+        };
+        let result = self.put_json(&path);
+        self.core.record_write(mode, channel, streamer_slider, setting, result.is_ok());
+        result
     }
 
     /// Get volume data for all channels.
     pub fn get_volume_data(&self) -> Result<Value> {
-        let url = format!("{}{}", self.web_server_address, self.volume_path);
-        let response = self.client.get(&url).send()?;
-        
-        if !response.status().is_success() {
-            return Err(SonarError::ServerNotAccessible(response.status().as_u16()));
-        }
+        self.ensure_connected()?;
+        self.get_json(self.core.mode().volume_path())
+    }
 
-        let volume_data: Value = response.json()?;
-        Ok(volume_data)
+    /// Get typed volume settings for all channels.
+    pub fn get_volumes(&self) -> Result<VolumeSettings> {
+        self.core.volumes(self.get_volume_data()?)
     }
 
     /// Set the volume for a specific channel.
-    pub fn set_volume(&self, channel: &str, volume: f64, streamer_slider: Option<&str>) -> Result<Value> {
-        if !crate::sonar::CHANNEL_NAMES.contains(&channel) {
-            return Err(SonarError::ChannelNotFound(channel.to_string()));
-        }
+    pub fn set_volume(&self, channel: &str, volume: f64, streamer_slider: Option<StreamerSlider>) -> Result<Value> {
+        let channel = self.core.resolve_channel(channel)?;
 
-        if !(0.0..=1.0).contains(&volume) {
-            return Err(SonarError::InvalidVolume(volume));
-        }
+        check_volume(volume)?;
 
-        let streamer_slider = streamer_slider.unwrap_or("streaming");
-        if self.streamer_mode && !crate::sonar::STREAMER_SLIDER_NAMES.contains(&streamer_slider) {
-            return Err(SonarError::SliderNotFound(streamer_slider.to_string()));
-        }
+        self.ensure_connected()?;
+        self.write_setting(self.core.mode(), channel, streamer_slider, Setting::Volume(volume))
+    }
 
-        let full_volume_path = if self.streamer_mode {
-            format!("{}/{}", self.volume_path, streamer_slider)
-        } else {
-            self.volume_path.clone()
-        };
+    /// Change the volume of a channel relative to its current value.
+    ///
+    /// `delta` moves along the client's [`VolumeCurve`](crate::VolumeCurve) and
+    /// the result is clamped to 0.0..=1.0. Returns the new volume.
+    pub fn adjust_volume(&self, channel: &str, delta: f64, streamer_slider: Option<StreamerSlider>) -> Result<f64> {
+        let current = self.get_channel_state(channel, streamer_slider)?;
+        let position = self.core.volume_curve.to_position(current.volume) + delta;
+        let volume = self.core.volume_curve.to_volume(position);
+
+        self.set_volume(channel, volume, streamer_slider)?;
+        Ok(volume)
+    }
 
-        let url = format!("{}{}/{}/Volume/{}", 
-            self.web_server_address, full_volume_path, channel, serde_json::to_string(&volume)?);
-        
-        let response = self.client.put(&url).send()?;
-        
-        if !response.status().is_success() {
-            return Err(SonarError::ServerNotAccessible(response.status().as_u16()));
-        }
+    /// Set the volume for a specific channel as a percentage.
+    ///
+    /// The percentage is a position on the client's [`VolumeCurve`](crate::VolumeCurve).
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The audio channel name
+    /// * `percent` - Volume level (0 to 100)
+    /// * `streamer_slider` - Streamer slider to use in streamer mode
+    pub fn set_volume_percent(&self, channel: &str, percent: u8, streamer_slider: Option<StreamerSlider>) -> Result<Value> {
+        let volume = self.core.volume_curve.to_volume(percent_to_volume(percent)?);
+        self.set_volume(channel, volume, streamer_slider)
+    }
 
-        let result: Value = response.json()?;
-        Ok(result)
+    /// Get the volume of a channel as a percentage, rounded to the nearest integer.
+    ///
+    /// The percentage is a position on the client's [`VolumeCurve`](crate::VolumeCurve).
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The audio channel name
+    /// * `streamer_slider` - Streamer slider to read in streamer mode
+    pub fn get_volume_percent(&self, channel: &str, streamer_slider: Option<StreamerSlider>) -> Result<u8> {
+        let volume = self.get_channel_state(channel, streamer_slider)?.volume;
+        Ok(volume_to_percent(self.core.volume_curve.to_position(volume)))
+    }
+
+    /// Invert the mute state of a channel.
+    ///
+    /// Returns the new mute state.
+    pub fn toggle_mute(&self, channel: &str, streamer_slider: Option<StreamerSlider>) -> Result<bool> {
+        let muted = !self.get_channel_state(channel, streamer_slider)?.muted;
+
+        self.mute_channel(channel, muted, streamer_slider)?;
+        Ok(muted)
+    }
+
+    /// Get the volume and mute state of a single channel.
+    ///
+    /// The state is read from the current mode (and `streamer_slider` in streamer mode).
+    pub fn get_channel_state(&self, channel: &str, streamer_slider: Option<StreamerSlider>) -> Result<VolumeState> {
+        let channel = self.core.resolve_channel(channel)?;
+
+        self.core.channel_state(&self.get_volumes()?, channel, streamer_slider)
     }
 
     /// Mute or unmute a specific channel.
-    pub fn mute_channel(&self, channel: &str, muted: bool, streamer_slider: Option<&str>) -> Result<Value> {
-        if !crate::sonar::CHANNEL_NAMES.contains(&channel) {
-            return Err(SonarError::ChannelNotFound(channel.to_string()));
-        }
+    pub fn mute_channel(&self, channel: &str, muted: bool, streamer_slider: Option<StreamerSlider>) -> Result<Value> {
+        let channel = self.core.resolve_channel(channel)?;
 
-        let streamer_slider = streamer_slider.unwrap_or("streaming");
-        if self.streamer_mode && !crate::sonar::STREAMER_SLIDER_NAMES.contains(&streamer_slider) {
-            return Err(SonarError::SliderNotFound(streamer_slider.to_string()));
-        }
+        self.ensure_connected()?;
+        self.write_setting(self.core.mode(), channel, streamer_slider, Setting::Muted(muted))
+    }
+
+    /// Set the volume of a channel in both streamer-mode mixes, sending the
+    /// two requests concurrently. Works in classic mode too.
+    pub fn set_volume_both(&self, channel: &str, streaming: f64, monitoring: f64) -> Result<()> {
+        let channel = self.core.resolve_channel(channel)?;
 
-        let full_volume_path = if self.streamer_mode {
-            format!("{}/{}", self.volume_path, streamer_slider)
-        } else {
-            self.volume_path.clone()
+        check_volume(streaming)?;
+        check_volume(monitoring)?;
+
+        self.ensure_connected()?;
+        self.write_both(channel, Setting::Volume(streaming), Setting::Volume(monitoring))
+    }
+
+    /// Mute or unmute a channel in both streamer-mode mixes, sending the two
+    /// requests concurrently. Works in classic mode too.
+    pub fn mute_both(&self, channel: &str, muted: bool) -> Result<()> {
+        let channel = self.core.resolve_channel(channel)?;
+
+        self.ensure_connected()?;
+        self.write_both(channel, Setting::Muted(muted), Setting::Muted(muted))
+    }
+
+    /// Mute every playback channel except `channel`, and unmute `channel`.
+    /// Soloing again while soloed stacks, see [`Sonar::solo`](crate::Sonar::solo).
+    pub fn solo(&self, channel: &str, streamer_slider: Option<StreamerSlider>) -> Result<()> {
+        let channel = self.core.resolve_channel(channel)?;
+
+        let volumes = self.get_volumes()?;
+        let mutes = self.core.push_solo(&volumes, channel, streamer_slider)?;
+        let mode = self.core.mode();
+        self.write_settings(mode, mutes.into_iter().map(|(channel, muted)| (channel, streamer_slider, Setting::Muted(muted))))
+    }
+
+    /// Restore the mute states saved by the latest [`solo`](Self::solo).
+    /// Returns `false` if nothing is soloed.
+    pub fn unsolo(&self) -> Result<bool> {
+        self.ensure_connected()?;
+        let Some(state) = self.core.pop_solo() else {
+            return Ok(false);
         };
+        let mutes = state.muted.into_iter().map(|(channel, muted)| (channel, state.slider, Setting::Muted(muted)));
+        self.write_settings(state.mode, mutes)?;
+        Ok(true)
+    }
+
+    /// Write several settings in `mode`, carrying on past failures, and
+    /// return the first error.
+    fn write_settings(&self, mode: Mode, settings: impl IntoIterator<Item = (&'static str, Option<StreamerSlider>, Setting)>) -> Result<()> {
+        let mut result = Ok(());
+        for (channel, streamer_slider, setting) in settings {
+            if let Err(error) = self.write_setting(mode, channel, streamer_slider, setting) {
+                result = result.and(Err(error));
+            }
+        }
+        result
+    }
 
-        let mute_keyword = if self.streamer_mode { "isMuted" } else { "Mute" };
+    /// Get the volume of a channel group: that of its loudest member.
+    pub fn get_group_volume(&self, group: &ChannelGroup) -> Result<f64> {
+        Ok(group.volume(&self.get_volumes()?, self.streamer_mode()))
+    }
+
+    /// Set the volume of a channel group, scaling every member by the same
+    /// factor. See [`ChannelGroup`].
+    pub fn set_group_volume(&self, group: &ChannelGroup, volume: f64) -> Result<()> {
+        check_volume(volume)?;
+
+        for (channel, volume) in group.scaled_volumes(&self.get_volumes()?, self.streamer_mode(), volume) {
+            self.set_volume(channel.as_str(), volume, group.slider())?;
+        }
+        Ok(())
+    }
 
-        let url = format!("{}{}/{}/{}/{}", 
-            self.web_server_address, full_volume_path, channel, mute_keyword, serde_json::to_string(&muted)?);
-        
-        let response = self.client.put(&url).send()?;
-        
-        if !response.status().is_success() {
-            return Err(SonarError::ServerNotAccessible(response.status().as_u16()));
+    /// Mute or unmute every channel of a group.
+    pub fn mute_group(&self, group: &ChannelGroup, muted: bool) -> Result<()> {
+        for channel in group.channels() {
+            self.mute_channel(channel.as_str(), muted, group.slider())?;
         }
+        Ok(())
+    }
 
-        let result: Value = response.json()?;
-        Ok(result)
+    /// Write the streaming and monitoring mix settings from two threads.
+    fn write_both(&self, channel: &str, streaming: Setting, monitoring: Setting) -> Result<()> {
+        std::thread::scope(|scope| {
+            let monitoring = scope.spawn(|| self.write_setting(Mode::Streamer, channel, Some(StreamerSlider::Monitoring), monitoring));
+            let streaming = self.write_setting(Mode::Streamer, channel, Some(StreamerSlider::Streaming), streaming);
+            let monitoring = monitoring.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+            streaming.and(monitoring).map(drop)
+        })
     }
 
     /// Get chat mix data.
     pub fn get_chat_mix_data(&self) -> Result<Value> {
-        let url = format!("{}/chatMix", self.web_server_address);
-        let response = self.client.get(&url).send()?;
-        
-        if !response.status().is_success() {
-            return Err(SonarError::ServerNotAccessible(response.status().as_u16()));
-        }
-
-        let chat_mix_data: Value = response.json()?;
-        Ok(chat_mix_data)
+        self.get_json("/chatMix")
     }
 
     /// Set the chat mix volume.
     pub fn set_chat_mix(&self, mix_volume: f64) -> Result<Value> {
-        if !(-1.0..=1.0).contains(&mix_volume) {
-            return Err(SonarError::InvalidMixVolume(mix_volume));
+        check_mix_volume(mix_volume)?;
+
+        let result: Result<Value> = self.put_json(&api_path_with_query(&["chatMix"], &[("balance", &serde_json::to_string(&mix_volume)?)])?);
+        match result {
+            Ok(data) if !chat_mix_enabled(&data) => Err(SonarError::ChatMixUnavailable),
+            Err(error) if error.status().is_some() => match self.chat_mix_supported() {
+                Ok(false) => Err(SonarError::ChatMixUnavailable),
+                _ => Err(error),
+            },
+            result => result,
         }
+    }
 
-        let url = format!("{}/chatMix?balance={}", 
-            self.web_server_address, serde_json::to_string(&mix_volume)?);
-        
-        let response = self.client.put(&url).send()?;
-        
-        if !response.status().is_success() {
-            return Err(SonarError::ServerNotAccessible(response.status().as_u16()));
-        }
+    /// Check whether a ChatMix-capable headset is connected and enabled.
+    ///
+    /// Without one, [`set_chat_mix`](Self::set_chat_mix) fails with
+    /// [`SonarError::ChatMixUnavailable`].
+    pub fn chat_mix_supported(&self) -> Result<bool> {
+        Ok(chat_mix_enabled(&self.get_chat_mix_data()?))
+    }
 
-        let result: Value = response.json()?;
-        Ok(result)
+    /// List the audio devices known to Sonar.
+    pub fn list_audio_devices(&self) -> Result<Vec<AudioDevice>> {
+        self.get_json("/audioDevices?removeSteelSeriesVAD=true")
     }
 
-    fn load_base_url(app_data_path: &Path) -> Result<String> {
-        use crate::sonar::CoreProps;
-        
-        if !app_data_path.exists() {
-            return Err(SonarError::EnginePathNotFound);
-        }
+    /// Get the classic-mode device routing of every channel.
+    pub fn get_redirections(&self) -> Result<Vec<ClassicRedirection>> {
+        self.get_json("/classicRedirections")
+    }
+
+    /// Get the audio device a channel is routed to: the output device, or
+    /// the microphone for `chatCapture`.
+    ///
+    /// Returns `None` if the channel has no device of its own, like
+    /// `master`, or the device is not connected. Streamer-mode mixes have
+    /// their own devices, see `get_stream_redirections`.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The audio channel name
+    pub fn get_channel_device(&self, channel: &str) -> Result<Option<AudioDevice>> {
+        let channel = self.core.resolve_channel(channel)?;
 
-        let content = std::fs::read_to_string(app_data_path)?;
-        let core_props: CoreProps = serde_json::from_str(&content)?;
-        
-        Ok(format!("https://{}", core_props.gg_encrypted_address))
+        let redirections = self.get_redirections()?;
+        Ok(channel_device(&redirections, self.list_audio_devices()?, channel))
     }
 
-    fn load_server_address(client: &Client, base_url: &str) -> Result<String> {
-        use crate::sonar::SubAppsResponse;
-        
-        let url = format!("{}/subApps", base_url);
-        let response = client.get(&url).send()?;
-        
-        if !response.status().is_success() {
-            return Err(SonarError::ServerNotAccessible(response.status().as_u16()));
-        }
+    /// Route a channel to a different audio device in classic mode.
+    pub fn set_redirection(&self, channel: &str, device_id: &str) -> Result<Value> {
+        let channel = self.core.resolve_channel(channel)?;
 
-        let sub_apps_response: SubAppsResponse = response.json()?;
-        let sonar = &sub_apps_response.sub_apps.sonar;
+        self.put_json(&api_path(&["classicRedirections", channel, "deviceId", device_id])?)
+    }
 
-        if !sonar.is_enabled {
-            return Err(SonarError::SonarNotEnabled);
+    /// Switch the microphone feeding the `chatCapture` channel, e.g. between
+    /// a headset and a desk mic.
+    ///
+    /// # Arguments
+    ///
+    /// * `device_id` - Id of a capture device, see `list_audio_devices`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SonarError::CaptureDeviceNotFound`] if no connected input
+    /// device has this id.
+    pub fn set_capture_device(&self, device_id: &str) -> Result<Value> {
+        check_capture_device(&self.list_audio_devices()?, device_id)?;
+        self.set_redirection(CAPTURE_CHANNEL, device_id)
+    }
+
+    /// Get the output device of each streamer-mode mix.
+    pub fn get_stream_redirections(&self) -> Result<Vec<StreamRedirection>> {
+        self.get_json("/streamRedirections")
+    }
+
+    /// Route a streamer-mode mix (streaming or monitoring) to a different audio device.
+    pub fn set_stream_redirection(&self, slider: StreamerSlider, device_id: &str) -> Result<Value> {
+        self.put_json(&api_path(&["streamRedirections", slider.as_str(), "deviceId", device_id])?)
+    }
+
+    /// Mute or unmute a channel in one streamer-mode mix only.
+    ///
+    /// Unlike [`mute_channel`](Self::mute_channel), this toggles whether the
+    /// channel is sent to the mix's output device at all.
+    pub fn mute_redirection(&self, channel: &str, slider: StreamerSlider, muted: bool) -> Result<Value> {
+        let channel = self.core.resolve_channel(channel)?;
+
+        self.put_json(&api_path(&["streamRedirections", slider.as_str(), "redirections", channel, "isEnabled", 
+            &serde_json::to_string(&!muted)?])?)
+    }
+
+    /// Get the applications Sonar has assigned to each channel.
+    pub fn get_routed_apps(&self) -> Result<Vec<ChannelApps>> {
+        self.get_json("/AudioDeviceRouting")
+    }
+
+    /// Move an application's audio to another channel.
+    pub fn route_app(&self, process_id: u32, channel: &str) -> Result<Value> {
+        let channel = self.core.resolve_channel(channel)?;
+
+        self.put_json(&api_path(&["AudioDeviceRouting", channel, &process_id.to_string()])?)
+    }
+
+    /// Move every process of an application, found by its executable name,
+    /// to another channel. Returns the ids of the moved processes.
+    ///
+    /// Processes are looked up among the audio sessions Sonar knows of and,
+    /// with the `windows-audio` feature, among all running processes.
+    pub fn route_app_by_name(&self, name: &str, channel: Channel) -> Result<Vec<u32>> {
+        let process_ids = find_processes(name, &self.get_routed_apps()?);
+        if process_ids.is_empty() {
+            return Err(SonarError::AppNotFound(name.to_string()));
+        }
+
+        for &process_id in &process_ids {
+            self.route_app(process_id, channel.as_str())?;
         }
+        Ok(process_ids)
+    }
+
+    /// List the audio configs (presets) available for a channel.
+    pub fn list_configs(&self, channel: &str) -> Result<Vec<SonarConfig>> {
+        let channel = self.core.resolve_channel(channel)?;
+
+        self.get_json(&api_path_with_query(&["configs"], &[("vad", channel)])?)
+    }
+
+    /// Get the currently selected config of every channel.
+    pub fn get_selected_configs(&self) -> Result<Vec<SonarConfig>> {
+        self.get_json("/configs/selected")
+    }
 
-        if !sonar.is_ready {
-            return Err(SonarError::ServerNotReady);
+    /// Select (activate) a config for its channel.
+    pub fn select_config(&self, config_id: &str) -> Result<Value> {
+        self.put_json(&api_path(&["configs", config_id, "select"])?)
+    }
+
+    /// Create a new config for a channel.
+    pub fn create_config(&self, channel: &str, name: &str) -> Result<SonarConfig> {
+        let channel = self.core.resolve_channel(channel)?;
+
+        self.post_json("/configs", &NewConfig { name, channel })
+    }
+
+    /// Rename a config.
+    pub fn rename_config(&self, config_id: &str, name: &str) -> Result<Value> {
+        self.put_json(&api_path(&["configs", config_id, "name", name])?)
+    }
+
+    /// Delete a config.
+    pub fn delete_config(&self, config_id: &str) -> Result<()> {
+        self.delete(&api_path(&["configs", config_id])?)
+    }
+
+    /// Get the parametric EQ of the config currently selected for a channel.
+    pub fn get_eq(&self, channel: &str) -> Result<Equalizer> {
+        Ok(Equalizer::from_config(&self.selected_config(channel)?))
+    }
+
+    /// Change one band of the parametric EQ of the config selected for a channel.
+    ///
+    /// Returns the updated EQ.
+    pub fn set_eq_band(&self, channel: &str, band: usize, update: EqBandUpdate) -> Result<Equalizer> {
+        let mut config = self.selected_config(channel)?;
+        apply_band_update(&mut config.settings, band, update)?;
+
+        let _: Value = self.put_body(&api_path(&["configs", &config.id])?, &config)?;
+        Ok(Equalizer::from_config(&config))
+    }
+
+    /// Probe which optional endpoints the running Sonar version has, so
+    /// features it lacks can be hidden instead of failing.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the web server cannot be reached or fails for
+    /// another reason than a missing endpoint.
+    pub fn capabilities(&self) -> Result<Capabilities> {
+        Capabilities::from_probes(PROBE_PATHS.map(|path| self.probe(path)))
+    }
+
+    fn probe(&self, path: &str) -> Result<()> {
+        self.send(Method::Get, path, None)?;
+        Ok(())
+    }
+
+    /// Send a `GET` request to an endpoint of the Sonar web server this crate
+    /// does not wrap yet, e.g. `/mode/`, returning the parsed JSON response.
+    ///
+    /// The request goes through the same discovery, retries and capture as
+    /// every other call. An empty response body is returned as `null`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SonarError::Api`] if the server answers with an error status.
+    pub fn raw_get(&self, path: &str) -> Result<Value> {
+        raw_body(&self.send(Method::Get, &raw_path(path), None)?.body)
+    }
+
+    /// Send a `PUT` request with an optional JSON body to an endpoint of the
+    /// Sonar web server, see [`raw_get`](Self::raw_get).
+    pub fn raw_put(&self, path: &str, body: Option<&Value>) -> Result<Value> {
+        raw_body(&self.send(Method::Put, &raw_path(path), body)?.body)
+    }
+
+    fn get_json<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        self.send(Method::Get, path, None)?.json()
+    }
+
+    fn put_json<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        self.send(Method::Put, path, None)?.json()
+    }
+
+    fn put_body<B: Serialize + ?Sized, T: DeserializeOwned>(&self, path: &str, body: &B) -> Result<T> {
+        let body = serde_json::to_value(body)?;
+        self.send(Method::Put, path, Some(&body))?.json()
+    }
+
+    fn post_json<B: Serialize + ?Sized, T: DeserializeOwned>(&self, path: &str, body: &B) -> Result<T> {
+        let body = serde_json::to_value(body)?;
+        self.send(Method::Post, path, Some(&body))?.json()
+    }
+
+    fn delete(&self, path: &str) -> Result<()> {
+        self.send(Method::Delete, path, None)?;
+        Ok(())
+    }
+
+    /// The Sonar web server address currently in use, e.g. to pass to
+    /// [`connect_to`](Self::connect_to) later.
+    pub fn web_server_address(&self) -> String {
+        self.core.web_server_address()
+    }
+
+    /// The last requests to the web server, oldest first, if enabled with
+    /// [`BlockingSonarBuilder::capture_requests`].
+    pub fn last_requests(&self) -> Vec<CapturedRequest> {
+        self.core.last_requests()
+    }
+
+    /// Send a request to the web server, connecting first if needed.
+    fn send(&self, method: Method, path: &str, body: Option<&Value>) -> Result<TransportResponse> {
+        self.ensure_connected()?;
+        self.send_connected(method, path, body)
+    }
+
+    /// Send a request to the web server, retrying transient failures
+    /// according to the retry policy. `POST` requests are only resent after
+    /// a reconnect.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "sonar.request",
+            level = "debug",
+            skip(self, method, body),
+            fields(method = method.as_str()),
+            err(Display)
+        )
+    )]
+    fn send_connected(&self, method: Method, path: &str, body: Option<&Value>) -> Result<TransportResponse> {
+        if let Some(interaction) = self.core.replay(method.as_str(), path, body) {
+            let interaction = interaction?;
+            let response = TransportResponse {
+                status: interaction.status,
+                body: interaction.response_body.into_bytes(),
+            };
+            if !response.is_success() {
+                return Err(response.into_api_error(path));
+            }
+            return Ok(response);
         }
 
-        if !sonar.is_running {
-            return Err(SonarError::ServerNotRunning);
+        let mut attempt = 1;
+        let mut reconnected = false;
+
+        loop {
+            if let Some(wait) = self.core.rate_limit_wait() {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(?wait, "rate limited");
+                std::thread::sleep(wait);
+            }
+
+            let url = self.core.url(path);
+            let started = Instant::now();
+
+            let error = match self.client.send(method, &url, body) {
+                Ok(response) => {
+                    self.core.record_response(method.as_str(), path, &url, body, started, response.status, &response.body)?;
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(attempt, status = response.status, elapsed = ?started.elapsed(), "response received");
+                    #[cfg(feature = "metrics")]
+                    telemetry::record_response(method.as_str(), path, response.status, started.elapsed());
+                    if response.is_success() {
+                        return Ok(response);
+                    }
+                    response.into_api_error(path)
+                }
+                Err(error) => {
+                    self.core.record_failure(method.as_str(), &url, body, started, &error);
+                    error
+                }
+            };
+
+            #[cfg(feature = "metrics")]
+            telemetry::record_error(method.as_str(), path, &error);
+
+            // A refused connection usually means GG restarted on a new port;
+            // the request never reached the server, so it is safe to resend.
+            if self.core.auto_reconnect && !reconnected && error.is_connect() {
+                reconnected = true;
+                #[cfg(feature = "tracing")]
+                tracing::info!(error = %error, "connection failed, re-resolving the server address");
+                #[cfg(feature = "metrics")]
+                telemetry::record_reconnect();
+                if let Ok(true) = self.refresh_addresses() {
+                    continue;
+                }
+            }
+
+            if method == Method::Post || !self.core.retry_policy.should_retry(&error, attempt) {
+                return Err(error);
+            }
+            #[cfg(feature = "tracing")]
+            tracing::warn!(attempt, error = %error, "request failed, retrying");
+            #[cfg(feature = "metrics")]
+            telemetry::record_retry(method.as_str(), path);
+            std::thread::sleep(self.core.retry_policy.delay(attempt));
+            attempt += 1;
         }
+    }
 
-        let web_server_address = &sonar.metadata.web_server_address;
-        if web_server_address.is_empty() || web_server_address == "null" {
-            return Err(SonarError::WebServerAddressNotFound);
+    /// Send a single request, e.g. to the Engine, without retries, reconnects
+    /// or capture.
+    fn send_raw(client: &BlockingTransport, method: Method, url: &str) -> Result<TransportResponse> {
+        let response = client.send(method, url, None)?;
+        if !response.is_success() {
+            return Err(SonarError::ServerNotAccessible(response.status));
         }
+        Ok(response)
+    }
 
-        Ok(web_server_address.clone())
+    client_orchestration! {
+        builder: BlockingSonarBuilder,
+        async: [],
+        await: [],
+        join: [try_join_in_turn],
+        client: BlockingTransport,
+        get: Method::Get,
+        put: Method::Put,
+        instant: Instant,
+        sleep: std::thread::sleep,
+        read_to_string: std::fs::read_to_string,
     }
 }
@@ -0,0 +1,497 @@
+//! Builders for configuring and constructing Sonar clients.
+
+#[cfg(any(feature = "blocking", feature = "ureq"))]
+use crate::blocking::BlockingSonar;
+use crate::cassette::CassetteMode;
+use crate::config_file::{ConfigFile, CONFIG_ENV};
+use crate::discovery::{find_core_props, ADDRESS_ENV, COREPROPS_ENV};
+use crate::error::Result;
+use crate::rate_limit::RateLimit;
+use crate::retry::RetryPolicy;
+use crate::sonar::{Channel, Mode, StreamerSlider};
+use crate::validation::Validation;
+use crate::volume::VolumeCurve;
+#[cfg(feature = "async")]
+use crate::sonar::Sonar;
+#[cfg(any(feature = "blocking", feature = "ureq"))]
+use crate::transport::Backend;
+use std::collections::HashMap;
+#[cfg(any(feature = "async", feature = "blocking", feature = "ureq"))]
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Options shared by the async and blocking client builders.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ClientOptions {
+    pub core_props_path: Option<PathBuf>,
+    pub streamer_mode: Option<bool>,
+    pub timeout: Option<Duration>,
+    pub connect_timeout: Option<Duration>,
+    pub pool_idle_timeout: Option<Duration>,
+    pub pool_max_idle: Option<usize>,
+    pub retry_policy: Option<RetryPolicy>,
+    pub rate_limit: Option<RateLimit>,
+    pub state_cache: Option<Duration>,
+    pub auto_reconnect: Option<bool>,
+    pub web_server_address: Option<String>,
+    pub cassette: Option<CassetteMode>,
+    pub capture_requests: Option<usize>,
+    pub validation: Option<Validation>,
+    pub volume_curve: Option<VolumeCurve>,
+    pub ready_timeout: Option<Duration>,
+    pub enable_sonar: bool,
+    pub launch_engine: bool,
+    pub config_file: Option<PathBuf>,
+    pub volume_caps: HashMap<Channel, f64>,
+    pub channel_aliases: HashMap<String, Channel>,
+}
+
+impl ClientOptions {
+    /// Options taken from the [`COREPROPS_ENV`], [`ADDRESS_ENV`] and
+    /// [`CONFIG_ENV`] environment variables, reading the default config file
+    /// if no other is named and it exists.
+    pub fn from_env() -> Self {
+        let mut options = Self::from_lookup(|name| std::env::var(name).ok());
+        if options.config_file.is_none() {
+            options.config_file = ConfigFile::default_path().filter(|path| path.exists());
+        }
+        options
+    }
+
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let var = |name| lookup(name).filter(|value| !value.trim().is_empty());
+        Self {
+            core_props_path: var(COREPROPS_ENV).map(PathBuf::from),
+            web_server_address: var(ADDRESS_ENV).map(|address| normalize_address(&address)),
+            config_file: var(CONFIG_ENV).map(PathBuf::from),
+            ..Self::default()
+        }
+    }
+
+    /// Fill in the options not set yet from the config file, if one was given.
+    pub fn load_config_file(&mut self) -> Result<()> {
+        match self.config_file.take() {
+            Some(path) => ConfigFile::load(path)?.apply(self),
+            None => Ok(()),
+        }
+    }
+
+    /// The API name of `channel`, which is either a channel name or an alias.
+    pub fn resolve_channel(&self, channel: &str) -> Result<&'static str> {
+        match channel.parse::<Channel>() {
+            Ok(channel) => Ok(channel.as_str()),
+            Err(error) => self.channel_aliases.get(channel).map(|channel| channel.as_str()).ok_or(error),
+        }
+    }
+
+    /// Report a streamer slider passed in classic mode, where it is ignored.
+    pub fn check_slider(&self, mode: Mode, slider: Option<StreamerSlider>) -> Result<()> {
+        match slider {
+            Some(slider) if mode == Mode::Classic => self
+                .validation
+                .unwrap_or_default()
+                .report(|| format!("the {} slider only applies in streamer mode", slider)),
+            _ => Ok(()),
+        }
+    }
+
+    /// `volume` lowered to the cap of `channel`, if it has one.
+    pub fn cap_volume(&self, channel: &str, volume: f64) -> f64 {
+        channel
+            .parse::<Channel>()
+            .ok()
+            .and_then(|channel| self.volume_caps.get(&channel))
+            .map_or(volume, |&cap| volume.min(cap))
+    }
+
+    /// The coreProps.json path to use, discovering it if none was set.
+    pub fn core_props_path(&self) -> PathBuf {
+        self.core_props_path
+            .clone()
+            .unwrap_or_else(find_core_props)
+    }
+}
+
+/// Trim whitespace and trailing slashes off a web server address.
+pub(crate) fn normalize_address(address: &str) -> String {
+    address.trim().trim_end_matches('/').to_string()
+}
+
+/// Expands to the constructors and the setters of the [`ClientOptions`]
+/// shared by [`SonarBuilder`] and [`BlockingSonarBuilder`], so that both
+/// offer the same options.
+#[cfg(any(feature = "async", feature = "blocking", feature = "ureq"))]
+macro_rules! option_setters {
+    () => {
+        /// Create a builder with default settings.
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Create a builder configured from the environment.
+        ///
+        /// `STEELSERIES_SONAR_COREPROPS` sets the coreProps.json path and
+        /// `STEELSERIES_SONAR_ADDRESS` the web server address, which skips
+        /// discovery entirely. Unset or empty variables are ignored.
+        /// `STEELSERIES_SONAR_CONFIG` names the config file to read, which
+        /// otherwise is [`ConfigFile::default_path`] if it exists.
+        pub fn from_env() -> Self {
+            Self {
+                options: ClientOptions::from_env(),
+                ..Self::default()
+            }
+        }
+
+        /// Read defaults from this `sonar.toml` file, see [`ConfigFile`].
+        /// Options set on this builder take precedence over the file.
+        pub fn config_file(mut self, path: impl AsRef<Path>) -> Self {
+            self.options.config_file = Some(path.as_ref().to_path_buf());
+            self
+        }
+
+        /// Accept `alias` wherever a channel name is expected.
+        pub fn channel_alias(mut self, alias: impl Into<String>, channel: Channel) -> Self {
+            self.options.channel_aliases.insert(alias.into(), channel);
+            self
+        }
+
+        /// Never set the volume of `channel` above `max`; higher volumes are
+        /// lowered to it.
+        pub fn volume_cap(mut self, channel: Channel, max: f64) -> Self {
+            self.options.volume_caps.insert(channel, max.clamp(0.0, 1.0));
+            self
+        }
+
+        /// Use a custom path to the coreProps.json file.
+        pub fn core_props_path(mut self, path: impl AsRef<Path>) -> Self {
+            self.options.core_props_path = Some(path.as_ref().to_path_buf());
+            self
+        }
+
+        /// Connect to this Sonar web server address (e.g. `http://127.0.0.1:51234`)
+        /// instead of discovering it through coreProps.json and the Engine.
+        /// Automatic reconnection does not apply to a fixed address.
+        pub fn web_server_address(mut self, address: impl AsRef<str>) -> Self {
+            self.options.web_server_address = Some(normalize_address(address.as_ref()));
+            self
+        }
+
+        /// Assume classic (`false`) or streamer (`true`) mode instead of detecting it.
+        pub fn streamer_mode(mut self, streamer_mode: bool) -> Self {
+            self.options.streamer_mode = Some(streamer_mode);
+            self
+        }
+
+        /// Timeout applied to every HTTP request, from connecting until the
+        /// response body has been read. Exceeding it yields [`SonarError::Timeout`](crate::SonarError::Timeout).
+        pub fn timeout(mut self, timeout: Duration) -> Self {
+            self.options.timeout = Some(timeout);
+            self
+        }
+
+        /// Timeout for establishing the connection to the Sonar server.
+        pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+            self.options.connect_timeout = Some(timeout);
+            self
+        }
+
+        /// How long an unused connection is kept open for reuse. Keeping it
+        /// longer than the gaps between hotkey presses saves a TLS handshake per
+        /// press. Ignored when a custom HTTP client is given.
+        pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+            self.options.pool_idle_timeout = Some(timeout);
+            self
+        }
+
+        /// Maximum number of unused connections kept open for reuse. Ignored
+        /// when a custom HTTP client is given.
+        pub fn pool_max_idle(mut self, connections: usize) -> Self {
+            self.options.pool_max_idle = Some(connections);
+            self
+        }
+
+        /// Retry transient failures, such as refused connections or `503`
+        /// responses while the SteelSeries Engine restarts. Requests are not
+        /// retried by default.
+        pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+            self.options.retry_policy = Some(policy);
+            self
+        }
+
+        /// Delay requests that exceed `limit`, to protect the GG web server from
+        /// request storms. Requests are not limited by default.
+        pub fn rate_limit(mut self, limit: RateLimit) -> Self {
+            self.options.rate_limit = Some(limit);
+            self
+        }
+
+        /// Remember the channel states written or read for `ttl` and skip
+        /// `set_volume` and `mute_channel` requests that would not change them.
+        /// Skipped writes return `Value::Null`. Changes made elsewhere, e.g. in
+        /// the GG app, go unnoticed until the entry expires or `get_volumes`
+        /// reads them. Off by default.
+        pub fn state_cache(mut self, ttl: Duration) -> Self {
+            self.options.state_cache = Some(ttl);
+            self
+        }
+
+        /// Map control positions to volume levels in the convenience setters
+        /// and fades, see [`VolumeCurve`]. Linear by default.
+        pub fn volume_curve(mut self, volume_curve: VolumeCurve) -> Self {
+            self.options.volume_curve = Some(volume_curve);
+            self
+        }
+
+        /// Keep trying to discover the Sonar server for up to `timeout` while
+        /// SteelSeries GG is still starting, instead of failing right away, e.g.
+        /// when launched at system startup.
+        pub fn wait_until_ready(mut self, timeout: Duration) -> Self {
+            self.options.ready_timeout = Some(timeout);
+            self
+        }
+
+        /// Enable the Sonar sub-app in SteelSeries GG if it is disabled, then
+        /// wait for it to start (30 seconds at most, unless set with
+        /// `wait_until_ready`), instead of failing with
+        /// [`SonarError::SonarNotEnabled`](crate::SonarError::SonarNotEnabled).
+        pub fn enable_sonar(mut self) -> Self {
+            self.options.enable_sonar = true;
+            self
+        }
+
+        /// Start SteelSeries GG if it is not running, then wait for Sonar to
+        /// start (30 seconds at most, unless set with `wait_until_ready`).
+        /// Only supported on Windows; elsewhere GG is expected to be running.
+        pub fn ensure_engine_running(mut self) -> Self {
+            self.options.launch_engine = true;
+            self
+        }
+
+        /// Re-resolve the server address and resend the request when a request
+        /// fails to connect, e.g. after SteelSeries GG restarted. Enabled by default.
+        pub fn auto_reconnect(mut self, enabled: bool) -> Self {
+            self.options.auto_reconnect = Some(enabled);
+            self
+        }
+
+        /// Record every request to the Sonar web server and its response to a
+        /// JSON [`Cassette`](crate::Cassette) at `path`, e.g. to attach to a bug report.
+        pub fn record(mut self, path: impl AsRef<Path>) -> Self {
+            self.options.cassette = Some(CassetteMode::Record(path.as_ref().to_path_buf()));
+            self
+        }
+
+        /// Answer requests from a cassette written by [`record`](Self::record)
+        /// instead of contacting SteelSeries GG.
+        pub fn replay(mut self, path: impl AsRef<Path>) -> Self {
+            self.options.cassette = Some(CassetteMode::Replay(path.as_ref().to_path_buf()));
+            self
+        }
+
+        /// Whether input that has no effect, such as a streamer slider in
+        /// classic mode, is an error, a warning or silently ignored (the
+        /// default), see [`Validation`].
+        pub fn validation(mut self, validation: Validation) -> Self {
+            self.options.validation = Some(validation);
+            self
+        }
+
+        /// Keep the last `capacity` requests to the web server with their full
+        /// URLs and response bodies, secrets redacted, see
+        /// [`CapturedRequest`](crate::CapturedRequest). With the `tracing`
+        /// feature each request is also logged at debug level.
+        pub fn capture_requests(mut self, capacity: usize) -> Self {
+            self.options.capture_requests = Some(capacity);
+            self
+        }
+    };
+}
+
+/// Builder for [`Sonar`] clients.
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use steelseries_sonar::Sonar;
+///
+/// # async fn example() -> steelseries_sonar::Result<()> {
+/// let sonar = Sonar::builder()
+///     .streamer_mode(true)
+///     .timeout(Duration::from_secs(5))
+///     .build()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "async")]
+#[derive(Debug, Clone, Default)]
+pub struct SonarBuilder {
+    options: ClientOptions,
+    client: Option<reqwest::Client>,
+}
+
+#[cfg(feature = "async")]
+impl SonarBuilder {
+    option_setters!();
+
+    /// Use a preconfigured HTTP client (proxy settings, custom TLS, pool tuning, ...).
+    ///
+    /// The SteelSeries Engine serves a self-signed certificate, so the client must
+    /// accept it. Client-level options of this builder, such as
+    /// [`timeout`](Self::timeout) and [`connect_timeout`](Self::connect_timeout),
+    /// are not applied to a custom client.
+    pub fn client(mut self, client: reqwest::Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Discover the Sonar server and build the client.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the SteelSeries Engine is not found or accessible.
+    pub async fn build(self) -> Result<Sonar> {
+        Sonar::from_options(self.options, self.client).await
+    }
+
+    /// Build the client without any I/O, see [`Sonar::lazy`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP client cannot be created.
+    pub fn build_lazy(self) -> Result<Sonar> {
+        Sonar::unconnected(self.options, self.client)
+    }
+}
+
+/// Builder for [`BlockingSonar`] clients.
+///
+/// Offers the same options as [`SonarBuilder`].
+#[cfg(any(feature = "blocking", feature = "ureq"))]
+#[derive(Debug, Clone, Default)]
+pub struct BlockingSonarBuilder {
+    options: ClientOptions,
+    backend: Backend,
+}
+
+#[cfg(any(feature = "blocking", feature = "ureq"))]
+impl BlockingSonarBuilder {
+    option_setters!();
+
+    /// Use a preconfigured HTTP client (proxy settings, custom TLS, pool tuning, ...).
+    ///
+    /// The SteelSeries Engine serves a self-signed certificate, so the client must
+    /// accept it. Client-level options of this builder, such as
+    /// [`timeout`](Self::timeout) and [`connect_timeout`](Self::connect_timeout),
+    /// are not applied to a custom client.
+    #[cfg(feature = "blocking")]
+    pub fn client(mut self, client: reqwest::blocking::Client) -> Self {
+        self.backend = Backend::Reqwest(Some(client));
+        self
+    }
+
+    /// Use the lightweight ureq backend, which does not depend on tokio.
+    ///
+    /// This is already the default when the `blocking` feature is disabled.
+    #[cfg(feature = "ureq")]
+    pub fn ureq(mut self) -> Self {
+        self.backend = Backend::Ureq(None);
+        self
+    }
+
+    /// Use a preconfigured ureq agent.
+    ///
+    /// As with [`client`](Self::client), the agent must accept the Engine's
+    /// self-signed certificate, should not treat error statuses as errors
+    /// (`http_status_as_error(false)`), and does not get this builder's timeouts.
+    #[cfg(feature = "ureq")]
+    pub fn ureq_agent(mut self, agent: ureq::Agent) -> Self {
+        self.backend = Backend::Ureq(Some(agent));
+        self
+    }
+
+    /// Discover the Sonar server and build the client.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the SteelSeries Engine is not found or accessible.
+    pub fn build(self) -> Result<BlockingSonar> {
+        BlockingSonar::from_options(self.options, self.backend)
+    }
+
+    /// Build the client without any I/O, see [`BlockingSonar::lazy`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP client cannot be created.
+    pub fn build_lazy(self) -> Result<BlockingSonar> {
+        BlockingSonar::unconnected(self.options, self.backend)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_builder_options() {
+        let builder = SonarBuilder::new()
+            .core_props_path("C:\\custom\\coreProps.json")
+            .streamer_mode(true)
+            .timeout(Duration::from_secs(3))
+            .connect_timeout(Duration::from_secs(1))
+            .retry_policy(RetryPolicy::default().max_attempts(5))
+            .rate_limit(RateLimit::per_second(20).burst(5))
+            .enable_sonar()
+            .ensure_engine_running()
+            .auto_reconnect(false)
+            .web_server_address(" http://127.0.0.1:51234/ ");
+
+        assert_eq!(
+            builder.options.core_props_path(),
+            PathBuf::from("C:\\custom\\coreProps.json")
+        );
+        assert_eq!(builder.options.streamer_mode, Some(true));
+        assert_eq!(builder.options.timeout, Some(Duration::from_secs(3)));
+        assert_eq!(builder.options.connect_timeout, Some(Duration::from_secs(1)));
+        assert_eq!(builder.options.retry_policy.map(|policy| policy.max_attempts), Some(5));
+        assert_eq!(builder.options.rate_limit.map(|limit| limit.burst), Some(5));
+        assert!(builder.options.enable_sonar);
+        assert!(builder.options.launch_engine);
+        assert_eq!(builder.options.auto_reconnect, Some(false));
+        assert_eq!(builder.options.web_server_address.as_deref(), Some("http://127.0.0.1:51234"));
+    }
+
+    #[cfg(any(feature = "blocking", feature = "ureq"))]
+    #[test]
+    fn test_builder_defaults() {
+        let builder = BlockingSonarBuilder::new();
+
+        assert_eq!(builder.options.core_props_path(), find_core_props());
+        assert_eq!(builder.options.streamer_mode, None);
+        assert_eq!(builder.options.timeout, None);
+        assert_eq!(builder.options.connect_timeout, None);
+        assert!(builder.options.retry_policy.is_none());
+        assert_eq!(builder.options.auto_reconnect, None);
+        assert_eq!(builder.options.web_server_address, None);
+    }
+
+    #[test]
+    fn test_options_from_env() {
+        let options = ClientOptions::from_lookup(|name| match name {
+            COREPROPS_ENV => Some("/data/coreProps.json".to_string()),
+            ADDRESS_ENV => Some("http://127.0.0.1:12345/".to_string()),
+            CONFIG_ENV => Some("/data/sonar.toml".to_string()),
+            _ => None,
+        });
+        assert_eq!(options.core_props_path(), PathBuf::from("/data/coreProps.json"));
+        assert_eq!(options.config_file, Some(PathBuf::from("/data/sonar.toml")));
+        assert_eq!(options.web_server_address.as_deref(), Some("http://127.0.0.1:12345"));
+
+        let options = ClientOptions::from_lookup(|_| Some(String::new()));
+        assert_eq!(options.core_props_path, None);
+        assert_eq!(options.web_server_address, None);
+        assert_eq!(options.config_file, None);
+    }
+}
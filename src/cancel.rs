@@ -0,0 +1,62 @@
+//! Aborting long-running operations.
+//!
+//! Every operation of the async client stops cleanly when its future is
+//! dropped: a fade keeps the last volume it sent, a retry loop sends nothing
+//! more and a [`SonarWatcher`](crate::watch::SonarWatcher) stops polling.
+//! [`cancellable`] drops an operation when a [`CancellationToken`] is
+//! cancelled, e.g. to abort a fade when the user grabs the slider.
+
+use crate::error::{Result, SonarError};
+use std::future::Future;
+pub use tokio_util::sync::CancellationToken;
+
+/// Run `operation` until it finishes or `token` is cancelled.
+///
+/// ```no_run
+/// # async fn example(sonar: steelseries_sonar::Sonar) -> steelseries_sonar::Result<()> {
+/// use std::time::Duration;
+/// use steelseries_sonar::{cancellable, CancellationToken};
+///
+/// let token = CancellationToken::new();
+/// // Call `token.cancel()` elsewhere, e.g. from a slider's drag handler.
+/// cancellable(&token, sonar.fade_volume("game", 0.2, Duration::from_secs(5), None)).await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// Returns [`SonarError::Cancelled`] if the token was cancelled first,
+/// including before the call.
+pub async fn cancellable<T>(token: &CancellationToken, operation: impl Future<Output = Result<T>>) -> Result<T> {
+    tokio::select! {
+        biased;
+        () = token.cancelled() => Err(SonarError::Cancelled),
+        result = operation => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_cancellable() {
+        let token = CancellationToken::new();
+        assert_eq!(cancellable(&token, async { Ok(1) }).await.unwrap(), 1);
+
+        let cancel = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            cancel.cancel();
+        });
+        let result = cancellable(&token, async {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            Ok(())
+        })
+        .await;
+        assert!(matches!(result, Err(SonarError::Cancelled)));
+        assert!(matches!(cancellable(&token, async { Ok(()) }).await, Err(SonarError::Cancelled)));
+    }
+}
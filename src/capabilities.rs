@@ -0,0 +1,80 @@
+//! Detection of the optional endpoints of the running Sonar version.
+//!
+//! Older Sonar releases lack some endpoints, e.g. streamer mode or app
+//! routing. `capabilities()` probes them once, so tools supporting several
+//! GG versions can hide features instead of failing on them.
+
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+
+/// The endpoint probed for each capability, in the order of the fields of
+/// [`Capabilities`].
+pub(crate) const PROBE_PATHS: [&str; 5] = [
+    "/volumeSettings/streamer",
+    "/chatMix",
+    "/classicRedirections",
+    "/AudioDeviceRouting",
+    "/configs/selected",
+];
+
+/// The optional features the running Sonar web server supports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Capabilities {
+    /// Streamer mode with separate streaming and monitoring mixes.
+    pub streamer_mode: bool,
+    /// The ChatMix endpoint. Whether a ChatMix-capable headset is connected
+    /// is told by `chat_mix_supported`.
+    pub chat_mix: bool,
+    /// Routing channels to output devices.
+    pub device_routing: bool,
+    /// Routing applications to channels.
+    pub app_routing: bool,
+    /// Audio configs and the equalizer.
+    pub configs: bool,
+}
+
+impl Capabilities {
+    /// Build from the outcome of requesting each of [`PROBE_PATHS`].
+    pub(crate) fn from_probes(probes: [Result<()>; 5]) -> Result<Self> {
+        let [streamer_mode, chat_mix, device_routing, app_routing, configs] = probes.map(is_supported);
+        Ok(Self {
+            streamer_mode: streamer_mode?,
+            chat_mix: chat_mix?,
+            device_routing: device_routing?,
+            app_routing: app_routing?,
+            configs: configs?,
+        })
+    }
+}
+
+/// Whether a probe found its endpoint. Only "not found"-like statuses mean
+/// the endpoint is missing; other failures are passed on.
+fn is_supported(probe: Result<()>) -> Result<bool> {
+    match probe {
+        Ok(()) => Ok(true),
+        Err(error) if matches!(error.status(), Some(404 | 405 | 501)) => Ok(false),
+        Err(error) => Err(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::SonarError;
+
+    fn api_error(status: u16) -> SonarError {
+        SonarError::Api { status, endpoint: "/chatMix".to_string(), body: String::new() }
+    }
+
+    #[test]
+    fn test_from_probes() {
+        let capabilities = Capabilities::from_probes([Ok(()), Err(api_error(404)), Ok(()), Err(api_error(405)), Ok(())]).unwrap();
+        assert_eq!(
+            capabilities,
+            Capabilities { streamer_mode: true, chat_mix: false, device_routing: true, app_routing: false, configs: true }
+        );
+        assert!(Capabilities::from_probes([Ok(()), Err(api_error(500)), Ok(()), Ok(()), Ok(())]).is_err());
+        assert!(Capabilities::from_probes([Ok(()), Ok(()), Ok(()), Ok(()), Err(SonarError::Timeout)]).is_err());
+    }
+}
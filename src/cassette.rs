@@ -0,0 +1,168 @@
+//! Recording and replaying of the traffic to the Sonar web server.
+//!
+//! A client built with `record(path)` writes every request and its response
+//! to a JSON [`Cassette`]. A client built with `replay(path)` answers its
+//! requests from that cassette without contacting SteelSeries GG, so issues
+//! seen with a specific GG version can be reproduced offline.
+
+use crate::error::{Result, SonarError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+
+/// Web server address reported by clients replaying a cassette.
+pub(crate) const REPLAY_ADDRESS: &str = "http://cassette.invalid";
+
+/// Recorded requests and responses, in the order they were made.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    /// Read a cassette from a JSON file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not a cassette.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+    }
+
+    /// Write the cassette to a JSON file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        Ok(std::fs::write(path, serde_json::to_vec_pretty(self)?)?)
+    }
+}
+
+/// A request to the web server and the response it got.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub method: String,
+    /// Path and query, relative to the web server address.
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_body: Option<Value>,
+    pub status: u16,
+    pub response_body: String,
+}
+
+/// Whether a client records to or replays from a cassette.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum CassetteMode {
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+/// A cassette in use by a client.
+#[derive(Debug)]
+pub(crate) struct Recorder {
+    mode: CassetteMode,
+    state: Mutex<RecorderState>,
+}
+
+#[derive(Debug)]
+struct RecorderState {
+    cassette: Cassette,
+    /// Which interactions have been replayed already.
+    replayed: Vec<bool>,
+}
+
+impl Recorder {
+    /// Start a new recording, or load the cassette to replay.
+    pub fn open(mode: CassetteMode) -> Result<Self> {
+        let cassette = match &mode {
+            CassetteMode::Record(path) => {
+                let cassette = Cassette::default();
+                cassette.save(path)?;
+                cassette
+            }
+            CassetteMode::Replay(path) => Cassette::load(path)?,
+        };
+        let replayed = vec![false; cassette.interactions.len()];
+
+        Ok(Self {
+            mode,
+            state: Mutex::new(RecorderState { cassette, replayed }),
+        })
+    }
+
+    pub fn is_replay(&self) -> bool {
+        matches!(self.mode, CassetteMode::Replay(_))
+    }
+
+    /// Append an interaction and save the cassette, so it survives a crash.
+    pub fn record(&self, interaction: Interaction) -> Result<()> {
+        let CassetteMode::Record(path) = &self.mode else {
+            return Ok(());
+        };
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.cassette.interactions.push(interaction);
+        state.cassette.save(path)
+    }
+
+    /// Take the first interaction not replayed yet that matches the request.
+    pub fn replay(&self, method: &str, path: &str, body: Option<&Value>) -> Result<Interaction> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let RecorderState { cassette, replayed } = &mut *state;
+
+        let index = cassette
+            .interactions
+            .iter()
+            .enumerate()
+            .position(|(index, interaction)| {
+                !replayed[index]
+                    && interaction.method == method
+                    && interaction.path == path
+                    && interaction.request_body.as_ref() == body
+            })
+            .ok_or_else(|| SonarError::CassetteMiss(format!("{} {}", method, path)))?;
+
+        replayed[index] = true;
+        Ok(cassette.interactions[index].clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn interaction(path: &str, response_body: &str) -> Interaction {
+        Interaction {
+            method: "GET".to_string(),
+            path: path.to_string(),
+            request_body: None,
+            status: 200,
+            response_body: response_body.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let path = std::env::temp_dir().join(format!("sonar-cassette-{}.json", std::process::id()));
+
+        let recorder = Recorder::open(CassetteMode::Record(path.clone())).unwrap();
+        recorder.record(interaction("/mode/", r#""classic""#)).unwrap();
+        recorder.record(interaction("/mode/", r#""stream""#)).unwrap();
+
+        let recorder = Recorder::open(CassetteMode::Replay(path.clone())).unwrap();
+        assert!(recorder.is_replay());
+        assert_eq!(recorder.replay("GET", "/mode/", None).unwrap().response_body, r#""classic""#);
+        assert_eq!(recorder.replay("GET", "/mode/", None).unwrap().response_body, r#""stream""#);
+        assert!(matches!(
+            recorder.replay("GET", "/mode/", None),
+            Err(SonarError::CassetteMiss(_))
+        ));
+        assert!(matches!(
+            recorder.replay("PUT", "/mode/stream", None),
+            Err(SonarError::CassetteMiss(_))
+        ));
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
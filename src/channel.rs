@@ -0,0 +1,99 @@
+//! Chained changes to a single channel.
+
+use crate::error::{Result, SonarError};
+use crate::sonar::{Channel, Sonar, StreamerSlider};
+
+/// Changes to one channel, started with [`Sonar::channel`] and sent by
+/// [`apply`](Self::apply).
+///
+/// Only the last value given for each setting is sent, so a chain costs at
+/// most one volume and one mute request.
+///
+/// ```no_run
+/// # async fn example(sonar: steelseries_sonar::Sonar) -> steelseries_sonar::Result<()> {
+/// use steelseries_sonar::{Channel, StreamerSlider};
+///
+/// sonar
+///     .channel(Channel::Game)
+///     .slider(StreamerSlider::Streaming)
+///     .volume(0.8)
+///     .mute(false)
+///     .apply()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+#[must_use = "channel changes do nothing until applied"]
+pub struct ChannelOps<'a> {
+    sonar: &'a Sonar,
+    channel: Channel,
+    slider: Option<StreamerSlider>,
+    volume: Option<f64>,
+    muted: Option<bool>,
+}
+
+impl<'a> ChannelOps<'a> {
+    pub(crate) fn new(sonar: &'a Sonar, channel: Channel) -> Self {
+        Self {
+            sonar,
+            channel,
+            slider: None,
+            volume: None,
+            muted: None,
+        }
+    }
+
+    /// The streamer slider to change in streamer mode. Ignored in classic mode.
+    pub fn slider(mut self, slider: StreamerSlider) -> Self {
+        self.slider = Some(slider);
+        self
+    }
+
+    /// Set the volume (0.0 to 1.0).
+    pub fn volume(mut self, volume: f64) -> Self {
+        self.volume = Some(volume);
+        self
+    }
+
+    /// Mute or unmute the channel.
+    pub fn mute(mut self, muted: bool) -> Self {
+        self.muted = Some(muted);
+        self
+    }
+
+    /// Whether nothing would be sent.
+    pub fn is_empty(&self) -> bool {
+        self.volume.is_none() && self.muted.is_none()
+    }
+
+    /// Send the changes, the volume and the mute state concurrently.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SonarError::InvalidVolume`] before sending anything if the
+    /// volume is out of range, or the first request error otherwise.
+    pub async fn apply(self) -> Result<()> {
+        if let Some(volume) = self.volume
+            && !(0.0..=1.0).contains(&volume)
+        {
+            return Err(SonarError::InvalidVolume(volume));
+        }
+
+        let channel = self.channel.as_str();
+        let volume = async {
+            match self.volume {
+                Some(volume) => self.sonar.set_volume(channel, volume, self.slider).await.map(drop),
+                None => Ok(()),
+            }
+        };
+        let mute = async {
+            match self.muted {
+                Some(muted) => self.sonar.mute_channel(channel, muted, self.slider).await.map(drop),
+                None => Ok(()),
+            }
+        };
+        tokio::try_join!(volume, mute)?;
+        Ok(())
+    }
+}
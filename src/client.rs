@@ -0,0 +1,512 @@
+//! Client traits for dependency injection.
+//!
+//! Applications that take a `impl SonarClient` (or `impl BlockingSonarClient`)
+//! instead of a concrete client can substitute a test double in their unit
+//! tests, without a running SteelSeries GG instance.
+//!
+//! ```
+//! use steelseries_sonar::{Result, SonarClient};
+//!
+//! async fn duck_game(sonar: &impl SonarClient) -> Result<()> {
+//!     sonar.set_volume("game", 0.2, None).await?;
+//!     Ok(())
+//! }
+//! ```
+//!
+//! Both traits mirror the inherent methods of the clients; see
+//! [`Sonar`] and [`BlockingSonar`](crate::BlockingSonar) for details.
+
+use crate::configs::SonarConfig;
+use crate::devices::AudioDevice;
+use crate::eq::{EqBandUpdate, Equalizer};
+use crate::error::Result;
+use crate::routing::{ChannelApps, ClassicRedirection, StreamRedirection};
+use crate::sonar::{Mode, StreamerSlider};
+use crate::volume::{VolumeSettings, VolumeState};
+use serde_json::Value;
+#[cfg(feature = "async")]
+use std::future::Future;
+
+#[cfg(any(feature = "blocking", feature = "ureq"))]
+use crate::blocking::BlockingSonar;
+#[cfg(feature = "async")]
+use crate::sonar::Sonar;
+
+/// Operations of the async [`Sonar`] client.
+///
+/// Futures are `Send`, so generic code can spawn them on a multi-threaded runtime.
+#[cfg(feature = "async")]
+pub trait SonarClient: Send + Sync {
+    /// Get the current mode.
+    fn get_mode(&self) -> impl Future<Output = Result<Mode>> + Send;
+
+    /// Switch between classic and streamer mode. Returns the new mode.
+    fn set_mode(&self, mode: Mode) -> impl Future<Output = Result<Mode>> + Send;
+
+    /// Check if streamer mode is currently enabled.
+    #[deprecated(note = "use `get_mode` instead")]
+    fn is_streamer_mode(&self) -> impl Future<Output = Result<bool>> + Send {
+        async { Ok(self.get_mode().await?.is_streamer()) }
+    }
+
+    /// Set streamer mode on or off. Returns the new mode.
+    #[deprecated(note = "use `set_mode` instead")]
+    fn set_streamer_mode(&self, streamer_mode: bool) -> impl Future<Output = Result<bool>> + Send {
+        async move { Ok(self.set_mode(Mode::from_streamer_mode(streamer_mode)).await?.is_streamer()) }
+    }
+
+    /// Get volume data for all channels.
+    fn get_volume_data(&self) -> impl Future<Output = Result<Value>> + Send;
+
+    /// Get typed volume settings for all channels.
+    fn get_volumes(&self) -> impl Future<Output = Result<VolumeSettings>> + Send;
+
+    /// Set the volume for a specific channel.
+    fn set_volume(&self, channel: &str, volume: f64, streamer_slider: Option<StreamerSlider>) -> impl Future<Output = Result<Value>> + Send;
+
+    /// Change the volume of a channel relative to its current value.
+    fn adjust_volume(&self, channel: &str, delta: f64, streamer_slider: Option<StreamerSlider>) -> impl Future<Output = Result<f64>> + Send;
+
+    /// Invert the mute state of a channel.
+    fn toggle_mute(&self, channel: &str, streamer_slider: Option<StreamerSlider>) -> impl Future<Output = Result<bool>> + Send;
+
+    /// Get the volume and mute state of a single channel.
+    fn get_channel_state(&self, channel: &str, streamer_slider: Option<StreamerSlider>) -> impl Future<Output = Result<VolumeState>> + Send;
+
+    /// Mute or unmute a specific channel.
+    fn mute_channel(&self, channel: &str, muted: bool, streamer_slider: Option<StreamerSlider>) -> impl Future<Output = Result<Value>> + Send;
+
+    /// Get chat mix data.
+    fn get_chat_mix_data(&self) -> impl Future<Output = Result<Value>> + Send;
+
+    /// Set the chat mix volume.
+    fn set_chat_mix(&self, mix_volume: f64) -> impl Future<Output = Result<Value>> + Send;
+
+    /// Check whether a ChatMix-capable headset is connected and enabled.
+    fn chat_mix_supported(&self) -> impl Future<Output = Result<bool>> + Send;
+
+    /// List the audio devices known to Sonar.
+    fn list_audio_devices(&self) -> impl Future<Output = Result<Vec<AudioDevice>>> + Send;
+
+    /// Get the classic-mode device routing of every channel.
+    fn get_redirections(&self) -> impl Future<Output = Result<Vec<ClassicRedirection>>> + Send;
+
+    /// Get the audio device a channel is routed to.
+    fn get_channel_device(&self, channel: &str) -> impl Future<Output = Result<Option<AudioDevice>>> + Send;
+
+    /// Route a channel to a different audio device in classic mode.
+    fn set_redirection(&self, channel: &str, device_id: &str) -> impl Future<Output = Result<Value>> + Send;
+
+    /// Switch the microphone feeding the `chatCapture` channel.
+    fn set_capture_device(&self, device_id: &str) -> impl Future<Output = Result<Value>> + Send;
+
+    /// Get the output device of each streamer-mode mix.
+    fn get_stream_redirections(&self) -> impl Future<Output = Result<Vec<StreamRedirection>>> + Send;
+
+    /// Route a streamer-mode mix to a different audio device.
+    fn set_stream_redirection(&self, slider: StreamerSlider, device_id: &str) -> impl Future<Output = Result<Value>> + Send;
+
+    /// Mute or unmute a channel in one streamer-mode mix only.
+    fn mute_redirection(&self, channel: &str, slider: StreamerSlider, muted: bool) -> impl Future<Output = Result<Value>> + Send;
+
+    /// Get the applications Sonar has assigned to each channel.
+    fn get_routed_apps(&self) -> impl Future<Output = Result<Vec<ChannelApps>>> + Send;
+
+    /// Move an application's audio to another channel.
+    fn route_app(&self, process_id: u32, channel: &str) -> impl Future<Output = Result<Value>> + Send;
+
+    /// List the audio configs (presets) available for a channel.
+    fn list_configs(&self, channel: &str) -> impl Future<Output = Result<Vec<SonarConfig>>> + Send;
+
+    /// Get the currently selected config of every channel.
+    fn get_selected_configs(&self) -> impl Future<Output = Result<Vec<SonarConfig>>> + Send;
+
+    /// Select (activate) a config for its channel.
+    fn select_config(&self, config_id: &str) -> impl Future<Output = Result<Value>> + Send;
+
+    /// Create a new config for a channel.
+    fn create_config(&self, channel: &str, name: &str) -> impl Future<Output = Result<SonarConfig>> + Send;
+
+    /// Rename a config.
+    fn rename_config(&self, config_id: &str, name: &str) -> impl Future<Output = Result<Value>> + Send;
+
+    /// Delete a config.
+    fn delete_config(&self, config_id: &str) -> impl Future<Output = Result<()>> + Send;
+
+    /// Get the parametric EQ of the config currently selected for a channel.
+    fn get_eq(&self, channel: &str) -> impl Future<Output = Result<Equalizer>> + Send;
+
+    /// Change one band of the parametric EQ of the config selected for a channel.
+    fn set_eq_band(&self, channel: &str, band: usize, update: EqBandUpdate) -> impl Future<Output = Result<Equalizer>> + Send;
+}
+
+/// Operations of the [`BlockingSonar`](crate::BlockingSonar) client.
+#[cfg(any(feature = "blocking", feature = "ureq"))]
+pub trait BlockingSonarClient {
+    /// Get the current mode.
+    fn get_mode(&self) -> Result<Mode>;
+
+    /// Switch between classic and streamer mode. Returns the new mode.
+    fn set_mode(&self, mode: Mode) -> Result<Mode>;
+
+    /// Check if streamer mode is currently enabled.
+    #[deprecated(note = "use `get_mode` instead")]
+    fn is_streamer_mode(&self) -> Result<bool> {
+        Ok(self.get_mode()?.is_streamer())
+    }
+
+    /// Set streamer mode on or off. Returns the new mode.
+    #[deprecated(note = "use `set_mode` instead")]
+    fn set_streamer_mode(&self, streamer_mode: bool) -> Result<bool> {
+        Ok(self.set_mode(Mode::from_streamer_mode(streamer_mode))?.is_streamer())
+    }
+
+    /// Get volume data for all channels.
+    fn get_volume_data(&self) -> Result<Value>;
+
+    /// Get typed volume settings for all channels.
+    fn get_volumes(&self) -> Result<VolumeSettings>;
+
+    /// Set the volume for a specific channel.
+    fn set_volume(&self, channel: &str, volume: f64, streamer_slider: Option<StreamerSlider>) -> Result<Value>;
+
+    /// Change the volume of a channel relative to its current value.
+    fn adjust_volume(&self, channel: &str, delta: f64, streamer_slider: Option<StreamerSlider>) -> Result<f64>;
+
+    /// Invert the mute state of a channel.
+    fn toggle_mute(&self, channel: &str, streamer_slider: Option<StreamerSlider>) -> Result<bool>;
+
+    /// Get the volume and mute state of a single channel.
+    fn get_channel_state(&self, channel: &str, streamer_slider: Option<StreamerSlider>) -> Result<VolumeState>;
+
+    /// Mute or unmute a specific channel.
+    fn mute_channel(&self, channel: &str, muted: bool, streamer_slider: Option<StreamerSlider>) -> Result<Value>;
+
+    /// Get chat mix data.
+    fn get_chat_mix_data(&self) -> Result<Value>;
+
+    /// Set the chat mix volume.
+    fn set_chat_mix(&self, mix_volume: f64) -> Result<Value>;
+
+    /// Check whether a ChatMix-capable headset is connected and enabled.
+    fn chat_mix_supported(&self) -> Result<bool>;
+
+    /// List the audio devices known to Sonar.
+    fn list_audio_devices(&self) -> Result<Vec<AudioDevice>>;
+
+    /// Get the classic-mode device routing of every channel.
+    fn get_redirections(&self) -> Result<Vec<ClassicRedirection>>;
+
+    /// Get the audio device a channel is routed to.
+    fn get_channel_device(&self, channel: &str) -> Result<Option<AudioDevice>>;
+
+    /// Route a channel to a different audio device in classic mode.
+    fn set_redirection(&self, channel: &str, device_id: &str) -> Result<Value>;
+
+    /// Switch the microphone feeding the `chatCapture` channel.
+    fn set_capture_device(&self, device_id: &str) -> Result<Value>;
+
+    /// Get the output device of each streamer-mode mix.
+    fn get_stream_redirections(&self) -> Result<Vec<StreamRedirection>>;
+
+    /// Route a streamer-mode mix to a different audio device.
+    fn set_stream_redirection(&self, slider: StreamerSlider, device_id: &str) -> Result<Value>;
+
+    /// Mute or unmute a channel in one streamer-mode mix only.
+    fn mute_redirection(&self, channel: &str, slider: StreamerSlider, muted: bool) -> Result<Value>;
+
+    /// Get the applications Sonar has assigned to each channel.
+    fn get_routed_apps(&self) -> Result<Vec<ChannelApps>>;
+
+    /// Move an application's audio to another channel.
+    fn route_app(&self, process_id: u32, channel: &str) -> Result<Value>;
+
+    /// List the audio configs (presets) available for a channel.
+    fn list_configs(&self, channel: &str) -> Result<Vec<SonarConfig>>;
+
+    /// Get the currently selected config of every channel.
+    fn get_selected_configs(&self) -> Result<Vec<SonarConfig>>;
+
+    /// Select (activate) a config for its channel.
+    fn select_config(&self, config_id: &str) -> Result<Value>;
+
+    /// Create a new config for a channel.
+    fn create_config(&self, channel: &str, name: &str) -> Result<SonarConfig>;
+
+    /// Rename a config.
+    fn rename_config(&self, config_id: &str, name: &str) -> Result<Value>;
+
+    /// Delete a config.
+    fn delete_config(&self, config_id: &str) -> Result<()>;
+
+    /// Get the parametric EQ of the config currently selected for a channel.
+    fn get_eq(&self, channel: &str) -> Result<Equalizer>;
+
+    /// Change one band of the parametric EQ of the config selected for a channel.
+    fn set_eq_band(&self, channel: &str, band: usize, update: EqBandUpdate) -> Result<Equalizer>;
+}
+
+#[cfg(feature = "async")]
+impl SonarClient for Sonar {
+    fn get_mode(&self) -> impl Future<Output = Result<Mode>> + Send {
+        Sonar::get_mode(self)
+    }
+
+    fn set_mode(&self, mode: Mode) -> impl Future<Output = Result<Mode>> + Send {
+        Sonar::set_mode(self, mode)
+    }
+
+    fn get_volume_data(&self) -> impl Future<Output = Result<Value>> + Send {
+        Sonar::get_volume_data(self)
+    }
+
+    fn get_volumes(&self) -> impl Future<Output = Result<VolumeSettings>> + Send {
+        Sonar::get_volumes(self)
+    }
+
+    fn set_volume(&self, channel: &str, volume: f64, streamer_slider: Option<StreamerSlider>) -> impl Future<Output = Result<Value>> + Send {
+        Sonar::set_volume(self, channel, volume, streamer_slider)
+    }
+
+    fn adjust_volume(&self, channel: &str, delta: f64, streamer_slider: Option<StreamerSlider>) -> impl Future<Output = Result<f64>> + Send {
+        Sonar::adjust_volume(self, channel, delta, streamer_slider)
+    }
+
+    fn toggle_mute(&self, channel: &str, streamer_slider: Option<StreamerSlider>) -> impl Future<Output = Result<bool>> + Send {
+        Sonar::toggle_mute(self, channel, streamer_slider)
+    }
+
+    fn get_channel_state(&self, channel: &str, streamer_slider: Option<StreamerSlider>) -> impl Future<Output = Result<VolumeState>> + Send {
+        Sonar::get_channel_state(self, channel, streamer_slider)
+    }
+
+    fn mute_channel(&self, channel: &str, muted: bool, streamer_slider: Option<StreamerSlider>) -> impl Future<Output = Result<Value>> + Send {
+        Sonar::mute_channel(self, channel, muted, streamer_slider)
+    }
+
+    fn get_chat_mix_data(&self) -> impl Future<Output = Result<Value>> + Send {
+        Sonar::get_chat_mix_data(self)
+    }
+
+    fn set_chat_mix(&self, mix_volume: f64) -> impl Future<Output = Result<Value>> + Send {
+        Sonar::set_chat_mix(self, mix_volume)
+    }
+
+    fn chat_mix_supported(&self) -> impl Future<Output = Result<bool>> + Send {
+        Sonar::chat_mix_supported(self)
+    }
+
+    fn list_audio_devices(&self) -> impl Future<Output = Result<Vec<AudioDevice>>> + Send {
+        Sonar::list_audio_devices(self)
+    }
+
+    fn get_redirections(&self) -> impl Future<Output = Result<Vec<ClassicRedirection>>> + Send {
+        Sonar::get_redirections(self)
+    }
+
+    fn get_channel_device(&self, channel: &str) -> impl Future<Output = Result<Option<AudioDevice>>> + Send {
+        Sonar::get_channel_device(self, channel)
+    }
+
+    fn set_redirection(&self, channel: &str, device_id: &str) -> impl Future<Output = Result<Value>> + Send {
+        Sonar::set_redirection(self, channel, device_id)
+    }
+
+    fn set_capture_device(&self, device_id: &str) -> impl Future<Output = Result<Value>> + Send {
+        Sonar::set_capture_device(self, device_id)
+    }
+
+    fn get_stream_redirections(&self) -> impl Future<Output = Result<Vec<StreamRedirection>>> + Send {
+        Sonar::get_stream_redirections(self)
+    }
+
+    fn set_stream_redirection(&self, slider: StreamerSlider, device_id: &str) -> impl Future<Output = Result<Value>> + Send {
+        Sonar::set_stream_redirection(self, slider, device_id)
+    }
+
+    fn mute_redirection(&self, channel: &str, slider: StreamerSlider, muted: bool) -> impl Future<Output = Result<Value>> + Send {
+        Sonar::mute_redirection(self, channel, slider, muted)
+    }
+
+    fn get_routed_apps(&self) -> impl Future<Output = Result<Vec<ChannelApps>>> + Send {
+        Sonar::get_routed_apps(self)
+    }
+
+    fn route_app(&self, process_id: u32, channel: &str) -> impl Future<Output = Result<Value>> + Send {
+        Sonar::route_app(self, process_id, channel)
+    }
+
+    fn list_configs(&self, channel: &str) -> impl Future<Output = Result<Vec<SonarConfig>>> + Send {
+        Sonar::list_configs(self, channel)
+    }
+
+    fn get_selected_configs(&self) -> impl Future<Output = Result<Vec<SonarConfig>>> + Send {
+        Sonar::get_selected_configs(self)
+    }
+
+    fn select_config(&self, config_id: &str) -> impl Future<Output = Result<Value>> + Send {
+        Sonar::select_config(self, config_id)
+    }
+
+    fn create_config(&self, channel: &str, name: &str) -> impl Future<Output = Result<SonarConfig>> + Send {
+        Sonar::create_config(self, channel, name)
+    }
+
+    fn rename_config(&self, config_id: &str, name: &str) -> impl Future<Output = Result<Value>> + Send {
+        Sonar::rename_config(self, config_id, name)
+    }
+
+    fn delete_config(&self, config_id: &str) -> impl Future<Output = Result<()>> + Send {
+        Sonar::delete_config(self, config_id)
+    }
+
+    fn get_eq(&self, channel: &str) -> impl Future<Output = Result<Equalizer>> + Send {
+        Sonar::get_eq(self, channel)
+    }
+
+    fn set_eq_band(&self, channel: &str, band: usize, update: EqBandUpdate) -> impl Future<Output = Result<Equalizer>> + Send {
+        Sonar::set_eq_band(self, channel, band, update)
+    }
+}
+
+#[cfg(any(feature = "blocking", feature = "ureq"))]
+impl BlockingSonarClient for BlockingSonar {
+    fn get_mode(&self) -> Result<Mode> {
+        BlockingSonar::get_mode(self)
+    }
+
+    fn set_mode(&self, mode: Mode) -> Result<Mode> {
+        BlockingSonar::set_mode(self, mode)
+    }
+
+    fn get_volume_data(&self) -> Result<Value> {
+        BlockingSonar::get_volume_data(self)
+    }
+
+    fn get_volumes(&self) -> Result<VolumeSettings> {
+        BlockingSonar::get_volumes(self)
+    }
+
+    fn set_volume(&self, channel: &str, volume: f64, streamer_slider: Option<StreamerSlider>) -> Result<Value> {
+        BlockingSonar::set_volume(self, channel, volume, streamer_slider)
+    }
+
+    fn adjust_volume(&self, channel: &str, delta: f64, streamer_slider: Option<StreamerSlider>) -> Result<f64> {
+        BlockingSonar::adjust_volume(self, channel, delta, streamer_slider)
+    }
+
+    fn toggle_mute(&self, channel: &str, streamer_slider: Option<StreamerSlider>) -> Result<bool> {
+        BlockingSonar::toggle_mute(self, channel, streamer_slider)
+    }
+
+    fn get_channel_state(&self, channel: &str, streamer_slider: Option<StreamerSlider>) -> Result<VolumeState> {
+        BlockingSonar::get_channel_state(self, channel, streamer_slider)
+    }
+
+    fn mute_channel(&self, channel: &str, muted: bool, streamer_slider: Option<StreamerSlider>) -> Result<Value> {
+        BlockingSonar::mute_channel(self, channel, muted, streamer_slider)
+    }
+
+    fn get_chat_mix_data(&self) -> Result<Value> {
+        BlockingSonar::get_chat_mix_data(self)
+    }
+
+    fn set_chat_mix(&self, mix_volume: f64) -> Result<Value> {
+        BlockingSonar::set_chat_mix(self, mix_volume)
+    }
+
+    fn chat_mix_supported(&self) -> Result<bool> {
+        BlockingSonar::chat_mix_supported(self)
+    }
+
+    fn list_audio_devices(&self) -> Result<Vec<AudioDevice>> {
+        BlockingSonar::list_audio_devices(self)
+    }
+
+    fn get_redirections(&self) -> Result<Vec<ClassicRedirection>> {
+        BlockingSonar::get_redirections(self)
+    }
+
+    fn get_channel_device(&self, channel: &str) -> Result<Option<AudioDevice>> {
+        BlockingSonar::get_channel_device(self, channel)
+    }
+
+    fn set_redirection(&self, channel: &str, device_id: &str) -> Result<Value> {
+        BlockingSonar::set_redirection(self, channel, device_id)
+    }
+
+    fn set_capture_device(&self, device_id: &str) -> Result<Value> {
+        BlockingSonar::set_capture_device(self, device_id)
+    }
+
+    fn get_stream_redirections(&self) -> Result<Vec<StreamRedirection>> {
+        BlockingSonar::get_stream_redirections(self)
+    }
+
+    fn set_stream_redirection(&self, slider: StreamerSlider, device_id: &str) -> Result<Value> {
+        BlockingSonar::set_stream_redirection(self, slider, device_id)
+    }
+
+    fn mute_redirection(&self, channel: &str, slider: StreamerSlider, muted: bool) -> Result<Value> {
+        BlockingSonar::mute_redirection(self, channel, slider, muted)
+    }
+
+    fn get_routed_apps(&self) -> Result<Vec<ChannelApps>> {
+        BlockingSonar::get_routed_apps(self)
+    }
+
+    fn route_app(&self, process_id: u32, channel: &str) -> Result<Value> {
+        BlockingSonar::route_app(self, process_id, channel)
+    }
+
+    fn list_configs(&self, channel: &str) -> Result<Vec<SonarConfig>> {
+        BlockingSonar::list_configs(self, channel)
+    }
+
+    fn get_selected_configs(&self) -> Result<Vec<SonarConfig>> {
+        BlockingSonar::get_selected_configs(self)
+    }
+
+    fn select_config(&self, config_id: &str) -> Result<Value> {
+        BlockingSonar::select_config(self, config_id)
+    }
+
+    fn create_config(&self, channel: &str, name: &str) -> Result<SonarConfig> {
+        BlockingSonar::create_config(self, channel, name)
+    }
+
+    fn rename_config(&self, config_id: &str, name: &str) -> Result<Value> {
+        BlockingSonar::rename_config(self, config_id, name)
+    }
+
+    fn delete_config(&self, config_id: &str) -> Result<()> {
+        BlockingSonar::delete_config(self, config_id)
+    }
+
+    fn get_eq(&self, channel: &str) -> Result<Equalizer> {
+        BlockingSonar::get_eq(self, channel)
+    }
+
+    fn set_eq_band(&self, channel: &str, band: usize, update: EqBandUpdate) -> Result<Equalizer> {
+        BlockingSonar::set_eq_band(self, channel, band, update)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_sonar_implements_client() {
+        fn assert_client<T: SonarClient>() {}
+        assert_client::<Sonar>();
+    }
+
+    #[cfg(any(feature = "blocking", feature = "ureq"))]
+    #[test]
+    fn test_blocking_sonar_implements_client() {
+        fn assert_client<T: BlockingSonarClient>() {}
+        assert_client::<BlockingSonar>();
+    }
+}
@@ -0,0 +1,363 @@
+//! State and request logic shared by the async and blocking clients.
+//!
+//! [`Sonar`](crate::Sonar) and [`BlockingSonar`](crate::BlockingSonar) only
+//! differ in how they send requests and wait. Everything else, such as
+//! addressing, mode tracking, input validation, the path of every setting,
+//! the state cache, cassettes and request capture, lives in [`ClientCore`],
+//! so the two clients cannot drift apart.
+
+use crate::builder::ClientOptions;
+use crate::capture::{CapturedRequest, RequestLog};
+use crate::cassette::{Interaction, Recorder, REPLAY_ADDRESS};
+use crate::endpoint::{mute_path, volume_path};
+use crate::error::{Result, SonarError};
+use crate::rate_limit::RateLimiter;
+use crate::retry::RetryPolicy;
+use crate::sonar::{Mode, ServerAddresses, SharedAddresses, StreamerSlider};
+use crate::state_cache::{Setting, StateCache};
+use crate::volume::{VolumeCurve, VolumeSettings, VolumeState};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+use std::time::{Duration, Instant};
+
+/// The channels muted by `solo`: every playback channel but master.
+const SOLO_CHANNELS: [&str; 4] = ["game", "chatRender", "media", "aux"];
+
+/// Mute states saved by `solo`, with the mode and slider they belong to.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SoloState {
+    pub mode: Mode,
+    pub slider: Option<StreamerSlider>,
+    pub muted: Vec<(&'static str, bool)>,
+}
+
+/// The transport-independent part of a client. Cloning is cheap, and clones
+/// share the addresses, mode, cache and logs.
+#[derive(Debug, Clone)]
+pub(crate) struct ClientCore {
+    pub addresses: SharedAddresses,
+    /// `None` when the web server address was given directly.
+    pub core_props_path: Option<PathBuf>,
+    pub auto_reconnect: bool,
+    /// The mode the volume endpoints are addressed in, switched by `set_mode`.
+    streamer_mode: Arc<AtomicBool>,
+    pub retry_policy: RetryPolicy,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    state_cache: Option<Arc<StateCache>>,
+    recorder: Option<Arc<Recorder>>,
+    request_log: Option<Arc<RequestLog>>,
+    pub volume_curve: VolumeCurve,
+    /// Discovery settings, kept for connecting later.
+    pub options: Arc<ClientOptions>,
+    /// States saved by `solo`, restored by `unsolo` from the last one.
+    solo_stack: Arc<Mutex<Vec<SoloState>>>,
+}
+
+impl ClientCore {
+    /// Set up the state of a client from its (config file completed) options.
+    pub fn new(options: ClientOptions) -> Result<Self> {
+        let recorder = options.cassette.clone().map(Recorder::open).transpose()?.map(Arc::new);
+        let replaying = recorder.as_ref().is_some_and(|recorder| recorder.is_replay());
+
+        let (addresses, core_props_path) = match options.web_server_address.clone() {
+            Some(web_server_address) => (ServerAddresses { base_url: None, web_server_address }, None),
+            None if replaying => {
+                (ServerAddresses { base_url: None, web_server_address: REPLAY_ADDRESS.to_string() }, None)
+            }
+            // Resolved when connecting.
+            None => (ServerAddresses { base_url: None, web_server_address: String::new() }, Some(options.core_props_path())),
+        };
+
+        Ok(Self {
+            addresses: Arc::new(RwLock::new(addresses)),
+            core_props_path,
+            auto_reconnect: options.auto_reconnect.unwrap_or(true),
+            streamer_mode: Arc::new(AtomicBool::new(false)),
+            retry_policy: options.retry_policy.unwrap_or_else(RetryPolicy::none),
+            rate_limiter: options.rate_limit.and_then(RateLimiter::new).map(Arc::new),
+            state_cache: options.state_cache.map(|ttl| Arc::new(StateCache::new(ttl))),
+            recorder,
+            request_log: options.capture_requests.and_then(RequestLog::new).map(Arc::new),
+            volume_curve: options.volume_curve.clone().unwrap_or_default(),
+            options: Arc::new(options),
+            solo_stack: Arc::default(),
+        })
+    }
+
+    /// Switch the endpoints used by the client to classic or streamer mode.
+    pub fn use_mode(&self, streamer_mode: bool) {
+        self.streamer_mode.store(streamer_mode, Ordering::Relaxed);
+    }
+
+    pub fn streamer_mode(&self) -> bool {
+        self.streamer_mode.load(Ordering::Relaxed)
+    }
+
+    pub fn mode(&self) -> Mode {
+        Mode::from_streamer_mode(self.streamer_mode())
+    }
+
+    pub fn base_url(&self) -> Option<String> {
+        self.addresses.read().unwrap_or_else(PoisonError::into_inner).base_url.clone()
+    }
+
+    pub fn web_server_address(&self) -> String {
+        self.addresses
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .web_server_address
+            .clone()
+    }
+
+    /// The URL of `path` on the Sonar web server, built with a single
+    /// allocation.
+    pub fn url(&self, path: &str) -> String {
+        let addresses = self.addresses.read().unwrap_or_else(PoisonError::into_inner);
+        let mut url = String::with_capacity(addresses.web_server_address.len() + path.len());
+        url.push_str(&addresses.web_server_address);
+        url.push_str(path);
+        url
+    }
+
+    pub fn set_addresses(&self, addresses: ServerAddresses) {
+        *self.addresses.write().unwrap_or_else(PoisonError::into_inner) = addresses;
+    }
+
+    /// Replace the server addresses, returning whether they changed.
+    pub fn update_addresses(&self, addresses: ServerAddresses) -> bool {
+        let mut current = self.addresses.write().unwrap_or_else(PoisonError::into_inner);
+        let changed = *current != addresses;
+        #[cfg(feature = "tracing")]
+        if changed {
+            tracing::info!(web_server_address = %addresses.web_server_address, "Sonar web server address changed");
+        }
+        *current = addresses;
+        changed
+    }
+
+    /// The API name of `channel`, which is either a channel name or an alias.
+    pub fn resolve_channel(&self, channel: &str) -> Result<&'static str> {
+        self.options.resolve_channel(channel)
+    }
+
+    /// The path of a volume or mute change in `mode`, or `None` if the state
+    /// cache knows it to be in effect already. Volumes are capped first.
+    pub fn setting_path(&self, mode: Mode, channel: &str, slider: Option<StreamerSlider>, setting: Setting) -> Result<(Setting, Option<String>)> {
+        self.options.check_slider(mode, slider)?;
+        let setting = match setting {
+            Setting::Volume(volume) => Setting::Volume(self.options.cap_volume(channel, volume)),
+            setting => setting,
+        };
+        if self.state_cache.as_deref().is_some_and(|cache| cache.contains(mode, channel, slider, setting)) {
+            return Ok((setting, None));
+        }
+        let path = match setting {
+            Setting::Volume(volume) => volume_path(mode, channel, slider, volume)?,
+            Setting::Muted(muted) => mute_path(mode, channel, slider, muted)?,
+        };
+        Ok((setting, Some(path)))
+    }
+
+    /// Update the state cache after writing `setting`; `written` is false for
+    /// failed writes, whose effect is unknown.
+    pub fn record_write(&self, mode: Mode, channel: &str, slider: Option<StreamerSlider>, setting: Setting, written: bool) {
+        if let Some(cache) = &self.state_cache {
+            cache.record(mode, channel, slider, setting, written);
+        }
+    }
+
+    /// Parse volume data, remembering it in the state cache.
+    pub fn volumes(&self, data: Value) -> Result<VolumeSettings> {
+        let volumes = VolumeSettings::from_value(data)?;
+        if let Some(cache) = &self.state_cache {
+            cache.store(&volumes);
+        }
+        Ok(volumes)
+    }
+
+    /// The state of `channel` in `volumes`, read in the current mode.
+    pub fn channel_state(&self, volumes: &VolumeSettings, channel: &str, slider: Option<StreamerSlider>) -> Result<VolumeState> {
+        self.options.check_slider(self.mode(), slider)?;
+        volumes
+            .channel(channel)
+            .map(|volumes| volumes.state(self.streamer_mode(), slider))
+            .ok_or_else(|| SonarError::ChannelNotFound(channel.to_string()))
+    }
+
+    pub fn clear_cache(&self) {
+        if let Some(cache) = &self.state_cache {
+            cache.clear();
+        }
+    }
+
+    /// How long to wait before the next request, if rate limited.
+    pub fn rate_limit_wait(&self) -> Option<Duration> {
+        self.rate_limiter.as_deref().map(RateLimiter::acquire).filter(|wait| !wait.is_zero())
+    }
+
+    /// The recorded interaction answering a request, if replaying a cassette.
+    pub fn replay(&self, method: &str, path: &str, body: Option<&Value>) -> Option<Result<Interaction>> {
+        let recorder = self.recorder.as_deref().filter(|recorder| recorder.is_replay())?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!("replaying response from cassette");
+        Some(recorder.replay(method, path, body))
+    }
+
+    /// Whether responses need to be read for [`record_response`](Self::record_response).
+    pub fn records_responses(&self) -> bool {
+        self.recorder.is_some() || self.request_log.is_some()
+    }
+
+    /// Write a response to the cassette and the request log, if enabled.
+    #[allow(clippy::too_many_arguments)]
+    pub fn record_response(&self, method: &str, path: &str, url: &str, body: Option<&Value>, started: Instant, status: u16, response_body: &[u8]) -> Result<()> {
+        if !self.records_responses() {
+            return Ok(());
+        }
+        let response_body = String::from_utf8_lossy(response_body);
+        if let Some(log) = &self.request_log {
+            log.push(CapturedRequest::response(method, url, body, status, &response_body, started.elapsed()));
+        }
+        if let Some(recorder) = &self.recorder {
+            recorder.record(Interaction {
+                method: method.to_string(),
+                path: path.to_string(),
+                request_body: body.cloned(),
+                status,
+                response_body: response_body.into_owned(),
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Write a request that got no response to the request log, if enabled.
+    pub fn record_failure(&self, method: &str, url: &str, body: Option<&Value>, started: Instant, error: &SonarError) {
+        if let Some(log) = &self.request_log {
+            log.push(CapturedRequest::failure(method, url, body, error, started.elapsed()));
+        }
+    }
+
+    pub fn last_requests(&self) -> Vec<CapturedRequest> {
+        self.request_log.as_deref().map(RequestLog::entries).unwrap_or_default()
+    }
+
+    /// Save the mute states in `volumes` for soloing `channel`, returning
+    /// the mute state to write for each channel.
+    pub fn push_solo(&self, volumes: &VolumeSettings, channel: &'static str, slider: Option<StreamerSlider>) -> Result<Vec<(&'static str, bool)>> {
+        if !SOLO_CHANNELS.contains(&channel) {
+            return Err(SonarError::NotSoloable(channel.to_string()));
+        }
+        let muted = SOLO_CHANNELS
+            .into_iter()
+            .map(|other| Ok((other, self.channel_state(volumes, other, slider)?.muted)))
+            .collect::<Result<_>>()?;
+        let state = SoloState { mode: self.mode(), slider, muted };
+        self.solo_stack.lock().unwrap_or_else(PoisonError::into_inner).push(state);
+        Ok(SOLO_CHANNELS.into_iter().map(|other| (other, other != channel)).collect())
+    }
+
+    /// The states saved by the last `solo` not undone yet.
+    pub fn pop_solo(&self) -> Option<SoloState> {
+        self.solo_stack.lock().unwrap_or_else(PoisonError::into_inner).pop()
+    }
+}
+
+/// Fail with [`SonarError::InvalidVolume`] unless `volume` is within 0.0..=1.0.
+pub(crate) fn check_volume(volume: f64) -> Result<()> {
+    if !(0.0..=1.0).contains(&volume) {
+        return Err(SonarError::InvalidVolume(volume));
+    }
+    Ok(())
+}
+
+/// Fail with [`SonarError::InvalidMixVolume`] unless `mix_volume` is within -1.0..=1.0.
+pub(crate) fn check_mix_volume(mix_volume: f64) -> Result<()> {
+    if !(-1.0..=1.0).contains(&mix_volume) {
+        return Err(SonarError::InvalidMixVolume(mix_volume));
+    }
+    Ok(())
+}
+
+/// The path of a raw request, with the leading `/` added if missing.
+pub(crate) fn raw_path(path: &str) -> String {
+    if path.starts_with('/') { path.to_string() } else { format!("/{}", path) }
+}
+
+/// Parse the body of a raw response; empty bodies are `null`.
+pub(crate) fn raw_body(body: &[u8]) -> Result<Value> {
+    if body.iter().all(u8::is_ascii_whitespace) {
+        return Ok(Value::Null);
+    }
+    Ok(serde_json::from_slice(body)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_setting_path() {
+        let core = ClientCore::new(ClientOptions {
+            web_server_address: Some("http://127.0.0.1:1234".to_string()),
+            state_cache: Some(Duration::from_secs(60)),
+            ..ClientOptions::default()
+        })
+        .unwrap();
+        assert_eq!(core.url("/mode/"), "http://127.0.0.1:1234/mode/");
+
+        let (setting, path) = core.setting_path(Mode::Classic, "game", None, Setting::Muted(true)).unwrap();
+        assert_eq!(path.as_deref(), Some("/volumeSettings/classic/game/Mute/true"));
+        core.record_write(Mode::Classic, "game", None, setting, true);
+        assert_eq!(core.setting_path(Mode::Classic, "game", None, Setting::Muted(true)).unwrap().1, None);
+
+        let (_, path) = core
+            .setting_path(Mode::Streamer, "game", Some(StreamerSlider::Monitoring), Setting::Muted(true))
+            .unwrap();
+        assert_eq!(path.as_deref(), Some("/volumeSettings/streamer/monitoring/game/isMuted/true"));
+
+        let volumes = core.volumes(json!({ "masters": { "classic": { "volume": 0.5, "muted": false } }, "devices": {} })).unwrap();
+        assert_eq!(core.channel_state(&volumes, "master", None).unwrap().volume, 0.5);
+        assert!(matches!(core.channel_state(&volumes, "game", None), Err(SonarError::ChannelNotFound(_))));
+    }
+
+    #[test]
+    fn test_solo_stack() {
+        let core = ClientCore::new(ClientOptions::default()).unwrap();
+        let devices = json!({
+            "game": { "classic": { "volume": 0.5, "muted": false } },
+            "chatRender": { "classic": { "volume": 0.5, "muted": false } },
+            "media": { "classic": { "volume": 0.5, "muted": true } },
+            "aux": { "classic": { "volume": 0.5, "muted": false } },
+        });
+        let volumes = core.volumes(json!({ "masters": { "classic": { "volume": 1.0, "muted": false } }, "devices": devices })).unwrap();
+
+        let mutes = core.push_solo(&volumes, "media", None).unwrap();
+        assert_eq!(mutes, [("game", true), ("chatRender", true), ("media", false), ("aux", true)]);
+        assert!(core.push_solo(&volumes, "game", None).is_ok());
+        assert!(matches!(core.push_solo(&volumes, "master", None), Err(SonarError::NotSoloable(_))));
+
+        // The latest solo is undone first
+        assert_eq!(core.pop_solo().unwrap().muted, [("game", false), ("chatRender", false), ("media", true), ("aux", false)]);
+        assert_eq!(core.pop_solo().unwrap().mode, Mode::Classic);
+        assert!(core.pop_solo().is_none());
+    }
+
+    #[test]
+    fn test_check_volume() {
+        assert!(check_volume(1.0).is_ok());
+        assert!(matches!(check_volume(1.5), Err(SonarError::InvalidVolume(_))));
+        assert!(check_mix_volume(-1.0).is_ok());
+        assert!(matches!(check_mix_volume(-1.5), Err(SonarError::InvalidMixVolume(_))));
+    }
+
+    #[test]
+    fn test_raw_request() {
+        assert_eq!(raw_path("mode/"), "/mode/");
+        assert_eq!(raw_path("/mode/"), "/mode/");
+        assert_eq!(raw_body(b"").unwrap(), Value::Null);
+        assert_eq!(raw_body(br#"{"a": 1}"#).unwrap(), json!({ "a": 1 }));
+    }
+}
@@ -0,0 +1,216 @@
+//! Client defaults read from a `sonar.toml` file.
+//!
+//! `Sonar::new` and the `from_env` builders read the file named by the
+//! `STEELSERIES_SONAR_CONFIG` environment variable or, if that is unset,
+//! `steelseries-sonar/sonar.toml` inside the platform config directory when
+//! it exists. Options set on a builder take precedence over the file.
+//!
+//! ```toml
+//! core_props = 'C:\ProgramData\SteelSeries\GG\coreProps.json'
+//! timeout_ms = 5000
+//! connect_timeout_ms = 1000
+//! validation = "warn"
+//!
+//! [retry]
+//! max_attempts = 3
+//! initial_backoff_ms = 100
+//!
+//! [volume_caps]
+//! master = 0.8
+//!
+//! [aliases]
+//! voice = "chatRender"
+//! mic = "chatCapture"
+//! ```
+
+use crate::builder::{normalize_address, ClientOptions};
+use crate::error::{Result, SonarError};
+use crate::retry::RetryPolicy;
+use crate::sonar::Channel;
+use crate::validation::Validation;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Environment variable naming the config file to read.
+pub(crate) const CONFIG_ENV: &str = "STEELSERIES_SONAR_CONFIG";
+
+/// The contents of a `sonar.toml` file.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+    /// Path to SteelSeries Engine's coreProps.json.
+    pub core_props: Option<PathBuf>,
+    /// Sonar web server address, skipping discovery.
+    pub address: Option<String>,
+    /// Timeout of every request, in milliseconds.
+    pub timeout_ms: Option<u64>,
+    /// Timeout for connecting to the server, in milliseconds.
+    pub connect_timeout_ms: Option<u64>,
+    pub retry: Option<RetryConfig>,
+    /// `strict`, `warn` or `lenient`, see [`Validation`].
+    pub validation: Option<Validation>,
+    /// Highest volume (0.0 to 1.0) by channel name.
+    pub volume_caps: BTreeMap<String, f64>,
+    /// Channel names by alias.
+    pub aliases: BTreeMap<String, String>,
+}
+
+/// The `[retry]` table of a [`ConfigFile`], see [`RetryPolicy`].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RetryConfig {
+    /// Total number of attempts, including the first one.
+    pub max_attempts: u32,
+    /// Delay before the first retry, in milliseconds.
+    pub initial_backoff_ms: Option<u64>,
+    /// Upper bound for the delay between attempts, in milliseconds.
+    pub max_backoff_ms: Option<u64>,
+}
+
+impl RetryConfig {
+    /// The policy described by this table.
+    pub fn policy(&self) -> RetryPolicy {
+        let default = RetryPolicy::default();
+        default.max_attempts(self.max_attempts).backoff(
+            self.initial_backoff_ms.map_or(default.initial_backoff, Duration::from_millis),
+            self.max_backoff_ms.map_or(default.max_backoff, Duration::from_millis),
+        )
+    }
+}
+
+impl ConfigFile {
+    /// Read and check a config file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, or
+    /// [`SonarError::InvalidConfig`] if it is not a valid config file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        Self::from_toml(&text).map_err(|error| match error {
+            SonarError::InvalidConfig(message) => SonarError::InvalidConfig(format!("{}: {}", path.display(), message)),
+            error => error,
+        })
+    }
+
+    /// Parse and check the contents of a config file.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SonarError::InvalidConfig`] if the text is not valid TOML,
+    /// has unknown keys, or names unknown channels or out-of-range caps.
+    pub fn from_toml(text: &str) -> Result<Self> {
+        let file: Self = toml::from_str(text).map_err(|error| SonarError::InvalidConfig(error.message().to_string()))?;
+        file.volume_caps()?;
+        file.aliases()?;
+        Ok(file)
+    }
+
+    /// `steelseries-sonar/sonar.toml` inside the platform config directory.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("steelseries-sonar").join("sonar.toml"))
+    }
+
+    /// Fill in the options not set yet.
+    pub(crate) fn apply(self, options: &mut ClientOptions) -> Result<()> {
+        for (channel, cap) in self.volume_caps()? {
+            options.volume_caps.entry(channel).or_insert(cap);
+        }
+        for (alias, channel) in self.aliases()? {
+            options.channel_aliases.entry(alias).or_insert(channel);
+        }
+        if options.core_props_path.is_none() {
+            options.core_props_path = self.core_props;
+        }
+        if options.web_server_address.is_none() {
+            options.web_server_address = self.address.as_deref().map(normalize_address);
+        }
+        options.timeout = options.timeout.or(self.timeout_ms.map(Duration::from_millis));
+        options.connect_timeout = options.connect_timeout.or(self.connect_timeout_ms.map(Duration::from_millis));
+        if options.retry_policy.is_none() {
+            options.retry_policy = self.retry.as_ref().map(RetryConfig::policy);
+        }
+        options.validation = options.validation.or(self.validation);
+        Ok(())
+    }
+
+    fn volume_caps(&self) -> Result<Vec<(Channel, f64)>> {
+        self.volume_caps
+            .iter()
+            .map(|(channel, &cap)| {
+                if !(0.0..=1.0).contains(&cap) {
+                    return Err(SonarError::InvalidConfig(format!("volume cap of {} must be between 0.0 and 1.0", channel)));
+                }
+                Ok((config_channel(channel)?, cap))
+            })
+            .collect()
+    }
+
+    fn aliases(&self) -> Result<Vec<(String, Channel)>> {
+        self.aliases
+            .iter()
+            .map(|(alias, channel)| Ok((alias.clone(), config_channel(channel)?)))
+            .collect()
+    }
+}
+
+fn config_channel(channel: &str) -> Result<Channel> {
+    channel
+        .parse()
+        .map_err(|_| SonarError::InvalidConfig(format!("unknown channel '{}'", channel)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config_file() {
+        let file = ConfigFile::from_toml(
+            r#"
+            address = "http://127.0.0.1:51234/"
+            timeout_ms = 5000
+            validation = "strict"
+
+            [retry]
+            max_attempts = 5
+
+            [volume_caps]
+            master = 0.8
+
+            [aliases]
+            voice = "chatRender"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(file.retry.as_ref().map(|retry| retry.policy().max_attempts), Some(5));
+
+        let mut options = ClientOptions {
+            timeout: Some(Duration::from_secs(1)),
+            ..ClientOptions::default()
+        };
+        file.apply(&mut options).unwrap();
+        // Options set on the builder win
+        assert_eq!(options.timeout, Some(Duration::from_secs(1)));
+        assert_eq!(options.web_server_address.as_deref(), Some("http://127.0.0.1:51234"));
+        assert_eq!(options.volume_caps.get(&Channel::Master), Some(&0.8));
+        assert_eq!(options.resolve_channel("voice").unwrap(), "chatRender");
+        assert_eq!(options.cap_volume("master", 1.0), 0.8);
+        assert_eq!(options.validation, Some(Validation::Strict));
+    }
+
+    #[test]
+    fn test_invalid_config_file() {
+        for text in [
+            "timeout = 5",
+            "[volume_caps]\nmaster = 1.5",
+            "[aliases]\nvoice = \"chat\"",
+            "timeout_ms = \"fast\"",
+        ] {
+            assert!(matches!(ConfigFile::from_toml(text), Err(SonarError::InvalidConfig(_))), "{}", text);
+        }
+    }
+}
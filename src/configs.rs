@@ -0,0 +1,45 @@
+//! Sonar audio configurations (EQ/preset profiles).
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// An audio configuration ("preset") for a channel, e.g. `"Flat"` or `"FPS Footsteps"`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SonarConfig {
+    pub id: String,
+    pub name: String,
+    /// The channel the config applies to.
+    #[serde(rename = "virtualAudioDevice")]
+    pub channel: String,
+    /// Raw config settings (EQ, effects, ...). See [`Equalizer`](crate::Equalizer)
+    /// for a typed view of the EQ.
+    #[serde(default)]
+    pub settings: Value,
+}
+
+/// Request body for creating a new config.
+#[derive(Debug, Serialize)]
+pub(crate) struct NewConfig<'a> {
+    pub name: &'a str,
+    #[serde(rename = "virtualAudioDevice")]
+    pub channel: &'a str,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_configs() {
+        let configs: Vec<SonarConfig> = serde_json::from_value(json!([
+            { "id": "7f6d1f7e", "name": "Flat", "virtualAudioDevice": "game" },
+            { "id": "0c9b2a11", "name": "FPS Footsteps", "virtualAudioDevice": "game", "settings": {} }
+        ]))
+        .unwrap();
+
+        assert_eq!(configs.len(), 2);
+        assert_eq!(configs[1].name, "FPS Footsteps");
+        assert_eq!(configs[1].channel, "game");
+    }
+}
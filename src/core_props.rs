@@ -0,0 +1,86 @@
+//! File watcher for coreProps.json.
+//!
+//! SteelSeries GG rewrites coreProps.json with a new address whenever it
+//! restarts. [`Sonar::watch_core_props`](crate::Sonar::watch_core_props)
+//! watches the file and refreshes the client's server addresses as soon as it
+//! changes, instead of waiting for the next request to fail.
+
+use crate::error::Result;
+use crate::sonar::{ServerAddresses, SharedAddresses, Sonar};
+use crate::watch::{SonarEvent, SonarWatcher};
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use reqwest::Client;
+use std::path::{Path, PathBuf};
+use std::sync::PoisonError;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Time to let a burst of file events settle before reading the file.
+const SETTLE_DELAY: Duration = Duration::from_millis(250);
+/// How often to try resolving the new address while Sonar starts up.
+const RESOLVE_ATTEMPTS: u32 = 20;
+const RESOLVE_INTERVAL: Duration = Duration::from_millis(500);
+
+pub(crate) fn spawn(client: Client, core_props_path: PathBuf, addresses: SharedAddresses) -> Result<SonarWatcher> {
+    let (changes_sender, mut changes) = mpsc::unbounded_channel();
+    let file_name = core_props_path.file_name().map(ToOwned::to_owned);
+    let mut file_watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        let touches_file = event.is_ok_and(|event| {
+            event
+                .paths
+                .iter()
+                .any(|path| path.file_name() == file_name.as_deref())
+        });
+        if touches_file {
+            let _ = changes_sender.send(());
+        }
+    })?;
+
+    // Watch the directory, since the file itself may be replaced rather than modified.
+    let directory = core_props_path.parent().unwrap_or(Path::new("."));
+    file_watcher.watch(directory, RecursiveMode::NonRecursive)?;
+
+    let (sender, receiver) = SonarWatcher::channel();
+    let task = tokio::spawn(async move {
+        // Keep the file watcher alive for as long as the task runs.
+        let _file_watcher: RecommendedWatcher = file_watcher;
+
+        while changes.recv().await.is_some() {
+            tokio::time::sleep(SETTLE_DELAY).await;
+            while changes.try_recv().is_ok() {}
+
+            let Some(resolved) = resolve(&client, &core_props_path).await else {
+                continue;
+            };
+
+            let changed = {
+                let mut current = addresses.write().unwrap_or_else(PoisonError::into_inner);
+                let changed = *current != resolved;
+                *current = resolved.clone();
+                changed
+            };
+
+            if changed {
+                let event = SonarEvent::Reconnected {
+                    web_server_address: resolved.web_server_address,
+                };
+                if sender.send(event).await.is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    Ok(SonarWatcher::from_task(receiver, task))
+}
+
+/// Resolve the addresses, waiting for Sonar to come up after a restart.
+async fn resolve(client: &Client, core_props_path: &Path) -> Option<ServerAddresses> {
+    for _ in 0..RESOLVE_ATTEMPTS {
+        if let Ok(addresses) = Sonar::resolve_addresses(client, core_props_path).await {
+            return Some(addresses);
+        }
+        tokio::time::sleep(RESOLVE_INTERVAL).await;
+    }
+    None
+}
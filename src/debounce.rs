@@ -0,0 +1,153 @@
+//! Coalescing of rapid volume changes, e.g. from GUI sliders or MIDI knobs.
+
+use crate::client_core::check_volume;
+use crate::error::{Result, SonarError};
+use crate::sonar::{Sonar, StreamerSlider};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+use tokio::time::Instant;
+
+type VolumeKey = (String, Option<StreamerSlider>);
+
+/// Wraps a [`Sonar`] client so that bursts of
+/// [`set_volume`](DebouncedSonar::set_volume) calls for the same channel and
+/// slider are sent as a single request with the latest volume, once no new
+/// value arrived for the quiet period.
+///
+/// Created by [`Sonar::debounced`](crate::Sonar::debounced). Requests are
+/// sent from tasks spawned onto the current tokio runtime, so pending volumes
+/// are still sent after the wrapper is dropped. Errors of those requests are
+/// returned by the next [`flush`](DebouncedSonar::flush).
+#[derive(Debug, Clone)]
+pub struct DebouncedSonar {
+    sonar: Sonar,
+    quiet_period: Duration,
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    pending: Mutex<HashMap<VolumeKey, Pending>>,
+    errors: Mutex<Vec<SonarError>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Pending {
+    volume: f64,
+    send_at: Instant,
+}
+
+impl DebouncedSonar {
+    /// Wrap a client, sending volumes after `quiet_period` without changes.
+    pub fn new(sonar: Sonar, quiet_period: Duration) -> Self {
+        Self {
+            sonar,
+            quiet_period,
+            shared: Arc::default(),
+        }
+    }
+
+    /// The wrapped client, for everything that should not be debounced.
+    pub fn sonar(&self) -> &Sonar {
+        &self.sonar
+    }
+
+    /// Set the volume of a channel once the quiet period has passed without
+    /// another volume for the same channel and slider.
+    ///
+    /// The channel and volume are validated immediately. Must be called from
+    /// within a tokio runtime.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The audio channel name
+    /// * `volume` - Volume level (0.0 to 1.0)
+    /// * `streamer_slider` - Streamer slider to use in streamer mode
+    pub fn set_volume(&self, channel: &str, volume: f64, streamer_slider: Option<StreamerSlider>) -> Result<()> {
+        let channel = self.sonar.core.resolve_channel(channel)?;
+
+        check_volume(volume)?;
+
+        let key = (channel.to_string(), streamer_slider);
+        let pending = Pending {
+            volume,
+            send_at: Instant::now() + self.quiet_period,
+        };
+        if self.shared.pending().insert(key.clone(), pending).is_none() {
+            tokio::spawn(send_when_quiet(self.sonar.clone(), Arc::clone(&self.shared), key));
+        }
+        Ok(())
+    }
+
+    /// Whether volumes are waiting for their quiet period to pass.
+    pub fn has_pending(&self) -> bool {
+        !self.shared.pending().is_empty()
+    }
+
+    /// Send all pending volumes now.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error of the volumes sent in the background since
+    /// the last flush or, failing that, of the volumes sent now. Other errors
+    /// are discarded.
+    pub async fn flush(&self) -> Result<()> {
+        let pending: Vec<(VolumeKey, Pending)> = self.shared.pending().drain().collect();
+        for ((channel, slider), pending) in pending {
+            if let Err(error) = self.sonar.set_volume(&channel, pending.volume, slider).await {
+                self.shared.errors().push(error);
+            }
+        }
+
+        match self.shared.errors().drain(..).next() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Shared {
+    fn pending(&self) -> MutexGuard<'_, HashMap<VolumeKey, Pending>> {
+        self.pending.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn errors(&self) -> MutexGuard<'_, Vec<SonarError>> {
+        self.errors.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Wait until the volume for `key` stopped changing, then send it. Returns
+/// early if a flush already sent it.
+async fn send_when_quiet(sonar: Sonar, shared: Arc<Shared>, key: VolumeKey) {
+    let volume = loop {
+        let send_at = match shared.pending().get(&key) {
+            None => return,
+            Some(entry) => entry.send_at,
+        };
+        if send_at > Instant::now() {
+            tokio::time::sleep_until(send_at).await;
+            continue;
+        }
+
+        // Re-checked under the same lock as the removal, so a volume set in
+        // between is not lost.
+        let mut pending = shared.pending();
+        match pending.get(&key) {
+            Some(entry) if entry.send_at <= Instant::now() => {
+                let volume = entry.volume;
+                pending.remove(&key);
+                break volume;
+            }
+            Some(_) => continue,
+            None => return,
+        }
+    };
+
+    let (channel, slider) = &key;
+    if let Err(error) = sonar.set_volume(channel, volume, *slider).await {
+        #[cfg(feature = "tracing")]
+        tracing::warn!(channel, volume, error = %error, "sending a debounced volume failed");
+        shared.errors().push(error);
+    }
+}
@@ -0,0 +1,79 @@
+//! Audio devices known to Sonar.
+
+use serde::{Deserialize, Serialize};
+
+/// Direction of audio flowing through a device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataFlow {
+    /// Output device (speakers, headphones).
+    Render,
+    /// Input device (microphones).
+    Capture,
+    /// Any value this crate does not know about.
+    #[serde(other)]
+    Unknown,
+}
+
+/// An audio device as reported by the `/audioDevices` endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AudioDevice {
+    pub id: String,
+    #[serde(rename = "friendlyName")]
+    pub friendly_name: String,
+    #[serde(rename = "dataFlow")]
+    pub data_flow: DataFlow,
+    /// Whether this is the system default device for its data flow.
+    #[serde(rename = "isDefault", default)]
+    pub is_default: bool,
+    /// Whether this is the system default communications device.
+    #[serde(rename = "isDefaultCommunication", default)]
+    pub is_default_communication: bool,
+}
+
+impl AudioDevice {
+    /// Whether this is an output device.
+    pub fn is_render(&self) -> bool {
+        self.data_flow == DataFlow::Render
+    }
+
+    /// Whether this is an input device.
+    pub fn is_capture(&self) -> bool {
+        self.data_flow == DataFlow::Capture
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_audio_devices() {
+        let devices: Vec<AudioDevice> = serde_json::from_value(json!([
+            {
+                "id": "{0.0.0.00000000}.{a}",
+                "friendlyName": "Headphones (Arctis Nova Pro)",
+                "dataFlow": "render",
+                "isDefault": true
+            },
+            {
+                "id": "{0.0.1.00000000}.{b}",
+                "friendlyName": "Microphone (Yeti)",
+                "dataFlow": "capture"
+            },
+            {
+                "id": "{0.0.2.00000000}.{c}",
+                "friendlyName": "Something else",
+                "dataFlow": "all"
+            }
+        ]))
+        .unwrap();
+
+        assert!(devices[0].is_render());
+        assert!(devices[0].is_default);
+        assert!(devices[1].is_capture());
+        assert!(!devices[1].is_default_communication);
+        assert_eq!(devices[2].data_flow, DataFlow::Unknown);
+    }
+}
@@ -0,0 +1,117 @@
+//! Discovery of the SteelSeries Engine coreProps.json file.
+
+use std::path::PathBuf;
+
+/// Environment variable overriding the coreProps.json path.
+pub(crate) const COREPROPS_ENV: &str = "STEELSERIES_SONAR_COREPROPS";
+/// Environment variable with a web server address to use without discovery.
+pub(crate) const ADDRESS_ENV: &str = "STEELSERIES_SONAR_ADDRESS";
+
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+const CORE_PROPS_FILE: &str = "coreProps.json";
+
+/// Find coreProps.json among the platform's known locations.
+///
+/// Returns the preferred location when none of them exists, so the caller
+/// reports [`SonarError::EnginePathNotFound`](crate::SonarError::EnginePathNotFound).
+/// On platforms without SteelSeries GG this is an empty path.
+pub(crate) fn find_core_props() -> PathBuf {
+    let candidates = core_props_candidates();
+    let path = candidates
+        .iter()
+        .find(|path| path.exists())
+        .or_else(|| candidates.first())
+        .cloned()
+        .unwrap_or_default();
+    #[cfg(feature = "tracing")]
+    tracing::debug!(path = %path.display(), exists = path.exists(), "located coreProps.json");
+    path
+}
+
+/// Known coreProps.json locations, in order of preference.
+#[cfg(target_os = "windows")]
+fn core_props_candidates() -> Vec<PathBuf> {
+    windows::core_props_paths()
+}
+
+/// Known coreProps.json locations, in order of preference.
+#[cfg(target_os = "macos")]
+fn core_props_candidates() -> Vec<PathBuf> {
+    const ENGINE_DATA_DIRS: [&str; 2] = ["SteelSeries Engine 3", "SteelSeries GG"];
+
+    // GG writes to the system-wide Application Support folder; the per-user
+    // one is checked for older installs.
+    let roots = std::iter::once(PathBuf::from("/Library/Application Support")).chain(dirs::data_dir());
+    roots
+        .flat_map(|root| ENGINE_DATA_DIRS.map(|dir| root.join(dir).join(CORE_PROPS_FILE)))
+        .collect()
+}
+
+/// SteelSeries GG is only available on Windows and macOS.
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn core_props_candidates() -> Vec<PathBuf> {
+    Vec::new()
+}
+
+#[cfg(target_os = "windows")]
+mod windows {
+    //! Default location plus registry lookups for non-standard installs.
+
+    use super::CORE_PROPS_FILE;
+    use std::path::PathBuf;
+    use windows_registry::LOCAL_MACHINE;
+
+    const ENGINE_DATA_DIR: &str = "SteelSeries\\SteelSeries Engine 3";
+    const DEFAULT_PROGRAM_DATA: &str = "C:\\ProgramData";
+    const SHELL_FOLDERS_KEY: &str = "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Explorer\\Shell Folders";
+    const UNINSTALL_KEYS: [&str; 4] = [
+        "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Uninstall\\SteelSeries GG",
+        "SOFTWARE\\WOW6432Node\\Microsoft\\Windows\\CurrentVersion\\Uninstall\\SteelSeries GG",
+        "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Uninstall\\SteelSeries Engine 3",
+        "SOFTWARE\\WOW6432Node\\Microsoft\\Windows\\CurrentVersion\\Uninstall\\SteelSeries Engine 3",
+    ];
+
+    /// coreProps.json in ProgramData, followed by candidates derived from the
+    /// registry: the relocated ProgramData folder and the install location
+    /// of GG/Engine 3.
+    pub(super) fn core_props_paths() -> Vec<PathBuf> {
+        let program_data = std::env::var_os("PROGRAMDATA")
+            .map_or_else(|| PathBuf::from(DEFAULT_PROGRAM_DATA), PathBuf::from)
+            .join(ENGINE_DATA_DIR)
+            .join(CORE_PROPS_FILE);
+
+        let registry_program_data = LOCAL_MACHINE
+            .open(SHELL_FOLDERS_KEY)
+            .and_then(|key| key.get_string("Common AppData"))
+            .ok()
+            .map(|dir| PathBuf::from(dir).join(ENGINE_DATA_DIR).join(CORE_PROPS_FILE));
+
+        let install_dirs = UNINSTALL_KEYS.iter().filter_map(|path| {
+            LOCAL_MACHINE
+                .open(path)
+                .and_then(|key| key.get_string("InstallLocation"))
+                .ok()
+                .filter(|dir| !dir.is_empty())
+                .map(|dir| PathBuf::from(dir).join(CORE_PROPS_FILE))
+        });
+
+        std::iter::once(program_data)
+            .chain(registry_program_data)
+            .chain(install_dirs)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_core_props() {
+        let path = find_core_props();
+        #[cfg(any(target_os = "windows", target_os = "macos"))]
+        assert!(path.ends_with(CORE_PROPS_FILE));
+        #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+        assert_eq!(path, PathBuf::new());
+    }
+}
@@ -0,0 +1,343 @@
+//! Automatic ducking while chosen applications play audio.
+//!
+//! [`Sonar::auto_duck`](crate::Sonar::auto_duck) watches the audio sessions
+//! of the default playback device through WASAPI. While one of the
+//! [`DuckingOptions::apps`] is audible, e.g. Discord during a voice call, the
+//! ducked channels are faded down; once the apps have been silent for
+//! [`DuckingOptions::release`], the previous volumes are faded back in.
+//!
+//! Sessions are polled rather than subscribed to: voice applications keep
+//! their session active while nobody talks, so only the peak meter tells
+//! whether they actually produce audio.
+//!
+//! Audio sessions are only available on Windows; elsewhere the ducker fails
+//! with an [`Unsupported`](std::io::ErrorKind::Unsupported) I/O error.
+
+use crate::cancel::CancellationToken;
+use crate::error::{Result, SonarError};
+use crate::sonar::{Channel, Sonar, StreamerSlider};
+use futures_util::future::try_join_all;
+use std::io;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+const EVENT_BUFFER: usize = 16;
+
+/// Options of [`Sonar::auto_duck`](crate::Sonar::auto_duck).
+///
+/// ```
+/// use steelseries_sonar::DuckingOptions;
+///
+/// let options = DuckingOptions { amount: 0.7, ..DuckingOptions::new(["Discord.exe"]) };
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuckingOptions {
+    /// Executable names of the applications that trigger ducking, e.g.
+    /// `"Discord.exe"`. Compared case-insensitively.
+    pub apps: Vec<String>,
+    /// The channels faded down while the apps are audible.
+    pub channels: Vec<Channel>,
+    /// Streamer slider of the ducked channels in streamer mode.
+    pub slider: Option<StreamerSlider>,
+    /// Share of the volume removed while ducked, from 0.0 (none) to 1.0
+    /// (silence).
+    pub amount: f64,
+    /// How long the apps must be silent before the volumes are restored.
+    pub release: Duration,
+    /// Duration of the fades down and back up.
+    pub fade: Duration,
+    /// Peak level (0.0 to 1.0) above which an app counts as audible.
+    pub threshold: f32,
+    /// How often the audio sessions are checked.
+    pub poll_interval: Duration,
+}
+
+impl DuckingOptions {
+    /// Duck the game and media channels by half while any of `apps` is audible.
+    pub fn new(apps: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            apps: apps.into_iter().map(Into::into).collect(),
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for DuckingOptions {
+    fn default() -> Self {
+        Self {
+            apps: Vec::new(),
+            channels: vec![Channel::Game, Channel::Media],
+            slider: None,
+            amount: 0.5,
+            release: Duration::from_secs(1),
+            fade: Duration::from_millis(300),
+            threshold: 0.01,
+            poll_interval: Duration::from_millis(100),
+        }
+    }
+}
+
+/// A change made by an [`AutoDucker`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DuckEvent {
+    /// The channels were faded down because `apps` became audible.
+    Ducked { apps: Vec<String> },
+    /// The previous volumes were restored.
+    Restored,
+}
+
+/// Handle to a running auto-ducker.
+///
+/// Stopping or dropping it restores the volumes if they are ducked.
+#[derive(Debug)]
+pub struct AutoDucker {
+    receiver: mpsc::Receiver<Result<DuckEvent>>,
+    token: CancellationToken,
+    task: Option<JoinHandle<()>>,
+}
+
+impl AutoDucker {
+    pub(crate) fn spawn(sonar: Sonar, options: DuckingOptions) -> Self {
+        let (sender, receiver) = mpsc::channel(EVENT_BUFFER);
+        let token = CancellationToken::new();
+        let task = tokio::spawn(run_loop(sonar, options, token.clone(), sender));
+        Self {
+            receiver,
+            token,
+            task: Some(task),
+        }
+    }
+
+    /// Wait for the next change, or the error that prevented one.
+    ///
+    /// Returns `None` once the ducker has stopped, e.g. because the audio
+    /// sessions could not be read.
+    pub async fn recv(&mut self) -> Option<Result<DuckEvent>> {
+        self.receiver.recv().await
+    }
+
+    /// Stop ducking, waiting until the previous volumes are restored.
+    pub async fn stop(mut self) {
+        self.token.cancel();
+        if let Some(task) = self.task.take() {
+            let _ = task.await;
+        }
+    }
+}
+
+impl Drop for AutoDucker {
+    fn drop(&mut self) {
+        // The task restores the volumes and exits on its own.
+        self.token.cancel();
+    }
+}
+
+/// What to do after polling the audio sessions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DuckAction {
+    Duck,
+    Restore,
+}
+
+/// Whether the channels are ducked, and when the apps were last audible.
+#[derive(Debug, Default)]
+struct DuckState {
+    ducked: bool,
+    last_audible: Option<Instant>,
+}
+
+impl DuckState {
+    fn update(&mut self, audible: bool, now: Instant, release: Duration) -> Option<DuckAction> {
+        if audible {
+            self.last_audible = Some(now);
+            if !self.ducked {
+                self.ducked = true;
+                return Some(DuckAction::Duck);
+            }
+        } else if self.ducked && self.last_audible.is_none_or(|last| now.duration_since(last) >= release) {
+            self.ducked = false;
+            return Some(DuckAction::Restore);
+        }
+        None
+    }
+}
+
+async fn run_loop(sonar: Sonar, options: DuckingOptions, token: CancellationToken, sender: mpsc::Sender<Result<DuckEvent>>) {
+    let mut state = DuckState::default();
+    // Volumes before ducking, restored afterwards.
+    let mut saved = Vec::new();
+
+    loop {
+        let apps = options.apps.clone();
+        let threshold = options.threshold;
+        let audible = match tokio::task::spawn_blocking(move || sessions::audible_apps(&apps, threshold)).await {
+            Ok(Ok(audible)) => audible,
+            Ok(Err(error)) => {
+                let _ = sender.try_send(Err(error.into()));
+                break;
+            }
+            Err(error) => {
+                let _ = sender.try_send(Err(SonarError::Io(io::Error::other(error))));
+                break;
+            }
+        };
+
+        let event = match state.update(!audible.is_empty(), Instant::now(), options.release) {
+            Some(DuckAction::Duck) => Some(duck(&sonar, &options, &mut saved).await.map(|()| DuckEvent::Ducked { apps: audible })),
+            Some(DuckAction::Restore) => Some(restore(&sonar, &options, &mut saved).await.map(|()| DuckEvent::Restored)),
+            None => None,
+        };
+        if let Some(event) = event {
+            // Nobody listening is fine, the ducking still happens.
+            let _ = sender.try_send(event);
+        }
+
+        tokio::select! {
+            () = token.cancelled() => break,
+            () = tokio::time::sleep(options.poll_interval) => {}
+        }
+    }
+
+    if !saved.is_empty() {
+        let _ = sender.try_send(restore(&sonar, &options, &mut saved).await.map(|()| DuckEvent::Restored));
+    }
+}
+
+/// Fade the ducked channels down, remembering their volumes in `saved`.
+async fn duck(sonar: &Sonar, options: &DuckingOptions, saved: &mut Vec<(Channel, f64)>) -> Result<()> {
+    for &channel in &options.channels {
+        let volume = sonar.get_channel_state(channel.as_str(), options.slider).await?.volume;
+        saved.push((channel, volume));
+    }
+    try_join_all(saved.iter().map(|&(channel, volume)| {
+        let target = ducked_volume(volume, options.amount);
+        sonar.fade_volume(channel.as_str(), target, options.fade, options.slider)
+    }))
+    .await?;
+    Ok(())
+}
+
+/// Fade the channels in `saved` back to their volumes.
+async fn restore(sonar: &Sonar, options: &DuckingOptions, saved: &mut Vec<(Channel, f64)>) -> Result<()> {
+    let volumes = std::mem::take(saved);
+    try_join_all(
+        volumes
+            .iter()
+            .map(|&(channel, volume)| sonar.fade_volume(channel.as_str(), volume, options.fade, options.slider)),
+    )
+    .await?;
+    Ok(())
+}
+
+fn ducked_volume(volume: f64, amount: f64) -> f64 {
+    (volume * (1.0 - amount.clamp(0.0, 1.0))).clamp(0.0, 1.0)
+}
+
+#[cfg(windows)]
+mod sessions {
+    use std::io;
+    use std::path::Path;
+    use windows::core::{Interface, PWSTR};
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::Media::Audio::Endpoints::IAudioMeterInformation;
+    use windows::Win32::Media::Audio::{
+        eMultimedia, eRender, AudioSessionStateActive, IAudioSessionControl2, IAudioSessionManager2,
+        IMMDeviceEnumerator, MMDeviceEnumerator,
+    };
+    use windows::Win32::System::Com::{CoCreateInstance, CoInitializeEx, CLSCTX_ALL, COINIT_MULTITHREADED};
+    use windows::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+
+    /// The executable names of `apps` with an active session on the default
+    /// playback device peaking above `threshold`.
+    pub(super) fn audible_apps(apps: &[String], threshold: f32) -> io::Result<Vec<String>> {
+        find_audible_apps(apps, threshold).map_err(io::Error::from)
+    }
+
+    fn find_audible_apps(apps: &[String], threshold: f32) -> windows::core::Result<Vec<String>> {
+        let mut audible = Vec::new();
+        // SAFETY: plain COM calls on interfaces owned by this function. The
+        // thread joins the multithreaded apartment; doing so again on a
+        // reused blocking thread is harmless.
+        unsafe {
+            let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+            let devices: IMMDeviceEnumerator = CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)?;
+            let device = devices.GetDefaultAudioEndpoint(eRender, eMultimedia)?;
+            let manager: IAudioSessionManager2 = device.Activate(CLSCTX_ALL, None)?;
+            let sessions = manager.GetSessionEnumerator()?;
+
+            for index in 0..sessions.GetCount()? {
+                let session = sessions.GetSession(index)?;
+                if session.GetState()? != AudioSessionStateActive {
+                    continue;
+                }
+                let session: IAudioSessionControl2 = session.cast()?;
+                let Some(name) = process_name(session.GetProcessId()?) else {
+                    continue;
+                };
+                if !apps.iter().any(|app| app.eq_ignore_ascii_case(&name)) || audible.contains(&name) {
+                    continue;
+                }
+                let meter: IAudioMeterInformation = session.cast()?;
+                if meter.GetPeakValue()? > threshold {
+                    audible.push(name);
+                }
+            }
+        }
+        Ok(audible)
+    }
+
+    /// The executable file name of a process, if it can be queried.
+    fn process_name(process_id: u32) -> Option<String> {
+        let mut buffer = [0u16; 1024];
+        let mut len = buffer.len() as u32;
+        // SAFETY: the handle is closed before returning and the buffer
+        // outlives the call, which writes at most `len` characters.
+        let path = unsafe {
+            let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, process_id).ok()?;
+            let result = QueryFullProcessImageNameW(process, PROCESS_NAME_WIN32, PWSTR(buffer.as_mut_ptr()), &mut len);
+            let _ = CloseHandle(process);
+            result.ok()?;
+            String::from_utf16_lossy(&buffer[..len as usize])
+        };
+        Path::new(&path).file_name().map(|name| name.to_string_lossy().into_owned())
+    }
+}
+
+#[cfg(not(windows))]
+mod sessions {
+    use std::io;
+
+    pub(super) fn audible_apps(_apps: &[String], _threshold: f32) -> io::Result<Vec<String>> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "audio sessions are only available on Windows"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duck_state() {
+        let release = Duration::from_secs(1);
+        let start = Instant::now();
+        let mut state = DuckState::default();
+
+        assert_eq!(state.update(false, start, release), None);
+        assert_eq!(state.update(true, start, release), Some(DuckAction::Duck));
+        assert_eq!(state.update(true, start + Duration::from_millis(100), release), None);
+        // Short pauses, e.g. between sentences, keep the channels ducked
+        assert_eq!(state.update(false, start + Duration::from_millis(600), release), None);
+        assert_eq!(state.update(false, start + Duration::from_millis(1100), release), Some(DuckAction::Restore));
+        assert_eq!(state.update(false, start + Duration::from_secs(5), release), None);
+    }
+
+    #[test]
+    fn test_ducked_volume() {
+        assert_eq!(ducked_volume(0.8, 0.5), 0.4);
+        assert_eq!(ducked_volume(0.8, 1.0), 0.0);
+        assert_eq!(ducked_volume(0.8, -1.0), 0.8);
+    }
+}
@@ -0,0 +1,134 @@
+//! Building request paths.
+
+use crate::error::{Result, SonarError};
+use crate::sonar::{Mode, StreamerSlider, CHANNEL_NAMES};
+use std::sync::LazyLock;
+use url::{Position, Url};
+
+/// Only the path and query of URLs built on this root are kept.
+const PATH_ROOT: &str = "http://sonar/";
+
+/// Build a request path such as `/configs/{id}/name/{name}` from its
+/// segments, percent-encoding each of them so names with spaces, slashes or
+/// unicode stay a single segment.
+pub(crate) fn api_path(segments: &[&str]) -> Result<String> {
+    api_path_with_query(segments, &[])
+}
+
+/// Like [`api_path`], followed by the encoded query `pairs`, if any.
+pub(crate) fn api_path_with_query(segments: &[&str], pairs: &[(&str, &str)]) -> Result<String> {
+    let mut url = Url::parse(PATH_ROOT)?;
+    url.path_segments_mut()
+        .map_err(|()| url::ParseError::RelativeUrlWithCannotBeABaseBase)?
+        .clear()
+        .extend(segments);
+    if !pairs.is_empty() {
+        url.query_pairs_mut().extend_pairs(pairs);
+    }
+    Ok(url[Position::BeforePath..].to_string())
+}
+
+/// The volume settings path of every channel, slider and mode, built once
+/// instead of on every volume or mute change.
+static CHANNEL_PATHS: LazyLock<ChannelPaths> = LazyLock::new(ChannelPaths::new);
+
+/// Volume settings paths in [`CHANNEL_NAMES`] order, e.g.
+/// `/volumeSettings/classic/game`.
+#[derive(Debug)]
+struct ChannelPaths {
+    classic: Vec<String>,
+    /// Indexed like [`StreamerSlider::ALL`].
+    streamer: [Vec<String>; 2],
+}
+
+impl ChannelPaths {
+    fn new() -> Self {
+        let paths = |prefix: String| CHANNEL_NAMES.iter().map(|channel| format!("{}/{}", prefix, channel)).collect();
+        Self {
+            classic: paths(Mode::Classic.volume_path().to_string()),
+            streamer: StreamerSlider::ALL.map(|slider| paths(format!("{}/{}", Mode::Streamer.volume_path(), slider))),
+        }
+    }
+}
+
+/// The path setting the volume of a channel, e.g.
+/// `/volumeSettings/streamer/monitoring/game/Volume/0.5`. `slider` is only
+/// used in streamer mode.
+pub(crate) fn volume_path(mode: Mode, channel: &str, slider: Option<StreamerSlider>, volume: f64) -> Result<String> {
+    setting_path(mode, channel, slider, "Volume", &serde_json::to_string(&volume)?)
+}
+
+/// The path muting or unmuting a channel: `.../{channel}/Mute/{muted}` in
+/// classic and `.../{channel}/isMuted/{muted}` in streamer mode.
+pub(crate) fn mute_path(mode: Mode, channel: &str, slider: Option<StreamerSlider>, muted: bool) -> Result<String> {
+    let keyword = if mode.is_streamer() { "isMuted" } else { "Mute" };
+    setting_path(mode, channel, slider, keyword, if muted { "true" } else { "false" })
+}
+
+fn setting_path(mode: Mode, channel: &str, slider: Option<StreamerSlider>, setting: &str, value: &str) -> Result<String> {
+    let index = CHANNEL_NAMES
+        .iter()
+        .position(|name| *name == channel)
+        .ok_or_else(|| SonarError::ChannelNotFound(channel.to_string()))?;
+    let paths = match mode {
+        Mode::Classic => &CHANNEL_PATHS.classic,
+        Mode::Streamer => match slider.unwrap_or_default() {
+            StreamerSlider::Streaming => &CHANNEL_PATHS.streamer[0],
+            StreamerSlider::Monitoring => &CHANNEL_PATHS.streamer[1],
+        },
+    };
+    let prefix = &paths[index];
+
+    let mut path = String::with_capacity(prefix.len() + setting.len() + value.len() + 2);
+    path.push_str(prefix);
+    path.push('/');
+    path.push_str(setting);
+    path.push('/');
+    path.push_str(value);
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_path() {
+        assert_eq!(api_path(&["configs", "Flat", "select"]).unwrap(), "/configs/Flat/select");
+        assert_eq!(
+            api_path(&["configs", "1", "name", "Late night / quiet"]).unwrap(),
+            "/configs/1/name/Late%20night%20%2F%20quiet"
+        );
+        assert_eq!(api_path(&["Músic?#%"]).unwrap(), "/M%C3%BAsic%3F%23%25");
+        assert_eq!(
+            api_path(&["classicRedirections", "game", "deviceId", "{0.0.0.00000000}.{a1b2}"]).unwrap(),
+            "/classicRedirections/game/deviceId/%7B0.0.0.00000000%7D.%7Ba1b2%7D"
+        );
+        assert_eq!(
+            api_path_with_query(&["chatMix"], &[("balance", "-0.5")]).unwrap(),
+            "/chatMix?balance=-0.5"
+        );
+        assert_eq!(
+            api_path_with_query(&["configs"], &[("vad", "a&b c")]).unwrap(),
+            "/configs?vad=a%26b+c"
+        );
+    }
+
+    #[test]
+    fn test_channel_setting_paths() {
+        assert_eq!(
+            volume_path(Mode::Classic, "game", Some(StreamerSlider::Monitoring), 0.5).unwrap(),
+            "/volumeSettings/classic/game/Volume/0.5"
+        );
+        assert_eq!(
+            volume_path(Mode::Streamer, "chatRender", None, 1.0).unwrap(),
+            "/volumeSettings/streamer/streaming/chatRender/Volume/1.0"
+        );
+        assert_eq!(mute_path(Mode::Classic, "master", None, true).unwrap(), "/volumeSettings/classic/master/Mute/true");
+        assert_eq!(
+            mute_path(Mode::Streamer, "aux", Some(StreamerSlider::Monitoring), false).unwrap(),
+            "/volumeSettings/streamer/monitoring/aux/isMuted/false"
+        );
+        assert!(matches!(volume_path(Mode::Classic, "chat", None, 0.5), Err(SonarError::ChannelNotFound(_))));
+    }
+}
@@ -0,0 +1,204 @@
+//! Parametric equalizer of Sonar configs.
+//!
+//! Each channel's selected config carries a parametric EQ made of numbered
+//! filters (`filter1` .. `filter10`). Bands are addressed by their zero-based
+//! position in that list.
+
+use crate::configs::SonarConfig;
+use crate::error::{Result, SonarError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Valid EQ gain range in dB.
+pub const EQ_GAIN_RANGE: std::ops::RangeInclusive<f64> = -12.0..=12.0;
+
+const FILTER_PREFIX: &str = "filter";
+
+/// A single band (filter) of the parametric EQ.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EqBand {
+    /// Center/corner frequency in Hz.
+    pub frequency: f64,
+    /// Gain in dB.
+    pub gain: f64,
+    pub q_factor: f64,
+    /// Filter type as reported by Sonar, e.g. `"peakingEQ"` or `"lowShelving"`.
+    pub filter_type: String,
+    pub enabled: bool,
+}
+
+/// The parametric EQ of a config.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Equalizer {
+    /// Id of the config this EQ belongs to.
+    pub config_id: String,
+    pub enabled: bool,
+    pub bands: Vec<EqBand>,
+}
+
+impl Equalizer {
+    /// Read the EQ from a config.
+    pub fn from_config(config: &SonarConfig) -> Self {
+        let eq = parametric_eq(&config.settings);
+        let enabled = eq
+            .and_then(|eq| eq.get("enabled"))
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let bands = eq
+            .map(|eq| {
+                sorted_filters(eq)
+                    .into_iter()
+                    .map(|(_, filter)| EqBand {
+                        frequency: number(filter, "frequency"),
+                        gain: number(filter, "gain"),
+                        q_factor: number(filter, "qFactor"),
+                        filter_type: filter
+                            .get("type")
+                            .and_then(Value::as_str)
+                            .unwrap_or_default()
+                            .to_string(),
+                        enabled: filter.get("enabled").and_then(Value::as_bool).unwrap_or(true),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            config_id: config.id.clone(),
+            enabled,
+            bands,
+        }
+    }
+}
+
+/// Changes to apply to one EQ band. Fields left as `None` are not modified.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct EqBandUpdate {
+    /// Gain in dB, see [`EQ_GAIN_RANGE`].
+    pub gain: Option<f64>,
+    /// Frequency in Hz.
+    pub frequency: Option<f64>,
+    pub q_factor: Option<f64>,
+}
+
+/// Apply a band update to the settings of a config.
+pub(crate) fn apply_band_update(settings: &mut Value, band: usize, update: EqBandUpdate) -> Result<()> {
+    if let Some(gain) = update.gain.filter(|gain| !EQ_GAIN_RANGE.contains(gain)) {
+        return Err(SonarError::InvalidEqGain(gain));
+    }
+
+    let key = parametric_eq(settings)
+        .map(sorted_filters)
+        .and_then(|filters| filters.get(band).map(|(key, _)| key.clone()))
+        .ok_or(SonarError::EqBandNotFound(band))?;
+
+    let filter = settings
+        .get_mut("parametricEQ")
+        .and_then(|eq| eq.get_mut(&key))
+        .and_then(Value::as_object_mut)
+        .ok_or(SonarError::EqBandNotFound(band))?;
+
+    if let Some(gain) = update.gain {
+        filter.insert("gain".to_string(), gain.into());
+    }
+    if let Some(frequency) = update.frequency {
+        filter.insert("frequency".to_string(), frequency.into());
+    }
+    if let Some(q_factor) = update.q_factor {
+        filter.insert("qFactor".to_string(), q_factor.into());
+    }
+
+    Ok(())
+}
+
+fn parametric_eq(settings: &Value) -> Option<&Value> {
+    settings.get("parametricEQ")
+}
+
+/// Filters of a parametric EQ as `(key, filter)`, ordered by their number.
+fn sorted_filters(eq: &Value) -> Vec<(String, &Value)> {
+    let mut filters: Vec<_> = eq
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter_map(|(key, filter)| {
+            let index: u32 = key.strip_prefix(FILTER_PREFIX)?.parse().ok()?;
+            Some((index, key.clone(), filter))
+        })
+        .collect();
+    filters.sort_by_key(|(index, _, _)| *index);
+    filters.into_iter().map(|(_, key, filter)| (key, filter)).collect()
+}
+
+fn number(value: &Value, key: &str) -> f64 {
+    value.get(key).and_then(Value::as_f64).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn config() -> SonarConfig {
+        serde_json::from_value(json!({
+            "id": "abc",
+            "name": "Custom",
+            "virtualAudioDevice": "game",
+            "settings": {
+                "parametricEQ": {
+                    "enabled": true,
+                    "filter10": { "frequency": 16000.0, "gain": 1.0, "qFactor": 0.7, "type": "highShelving", "enabled": true },
+                    "filter2": { "frequency": 62.0, "gain": -3.0, "qFactor": 1.41, "type": "peakingEQ", "enabled": false },
+                    "filter1": { "frequency": 31.0, "gain": 2.0, "qFactor": 0.7, "type": "lowShelving", "enabled": true }
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_read_equalizer() {
+        let eq = Equalizer::from_config(&config());
+
+        assert_eq!(eq.config_id, "abc");
+        assert!(eq.enabled);
+        let frequencies: Vec<_> = eq.bands.iter().map(|band| band.frequency).collect();
+        assert_eq!(frequencies, [31.0, 62.0, 16000.0]);
+        assert!(!eq.bands[1].enabled);
+        assert_eq!(eq.bands[2].filter_type, "highShelving");
+    }
+
+    #[test]
+    fn test_apply_band_update() {
+        let mut config = config();
+        let update = EqBandUpdate {
+            gain: Some(6.0),
+            q_factor: Some(2.0),
+            ..Default::default()
+        };
+        apply_band_update(&mut config.settings, 1, update).unwrap();
+
+        let band = &Equalizer::from_config(&config).bands[1];
+        assert_eq!(band.gain, 6.0);
+        assert_eq!(band.q_factor, 2.0);
+        assert_eq!(band.frequency, 62.0);
+    }
+
+    #[test]
+    fn test_apply_band_update_errors() {
+        let mut settings = config().settings;
+
+        let update = EqBandUpdate {
+            gain: Some(20.0),
+            ..Default::default()
+        };
+        assert!(matches!(
+            apply_band_update(&mut settings, 0, update),
+            Err(SonarError::InvalidEqGain(_))
+        ));
+        assert!(matches!(
+            apply_band_update(&mut settings, 3, EqBandUpdate::default()),
+            Err(SonarError::EqBandNotFound(3))
+        ));
+    }
+}
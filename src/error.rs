@@ -1,7 +1,28 @@
 //! Error types for the SteelSeries Sonar API.
 
+use crate::snapshot::SnapshotItem;
 use thiserror::Error;
 
+/// Broad category of a [`SonarError`], see [`SonarError::kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// SteelSeries GG or Sonar could not be found, or is not running, enabled
+    /// or ready.
+    Discovery,
+    /// The connection to the Sonar web server, the daemon or the file system
+    /// failed.
+    Connectivity,
+    /// An argument was rejected before sending a request, e.g. an unknown
+    /// channel or an out-of-range volume.
+    Validation,
+    /// The server rejected a request, or answered in an unexpected way.
+    Api,
+    /// A request timed out.
+    Timeout,
+    /// The operation was cancelled, see [`cancellable`](crate::cancel::cancellable).
+    Cancelled,
+}
+
 /// Errors that can occur when using the SteelSeries Sonar API.
 #[derive(Error, Debug)]
 pub enum SonarError {
@@ -11,6 +32,15 @@ pub enum SonarError {
     #[error("SteelSeries server not accessible! Status code: {0}")]
     ServerNotAccessible(u16),
 
+    #[error("Sonar API request to '{endpoint}' failed with status {status}: {body}")]
+    Api {
+        status: u16,
+        /// Path and query of the request, e.g. `/volumeSettings/classic/game/Volume/0.5`.
+        endpoint: String,
+        /// The response body, usually JSON describing the error.
+        body: String,
+    },
+
     #[error("SteelSeries Sonar is not enabled!")]
     SonarNotEnabled,
 
@@ -29,21 +59,254 @@ pub enum SonarError {
     #[error("Slider '{0}' not found")]
     SliderNotFound(String),
 
+    #[error("Capture device '{0}' not found")]
+    CaptureDeviceNotFound(String),
+
+    #[error("Mode '{0}' not found")]
+    ModeNotFound(String),
+
+    #[error("No running process of '{0}' found")]
+    AppNotFound(String),
+
     #[error("Invalid volume '{0}'! Value must be between 0.0 and 1.0!")]
     InvalidVolume(f64),
 
+    #[error("Invalid volume percent '{0}'! Value must be between 0 and 100!")]
+    InvalidVolumePercent(u8),
+
     #[error("Invalid mix volume '{0}'! Value must be between -1.0 and 1.0!")]
     InvalidMixVolume(f64),
 
+    #[error("ChatMix is not available! Connect and select a ChatMix-capable headset.")]
+    ChatMixUnavailable,
+
+    #[error("Channel '{0}' cannot be soloed")]
+    NotSoloable(String),
+
+    #[error("No config selected for channel '{0}'")]
+    ConfigNotFound(String),
+
+    #[error("EQ band {0} not found")]
+    EqBandNotFound(usize),
+
+    #[error("Invalid EQ gain '{0}'! Value must be between -12.0 and 12.0!")]
+    InvalidEqGain(f64),
+
+    #[error("Request to SteelSeries Sonar timed out")]
+    Timeout,
+
+    #[error("Operation cancelled")]
+    Cancelled,
+
+    #[error("No recorded response for '{0}' in the cassette")]
+    CassetteMiss(String),
+
+    #[error("Change not applied: {0}")]
+    NotApplied(String),
+
+    #[error("Profile '{0}' not found")]
+    ProfileNotFound(String),
+
+    #[error("Invalid profile name '{0}'")]
+    InvalidProfileName(String),
+
+    #[error("Input has no effect: {0}")]
+    IgnoredInput(String),
+
+    #[error("Invalid config file: {0}")]
+    InvalidConfig(String),
+
+    #[error("Invalid hotkey {0}")]
+    InvalidHotkey(String),
+
+    #[error("Script error: {0}")]
+    Script(String),
+
+    #[error("Invalid schedule time {hour}:{minute:02}")]
+    InvalidScheduleTime { hour: u32, minute: u32 },
+
+    #[error("Profile '{name}' could not be applied ({} settings failed), the previous state was restored", .failed.len())]
+    ProfileNotApplied {
+        name: String,
+        failed: Vec<(SnapshotItem, SonarError)>,
+    },
+
+    #[error(
+        "Profile '{name}' could not be applied ({} settings failed), and {} settings of the previous state could not be restored",
+        .failed.len(),
+        .rollback_failed.len()
+    )]
+    ProfileRollbackFailed {
+        name: String,
+        failed: Vec<(SnapshotItem, SonarError)>,
+        /// The settings of the previous state that could not be restored.
+        rollback_failed: Vec<(SnapshotItem, SonarError)>,
+    },
+
+    #[error("Sonar daemon error: {0}")]
+    Daemon(String),
+
+    #[error("OBS error: {0}")]
+    Obs(String),
+
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    #[error("HTTP request error: {0}")]
+    Http(#[source] reqwest::Error),
+
+    #[cfg(feature = "ureq")]
     #[error("HTTP request error: {0}")]
-    Http(#[from] reqwest::Error),
+    Ureq(#[source] ureq::Error),
+
+    #[error("Invalid URL: {0}")]
+    Url(#[from] url::ParseError),
 
     #[error("JSON serialization/deserialization error: {0}")]
     Json(#[from] serde_json::Error),
 
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
+
+    #[cfg(feature = "core-props-watcher")]
+    #[error("File watch error: {0}")]
+    Watch(#[from] notify::Error),
+}
+
+#[cfg(any(feature = "async", feature = "blocking"))]
+impl From<reqwest::Error> for SonarError {
+    fn from(error: reqwest::Error) -> Self {
+        if error.is_timeout() {
+            SonarError::Timeout
+        } else {
+            SonarError::Http(error)
+        }
+    }
+}
+
+#[cfg(feature = "ureq")]
+impl From<ureq::Error> for SonarError {
+    fn from(error: ureq::Error) -> Self {
+        match error {
+            ureq::Error::Timeout(_) => SonarError::Timeout,
+            error => SonarError::Ureq(error),
+        }
+    }
+}
+
+impl SonarError {
+    /// The category of this error.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            SonarError::EnginePathNotFound
+            | SonarError::ServerNotAccessible(_)
+            | SonarError::SonarNotEnabled
+            | SonarError::ServerNotReady
+            | SonarError::ServerNotRunning
+            | SonarError::WebServerAddressNotFound => ErrorKind::Discovery,
+            #[cfg(feature = "core-props-watcher")]
+            SonarError::Watch(_) => ErrorKind::Discovery,
+            SonarError::ChannelNotFound(_)
+            | SonarError::SliderNotFound(_)
+            | SonarError::CaptureDeviceNotFound(_)
+            | SonarError::ModeNotFound(_)
+            | SonarError::AppNotFound(_)
+            | SonarError::InvalidVolume(_)
+            | SonarError::InvalidVolumePercent(_)
+            | SonarError::InvalidMixVolume(_)
+            | SonarError::ChatMixUnavailable
+            | SonarError::NotSoloable(_)
+            | SonarError::ConfigNotFound(_)
+            | SonarError::EqBandNotFound(_)
+            | SonarError::InvalidEqGain(_)
+            | SonarError::ProfileNotFound(_)
+            | SonarError::InvalidProfileName(_)
+            | SonarError::InvalidScheduleTime { .. }
+            | SonarError::InvalidConfig(_)
+            | SonarError::InvalidHotkey(_)
+            | SonarError::Script(_)
+            | SonarError::IgnoredInput(_)
+            | SonarError::Url(_) => ErrorKind::Validation,
+            SonarError::Api { .. }
+            | SonarError::CassetteMiss(_)
+            | SonarError::NotApplied(_)
+            | SonarError::ProfileNotApplied { .. }
+            | SonarError::ProfileRollbackFailed { .. }
+            | SonarError::Json(_) => ErrorKind::Api,
+            SonarError::Timeout => ErrorKind::Timeout,
+            SonarError::Cancelled => ErrorKind::Cancelled,
+            SonarError::Daemon(_) | SonarError::Obs(_) | SonarError::Io(_) => ErrorKind::Connectivity,
+            #[cfg(any(feature = "async", feature = "blocking"))]
+            SonarError::Http(_) => ErrorKind::Connectivity,
+            #[cfg(feature = "ureq")]
+            SonarError::Ureq(_) => ErrorKind::Connectivity,
+        }
+    }
+
+    /// Whether retrying the operation may succeed: connection failures,
+    /// timeouts and `502`/`503`/`504` responses. This is the default
+    /// classification of [`RetryPolicy`](crate::RetryPolicy).
+    pub fn is_retryable(&self) -> bool {
+        self.is_connect()
+            || matches!(self, SonarError::Timeout)
+            || matches!(self.status(), Some(502..=504))
+    }
+
+    /// The HTTP status of a rejected request, from [`SonarError::Api`] or
+    /// [`SonarError::ServerNotAccessible`].
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            SonarError::Api { status, .. } | SonarError::ServerNotAccessible(status) => Some(*status),
+            _ => None,
+        }
+    }
+
+    /// Whether the server could not be connected to at all.
+    pub(crate) fn is_connect(&self) -> bool {
+        match self {
+            #[cfg(any(feature = "async", feature = "blocking"))]
+            SonarError::Http(error) => error.is_connect(),
+            #[cfg(feature = "ureq")]
+            SonarError::Ureq(error) => matches!(
+                error,
+                ureq::Error::ConnectionFailed | ureq::Error::HostNotFound
+            ) || matches!(error, ureq::Error::Io(error) if error.kind() == std::io::ErrorKind::ConnectionRefused),
+            _ => false,
+        }
+    }
+}
+
+/// The [`SonarError::Api`] error for an unsuccessful response.
+#[cfg(feature = "async")]
+pub(crate) async fn api_error(response: reqwest::Response) -> SonarError {
+    SonarError::Api {
+        status: response.status().as_u16(),
+        endpoint: response.url()[url::Position::BeforePath..].to_string(),
+        body: response.text().await.unwrap_or_default(),
+    }
 }
 
 /// Result type for SteelSeries Sonar operations.
 pub type Result<T> = std::result::Result<T, SonarError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kind() {
+        assert_eq!(SonarError::ServerNotRunning.kind(), ErrorKind::Discovery);
+        assert_eq!(SonarError::ChannelNotFound("chat".into()).kind(), ErrorKind::Validation);
+        assert_eq!(SonarError::Timeout.kind(), ErrorKind::Timeout);
+        assert_eq!(SonarError::Io(std::io::ErrorKind::BrokenPipe.into()).kind(), ErrorKind::Connectivity);
+
+        let api_error = SonarError::Api {
+            status: 503,
+            endpoint: "/mode/".to_string(),
+            body: String::new(),
+        };
+        assert_eq!(api_error.kind(), ErrorKind::Api);
+        assert!(api_error.is_retryable());
+        assert!(SonarError::Timeout.is_retryable());
+        assert!(!SonarError::ServerNotAccessible(404).is_retryable());
+        assert!(!SonarError::InvalidVolume(2.0).is_retryable());
+    }
+}
@@ -0,0 +1,201 @@
+//! Volume fades (ramps) over time.
+
+use crate::snapshot::SonarSnapshot;
+use crate::sonar::StreamerSlider;
+use crate::volume::VolumeCurve;
+
+/// Easing applied to the progress of a fade.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FadeCurve {
+    /// Constant rate of change.
+    #[default]
+    Linear,
+    /// Starts slow, ends fast.
+    EaseIn,
+    /// Starts fast, ends slow.
+    EaseOut,
+    /// Slow at both ends.
+    EaseInOut,
+}
+
+impl FadeCurve {
+    /// Map linear progress (0.0..=1.0) to eased progress (0.0..=1.0).
+    pub fn apply(self, progress: f64) -> f64 {
+        let t = progress.clamp(0.0, 1.0);
+        match self {
+            FadeCurve::Linear => t,
+            FadeCurve::EaseIn => t * t,
+            FadeCurve::EaseOut => t * (2.0 - t),
+            FadeCurve::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// Options controlling how a fade is performed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FadeOptions {
+    /// Number of volume updates sent over the fade duration.
+    pub steps: u32,
+    pub curve: FadeCurve,
+}
+
+impl Default for FadeOptions {
+    fn default() -> Self {
+        Self {
+            steps: 20,
+            curve: FadeCurve::Linear,
+        }
+    }
+}
+
+/// Intermediate volumes of a fade from `from` to `to`, ending exactly at `to`.
+pub(crate) fn fade_steps(from: f64, to: f64, options: FadeOptions) -> Vec<f64> {
+    let steps = options.steps.max(1);
+    (1..=steps)
+        .map(|step| {
+            if step == steps {
+                return to;
+            }
+            let progress = options.curve.apply(f64::from(step) / f64::from(steps));
+            (from + (to - from) * progress).clamp(0.0, 1.0)
+        })
+        .collect()
+}
+
+/// Like [`fade_steps`], but moving evenly along `volume_curve` instead of in
+/// raw volume levels.
+pub(crate) fn curved_fade_steps(from: f64, to: f64, options: FadeOptions, volume_curve: &VolumeCurve) -> Vec<f64> {
+    let mut steps: Vec<f64> = fade_steps(volume_curve.to_position(from), volume_curve.to_position(to), options)
+        .into_iter()
+        .map(|position| volume_curve.to_volume(position))
+        .collect();
+    // Mapping back and forth may be off by rounding; the fade ends on `to`.
+    if let Some(last) = steps.last_mut() {
+        *last = to;
+    }
+    steps
+}
+
+/// A volume moving from one snapshot to another during a crossfade.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CrossfadeTrack {
+    pub channel: String,
+    pub slider: Option<StreamerSlider>,
+    /// One volume per step, as returned by [`curved_fade_steps`].
+    pub steps: Vec<f64>,
+}
+
+/// The volumes that change between `from` and `to`, each with its fade
+/// steps.
+///
+/// Volumes are compared in the mode of `to`, like [`SonarSnapshot::diff`];
+/// channels missing from either snapshot are skipped.
+pub(crate) fn crossfade_tracks(
+    from: &SonarSnapshot,
+    to: &SonarSnapshot,
+    options: FadeOptions,
+    volume_curve: &VolumeCurve,
+) -> Vec<CrossfadeTrack> {
+    let sliders: Vec<Option<StreamerSlider>> = if to.streamer_mode {
+        StreamerSlider::ALL.into_iter().map(Some).collect()
+    } else {
+        vec![None]
+    };
+    let mut tracks = Vec::new();
+    for (channel, volumes) in &to.volumes {
+        let Some(old_volumes) = from.volumes.get(channel) else {
+            continue;
+        };
+        for &slider in &sliders {
+            let start = old_volumes.state(to.streamer_mode, slider).volume;
+            let end = volumes.state(to.streamer_mode, slider).volume;
+            if start != end {
+                tracks.push(CrossfadeTrack {
+                    channel: channel.clone(),
+                    slider,
+                    steps: curved_fade_steps(start, end, options, volume_curve),
+                });
+            }
+        }
+    }
+    tracks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_linear_fade_steps() {
+        let options = FadeOptions {
+            steps: 4,
+            curve: FadeCurve::Linear,
+        };
+        assert_eq!(fade_steps(0.0, 1.0, options), [0.25, 0.5, 0.75, 1.0]);
+        assert_eq!(fade_steps(1.0, 0.0, options), [0.75, 0.5, 0.25, 0.0]);
+    }
+
+    #[test]
+    fn test_fade_always_reaches_target() {
+        for curve in [FadeCurve::Linear, FadeCurve::EaseIn, FadeCurve::EaseOut, FadeCurve::EaseInOut] {
+            let steps = fade_steps(0.2, 0.9, FadeOptions { steps: 7, curve });
+            assert_eq!(steps.len(), 7);
+            assert_eq!(steps.last().copied(), Some(0.9));
+            assert!(steps.windows(2).all(|pair| pair[0] <= pair[1]));
+        }
+
+        let steps = fade_steps(0.2, 0.9, FadeOptions { steps: 0, ..Default::default() });
+        assert_eq!(steps, [0.9]);
+    }
+
+    #[test]
+    fn test_curved_fade_steps() {
+        let options = FadeOptions {
+            steps: 4,
+            curve: FadeCurve::Linear,
+        };
+        assert_eq!(curved_fade_steps(0.0, 1.0, options, &VolumeCurve::Linear), [0.25, 0.5, 0.75, 1.0]);
+
+        let steps = curved_fade_steps(0.0, 0.8, options, &VolumeCurve::Logarithmic);
+        assert_eq!(steps.last().copied(), Some(0.8));
+        assert!(steps[1] < 0.4, "the first half of a logarithmic fade stays quiet");
+        assert!(steps.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn test_crossfade_tracks() {
+        use crate::volume::{ChannelVolumes, VolumeState};
+        use std::collections::BTreeMap;
+
+        let classic = |volume| ChannelVolumes {
+            classic: VolumeState { volume, muted: false },
+            ..ChannelVolumes::default()
+        };
+        let snapshot = |game, media| SonarSnapshot {
+            streamer_mode: false,
+            volumes: BTreeMap::from([
+                ("game".to_string(), classic(game)),
+                ("media".to_string(), classic(media)),
+            ]),
+            chat_mix: 0.0,
+            redirections: Vec::new(),
+            stream_redirections: Vec::new(),
+            selected_configs: Vec::new(),
+        };
+        let options = FadeOptions {
+            steps: 2,
+            curve: FadeCurve::Linear,
+        };
+
+        let curve = VolumeCurve::Linear;
+        assert_eq!(
+            crossfade_tracks(&snapshot(1.0, 0.5), &snapshot(0.0, 0.5), options, &curve),
+            [CrossfadeTrack {
+                channel: "game".to_string(),
+                slider: None,
+                steps: vec![0.5, 0.0],
+            }]
+        );
+        assert!(crossfade_tracks(&snapshot(1.0, 0.5), &snapshot(1.0, 0.5), options, &curve).is_empty());
+    }
+}
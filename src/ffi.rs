@@ -0,0 +1,301 @@
+//! C ABI over [`BlockingSonar`], for C, C++ and C# applications, OBS plugins
+//! or AutoHotkey scripts via `DllCall`.
+//!
+//! Build the shared library with
+//! `cargo rustc --lib --release --features ffi --crate-type cdylib`. The
+//! functions are declared in `include/steelseries_sonar.h`. Every
+//! function except [`sonar_free`] and [`sonar_last_error`] returns
+//! [`SONAR_OK`] or one of the `SONAR_ERROR_*` codes and passes results through
+//! out pointers. The message of the last failed call on a thread is available
+//! from [`sonar_last_error`].
+
+use crate::blocking::BlockingSonar;
+use crate::error::{Result, SonarError};
+use crate::sonar::StreamerSlider;
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+/// The call succeeded.
+pub const SONAR_OK: i32 = 0;
+/// A pointer was null, a string was not valid UTF-8 or a slider was unknown.
+pub const SONAR_ERROR_INVALID_ARGUMENT: i32 = 1;
+/// SteelSeries GG or Sonar is not installed, enabled or running.
+pub const SONAR_ERROR_NOT_RUNNING: i32 = 2;
+pub const SONAR_ERROR_CHANNEL_NOT_FOUND: i32 = 3;
+/// A volume or chat mix value was out of range.
+pub const SONAR_ERROR_INVALID_VOLUME: i32 = 4;
+pub const SONAR_ERROR_CHAT_MIX_UNAVAILABLE: i32 = 5;
+pub const SONAR_ERROR_TIMEOUT: i32 = 6;
+/// Any other failure, e.g. an unexpected response.
+pub const SONAR_ERROR_OTHER: i32 = 7;
+/// A bug in this library; the message describes it.
+pub const SONAR_ERROR_INTERNAL: i32 = 8;
+
+/// Use the mode's default slider: none in classic mode, streaming in
+/// streamer mode.
+pub const SONAR_SLIDER_DEFAULT: i32 = 0;
+pub const SONAR_SLIDER_STREAMING: i32 = 1;
+pub const SONAR_SLIDER_MONITORING: i32 = 2;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Why a call failed.
+enum Failure {
+    InvalidArgument(&'static str),
+    Sonar(SonarError),
+}
+
+impl From<SonarError> for Failure {
+    fn from(error: SonarError) -> Self {
+        Failure::Sonar(error)
+    }
+}
+
+/// Run `f`, translating errors and panics into status codes.
+fn call(f: impl FnOnce() -> std::result::Result<(), Failure>) -> i32 {
+    let (code, message) = match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => return SONAR_OK,
+        Ok(Err(Failure::InvalidArgument(message))) => (SONAR_ERROR_INVALID_ARGUMENT, message.to_string()),
+        Ok(Err(Failure::Sonar(error))) => (error_code(&error), error.to_string()),
+        Err(_) => (SONAR_ERROR_INTERNAL, "panicked".to_string()),
+    };
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+    code
+}
+
+fn error_code(error: &SonarError) -> i32 {
+    match error {
+        SonarError::EnginePathNotFound
+        | SonarError::ServerNotAccessible(_)
+        | SonarError::SonarNotEnabled
+        | SonarError::ServerNotReady
+        | SonarError::ServerNotRunning
+        | SonarError::WebServerAddressNotFound => SONAR_ERROR_NOT_RUNNING,
+        SonarError::ChannelNotFound(_) => SONAR_ERROR_CHANNEL_NOT_FOUND,
+        SonarError::SliderNotFound(_) => SONAR_ERROR_INVALID_ARGUMENT,
+        SonarError::InvalidVolume(_) | SonarError::InvalidVolumePercent(_) | SonarError::InvalidMixVolume(_) => {
+            SONAR_ERROR_INVALID_VOLUME
+        }
+        SonarError::ChatMixUnavailable => SONAR_ERROR_CHAT_MIX_UNAVAILABLE,
+        SonarError::Timeout => SONAR_ERROR_TIMEOUT,
+        error if error.is_connect() => SONAR_ERROR_NOT_RUNNING,
+        _ => SONAR_ERROR_OTHER,
+    }
+}
+
+/// # Safety
+///
+/// `sonar` must be null or come from [`sonar_new`] or [`sonar_connect_to`].
+unsafe fn client<'a>(sonar: *const BlockingSonar) -> std::result::Result<&'a BlockingSonar, Failure> {
+    unsafe { sonar.as_ref() }.ok_or(Failure::InvalidArgument("sonar is null"))
+}
+
+/// # Safety
+///
+/// `string` must be null or a NUL-terminated string.
+unsafe fn string<'a>(string: *const c_char) -> std::result::Result<&'a str, Failure> {
+    if string.is_null() {
+        return Err(Failure::InvalidArgument("string argument is null"));
+    }
+    unsafe { CStr::from_ptr(string) }
+        .to_str()
+        .map_err(|_| Failure::InvalidArgument("string argument is not valid UTF-8"))
+}
+
+fn slider(slider: i32) -> std::result::Result<Option<StreamerSlider>, Failure> {
+    match slider {
+        SONAR_SLIDER_DEFAULT => Ok(None),
+        SONAR_SLIDER_STREAMING => Ok(Some(StreamerSlider::Streaming)),
+        SONAR_SLIDER_MONITORING => Ok(Some(StreamerSlider::Monitoring)),
+        _ => Err(Failure::InvalidArgument("unknown slider")),
+    }
+}
+
+/// # Safety
+///
+/// `out` must be null or valid for writes.
+unsafe fn write<T>(out: *mut T, value: T) -> std::result::Result<(), Failure> {
+    if out.is_null() {
+        return Err(Failure::InvalidArgument("out pointer is null"));
+    }
+    unsafe { out.write(value) };
+    Ok(())
+}
+
+fn boxed(sonar: Result<BlockingSonar>) -> std::result::Result<*mut BlockingSonar, Failure> {
+    Ok(Box::into_raw(Box::new(sonar?)))
+}
+
+/// Discover SteelSeries GG and connect to Sonar, storing the client in `out`.
+///
+/// # Safety
+///
+/// `out` must be valid for writes. The client must be released with
+/// [`sonar_free`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sonar_new(out: *mut *mut BlockingSonar) -> i32 {
+    call(|| unsafe { write(out, boxed(BlockingSonar::new())?) })
+}
+
+/// Connect to the Sonar web server at `address`, skipping discovery.
+///
+/// # Safety
+///
+/// `address` must be a NUL-terminated string and `out` valid for writes. The
+/// client must be released with [`sonar_free`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sonar_connect_to(address: *const c_char, out: *mut *mut BlockingSonar) -> i32 {
+    call(|| unsafe {
+        let address = string(address)?;
+        write(out, boxed(BlockingSonar::connect_to(address))?)
+    })
+}
+
+/// Release a client. Null is ignored.
+///
+/// # Safety
+///
+/// `sonar` must be null or come from [`sonar_new`] or [`sonar_connect_to`],
+/// and must not be used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sonar_free(sonar: *mut BlockingSonar) {
+    if !sonar.is_null() {
+        drop(unsafe { Box::from_raw(sonar) });
+    }
+}
+
+/// Set the volume (0.0 to 1.0) of a channel.
+///
+/// # Safety
+///
+/// `sonar` must be a live client and `channel` a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sonar_set_volume(
+    sonar: *const BlockingSonar,
+    channel: *const c_char,
+    volume: f64,
+    streamer_slider: i32,
+) -> i32 {
+    call(|| unsafe {
+        client(sonar)?.set_volume(string(channel)?, volume, slider(streamer_slider)?)?;
+        Ok(())
+    })
+}
+
+/// Read the volume (0.0 to 1.0) of a channel into `out`.
+///
+/// # Safety
+///
+/// `sonar` must be a live client, `channel` a NUL-terminated string and `out`
+/// valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sonar_get_volume(
+    sonar: *const BlockingSonar,
+    channel: *const c_char,
+    streamer_slider: i32,
+    out: *mut f64,
+) -> i32 {
+    call(|| unsafe {
+        let state = client(sonar)?.get_channel_state(string(channel)?, slider(streamer_slider)?)?;
+        write(out, state.volume)
+    })
+}
+
+/// Mute or unmute a channel.
+///
+/// # Safety
+///
+/// `sonar` must be a live client and `channel` a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sonar_mute(
+    sonar: *const BlockingSonar,
+    channel: *const c_char,
+    muted: bool,
+    streamer_slider: i32,
+) -> i32 {
+    call(|| unsafe {
+        client(sonar)?.mute_channel(string(channel)?, muted, slider(streamer_slider)?)?;
+        Ok(())
+    })
+}
+
+/// Read whether a channel is muted into `out`.
+///
+/// # Safety
+///
+/// `sonar` must be a live client, `channel` a NUL-terminated string and `out`
+/// valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sonar_is_muted(
+    sonar: *const BlockingSonar,
+    channel: *const c_char,
+    streamer_slider: i32,
+    out: *mut bool,
+) -> i32 {
+    call(|| unsafe {
+        let state = client(sonar)?.get_channel_state(string(channel)?, slider(streamer_slider)?)?;
+        write(out, state.muted)
+    })
+}
+
+/// Set the chat mix balance (-1.0 for all game to 1.0 for all chat).
+///
+/// # Safety
+///
+/// `sonar` must be a live client.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sonar_set_chat_mix(sonar: *const BlockingSonar, mix_volume: f64) -> i32 {
+    call(|| unsafe {
+        client(sonar)?.set_chat_mix(mix_volume)?;
+        Ok(())
+    })
+}
+
+/// The message of the last failed call on this thread, or null if none
+/// failed. Valid until the next failed call on the same thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn sonar_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |message| message.as_ptr()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn last_error() -> String {
+        unsafe { CStr::from_ptr(sonar_last_error()) }.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_invalid_arguments() {
+        let channel = c"game";
+        unsafe {
+            assert_eq!(sonar_set_volume(ptr::null(), channel.as_ptr(), 0.5, SONAR_SLIDER_DEFAULT), SONAR_ERROR_INVALID_ARGUMENT);
+            assert_eq!(last_error(), "sonar is null");
+            assert_eq!(sonar_connect_to(ptr::null(), ptr::null_mut()), SONAR_ERROR_INVALID_ARGUMENT);
+            sonar_free(ptr::null_mut());
+        }
+        assert!(matches!(slider(3), Err(Failure::InvalidArgument(_))));
+        assert_eq!(error_code(&SonarError::ChannelNotFound("x".into())), SONAR_ERROR_CHANNEL_NOT_FOUND);
+        assert_eq!(error_code(&SonarError::SonarNotEnabled), SONAR_ERROR_NOT_RUNNING);
+    }
+
+    #[test]
+    fn test_header_declares_exports() {
+        let header = include_str!("../include/steelseries_sonar.h");
+        let source = include_str!("ffi.rs");
+        for line in source.lines().filter(|line| line.contains("extern \"C\" fn ")) {
+            let name = line.split("fn ").nth(1).and_then(|rest| rest.split('(').next()).unwrap();
+            assert!(header.contains(&format!(" {name}(")), "{name} missing from the header");
+        }
+        for line in source.lines().filter(|line| line.starts_with("pub const SONAR_")) {
+            let name = line["pub const ".len()..].split(':').next().unwrap();
+            assert!(header.contains(&format!("#define {name} ")), "{name} missing from the header");
+        }
+    }
+}
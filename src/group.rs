@@ -0,0 +1,127 @@
+//! Linked channel groups.
+
+use crate::sonar::{Channel, StreamerSlider};
+use crate::volume::VolumeSettings;
+use serde::{Deserialize, Serialize};
+
+/// Channels controlled together, like ganged faders.
+///
+/// The volume of a group is the volume of its loudest member. Setting it with
+/// [`Sonar::set_group_volume`](crate::Sonar::set_group_volume) scales every
+/// member by the same factor, so their relative balance is kept; muting it
+/// with [`Sonar::mute_group`](crate::Sonar::mute_group) mutes every member.
+///
+/// ```
+/// use steelseries_sonar::{Channel, ChannelGroup};
+///
+/// let voice = ChannelGroup::new([Channel::ChatRender, Channel::Aux]);
+/// assert_eq!(voice.channels(), [Channel::ChatRender, Channel::Aux]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ChannelGroup {
+    channels: Vec<Channel>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    slider: Option<StreamerSlider>,
+}
+
+impl ChannelGroup {
+    /// Group `channels`. Repeated channels are only kept once.
+    pub fn new(channels: impl IntoIterator<Item = Channel>) -> Self {
+        let mut group = Self::default();
+        for channel in channels {
+            if !group.channels.contains(&channel) {
+                group.channels.push(channel);
+            }
+        }
+        group
+    }
+
+    /// The streamer slider to control in streamer mode. Ignored in classic mode.
+    pub fn with_slider(mut self, slider: StreamerSlider) -> Self {
+        self.slider = Some(slider);
+        self
+    }
+
+    /// The member channels.
+    pub fn channels(&self) -> &[Channel] {
+        &self.channels
+    }
+
+    /// The streamer slider controlled in streamer mode.
+    pub fn slider(&self) -> Option<StreamerSlider> {
+        self.slider
+    }
+
+    /// The group volume in `volumes`: the volume of the loudest member, or 0.0
+    /// for an empty group.
+    pub fn volume(&self, volumes: &VolumeSettings, streamer_mode: bool) -> f64 {
+        self.member_volumes(volumes, streamer_mode)
+            .map(|(_, volume)| volume)
+            .fold(0.0, f64::max)
+    }
+
+    /// The member volumes that bring the group volume to `target`.
+    ///
+    /// Members are scaled proportionally. If every member is silent there is
+    /// no balance to keep, and all of them are set to `target`.
+    pub(crate) fn scaled_volumes(&self, volumes: &VolumeSettings, streamer_mode: bool, target: f64) -> Vec<(Channel, f64)> {
+        let current = self.volume(volumes, streamer_mode);
+        let factor = target / current;
+        self.member_volumes(volumes, streamer_mode)
+            .map(|(channel, volume)| {
+                let scaled = if current > 0.0 { volume * factor } else { target };
+                (channel, scaled.clamp(0.0, 1.0))
+            })
+            .collect()
+    }
+
+    fn member_volumes<'a>(
+        &'a self,
+        volumes: &'a VolumeSettings,
+        streamer_mode: bool,
+    ) -> impl Iterator<Item = (Channel, f64)> + 'a {
+        self.channels.iter().map(move |channel| {
+            let volume = volumes
+                .channel(channel.as_str())
+                .map_or(0.0, |state| state.state(streamer_mode, self.slider).volume);
+            (*channel, volume)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn volumes(game: f64, media: f64) -> VolumeSettings {
+        VolumeSettings::from_value(json!({
+            "masters": {"classic": {"volume": 1.0, "muted": false}},
+            "devices": {
+                "game": {"classic": {"volume": game, "muted": false}},
+                "media": {"classic": {"volume": media, "muted": false}},
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_channel_group() {
+        let group = ChannelGroup::new([Channel::Game, Channel::Media, Channel::Game]);
+        assert_eq!(group.channels(), [Channel::Game, Channel::Media]);
+
+        let settings = volumes(0.8, 0.4);
+        assert_eq!(group.volume(&settings, false), 0.8);
+        assert_eq!(
+            group.scaled_volumes(&settings, false, 0.4),
+            [(Channel::Game, 0.4), (Channel::Media, 0.2)]
+        );
+
+        let silent = volumes(0.0, 0.0);
+        assert_eq!(
+            group.scaled_volumes(&silent, false, 0.5),
+            [(Channel::Game, 0.5), (Channel::Media, 0.5)]
+        );
+        assert_eq!(ChannelGroup::default().volume(&settings, false), 0.0);
+    }
+}
@@ -0,0 +1,223 @@
+//! RAII guards for temporary changes to the Sonar mixer.
+
+use crate::error::{Result, SonarError};
+use crate::sonar::{Mode, StreamerSlider};
+use crate::volume::VolumeState;
+use crate::Sonar;
+use serde_json::Value;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+/// Keeps a channel muted for as long as the guard is alive.
+///
+/// Created by [`Sonar::mute_guard`](crate::Sonar::mute_guard). The channel is
+/// unmuted when the guard is dropped. Since `Drop` cannot wait for a request,
+/// the unmute is spawned onto the current tokio runtime on a best-effort basis;
+/// call [`MuteGuard::release`] instead when you need to know it succeeded.
+#[derive(Debug)]
+#[must_use = "the channel is unmuted as soon as the guard is dropped"]
+pub struct MuteGuard {
+    sonar: Option<Sonar>,
+    mode: Mode,
+    channel: &'static str,
+    streamer_slider: Option<StreamerSlider>,
+}
+
+impl MuteGuard {
+    pub(crate) fn new(sonar: Sonar, mode: Mode, channel: &'static str, streamer_slider: Option<StreamerSlider>) -> Self {
+        Self {
+            sonar: Some(sonar),
+            mode,
+            channel,
+            streamer_slider,
+        }
+    }
+
+    /// Unmute the channel now and wait for the server to confirm it.
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "sonar.unmute", level = "debug", skip_all, err(Display)))]
+    pub async fn release(mut self) -> Result<Value> {
+        match self.sonar.take() {
+            Some(sonar) => sonar.end_mute_guard(self.mode, self.channel, self.streamer_slider).await,
+            None => Ok(Value::Null),
+        }
+    }
+}
+
+impl Drop for MuteGuard {
+    fn drop(&mut self) {
+        let Some(sonar) = self.sonar.take() else {
+            return;
+        };
+
+        // Without a runtime there is nothing to drive the request; the channel
+        // stays muted in that case.
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            let (mode, channel, streamer_slider) = (self.mode, self.channel, self.streamer_slider);
+            handle.spawn(async move {
+                let _result = sonar.end_mute_guard(mode, channel, streamer_slider).await;
+                #[cfg(feature = "tracing")]
+                if let Err(error) = _result {
+                    tracing::warn!(channel, error = %error, "unmuting a dropped MuteGuard failed");
+                }
+            });
+        }
+    }
+}
+
+/// The saved state of one channel, or one slider of it in streamer mode.
+pub(crate) type SavedState = (&'static str, Option<StreamerSlider>, VolumeState);
+
+/// Restores the volume and mute state of channels when dropped.
+///
+/// Created by [`Sonar::scoped`](crate::Sonar::scoped), which saves the state
+/// of the given channels. Any changes made to them while the guard is alive,
+/// e.g. muting media during an announcement, are undone when it goes out of
+/// scope, also while unwinding from a panic. As with [`MuteGuard`], the
+/// restore on drop is spawned onto the current tokio runtime; call
+/// [`RestoreGuard::restore`] to wait for it and see errors.
+#[derive(Debug)]
+#[must_use = "the channels are restored as soon as the guard is dropped"]
+pub struct RestoreGuard {
+    sonar: Option<Sonar>,
+    mode: Mode,
+    states: Vec<SavedState>,
+}
+
+impl RestoreGuard {
+    pub(crate) fn new(sonar: Sonar, mode: Mode, states: Vec<SavedState>) -> Self {
+        Self {
+            sonar: Some(sonar),
+            mode,
+            states,
+        }
+    }
+
+    /// The saved state of `channel`, or of one of its sliders in streamer mode.
+    pub fn saved(&self, channel: &str, streamer_slider: Option<StreamerSlider>) -> Option<VolumeState> {
+        self.states
+            .iter()
+            .find(|(saved, slider, _)| *saved == channel && *slider == streamer_slider)
+            .map(|(_, _, state)| *state)
+    }
+
+    /// Restore the saved state now and wait for the server to confirm it.
+    ///
+    /// Every channel is restored even if some fail; the first error is
+    /// returned.
+    pub async fn restore(mut self) -> Result<()> {
+        match self.sonar.take() {
+            Some(sonar) => sonar.restore_states(self.mode, &self.states).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Keep the current state instead of restoring the saved one.
+    pub fn disarm(mut self) {
+        self.sonar = None;
+    }
+}
+
+impl Drop for RestoreGuard {
+    fn drop(&mut self) {
+        let Some(sonar) = self.sonar.take() else {
+            return;
+        };
+
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            let mode = self.mode;
+            let states = std::mem::take(&mut self.states);
+            handle.spawn(async move {
+                let _result = sonar.restore_states(mode, &states).await;
+                #[cfg(feature = "tracing")]
+                if let Err(error) = _result {
+                    tracing::warn!(error = %error, "restoring a dropped RestoreGuard failed");
+                }
+            });
+        }
+    }
+}
+
+/// A channel muted for a while by [`Sonar::mute_for`](crate::Sonar::mute_for).
+///
+/// The channel is unmuted when the time is up, unless it was muted already
+/// or has been unmuted by hand in the meantime. Dropping the handle does not
+/// stop the timer.
+#[derive(Debug)]
+pub struct TimedMute {
+    /// When to unmute; `None` once cancelled.
+    deadline: watch::Sender<Option<Instant>>,
+    task: JoinHandle<Result<bool>>,
+}
+
+impl TimedMute {
+    pub(crate) fn start(sonar: Sonar, mode: Mode, channel: &'static str, streamer_slider: Option<StreamerSlider>, duration: Duration, was_muted: bool) -> Self {
+        let (deadline, receiver) = watch::channel(Some(Instant::now() + duration));
+        let task = tokio::spawn(async move {
+            if !wait_for_deadline(receiver).await || was_muted {
+                return Ok(false);
+            }
+            sonar.end_timed_mute(mode, channel, streamer_slider).await
+        });
+        Self { deadline, task }
+    }
+
+    /// Time left until the channel is unmuted, `None` once cancelled or over.
+    pub fn remaining(&self) -> Option<Duration> {
+        let deadline = (*self.deadline.borrow())?;
+        (!self.task.is_finished()).then(|| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Keep the channel muted for `duration` longer.
+    pub fn extend(&self, duration: Duration) {
+        self.deadline.send_modify(|deadline| {
+            if let Some(deadline) = deadline {
+                *deadline += duration;
+            }
+        });
+    }
+
+    /// Stop the timer, leaving the channel muted.
+    pub fn cancel(self) {
+        self.deadline.send_replace(None);
+    }
+
+    /// Unmute the channel now instead of when the time is up.
+    ///
+    /// Returns whether the channel was unmuted, see [`wait`](Self::wait).
+    pub async fn unmute_now(self) -> Result<bool> {
+        self.deadline.send_modify(|deadline| {
+            if deadline.is_some() {
+                *deadline = Some(Instant::now());
+            }
+        });
+        self.wait().await
+    }
+
+    /// Wait until the time is up. Returns whether the channel was unmuted;
+    /// it is not if it was muted before, or has been unmuted in the meantime.
+    pub async fn wait(self) -> Result<bool> {
+        self.task.await.unwrap_or(Err(SonarError::Cancelled))
+    }
+}
+
+/// Sleep until the deadline, following changes to it. Returns false if the
+/// timer was cancelled.
+async fn wait_for_deadline(mut deadline: watch::Receiver<Option<Instant>>) -> bool {
+    loop {
+        let Some(until) = *deadline.borrow_and_update() else {
+            return false;
+        };
+        tokio::select! {
+            () = tokio::time::sleep_until(until) => return true,
+            changed = deadline.changed() => {
+                // The handle is gone, so the deadline is final.
+                if changed.is_err() {
+                    tokio::time::sleep_until(until).await;
+                    return true;
+                }
+            }
+        }
+    }
+}
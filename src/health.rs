@@ -0,0 +1,96 @@
+//! Health checks of the SteelSeries Engine and the Sonar web server.
+
+use crate::error::SonarError;
+use crate::sonar::SubApp;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How often readiness is polled while waiting for Sonar to start.
+pub(crate) const READY_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How long to wait for Sonar to start after enabling it or launching GG,
+/// unless a ready timeout is configured.
+pub(crate) const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Result of [`Sonar::ping`](crate::Sonar::ping).
+///
+/// The sub-app flags come from the SteelSeries Engine. When the Engine cannot
+/// be asked, because it did not answer or the client was connected to the web
+/// server directly (see [`Sonar::connect_to`](crate::Sonar::connect_to)), they
+/// mirror `reachable`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HealthStatus {
+    /// Whether the Sonar web server answered.
+    pub reachable: bool,
+    /// Whether the Sonar sub-app is enabled in SteelSeries GG.
+    pub enabled: bool,
+    pub ready: bool,
+    pub running: bool,
+    /// Round-trip time of the web server request, `None` if it failed.
+    pub round_trip: Option<Duration>,
+}
+
+impl HealthStatus {
+    /// Combine the Engine's view of the Sonar sub-app (`None` if unknown)
+    /// with the outcome of a web server request.
+    pub(crate) fn new(sub_app: Option<&SubApp>, round_trip: Option<Duration>) -> Self {
+        let reachable = round_trip.is_some();
+        Self {
+            reachable,
+            enabled: sub_app.map_or(reachable, |sub_app| sub_app.is_enabled),
+            ready: sub_app.map_or(reachable, |sub_app| sub_app.is_ready),
+            running: sub_app.map_or(reachable, |sub_app| sub_app.is_running),
+            round_trip,
+        }
+    }
+
+    /// Whether Sonar is enabled, ready, running and answering requests.
+    pub fn is_ready(&self) -> bool {
+        self.reachable && self.enabled && self.ready && self.running
+    }
+}
+
+/// Whether connecting failed in a way that goes away once SteelSeries GG has
+/// finished starting.
+pub(crate) fn is_starting_up(error: &SonarError) -> bool {
+    matches!(
+        error,
+        SonarError::EnginePathNotFound
+            | SonarError::ServerNotAccessible(_)
+            | SonarError::Api { .. }
+            | SonarError::ServerNotReady
+            | SonarError::ServerNotRunning
+            | SonarError::WebServerAddressNotFound
+            | SonarError::Timeout
+            | SonarError::Io(_)
+            | SonarError::Json(_)
+    ) || error.is_connect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sonar::SubAppMetadata;
+
+    #[test]
+    fn test_health_status() {
+        let sub_app = SubApp {
+            is_enabled: true,
+            is_ready: false,
+            is_running: true,
+            metadata: SubAppMetadata {
+                web_server_address: String::new(),
+            },
+        };
+        let status = HealthStatus::new(Some(&sub_app), None);
+        assert!(!status.reachable && status.enabled && !status.ready);
+        assert!(!status.is_ready());
+
+        let status = HealthStatus::new(None, Some(Duration::from_millis(3)));
+        assert!(status.is_ready());
+        assert!(!HealthStatus::new(None, None).enabled);
+
+        assert!(is_starting_up(&SonarError::ServerNotReady));
+        assert!(!is_starting_up(&SonarError::SonarNotEnabled));
+    }
+}
@@ -0,0 +1,82 @@
+//! The thread listening for global hotkeys.
+//!
+//! `global-hotkey` reports every registered hotkey through one process-wide
+//! channel, so only one listener should run at a time.
+
+use crate::error::{Result, SonarError};
+use global_hotkey::hotkey::HotKey;
+use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager};
+use std::io;
+use std::sync::mpsc as std_mpsc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// How long the thread waits for an event before checking whether it is
+/// still needed, and pumping window messages on Windows.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Parse a hotkey like `"Ctrl+Shift+KeyT"` or `"F13"`.
+pub(crate) fn parse_hotkey(hotkey: &str) -> Result<HotKey> {
+    hotkey
+        .parse()
+        .map_err(|error: global_hotkey::hotkey::HotKeyParseError| SonarError::InvalidHotkey(format!("{}: {}", hotkey, error)))
+}
+
+/// Register `hotkeys` on a new thread and receive their events. The thread
+/// unregisters them and exits shortly after the receiver is dropped.
+pub(crate) fn listen(hotkeys: Vec<HotKey>) -> Result<(mpsc::UnboundedReceiver<GlobalHotKeyEvent>, JoinHandle<()>)> {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let (registered, registration) = std_mpsc::sync_channel(1);
+
+    let thread = std::thread::Builder::new()
+        .name("sonar-hotkeys".to_string())
+        .spawn(move || {
+            // The manager has to live on the thread pumping its messages.
+            let manager = match GlobalHotKeyManager::new().and_then(|manager| manager.register_all(&hotkeys).map(|()| manager)) {
+                Ok(manager) => manager,
+                Err(error) => {
+                    let _ = registered.send(Err(error));
+                    return;
+                }
+            };
+            let _ = registered.send(Ok(()));
+
+            let ids: Vec<u32> = hotkeys.iter().map(HotKey::id).collect();
+            let events = GlobalHotKeyEvent::receiver();
+            while !sender.is_closed() {
+                pump_messages();
+                if let Ok(event) = events.recv_timeout(POLL_INTERVAL)
+                    && ids.contains(&event.id)
+                    && sender.send(event).is_err()
+                {
+                    break;
+                }
+            }
+            let _ = manager.unregister_all(&hotkeys);
+        })?;
+
+    match registration.recv() {
+        Ok(Ok(())) => Ok((receiver, thread)),
+        Ok(Err(error)) => Err(SonarError::Io(io::Error::other(error))),
+        Err(error) => Err(SonarError::Io(io::Error::other(error))),
+    }
+}
+
+/// Dispatch the window messages through which Windows delivers hotkeys.
+#[cfg(windows)]
+fn pump_messages() {
+    use windows::Win32::UI::WindowsAndMessaging::{DispatchMessageW, PeekMessageW, TranslateMessage, MSG, PM_REMOVE};
+
+    let mut message = MSG::default();
+    // SAFETY: `message` outlives the calls, which only read and write it.
+    unsafe {
+        while PeekMessageW(&mut message, None, 0, 0, PM_REMOVE).as_bool() {
+            let _ = TranslateMessage(&message);
+            DispatchMessageW(&message);
+        }
+    }
+}
+
+#[cfg(not(windows))]
+fn pump_messages() {}
@@ -0,0 +1,204 @@
+//! Global hotkeys bound to mixer actions.
+//!
+//! [`Sonar::hotkeys`](crate::Sonar::hotkeys) registers the key combinations
+//! of a [`HotkeyBindings`] and runs the bound [`HotkeyAction`] whenever one
+//! is pressed. The hotkeys are watched on a background thread until the
+//! returned [`Hotkeys`] is stopped or dropped.
+//!
+//! Hotkeys are global on Windows and on Linux with X11. macOS only delivers
+//! them to the main thread, so they do not work there. Only one set of
+//! hotkeys, or [push-to-talk](crate::Sonar::push_to_talk), can be active at
+//! a time.
+
+use crate::cancel::CancellationToken;
+use crate::error::{Result, SonarError};
+use crate::hotkey_listener::{listen, parse_hotkey};
+use crate::sonar::{Channel, Mode, Sonar, StreamerSlider};
+use global_hotkey::hotkey::HotKey;
+use global_hotkey::{GlobalHotKeyEvent, HotKeyState};
+use std::collections::HashMap;
+use std::thread;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+const EVENT_BUFFER: usize = 16;
+
+/// What a hotkey does.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HotkeyAction {
+    /// Mute or unmute a channel, depending on its current state.
+    ToggleMute { channel: Channel, slider: Option<StreamerSlider> },
+    /// Change the volume of a channel by `delta` (-1.0 to 1.0).
+    VolumeStep { channel: Channel, delta: f64, slider: Option<StreamerSlider> },
+    /// Apply a saved profile, see [`Sonar::load_profile`](crate::Sonar::load_profile).
+    LoadProfile(String),
+    /// Switch between classic and streamer mode.
+    ToggleMode,
+}
+
+/// Key combinations and the actions bound to them.
+///
+/// Hotkeys are written like `"Ctrl+Shift+KeyM"`, `"Alt+ArrowUp"` or `"F13"`.
+///
+/// ```
+/// use steelseries_sonar::{Channel, HotkeyAction, HotkeyBindings};
+///
+/// let bindings = HotkeyBindings::new()
+///     .bind("Ctrl+Shift+KeyM", HotkeyAction::ToggleMute { channel: Channel::ChatCapture, slider: None })
+///     .bind("Ctrl+Shift+ArrowUp", HotkeyAction::VolumeStep { channel: Channel::Game, delta: 0.05, slider: None })
+///     .bind("Ctrl+Shift+KeyS", HotkeyAction::ToggleMode);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HotkeyBindings {
+    bindings: Vec<(String, HotkeyAction)>,
+}
+
+impl HotkeyBindings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `action` when `hotkey` is pressed.
+    pub fn bind(mut self, hotkey: impl Into<String>, action: HotkeyAction) -> Self {
+        self.bindings.push((hotkey.into(), action));
+        self
+    }
+
+    /// The parsed hotkeys with their names and actions, failing on
+    /// unparseable or duplicate hotkeys.
+    fn resolve(&self) -> Result<Vec<(HotKey, String, HotkeyAction)>> {
+        let mut resolved: Vec<(HotKey, String, HotkeyAction)> = Vec::with_capacity(self.bindings.len());
+        for (hotkey, action) in &self.bindings {
+            let parsed = parse_hotkey(hotkey)?;
+            if resolved.iter().any(|(other, _, _)| other.id() == parsed.id()) {
+                return Err(SonarError::InvalidHotkey(format!("{}: bound twice", hotkey)));
+            }
+            resolved.push((parsed, hotkey.clone(), action.clone()));
+        }
+        Ok(resolved)
+    }
+}
+
+/// An action run by [`Hotkeys`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct HotkeyEvent {
+    /// The hotkey as it was bound.
+    pub hotkey: String,
+    pub action: HotkeyAction,
+}
+
+/// Handle to active hotkeys.
+///
+/// Stopping or dropping it unregisters the hotkeys.
+#[derive(Debug)]
+pub struct Hotkeys {
+    receiver: mpsc::Receiver<Result<HotkeyEvent>>,
+    token: CancellationToken,
+    task: Option<JoinHandle<()>>,
+}
+
+impl Hotkeys {
+    pub(crate) fn spawn(sonar: Sonar, bindings: HotkeyBindings) -> Result<Self> {
+        let resolved = bindings.resolve()?;
+        let (keys, thread) = listen(resolved.iter().map(|(hotkey, _, _)| *hotkey).collect())?;
+        let actions = resolved
+            .into_iter()
+            .map(|(hotkey, name, action)| (hotkey.id(), HotkeyEvent { hotkey: name, action }))
+            .collect();
+
+        let (sender, receiver) = mpsc::channel(EVENT_BUFFER);
+        let token = CancellationToken::new();
+        let task = tokio::spawn(run_loop(sonar, actions, keys, thread, token.clone(), sender));
+        Ok(Self {
+            receiver,
+            token,
+            task: Some(task),
+        })
+    }
+
+    /// Wait for the next action run, or the error it failed with.
+    ///
+    /// Returns `None` once the hotkeys have been stopped.
+    pub async fn recv(&mut self) -> Option<Result<HotkeyEvent>> {
+        self.receiver.recv().await
+    }
+
+    /// Stop handling the hotkeys, waiting until they are unregistered.
+    pub async fn stop(mut self) {
+        self.token.cancel();
+        if let Some(task) = self.task.take() {
+            let _ = task.await;
+        }
+    }
+}
+
+impl Drop for Hotkeys {
+    fn drop(&mut self) {
+        // The listener thread unregisters the hotkeys once the task is gone.
+        self.token.cancel();
+    }
+}
+
+async fn run_loop(
+    sonar: Sonar,
+    actions: HashMap<u32, HotkeyEvent>,
+    mut keys: mpsc::UnboundedReceiver<GlobalHotKeyEvent>,
+    thread: thread::JoinHandle<()>,
+    token: CancellationToken,
+    sender: mpsc::Sender<Result<HotkeyEvent>>,
+) {
+    loop {
+        let key = tokio::select! {
+            () = token.cancelled() => break,
+            key = keys.recv() => key,
+        };
+        let Some(key) = key else { break };
+        if key.state != HotKeyState::Pressed {
+            continue;
+        }
+        if let Some(event) = actions.get(&key.id) {
+            // Nobody listening is fine, the action is run anyway.
+            let _ = sender.try_send(run_action(&sonar, &event.action).await.map(|()| event.clone()));
+        }
+    }
+
+    drop(keys);
+    let _ = tokio::task::spawn_blocking(move || thread.join()).await;
+}
+
+async fn run_action(sonar: &Sonar, action: &HotkeyAction) -> Result<()> {
+    match action {
+        HotkeyAction::ToggleMute { channel, slider } => {
+            sonar.toggle_mute(channel.as_str(), *slider).await?;
+        }
+        HotkeyAction::VolumeStep { channel, delta, slider } => {
+            sonar.adjust_volume(channel.as_str(), *delta, *slider).await?;
+        }
+        HotkeyAction::LoadProfile(name) => sonar.load_profile(name).await?,
+        HotkeyAction::ToggleMode => {
+            let mode = sonar.get_mode().await?;
+            sonar.set_mode(Mode::from_streamer_mode(!mode.is_streamer())).await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_bindings() {
+        let bindings = HotkeyBindings::new()
+            .bind("Ctrl+Shift+KeyM", HotkeyAction::ToggleMode)
+            .bind("F13", HotkeyAction::LoadProfile("gaming".to_string()));
+        let resolved = bindings.resolve().unwrap();
+        assert_eq!(resolved.len(), 2);
+        assert_eq!(resolved[1].1, "F13");
+
+        let twice = bindings.bind("shift+ctrl+KeyM", HotkeyAction::ToggleMode);
+        assert!(matches!(twice.resolve(), Err(SonarError::InvalidHotkey(_))));
+        let invalid = HotkeyBindings::new().bind("Ctrl+Nope", HotkeyAction::ToggleMode);
+        assert!(matches!(invalid.resolve(), Err(SonarError::InvalidHotkey(_))));
+    }
+}
@@ -0,0 +1,323 @@
+//! A daemon sharing one Sonar connection with other local processes.
+//!
+//! [`IpcServer`] owns a [`Sonar`] client and serves requests over a Unix
+//! domain socket, or a named pipe on Windows, so several tools controlling
+//! Sonar at once don't each run discovery and their own TLS handshakes.
+//! [`IpcClient`] talks to it. The protocol is one JSON [`IpcRequest`] per
+//! line, each answered by one JSON [`IpcResponse`] line, so clients in other
+//! languages only need a socket and a JSON library.
+
+use crate::error::{Result, SonarError};
+use crate::scene::{ChangeOutcome, Scene};
+use crate::snapshot::SonarSnapshot;
+use crate::sonar::Sonar;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+/// A request to the daemon, e.g. `{"request": "snapshot"}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "request", rename_all = "camelCase")]
+pub enum IpcRequest {
+    /// Apply the changes of a scene, see [`Sonar::apply_scene`].
+    Apply { scene: Scene },
+    /// Read the mixer state, see [`Sonar::export_state`].
+    Snapshot,
+}
+
+/// The daemon's answer to an [`IpcRequest`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IpcResponse {
+    /// The scene was applied.
+    Done,
+    Snapshot(SonarSnapshot),
+    /// The request failed, with the error message.
+    Error(String),
+}
+
+/// The socket path or pipe name used when none is given:
+/// `\\.\pipe\steelseries-sonar` on Windows, `steelseries-sonar.sock` in the
+/// runtime directory (or the temp directory) elsewhere.
+pub fn default_endpoint() -> String {
+    #[cfg(windows)]
+    {
+        r"\\.\pipe\steelseries-sonar".to_string()
+    }
+    #[cfg(not(windows))]
+    {
+        dirs::runtime_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("steelseries-sonar.sock")
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+/// Serves a [`Sonar`] client to other processes.
+#[derive(Debug)]
+pub struct IpcServer {
+    sonar: Sonar,
+    listener: platform::Listener,
+}
+
+impl IpcServer {
+    /// Start listening on `endpoint`, a socket path or pipe name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the endpoint is in use by another daemon or
+    /// cannot be created.
+    pub fn bind(sonar: Sonar, endpoint: &str) -> Result<Self> {
+        Ok(Self {
+            sonar,
+            listener: platform::Listener::bind(endpoint)?,
+        })
+    }
+
+    /// Accept connections and answer their requests until accepting fails.
+    /// Each connection is served by its own task.
+    pub async fn run(mut self) -> Result<()> {
+        loop {
+            let stream = self.listener.accept().await?;
+            let sonar = self.sonar.clone();
+            tokio::spawn(async move {
+                if let Err(_error) = serve_connection(&sonar, stream).await {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(error = %_error, "IPC connection closed");
+                }
+            });
+        }
+    }
+}
+
+async fn serve_connection(sonar: &Sonar, stream: impl AsyncRead + AsyncWrite + Unpin) -> Result<()> {
+    let mut stream = BufReader::new(stream);
+    let mut line = String::new();
+    while stream.read_line(&mut line).await? > 0 {
+        let response = match serde_json::from_str(&line) {
+            Ok(request) => handle(sonar, request).await,
+            Err(error) => IpcResponse::Error(format!("invalid request: {}", error)),
+        };
+        line.clear();
+        write_line(stream.get_mut(), &response).await?;
+    }
+    Ok(())
+}
+
+async fn handle(sonar: &Sonar, request: IpcRequest) -> IpcResponse {
+    match request {
+        IpcRequest::Apply { scene } => {
+            let report = sonar.apply_scene(&scene).await;
+            let failure = report.outcomes.iter().find_map(|(change, outcome)| match outcome {
+                ChangeOutcome::Failed(error) => Some(format!("{}: {}", change, error)),
+                _ => None,
+            });
+            failure.map_or(IpcResponse::Done, IpcResponse::Error)
+        }
+        IpcRequest::Snapshot => match sonar.export_state().await {
+            Ok(snapshot) => IpcResponse::Snapshot(snapshot),
+            Err(error) => IpcResponse::Error(error.to_string()),
+        },
+    }
+}
+
+async fn write_line(stream: &mut (impl AsyncWrite + Unpin), message: &impl Serialize) -> Result<()> {
+    let mut line = serde_json::to_vec(message)?;
+    line.push(b'\n');
+    stream.write_all(&line).await?;
+    stream.flush().await?;
+    Ok(())
+}
+
+/// A connection to an [`IpcServer`].
+#[derive(Debug)]
+pub struct IpcClient {
+    stream: BufReader<platform::Stream>,
+}
+
+impl IpcClient {
+    /// Connect to the daemon listening on `endpoint`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no daemon is listening.
+    pub async fn connect(endpoint: &str) -> Result<Self> {
+        Ok(Self {
+            stream: BufReader::new(platform::connect(endpoint).await?),
+        })
+    }
+
+    /// Apply a scene through the daemon.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SonarError::Daemon`] with the first failed change if the
+    /// scene was not applied.
+    pub async fn apply(&mut self, scene: &Scene) -> Result<()> {
+        match self.request(&IpcRequest::Apply { scene: scene.clone() }).await? {
+            IpcResponse::Done => Ok(()),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    /// Read the mixer state through the daemon.
+    pub async fn snapshot(&mut self) -> Result<SonarSnapshot> {
+        match self.request(&IpcRequest::Snapshot).await? {
+            IpcResponse::Snapshot(snapshot) => Ok(snapshot),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    /// Send a request and wait for its response.
+    pub async fn request(&mut self, request: &IpcRequest) -> Result<IpcResponse> {
+        write_line(self.stream.get_mut(), request).await?;
+        let mut line = String::new();
+        if self.stream.read_line(&mut line).await? == 0 {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+        Ok(serde_json::from_str(&line)?)
+    }
+}
+
+fn unexpected(response: IpcResponse) -> SonarError {
+    match response {
+        IpcResponse::Error(message) => SonarError::Daemon(message),
+        response => SonarError::Daemon(format!("unexpected response {:?}", response)),
+    }
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::io;
+    use std::os::unix::fs::FileTypeExt;
+    use std::path::PathBuf;
+    use tokio::net::{UnixListener, UnixStream};
+
+    pub(super) type Stream = UnixStream;
+
+    #[derive(Debug)]
+    pub(super) struct Listener {
+        listener: UnixListener,
+        path: PathBuf,
+    }
+
+    impl Listener {
+        pub(super) fn bind(endpoint: &str) -> io::Result<Self> {
+            // A socket file nobody answers on was left behind by a daemon
+            // that did not shut down cleanly. Anything else at the path is
+            // left alone and fails the bind.
+            let is_socket = std::fs::symlink_metadata(endpoint).is_ok_and(|metadata| metadata.file_type().is_socket());
+            if is_socket && std::os::unix::net::UnixStream::connect(endpoint).is_err() {
+                let _ = std::fs::remove_file(endpoint);
+            }
+            Ok(Self {
+                listener: UnixListener::bind(endpoint)?,
+                path: PathBuf::from(endpoint),
+            })
+        }
+
+        pub(super) async fn accept(&mut self) -> io::Result<Stream> {
+            Ok(self.listener.accept().await?.0)
+        }
+    }
+
+    impl Drop for Listener {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+
+    pub(super) async fn connect(endpoint: &str) -> io::Result<Stream> {
+        UnixStream::connect(endpoint).await
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::io;
+    use std::time::Duration;
+    use tokio::net::windows::named_pipe::{ClientOptions, NamedPipeClient, NamedPipeServer, ServerOptions};
+
+    /// All instances of the pipe are busy serving other clients.
+    const ERROR_PIPE_BUSY: i32 = 231;
+    const BUSY_RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+    pub(super) type Stream = NamedPipeClient;
+
+    #[derive(Debug)]
+    pub(super) struct Listener {
+        endpoint: String,
+        /// The instance the next client connects to.
+        next: NamedPipeServer,
+    }
+
+    impl Listener {
+        pub(super) fn bind(endpoint: &str) -> io::Result<Self> {
+            Ok(Self {
+                endpoint: endpoint.to_string(),
+                next: ServerOptions::new().first_pipe_instance(true).create(endpoint)?,
+            })
+        }
+
+        pub(super) async fn accept(&mut self) -> io::Result<NamedPipeServer> {
+            self.next.connect().await?;
+            let next = ServerOptions::new().create(&self.endpoint)?;
+            Ok(std::mem::replace(&mut self.next, next))
+        }
+    }
+
+    pub(super) async fn connect(endpoint: &str) -> io::Result<Stream> {
+        loop {
+            match ClientOptions::new().open(endpoint) {
+                Err(error) if error.raw_os_error() == Some(ERROR_PIPE_BUSY) => {
+                    tokio::time::sleep(BUSY_RETRY_INTERVAL).await;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protocol() {
+        let request: IpcRequest = serde_json::from_str(r#"{"request": "snapshot"}"#).unwrap();
+        assert_eq!(request, IpcRequest::Snapshot);
+
+        let apply = IpcRequest::Apply { scene: Scene::new().mute("game", true) };
+        let json = serde_json::to_string(&apply).unwrap();
+        assert!(json.starts_with(r#"{"request":"apply","scene":"#));
+        assert_eq!(serde_json::from_str::<IpcRequest>(&json).unwrap(), apply);
+
+        assert_eq!(serde_json::to_string(&IpcResponse::Done).unwrap(), r#""done""#);
+        assert!(matches!(unexpected(IpcResponse::Error("boom".into())), SonarError::Daemon(message) if message == "boom"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_stale_socket() {
+        let path = std::env::temp_dir().join(format!("steelseries-sonar-stale-{}.sock", std::process::id()));
+        let endpoint = path.to_str().unwrap();
+
+        // A daemon that did not shut down cleanly leaves its socket behind.
+        drop(std::os::unix::net::UnixListener::bind(endpoint).unwrap());
+        assert!(path.exists());
+        let listener = platform::Listener::bind(endpoint).unwrap();
+
+        // A live daemon is not replaced.
+        assert!(platform::Listener::bind(endpoint).is_err());
+        assert!(platform::connect(endpoint).await.is_ok());
+
+        drop(listener);
+        assert!(!path.exists());
+
+        // Other files are not mistaken for stale sockets.
+        std::fs::write(&path, "not a socket").unwrap();
+        assert!(platform::Listener::bind(endpoint).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "not a socket");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
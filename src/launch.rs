@@ -0,0 +1,102 @@
+//! Starting SteelSeries GG when it is not running.
+
+use crate::error::Result;
+
+/// Process image of the SteelSeries GG client.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+const GG_EXECUTABLE: &str = "SteelSeriesGG.exe";
+
+/// Start SteelSeries GG unless it is already running, returning whether it
+/// was started.
+#[cfg(target_os = "windows")]
+pub(crate) fn launch_if_not_running() -> Result<bool> {
+    use crate::error::SonarError;
+
+    if windows::is_running()? {
+        return Ok(false);
+    }
+    let executable = windows::gg_executable().ok_or(SonarError::EnginePathNotFound)?;
+    #[cfg(feature = "tracing")]
+    tracing::info!(path = %executable.display(), "starting SteelSeries GG");
+    std::process::Command::new(executable).spawn()?;
+    Ok(true)
+}
+
+/// Launching is only supported on Windows; elsewhere GG is left alone.
+#[cfg(not(target_os = "windows"))]
+pub(crate) fn launch_if_not_running() -> Result<bool> {
+    Ok(false)
+}
+
+/// Whether the CSV output of `tasklist` lists a process with this image name.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn lists_process(tasklist_output: &str, image: &str) -> bool {
+    tasklist_output.lines().any(|line| {
+        line.split(',')
+            .next()
+            .is_some_and(|name| name.trim_matches('"').eq_ignore_ascii_case(image))
+    })
+}
+
+#[cfg(target_os = "windows")]
+mod windows {
+    //! Process lookup via `tasklist` and the GG install location.
+
+    use super::{lists_process, GG_EXECUTABLE};
+    use crate::error::Result;
+    use std::os::windows::process::CommandExt;
+    use std::path::PathBuf;
+    use std::process::Command;
+    use windows_registry::LOCAL_MACHINE;
+
+    /// Keeps `tasklist` from flashing a console window.
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    const DEFAULT_PROGRAM_FILES: &str = "C:\\Program Files";
+    const GG_DIR: &str = "SteelSeries\\GG";
+    const UNINSTALL_KEYS: [&str; 2] = [
+        "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Uninstall\\SteelSeries GG",
+        "SOFTWARE\\WOW6432Node\\Microsoft\\Windows\\CurrentVersion\\Uninstall\\SteelSeries GG",
+    ];
+
+    pub(super) fn is_running() -> Result<bool> {
+        let output = Command::new("tasklist")
+            .args(["/FI", &format!("IMAGENAME eq {}", GG_EXECUTABLE), "/NH", "/FO", "CSV"])
+            .creation_flags(CREATE_NO_WINDOW)
+            .output()?;
+        Ok(lists_process(&String::from_utf8_lossy(&output.stdout), GG_EXECUTABLE))
+    }
+
+    /// The GG executable in its install location from the registry, falling
+    /// back to the default location in Program Files.
+    pub(super) fn gg_executable() -> Option<PathBuf> {
+        let install_dirs = UNINSTALL_KEYS.iter().filter_map(|path| {
+            LOCAL_MACHINE
+                .open(path)
+                .and_then(|key| key.get_string("InstallLocation"))
+                .ok()
+                .filter(|dir| !dir.is_empty())
+                .map(PathBuf::from)
+        });
+        let default_dir = std::env::var_os("ProgramFiles")
+            .map_or_else(|| PathBuf::from(DEFAULT_PROGRAM_FILES), PathBuf::from)
+            .join(GG_DIR);
+
+        install_dirs
+            .chain(std::iter::once(default_dir))
+            .map(|dir| dir.join(GG_EXECUTABLE))
+            .find(|path| path.exists())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lists_process() {
+        let output = "\"SteelSeriesGG.exe\",\"8120\",\"Console\",\"1\",\"120,344 K\"\r\n";
+        assert!(lists_process(output, GG_EXECUTABLE));
+        assert!(!lists_process("INFO: No tasks are running which match the specified criteria.", GG_EXECUTABLE));
+        assert!(!lists_process("\"SteelSeriesGGClient.exe\",\"8121\"", GG_EXECUTABLE));
+    }
+}
@@ -9,7 +9,9 @@
 //! - Control volume levels for different audio channels
 //! - Mute/unmute specific channels
 //! - Manage chat mix settings
+//! - Temporarily mute channels with RAII guards
 //! - Support for both classic and streamer modes
+//! - Watch for mixer changes made outside your application
 //! - Async/await support with tokio
 //!
 //! ## Quick Start
@@ -36,10 +38,167 @@
 //! }
 //! ```
 
+// Without an HTTP backend only the data types are built, and the request
+// plumbing shared by the clients goes unused.
+#![cfg_attr(not(any(feature = "async", feature = "blocking", feature = "ureq")), allow(dead_code))]
+
+#[cfg(all(
+    any(feature = "async", feature = "blocking", feature = "ureq"),
+    not(any(feature = "rustls", feature = "native-tls"))
+))]
+compile_error!("enable either the `rustls` or the `native-tls` feature to select a TLS backend");
+
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!();
+
+#[cfg(feature = "async")]
+pub mod batch;
+#[cfg(feature = "uniffi")]
+pub mod bindings;
+pub mod builder;
+#[cfg(feature = "async")]
+pub mod cancel;
+pub mod capabilities;
+pub mod capture;
+pub mod cassette;
+#[cfg(feature = "async")]
+pub mod channel;
+#[cfg(any(feature = "async", feature = "blocking", feature = "ureq"))]
+pub mod client;
+#[cfg(any(feature = "async", feature = "blocking", feature = "ureq"))]
+mod client_core;
+pub mod config_file;
+pub mod configs;
+#[cfg(feature = "async")]
+pub mod debounce;
+#[cfg(feature = "core-props-watcher")]
+mod core_props;
+pub mod devices;
+mod discovery;
+#[cfg(feature = "windows-audio")]
+pub mod ducking;
+#[cfg(any(feature = "async", feature = "blocking", feature = "ureq"))]
+mod endpoint;
+pub mod eq;
 pub mod error;
+#[cfg(feature = "async")]
+pub mod fade;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod group;
+#[cfg(feature = "async")]
+pub mod guard;
+pub mod health;
+#[cfg(any(feature = "push-to-talk", feature = "hotkeys"))]
+mod hotkey_listener;
+#[cfg(feature = "hotkeys")]
+pub mod hotkeys;
+#[cfg(feature = "async")]
+pub mod ipc;
+mod launch;
+#[cfg(feature = "mock")]
+pub mod mock;
+#[cfg(feature = "obs")]
+pub mod obs;
+#[cfg(any(feature = "async", feature = "blocking", feature = "ureq"))]
+mod orchestration;
+#[cfg(any(feature = "async", feature = "blocking", feature = "ureq"))]
+mod processes;
+pub mod profiles;
+#[cfg(feature = "push-to-talk")]
+pub mod push_to_talk;
+pub mod rate_limit;
+pub mod retry;
+pub mod routing;
+pub mod scene;
+#[cfg(feature = "scheduler")]
+pub mod scheduler;
+#[cfg(feature = "scripting")]
+pub mod scripting;
+#[cfg(feature = "async")]
+pub mod session;
+pub mod snapshot;
 pub mod sonar;
+mod state_cache;
+#[cfg(feature = "async")]
+pub mod streamdeck;
+#[cfg(feature = "metrics")]
+mod telemetry;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+#[cfg(any(feature = "blocking", feature = "ureq"))]
 pub mod blocking;
+#[cfg(any(feature = "blocking", feature = "ureq"))]
+mod transport;
+pub mod validation;
+pub mod volume;
+#[cfg(feature = "async")]
+pub mod watch;
 
-pub use error::{Result, SonarError};
-pub use sonar::{Sonar, CHANNEL_NAMES, STREAMER_SLIDER_NAMES};
+#[cfg(feature = "async")]
+pub use batch::Batch;
+#[cfg(any(feature = "blocking", feature = "ureq"))]
+pub use builder::BlockingSonarBuilder;
+#[cfg(feature = "async")]
+pub use builder::SonarBuilder;
+#[cfg(any(feature = "blocking", feature = "ureq"))]
+pub use client::BlockingSonarClient;
+#[cfg(feature = "async")]
+pub use client::SonarClient;
+#[cfg(feature = "async")]
+pub use cancel::{cancellable, CancellationToken};
+pub use capabilities::Capabilities;
+pub use capture::CapturedRequest;
+pub use cassette::{Cassette, Interaction};
+#[cfg(feature = "async")]
+pub use channel::ChannelOps;
+pub use config_file::ConfigFile;
+pub use configs::SonarConfig;
+#[cfg(feature = "async")]
+pub use debounce::DebouncedSonar;
+pub use devices::{AudioDevice, DataFlow};
+#[cfg(feature = "windows-audio")]
+pub use ducking::{AutoDucker, DuckEvent, DuckingOptions};
+pub use eq::{EqBand, EqBandUpdate, Equalizer, EQ_GAIN_RANGE};
+pub use error::{ErrorKind, Result, SonarError};
+#[cfg(feature = "async")]
+pub use fade::{FadeCurve, FadeOptions};
+pub use group::ChannelGroup;
+#[cfg(feature = "async")]
+pub use guard::{MuteGuard, RestoreGuard, TimedMute};
+#[cfg(feature = "mock")]
+pub use mock::{MockSonar, MockState};
+#[cfg(feature = "obs")]
+pub use obs::{ObsEvent, ObsFollower, ObsOptions};
+pub use health::HealthStatus;
+#[cfg(feature = "hotkeys")]
+pub use hotkeys::{HotkeyAction, HotkeyBindings, HotkeyEvent, Hotkeys};
+#[cfg(feature = "async")]
+pub use ipc::{IpcClient, IpcRequest, IpcResponse, IpcServer};
+pub use profiles::{list_profiles, ProfileStore};
+#[cfg(feature = "push-to-talk")]
+pub use push_to_talk::{PttEvent, PushToTalk, PushToTalkOptions};
+pub use rate_limit::RateLimit;
+pub use retry::RetryPolicy;
+pub use routing::{
+    ChannelApps, ChannelRedirection, ClassicRedirection, RoutedApp, StreamRedirection,
+};
+pub use scene::{ChangeOutcome, Scene, SceneChange, SceneReport};
+#[cfg(feature = "scheduler")]
+pub use scheduler::{Schedule, ScheduleEvent, ScheduleRule, Scheduler, Trigger};
+#[cfg(feature = "scripting")]
+pub use scripting::{Script, ScriptAction, ScriptEvent, ScriptRunner};
+#[cfg(feature = "async")]
+pub use session::{Session, SessionEntry};
+pub use snapshot::{ApplyReport, FullState, SnapshotItem, SonarSnapshot, StateChange};
+#[cfg(feature = "async")]
+pub use sonar::Sonar;
+#[cfg(feature = "async")]
+pub use streamdeck::{KeyAction, KeyFeedback, StreamDeck};
+pub use sonar::{Channel, ChannelInfo, EngineInfo, Mode, StreamerSlider, SubAppStatus, VersionInfo, CHANNEL_NAMES, STREAMER_SLIDER_NAMES};
+#[cfg(any(feature = "blocking", feature = "ureq"))]
 pub use blocking::BlockingSonar;
+pub use validation::Validation;
+pub use volume::{ChannelVolumes, StreamerVolumes, VolumeCurve, VolumeSettings, VolumeState};
+#[cfg(feature = "async")]
+pub use watch::{SonarEvent, SonarEventStream, SonarWatcher};
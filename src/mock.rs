@@ -0,0 +1,607 @@
+//! In-memory test double for the Sonar clients.
+//!
+//! [`MockSonar`] implements [`SonarClient`](crate::SonarClient) and
+//! [`BlockingSonarClient`](crate::BlockingSonarClient) on top of a
+//! [`MockState`], so code written against those traits can be tested
+//! deterministically, without SteelSeries GG.
+//!
+//! ```
+//! use steelseries_sonar::{BlockingSonarClient, MockSonar};
+//!
+//! let sonar = MockSonar::new();
+//! sonar.set_volume("game", 0.25, None).unwrap();
+//! assert_eq!(sonar.get_channel_state("game", None).unwrap().volume, 0.25);
+//! assert_eq!(sonar.state().chat_mix, 0.0);
+//! ```
+
+use crate::configs::SonarConfig;
+use crate::devices::AudioDevice;
+use crate::eq::{apply_band_update, EqBandUpdate, Equalizer};
+use crate::error::{Result, SonarError};
+use crate::routing::{channel_device, check_capture_device, ChannelApps, ChannelRedirection, ClassicRedirection, StreamRedirection, CAPTURE_CHANNEL};
+#[cfg(any(feature = "async", feature = "blocking", feature = "ureq"))]
+use crate::sonar::Mode;
+use crate::sonar::{StreamerSlider, CHANNEL_NAMES, CHAT_MIX_ENABLED};
+#[cfg(any(feature = "async", feature = "blocking", feature = "ureq"))]
+use crate::volume::VolumeSettings;
+use crate::volume::{ChannelVolumes, StreamerVolumes, VolumeState};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+/// Status the mock reports for unknown config ids and processes, like the
+/// real server.
+const NOT_FOUND: u16 = 404;
+
+/// Chat mix `state` reported when no ChatMix-capable device is in use.
+const CHAT_MIX_UNAVAILABLE: &str = "differentDeviceSelected";
+
+/// The mixer state behind a [`MockSonar`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockState {
+    pub streamer_mode: bool,
+    /// Volume states by channel name, `"master"` included.
+    pub volumes: BTreeMap<String, ChannelVolumes>,
+    pub chat_mix: f64,
+    /// Whether a ChatMix-capable headset is in use.
+    pub chat_mix_available: bool,
+    pub devices: Vec<AudioDevice>,
+    pub redirections: Vec<ClassicRedirection>,
+    pub stream_redirections: Vec<StreamRedirection>,
+    pub routed_apps: Vec<ChannelApps>,
+    pub configs: Vec<SonarConfig>,
+    /// Id of the selected config, by channel.
+    pub selected_configs: BTreeMap<String, String>,
+}
+
+impl Default for MockState {
+    /// Classic mode, every channel at full volume and unmuted, chat mix centered.
+    fn default() -> Self {
+        let full = VolumeState {
+            volume: 1.0,
+            muted: false,
+        };
+        let volumes = ChannelVolumes {
+            classic: full,
+            stream: StreamerVolumes {
+                streaming: full,
+                monitoring: full,
+            },
+        };
+
+        Self {
+            streamer_mode: false,
+            volumes: CHANNEL_NAMES
+                .iter()
+                .map(|channel| (channel.to_string(), volumes))
+                .collect(),
+            chat_mix: 0.0,
+            chat_mix_available: true,
+            devices: Vec::new(),
+            redirections: Vec::new(),
+            stream_redirections: Vec::new(),
+            routed_apps: Vec::new(),
+            configs: Vec::new(),
+            selected_configs: BTreeMap::new(),
+        }
+    }
+}
+
+/// In-memory implementation of the client traits.
+///
+/// Validation matches the real clients, e.g. unknown channels yield
+/// [`SonarError::ChannelNotFound`] and out-of-range volumes
+/// [`SonarError::InvalidVolume`].
+#[derive(Debug, Default)]
+pub struct MockSonar {
+    state: Mutex<MockState>,
+    next_config_id: AtomicU64,
+}
+
+impl MockSonar {
+    /// Create a mock with the [default state](MockState::default).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a mock with the given initial state.
+    pub fn with_state(state: MockState) -> Self {
+        Self {
+            state: Mutex::new(state),
+            next_config_id: AtomicU64::default(),
+        }
+    }
+
+    /// A snapshot of the current state.
+    pub fn state(&self) -> MockState {
+        self.lock().clone()
+    }
+
+    /// Modify the state, e.g. to simulate changes made in the GG app.
+    pub fn update<R>(&self, update: impl FnOnce(&mut MockState) -> R) -> R {
+        update(&mut self.lock())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn create_config(&self, channel: &str, name: &str) -> Result<SonarConfig> {
+        check_channel(channel)?;
+        let id = self.next_config_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let config = SonarConfig {
+            id: format!("mock-config-{}", id),
+            name: name.to_string(),
+            channel: channel.to_string(),
+            settings: json!({}),
+        };
+        self.lock().configs.push(config.clone());
+        Ok(config)
+    }
+}
+
+fn check_channel(channel: &str) -> Result<()> {
+    if CHANNEL_NAMES.contains(&channel) {
+        Ok(())
+    } else {
+        Err(SonarError::ChannelNotFound(channel.to_string()))
+    }
+}
+
+fn state_json(state: &VolumeState) -> Value {
+    json!({ "volume": state.volume, "muted": state.muted })
+}
+
+fn channel_json(volumes: &ChannelVolumes) -> Value {
+    json!({
+        "classic": state_json(&volumes.classic),
+        "stream": {
+            "streaming": state_json(&volumes.stream.streaming),
+            "monitoring": state_json(&volumes.stream.monitoring),
+        }
+    })
+}
+
+impl MockState {
+    pub(crate) fn volume_data(&self) -> Value {
+        let master = self.volumes.get("master").copied().unwrap_or_default();
+        let devices: serde_json::Map<String, Value> = self
+            .volumes
+            .iter()
+            .filter(|(channel, _)| *channel != "master")
+            .map(|(channel, volumes)| (channel.clone(), channel_json(volumes)))
+            .collect();
+        json!({ "masters": channel_json(&master), "devices": devices })
+    }
+
+    pub(crate) fn chat_mix_data(&self) -> Value {
+        let state = if self.chat_mix_available { CHAT_MIX_ENABLED } else { CHAT_MIX_UNAVAILABLE };
+        json!({ "balance": self.chat_mix, "state": state })
+    }
+
+    /// The classic volume state of a channel, or the one of a streamer slider.
+    pub(crate) fn volume_state_mut(&mut self, channel: &str, slider: Option<StreamerSlider>) -> Result<&mut VolumeState> {
+        check_channel(channel)?;
+        let volumes = self.volumes.entry(channel.to_string()).or_default();
+        Ok(match slider {
+            None => &mut volumes.classic,
+            Some(StreamerSlider::Streaming) => &mut volumes.stream.streaming,
+            Some(StreamerSlider::Monitoring) => &mut volumes.stream.monitoring,
+        })
+    }
+
+    fn channel_state_mut(&mut self, channel: &str, streamer_slider: Option<StreamerSlider>) -> Result<&mut VolumeState> {
+        let slider = self.streamer_mode.then(|| streamer_slider.unwrap_or_default());
+        self.volume_state_mut(channel, slider)
+    }
+
+    fn channel_state(&mut self, channel: &str, streamer_slider: Option<StreamerSlider>) -> Result<VolumeState> {
+        self.channel_state_mut(channel, streamer_slider).map(|state| *state)
+    }
+
+    fn set_volume(&mut self, channel: &str, volume: f64, streamer_slider: Option<StreamerSlider>) -> Result<Value> {
+        check_channel(channel)?;
+        if !(0.0..=1.0).contains(&volume) {
+            return Err(SonarError::InvalidVolume(volume));
+        }
+        self.channel_state_mut(channel, streamer_slider)?.volume = volume;
+        Ok(self.volume_data())
+    }
+
+    fn adjust_volume(&mut self, channel: &str, delta: f64, streamer_slider: Option<StreamerSlider>) -> Result<f64> {
+        let state = self.channel_state_mut(channel, streamer_slider)?;
+        state.volume = (state.volume + delta).clamp(0.0, 1.0);
+        Ok(state.volume)
+    }
+
+    fn mute_channel(&mut self, channel: &str, muted: bool, streamer_slider: Option<StreamerSlider>) -> Result<Value> {
+        self.channel_state_mut(channel, streamer_slider)?.muted = muted;
+        Ok(self.volume_data())
+    }
+
+    fn toggle_mute(&mut self, channel: &str, streamer_slider: Option<StreamerSlider>) -> Result<bool> {
+        let state = self.channel_state_mut(channel, streamer_slider)?;
+        state.muted = !state.muted;
+        Ok(state.muted)
+    }
+
+    pub(crate) fn set_chat_mix(&mut self, mix_volume: f64) -> Result<Value> {
+        if !(-1.0..=1.0).contains(&mix_volume) {
+            return Err(SonarError::InvalidMixVolume(mix_volume));
+        }
+        if !self.chat_mix_available {
+            return Err(SonarError::ChatMixUnavailable);
+        }
+        self.chat_mix = mix_volume;
+        Ok(self.chat_mix_data())
+    }
+
+    pub(crate) fn channel_device(&self, channel: &str) -> Result<Option<AudioDevice>> {
+        check_channel(channel)?;
+        Ok(channel_device(&self.redirections, self.devices.clone(), channel))
+    }
+
+    pub(crate) fn set_redirection(&mut self, channel: &str, device_id: &str) -> Result<Value> {
+        check_channel(channel)?;
+        match self.redirections.iter_mut().find(|redirection| redirection.channel == channel) {
+            Some(redirection) => redirection.device_id = device_id.to_string(),
+            None => self.redirections.push(ClassicRedirection {
+                channel: channel.to_string(),
+                device_id: device_id.to_string(),
+                is_running: true,
+            }),
+        }
+        Ok(Value::Null)
+    }
+
+    pub(crate) fn set_capture_device(&mut self, device_id: &str) -> Result<Value> {
+        check_capture_device(&self.devices, device_id)?;
+        self.set_redirection(CAPTURE_CHANNEL, device_id)
+    }
+
+    fn stream_redirection_mut(&mut self, slider: StreamerSlider) -> &mut StreamRedirection {
+        let index = match self.stream_redirections.iter().position(|redirection| redirection.slider == slider) {
+            Some(index) => index,
+            None => {
+                self.stream_redirections.push(StreamRedirection {
+                    slider,
+                    device_id: String::new(),
+                    redirections: Vec::new(),
+                });
+                self.stream_redirections.len() - 1
+            }
+        };
+        &mut self.stream_redirections[index]
+    }
+
+    pub(crate) fn set_stream_redirection(&mut self, slider: StreamerSlider, device_id: &str) -> Result<Value> {
+        self.stream_redirection_mut(slider).device_id = device_id.to_string();
+        Ok(Value::Null)
+    }
+
+    pub(crate) fn mute_redirection(&mut self, channel: &str, slider: StreamerSlider, muted: bool) -> Result<Value> {
+        check_channel(channel)?;
+        let redirections = &mut self.stream_redirection_mut(slider).redirections;
+        match redirections.iter_mut().find(|redirection| redirection.channel == channel) {
+            Some(redirection) => redirection.is_enabled = !muted,
+            None => redirections.push(ChannelRedirection {
+                channel: channel.to_string(),
+                is_enabled: !muted,
+            }),
+        }
+        Ok(Value::Null)
+    }
+
+    pub(crate) fn route_app(&mut self, process_id: u32, channel: &str) -> Result<Value> {
+        check_channel(channel)?;
+        let app = self
+            .routed_apps
+            .iter_mut()
+            .find_map(|channel_apps| {
+                let index = channel_apps.apps.iter().position(|app| app.process_id == process_id)?;
+                Some(channel_apps.apps.remove(index))
+            })
+            .ok_or(SonarError::ServerNotAccessible(NOT_FOUND))?;
+
+        match self.routed_apps.iter_mut().find(|channel_apps| channel_apps.channel == channel) {
+            Some(channel_apps) => channel_apps.apps.push(app),
+            None => self.routed_apps.push(ChannelApps {
+                channel: channel.to_string(),
+                device_id: String::new(),
+                apps: vec![app],
+            }),
+        }
+        Ok(Value::Null)
+    }
+
+    fn list_configs(&self, channel: &str) -> Result<Vec<SonarConfig>> {
+        check_channel(channel)?;
+        Ok(self.configs.iter().filter(|config| config.channel == channel).cloned().collect())
+    }
+
+    pub(crate) fn selected_configs(&self) -> Vec<SonarConfig> {
+        self.configs
+            .iter()
+            .filter(|config| self.selected_configs.get(&config.channel) == Some(&config.id))
+            .cloned()
+            .collect()
+    }
+
+    fn config_mut(&mut self, config_id: &str) -> Result<&mut SonarConfig> {
+        self.configs
+            .iter_mut()
+            .find(|config| config.id == config_id)
+            .ok_or(SonarError::ServerNotAccessible(NOT_FOUND))
+    }
+
+    pub(crate) fn select_config(&mut self, config_id: &str) -> Result<Value> {
+        let channel = self.config_mut(config_id)?.channel.clone();
+        self.selected_configs.insert(channel, config_id.to_string());
+        Ok(Value::Null)
+    }
+
+    fn rename_config(&mut self, config_id: &str, name: &str) -> Result<Value> {
+        self.config_mut(config_id)?.name = name.to_string();
+        Ok(Value::Null)
+    }
+
+    fn delete_config(&mut self, config_id: &str) -> Result<()> {
+        let index = self
+            .configs
+            .iter()
+            .position(|config| config.id == config_id)
+            .ok_or(SonarError::ServerNotAccessible(NOT_FOUND))?;
+        self.configs.remove(index);
+        self.selected_configs.retain(|_, id| id != config_id);
+        Ok(())
+    }
+
+    fn selected_config_mut(&mut self, channel: &str) -> Result<&mut SonarConfig> {
+        check_channel(channel)?;
+        let id = self
+            .selected_configs
+            .get(channel)
+            .cloned()
+            .ok_or_else(|| SonarError::ConfigNotFound(channel.to_string()))?;
+        self.config_mut(&id)
+    }
+
+    fn get_eq(&mut self, channel: &str) -> Result<Equalizer> {
+        Ok(Equalizer::from_config(self.selected_config_mut(channel)?))
+    }
+
+    fn set_eq_band(&mut self, channel: &str, band: usize, update: EqBandUpdate) -> Result<Equalizer> {
+        let config = self.selected_config_mut(channel)?;
+        apply_band_update(&mut config.settings, band, update)?;
+        Ok(Equalizer::from_config(config))
+    }
+}
+
+/// Implements one of the client traits by running each operation against
+/// the locked state; `$wrap` turns the result into the trait's return type.
+#[cfg(any(feature = "async", feature = "blocking", feature = "ureq"))]
+macro_rules! impl_mock_client {
+    ($trait:path, $wrap:path, $ret:ident) => {
+        impl $trait for MockSonar {
+            fn get_mode(&self) -> $ret!(Mode) {
+                $wrap(Ok(Mode::from_streamer_mode(self.lock().streamer_mode)))
+            }
+
+            fn set_mode(&self, mode: Mode) -> $ret!(Mode) {
+                self.lock().streamer_mode = mode.is_streamer();
+                $wrap(Ok(mode))
+            }
+
+            fn get_volume_data(&self) -> $ret!(Value) {
+                $wrap(Ok(self.lock().volume_data()))
+            }
+
+            fn get_volumes(&self) -> $ret!(VolumeSettings) {
+                $wrap(VolumeSettings::from_value(self.lock().volume_data()))
+            }
+
+            fn set_volume(&self, channel: &str, volume: f64, streamer_slider: Option<StreamerSlider>) -> $ret!(Value) {
+                $wrap(self.lock().set_volume(channel, volume, streamer_slider))
+            }
+
+            fn adjust_volume(&self, channel: &str, delta: f64, streamer_slider: Option<StreamerSlider>) -> $ret!(f64) {
+                $wrap(self.lock().adjust_volume(channel, delta, streamer_slider))
+            }
+
+            fn toggle_mute(&self, channel: &str, streamer_slider: Option<StreamerSlider>) -> $ret!(bool) {
+                $wrap(self.lock().toggle_mute(channel, streamer_slider))
+            }
+
+            fn get_channel_state(&self, channel: &str, streamer_slider: Option<StreamerSlider>) -> $ret!(VolumeState) {
+                $wrap(self.lock().channel_state(channel, streamer_slider))
+            }
+
+            fn mute_channel(&self, channel: &str, muted: bool, streamer_slider: Option<StreamerSlider>) -> $ret!(Value) {
+                $wrap(self.lock().mute_channel(channel, muted, streamer_slider))
+            }
+
+            fn get_chat_mix_data(&self) -> $ret!(Value) {
+                $wrap(Ok(self.lock().chat_mix_data()))
+            }
+
+            fn set_chat_mix(&self, mix_volume: f64) -> $ret!(Value) {
+                $wrap(self.lock().set_chat_mix(mix_volume))
+            }
+
+            fn chat_mix_supported(&self) -> $ret!(bool) {
+                $wrap(Ok(self.lock().chat_mix_available))
+            }
+
+            fn list_audio_devices(&self) -> $ret!(Vec<AudioDevice>) {
+                $wrap(Ok(self.lock().devices.clone()))
+            }
+
+            fn get_redirections(&self) -> $ret!(Vec<ClassicRedirection>) {
+                $wrap(Ok(self.lock().redirections.clone()))
+            }
+
+            fn get_channel_device(&self, channel: &str) -> $ret!(Option<AudioDevice>) {
+                $wrap(self.lock().channel_device(channel))
+            }
+
+            fn set_redirection(&self, channel: &str, device_id: &str) -> $ret!(Value) {
+                $wrap(self.lock().set_redirection(channel, device_id))
+            }
+
+            fn set_capture_device(&self, device_id: &str) -> $ret!(Value) {
+                $wrap(self.lock().set_capture_device(device_id))
+            }
+
+            fn get_stream_redirections(&self) -> $ret!(Vec<StreamRedirection>) {
+                $wrap(Ok(self.lock().stream_redirections.clone()))
+            }
+
+            fn set_stream_redirection(&self, slider: StreamerSlider, device_id: &str) -> $ret!(Value) {
+                $wrap(self.lock().set_stream_redirection(slider, device_id))
+            }
+
+            fn mute_redirection(&self, channel: &str, slider: StreamerSlider, muted: bool) -> $ret!(Value) {
+                $wrap(self.lock().mute_redirection(channel, slider, muted))
+            }
+
+            fn get_routed_apps(&self) -> $ret!(Vec<ChannelApps>) {
+                $wrap(Ok(self.lock().routed_apps.clone()))
+            }
+
+            fn route_app(&self, process_id: u32, channel: &str) -> $ret!(Value) {
+                $wrap(self.lock().route_app(process_id, channel))
+            }
+
+            fn list_configs(&self, channel: &str) -> $ret!(Vec<SonarConfig>) {
+                $wrap(self.lock().list_configs(channel))
+            }
+
+            fn get_selected_configs(&self) -> $ret!(Vec<SonarConfig>) {
+                $wrap(Ok(self.lock().selected_configs()))
+            }
+
+            fn select_config(&self, config_id: &str) -> $ret!(Value) {
+                $wrap(self.lock().select_config(config_id))
+            }
+
+            fn create_config(&self, channel: &str, name: &str) -> $ret!(SonarConfig) {
+                $wrap(MockSonar::create_config(self, channel, name))
+            }
+
+            fn rename_config(&self, config_id: &str, name: &str) -> $ret!(Value) {
+                $wrap(self.lock().rename_config(config_id, name))
+            }
+
+            fn delete_config(&self, config_id: &str) -> $ret!(()) {
+                $wrap(self.lock().delete_config(config_id))
+            }
+
+            fn get_eq(&self, channel: &str) -> $ret!(Equalizer) {
+                $wrap(self.lock().get_eq(channel))
+            }
+
+            fn set_eq_band(&self, channel: &str, band: usize, update: EqBandUpdate) -> $ret!(Equalizer) {
+                $wrap(self.lock().set_eq_band(channel, band, update))
+            }
+        }
+    };
+}
+
+#[cfg(feature = "async")]
+macro_rules! ready_future {
+    ($t:ty) => { impl std::future::Future<Output = Result<$t>> + Send };
+}
+
+#[cfg(feature = "async")]
+impl_mock_client!(crate::client::SonarClient, std::future::ready, ready_future);
+
+#[cfg(any(feature = "blocking", feature = "ureq"))]
+macro_rules! plain_result {
+    ($t:ty) => { Result<$t> };
+}
+
+#[cfg(any(feature = "blocking", feature = "ureq"))]
+fn identity<T>(value: T) -> T {
+    value
+}
+
+#[cfg(any(feature = "blocking", feature = "ureq"))]
+impl_mock_client!(crate::client::BlockingSonarClient, identity, plain_result);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(any(feature = "blocking", feature = "ureq"))]
+    #[test]
+    fn test_mock_volumes() {
+        use crate::BlockingSonarClient;
+
+        let sonar = MockSonar::new();
+        sonar.set_volume("game", 0.25, None).unwrap();
+        sonar.mute_channel("media", true, None).unwrap();
+        assert_eq!(sonar.get_channel_state("game", None).unwrap().volume, 0.25);
+        assert!(sonar.toggle_mute("aux", None).unwrap());
+        assert_eq!(sonar.adjust_volume("game", 1.0, None).unwrap(), 1.0);
+
+        let volumes = sonar.get_volumes().unwrap();
+        assert!(volumes.channel("media").unwrap().classic.muted);
+        assert_eq!(volumes.master().classic.volume, 1.0);
+
+        sonar.set_mode(Mode::Streamer).unwrap();
+        sonar
+            .set_volume("game", 0.5, Some(StreamerSlider::Monitoring))
+            .unwrap();
+        let game = sonar.state().volumes["game"];
+        assert_eq!(game.classic.volume, 1.0);
+        assert_eq!(game.stream.monitoring.volume, 0.5);
+        assert_eq!(game.stream.streaming.volume, 1.0);
+
+        assert!(matches!(
+            sonar.set_volume("invalid", 0.5, None),
+            Err(SonarError::ChannelNotFound(_))
+        ));
+        assert!(matches!(
+            sonar.set_volume("game", 1.5, None),
+            Err(SonarError::InvalidVolume(_))
+        ));
+        assert!(matches!(sonar.set_chat_mix(2.0), Err(SonarError::InvalidMixVolume(_))));
+    }
+
+    #[cfg(any(feature = "blocking", feature = "ureq"))]
+    #[test]
+    fn test_mock_configs() {
+        use crate::BlockingSonarClient;
+
+        let sonar = MockSonar::new();
+        let config = sonar.create_config("game", "FPS").unwrap();
+        assert!(matches!(sonar.get_eq("game"), Err(SonarError::ConfigNotFound(_))));
+
+        sonar.select_config(&config.id).unwrap();
+        sonar.rename_config(&config.id, "Footsteps").unwrap();
+        let selected = sonar.get_selected_configs().unwrap();
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].name, "Footsteps");
+
+        sonar.delete_config(&config.id).unwrap();
+        assert!(sonar.get_selected_configs().unwrap().is_empty());
+        assert!(matches!(
+            sonar.delete_config(&config.id),
+            Err(SonarError::ServerNotAccessible(404))
+        ));
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_mock_async() {
+        use crate::SonarClient;
+
+        let sonar = MockSonar::new();
+        sonar.set_chat_mix(-0.5).await.unwrap();
+        assert_eq!(sonar.get_chat_mix_data().await.unwrap()["balance"], -0.5);
+
+        sonar.update(|state| state.streamer_mode = true);
+        assert_eq!(sonar.get_mode().await.unwrap(), Mode::Streamer);
+    }
+}
@@ -0,0 +1,239 @@
+//! Following the streaming and recording state of OBS Studio.
+//!
+//! [`Sonar::follow_obs`](crate::Sonar::follow_obs) connects to the
+//! obs-websocket server built into OBS Studio 28 and later. When the stream
+//! starts, the mixer state is saved, Sonar is switched to streamer mode and
+//! [`ObsOptions::profile`] is applied; when it stops, the saved state is
+//! restored. With [`ObsOptions::recording`], recording counts as going live
+//! too.
+
+use crate::cancel::CancellationToken;
+use crate::error::{Result, SonarError};
+use crate::snapshot::SonarSnapshot;
+use crate::sonar::{Mode, Sonar};
+use futures_util::StreamExt;
+use obws::events::Event;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+const EVENT_BUFFER: usize = 16;
+
+/// Options of [`Sonar::follow_obs`](crate::Sonar::follow_obs).
+///
+/// ```
+/// use steelseries_sonar::ObsOptions;
+///
+/// let options = ObsOptions {
+///     password: Some("secret".to_string()),
+///     profile: Some("streaming".to_string()),
+///     ..ObsOptions::default()
+/// };
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObsOptions {
+    /// Host of the obs-websocket server.
+    pub host: String,
+    /// Port of the obs-websocket server, 4455 unless changed in OBS.
+    pub port: u16,
+    /// Server password, if authentication is enabled in OBS.
+    pub password: Option<String>,
+    /// Profile applied while live, see [`Sonar::load_profile`](crate::Sonar::load_profile).
+    pub profile: Option<String>,
+    /// Whether recording counts as going live too.
+    pub recording: bool,
+}
+
+impl Default for ObsOptions {
+    fn default() -> Self {
+        Self {
+            host: "localhost".to_string(),
+            port: 4455,
+            password: None,
+            profile: None,
+            recording: false,
+        }
+    }
+}
+
+/// A change made by an [`ObsFollower`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObsEvent {
+    /// OBS went live; Sonar is in streamer mode with the profile applied.
+    WentLive,
+    /// OBS went offline; the previous mixer state was restored.
+    WentOffline,
+}
+
+/// Handle to a running OBS follower.
+///
+/// Stopping or dropping it restores the previous mixer state if OBS is live.
+#[derive(Debug)]
+pub struct ObsFollower {
+    receiver: mpsc::Receiver<Result<ObsEvent>>,
+    token: CancellationToken,
+    task: Option<JoinHandle<()>>,
+}
+
+impl ObsFollower {
+    pub(crate) async fn connect(sonar: Sonar, options: ObsOptions) -> Result<Self> {
+        let obs = obws::Client::connect(&options.host, options.port, options.password.as_deref())
+            .await
+            .map_err(obs_error)?;
+        let outputs = Outputs {
+            streaming: obs.streaming().status().await.map_err(obs_error)?.active,
+            recording: obs.recording().status().await.map_err(obs_error)?.active,
+        };
+
+        let (sender, receiver) = mpsc::channel(EVENT_BUFFER);
+        let token = CancellationToken::new();
+        let task = tokio::spawn(run_loop(sonar, options, obs, outputs, token.clone(), sender));
+        Ok(Self {
+            receiver,
+            token,
+            task: Some(task),
+        })
+    }
+
+    /// Wait for the next change, or the error that prevented one.
+    ///
+    /// Returns `None` once the follower has stopped, e.g. because OBS was
+    /// closed.
+    pub async fn recv(&mut self) -> Option<Result<ObsEvent>> {
+        self.receiver.recv().await
+    }
+
+    /// Stop following OBS, waiting until the previous state is restored.
+    pub async fn stop(mut self) {
+        self.token.cancel();
+        if let Some(task) = self.task.take() {
+            let _ = task.await;
+        }
+    }
+}
+
+impl Drop for ObsFollower {
+    fn drop(&mut self) {
+        // The task restores the previous state and exits on its own.
+        self.token.cancel();
+    }
+}
+
+/// Which outputs of OBS are active.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Outputs {
+    streaming: bool,
+    recording: bool,
+}
+
+impl Outputs {
+    /// Apply an event, returning whether it concerned the outputs.
+    fn update(&mut self, event: &Event) -> bool {
+        match event {
+            Event::StreamStateChanged { active, .. } => self.streaming = *active,
+            Event::RecordStateChanged { active, .. } => self.recording = *active,
+            _ => return false,
+        }
+        true
+    }
+
+    fn is_live(self, options: &ObsOptions) -> bool {
+        self.streaming || (options.recording && self.recording)
+    }
+}
+
+async fn run_loop(
+    sonar: Sonar,
+    options: ObsOptions,
+    obs: obws::Client,
+    mut outputs: Outputs,
+    token: CancellationToken,
+    sender: mpsc::Sender<Result<ObsEvent>>,
+) {
+    let events = match obs.events() {
+        Ok(events) => events,
+        Err(error) => {
+            let _ = sender.try_send(Err(obs_error(error)));
+            return;
+        }
+    };
+    let mut events = std::pin::pin!(events);
+    // The mixer state from before going live, restored afterwards.
+    let mut saved = None;
+
+    loop {
+        let live = outputs.is_live(&options);
+        let event = if live && saved.is_none() {
+            Some(go_live(&sonar, &options, &mut saved).await.map(|()| ObsEvent::WentLive))
+        } else if !live && saved.is_some() {
+            Some(restore(&sonar, &mut saved).await.map(|()| ObsEvent::WentOffline))
+        } else {
+            None
+        };
+        if let Some(event) = event {
+            // Nobody listening is fine, the change happens anyway.
+            let _ = sender.try_send(event);
+        }
+
+        loop {
+            let event = tokio::select! {
+                () = token.cancelled() => None,
+                event = events.next() => event,
+            };
+            match event {
+                Some(event) if !outputs.update(&event) => continue,
+                Some(_) => break,
+                None => {
+                    if saved.is_some() {
+                        let _ = sender.try_send(restore(&sonar, &mut saved).await.map(|()| ObsEvent::WentOffline));
+                    }
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Save the mixer state into `saved`, then switch to streamer mode and apply
+/// the profile.
+async fn go_live(sonar: &Sonar, options: &ObsOptions, saved: &mut Option<SonarSnapshot>) -> Result<()> {
+    *saved = Some(sonar.export_state().await?);
+    sonar.set_mode(Mode::Streamer).await?;
+    if let Some(profile) = &options.profile {
+        sonar.load_profile(profile).await?;
+    }
+    Ok(())
+}
+
+/// Apply the state in `saved`, failing with the first setting that could not
+/// be restored.
+async fn restore(sonar: &Sonar, saved: &mut Option<SonarSnapshot>) -> Result<()> {
+    let Some(snapshot) = saved.take() else {
+        return Ok(());
+    };
+    match sonar.apply_state(&snapshot).await.failed.into_iter().next() {
+        Some((_, error)) => Err(error),
+        None => Ok(()),
+    }
+}
+
+fn obs_error(error: obws::error::Error) -> SonarError {
+    SonarError::Obs(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outputs() {
+        let mut outputs = Outputs::default();
+        let options = ObsOptions::default();
+        assert!(outputs.update(&Event::RecordStateChanged { active: true, state: obws::events::OutputState::Started, path: None }));
+        assert!(!outputs.is_live(&options));
+        assert!(outputs.is_live(&ObsOptions { recording: true, ..ObsOptions::default() }));
+
+        assert!(outputs.update(&Event::StreamStateChanged { active: true, state: obws::events::OutputState::Started }));
+        assert!(outputs.is_live(&options));
+        assert!(!outputs.update(&Event::ExitStarted));
+    }
+}
@@ -0,0 +1,536 @@
+//! Client logic shared by [`Sonar`](crate::Sonar) and
+//! [`BlockingSonar`](crate::blocking::BlockingSonar).
+//!
+//! Everything that only strings requests together, e.g. discovery, health
+//! checks, snapshots and scenes, is written once in
+//! [`client_orchestration!`] and expanded into both clients. The async client
+//! passes `async` and `.await`, the blocking one leaves them empty.
+//!
+//! The invoking client provides the request primitives:
+//!
+//! - the request helpers `get_json`, `get_mode`, `set_volume` and so on,
+//!   with the same signatures apart from `async`;
+//! - `send_raw(client, method, url)`, which sends a single request outside
+//!   of the retry and reconnect handling and fails with
+//!   [`SonarError::ServerNotAccessible`](crate::SonarError::ServerNotAccessible)
+//!   unless it succeeds.
+
+/// Expands to the shared methods inside an `impl` block of a client.
+///
+/// * `builder` - The client's builder, linked from the docs
+/// * `async` / `await` - `async` and `.await`, or nothing
+/// * `join` - A `try_join!`-like macro for requests that may run concurrently
+/// * `client` - The HTTP client type passed to `send_raw`
+/// * `get` / `put` - The HTTP methods passed to `send_raw`
+/// * `instant` - The clock used for deadlines
+/// * `sleep` - Waits for a `Duration`
+/// * `read_to_string` - Reads a file into a `String`
+macro_rules! client_orchestration {
+    (
+        builder: $builder:ident,
+        async: [$($async:tt)?],
+        await: [$($await:tt)*],
+        join: [$($join:tt)*],
+        client: $client:ty,
+        get: $get:expr,
+        put: $put:expr,
+        instant: $instant:ty,
+        sleep: $sleep:path,
+        read_to_string: $read_to_string:path $(,)?
+    ) => {
+        /// Check whether the SteelSeries Engine and the Sonar web server are up.
+        ///
+        /// Sends one request to each, without retries or reconnects.
+        pub $($async)? fn ping(&self) -> crate::health::HealthStatus {
+            let base_url = match (self.core.base_url(), &self.core.core_props_path) {
+                (Some(base_url), _) => Some(base_url),
+                // A lazy client that has not connected yet.
+                (None, Some(core_props_path)) => Self::load_base_url(core_props_path)$($await)*.ok(),
+                (None, None) => None,
+            };
+            let sub_app = match base_url {
+                Some(base_url) => Self::load_sub_app(&self.client, &base_url)$($await)*.ok(),
+                None => None,
+            };
+
+            let web_server_address = match self.web_server_address() {
+                address if address.is_empty() => sub_app.as_ref().and_then(|sub_app| sub_app.web_server_address().ok()),
+                address => Some(address),
+            };
+            let started = <$instant>::now();
+            let reachable = match web_server_address {
+                Some(address) => Self::send_raw(&self.client, $get, &format!("{}/mode/", address))$($await)*.is_ok(),
+                None => false,
+            };
+            let round_trip = reachable.then(|| started.elapsed());
+            crate::health::HealthStatus::new(sub_app.as_ref(), round_trip)
+        }
+
+        /// Ask the SteelSeries Engine which sub-apps exist and what state they
+        /// are in, e.g. to tell a disabled Sonar from a stopped one.
+        ///
+        /// A client connected to the web server directly looks the Engine up
+        /// through the discovered coreProps.json.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if the SteelSeries Engine is not found or accessible.
+        pub $($async)? fn engine_info(&self) -> crate::error::Result<crate::sonar::EngineInfo> {
+            Self::load_sub_apps(&self.client, &self.engine_base_url()$($await)*?)$($await)*
+        }
+
+        /// Ask the SteelSeries Engine for the versions of GG and Sonar, e.g. to
+        /// include them in bug reports or to adapt to API differences between
+        /// GG releases.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if the SteelSeries Engine is not found or accessible.
+        pub $($async)? fn version_info(&self) -> crate::error::Result<crate::sonar::VersionInfo> {
+            let base_url = self.engine_base_url()$($await)*?;
+            let engine_info: crate::sonar::EngineInfo = Self::load_sub_apps(&self.client, &base_url)$($await)*?;
+            Ok(crate::sonar::VersionInfo::new(Self::load_engine_version(&self.client, &base_url)$($await)*, &engine_info))
+        }
+
+        /// The URL of the SteelSeries Engine, looked up through coreProps.json
+        /// if the web server address was given directly.
+        $($async)? fn engine_base_url(&self) -> crate::error::Result<String> {
+            match self.core.base_url() {
+                Some(base_url) => Ok(base_url),
+                None => {
+                    let core_props_path = self.core.core_props_path.clone().unwrap_or_else(crate::discovery::find_core_props);
+                    Self::load_base_url(&core_props_path)$($await)*
+                }
+            }
+        }
+
+        /// Poll until Sonar is ready, e.g. while SteelSeries GG is still starting.
+        ///
+        /// The web server address is re-resolved along the way, since GG picks a
+        /// new port whenever it starts. To wait before the client can be built,
+        #[doc = concat!("use [`", stringify!($builder), "::wait_until_ready`](crate::", stringify!($builder), "::wait_until_ready).")]
+        ///
+        /// # Errors
+        ///
+        /// Returns [`SonarError::Timeout`](crate::SonarError::Timeout) if Sonar
+        /// is not ready within `timeout`.
+        pub $($async)? fn wait_until_ready(&self, timeout: std::time::Duration) -> crate::error::Result<crate::health::HealthStatus> {
+            let deadline = <$instant>::now() + timeout;
+            loop {
+                // Lazy clients resolve the addresses here before connecting.
+                if self.core.base_url().is_none() {
+                    let _ = self.refresh_addresses()$($await)*;
+                }
+                let status = self.ping()$($await)*;
+                if status.is_ready() {
+                    return Ok(status);
+                }
+                if status.running && !status.reachable {
+                    let _ = self.refresh_addresses()$($await)*;
+                }
+
+                let remaining = deadline.saturating_duration_since(<$instant>::now());
+                if remaining.is_zero() {
+                    return Err(crate::error::SonarError::Timeout);
+                }
+                $sleep(crate::health::READY_POLL_INTERVAL.min(remaining))$($await)*;
+            }
+        }
+
+        /// Fetch the mode, volumes, chat mix, device routing, routed applications
+        /// and selected configs in one call.
+        ///
+        /// The async client sends the requests concurrently, the blocking one
+        /// in turn. Volumes are read in the mode the client last saw; if the
+        /// server turns out to be in the other mode, they are read again.
+        pub $($async)? fn get_full_state(&self) -> crate::error::Result<crate::snapshot::FullState> {
+            self.ensure_connected()$($await)*?;
+            let tracked_mode = crate::sonar::Mode::from_streamer_mode(self.streamer_mode());
+            let (mode, volumes, chat_mix, redirections, stream_redirections, routed_apps, selected_configs) = $($join)*!(
+                self.get_mode(),
+                self.get_json(tracked_mode.volume_path()),
+                self.get_chat_mix_data(),
+                self.get_redirections(),
+                self.get_stream_redirections(),
+                self.get_routed_apps(),
+                self.get_selected_configs(),
+            )?;
+            let volumes = if mode == tracked_mode { volumes } else { self.get_json(mode.volume_path())$($await)*? };
+
+            Ok(crate::snapshot::FullState {
+                mode,
+                volumes: crate::volume::VolumeSettings::from_value(volumes)?,
+                chat_mix: chat_mix["balance"].as_f64().unwrap_or_default(),
+                chat_mix_available: crate::sonar::chat_mix_enabled(&chat_mix),
+                redirections,
+                stream_redirections,
+                routed_apps,
+                selected_configs,
+            })
+        }
+
+        /// Capture the current mixer state: mode, volumes, mutes, chat mix,
+        /// device routing and selected configs.
+        pub $($async)? fn export_state(&self) -> crate::error::Result<crate::snapshot::SonarSnapshot> {
+            let mode = self.get_mode()$($await)*?;
+            let streamer_mode = mode.is_streamer();
+            let volumes = crate::volume::VolumeSettings::from_value(self.get_json(mode.volume_path())$($await)*?)?;
+            let chat_mix = self.get_chat_mix_data()$($await)*?;
+
+            Ok(crate::snapshot::SonarSnapshot {
+                streamer_mode,
+                volumes: volumes
+                    .channels()
+                    .map(|(channel, volumes)| (channel.to_string(), *volumes))
+                    .collect(),
+                chat_mix: chat_mix["balance"].as_f64().unwrap_or_default(),
+                redirections: self.get_redirections()$($await)*?,
+                stream_redirections: self.get_stream_redirections()$($await)*?,
+                selected_configs: self.get_selected_configs()$($await)*?,
+            })
+        }
+
+        /// Restore a snapshot taken with [`export_state`](Self::export_state).
+        ///
+        /// Every item is written even if earlier ones fail; the returned report
+        /// lists what was restored and what was not. The chat mix is skipped
+        /// if no ChatMix-capable headset is connected.
+        pub $($async)? fn apply_state(&self, snapshot: &crate::snapshot::SonarSnapshot) -> crate::snapshot::ApplyReport {
+            use crate::snapshot::SnapshotItem;
+            use crate::sonar::StreamerSlider;
+
+            let mut report = crate::snapshot::ApplyReport::default();
+            report.record(SnapshotItem::Mode, self.set_mode(crate::sonar::Mode::from_streamer_mode(snapshot.streamer_mode))$($await)*);
+
+            let sliders: Vec<Option<StreamerSlider>> = if snapshot.streamer_mode {
+                StreamerSlider::ALL.into_iter().map(Some).collect()
+            } else {
+                vec![None]
+            };
+            for (channel, volumes) in &snapshot.volumes {
+                for &slider in &sliders {
+                    let state = volumes.state(snapshot.streamer_mode, slider);
+                    report.record(
+                        SnapshotItem::Volume { channel: channel.clone(), slider },
+                        self.set_volume(channel, state.volume, slider)$($await)*,
+                    );
+                    report.record(
+                        SnapshotItem::Mute { channel: channel.clone(), slider },
+                        self.mute_channel(channel, state.muted, slider)$($await)*,
+                    );
+                }
+            }
+
+            // Without a ChatMix headset there is no balance to restore.
+            match self.chat_mix_supported()$($await)* {
+                Ok(false) => {}
+                Ok(true) => report.record(SnapshotItem::ChatMix, self.set_chat_mix(snapshot.chat_mix)$($await)*),
+                result => report.record(SnapshotItem::ChatMix, result),
+            }
+
+            for redirection in &snapshot.redirections {
+                report.record(
+                    SnapshotItem::Redirection { channel: redirection.channel.clone() },
+                    self.set_redirection(&redirection.channel, &redirection.device_id)$($await)*,
+                );
+            }
+            for stream_redirection in &snapshot.stream_redirections {
+                let slider = stream_redirection.slider;
+                report.record(
+                    SnapshotItem::StreamRedirection { slider },
+                    self.set_stream_redirection(slider, &stream_redirection.device_id)$($await)*,
+                );
+                for redirection in &stream_redirection.redirections {
+                    report.record(
+                        SnapshotItem::RedirectionMute { channel: redirection.channel.clone(), slider },
+                        self.mute_redirection(&redirection.channel, slider, redirection.is_muted())$($await)*,
+                    );
+                }
+            }
+
+            for config in &snapshot.selected_configs {
+                report.record(
+                    SnapshotItem::SelectedConfig { channel: config.channel.clone() },
+                    self.select_config(&config.id)$($await)*,
+                );
+            }
+            report
+        }
+
+        /// Save the current mixer state as a named profile in the
+        /// [default profile store](crate::profiles::ProfileStore::open_default).
+        pub $($async)? fn save_profile(&self, name: &str) -> crate::error::Result<()> {
+            let snapshot = self.export_state()$($await)*?;
+            crate::profiles::ProfileStore::open_default()?.save(name, &snapshot)
+        }
+
+        /// Apply a profile saved with [`save_profile`](Self::save_profile).
+        ///
+        /// The profile is applied as a unit: if any setting fails, the previous
+        /// mixer state is restored and
+        /// [`SonarError::ProfileNotApplied`](crate::SonarError::ProfileNotApplied)
+        /// lists the failed settings. If the previous state cannot be fully
+        /// restored either,
+        /// [`SonarError::ProfileRollbackFailed`](crate::SonarError::ProfileRollbackFailed)
+        /// lists the settings of both that failed.
+        pub $($async)? fn load_profile(&self, name: &str) -> crate::error::Result<()> {
+            self.load_profile_from(&crate::profiles::ProfileStore::open_default()?, name)$($await)*
+        }
+
+        /// Apply a profile from `store` as a unit, see
+        /// [`load_profile`](Self::load_profile).
+        pub $($async)? fn load_profile_from(&self, store: &crate::profiles::ProfileStore, name: &str) -> crate::error::Result<()> {
+            let profile = store.load(name)?;
+            let previous = self.export_state()$($await)*?;
+
+            let report = self.apply_state(&profile)$($await)*;
+            if report.is_complete() {
+                return Ok(());
+            }
+            let rollback = self.apply_state(&previous)$($await)*;
+            if rollback.is_complete() {
+                Err(crate::error::SonarError::ProfileNotApplied {
+                    name: name.to_string(),
+                    failed: report.failed,
+                })
+            } else {
+                Err(crate::error::SonarError::ProfileRollbackFailed {
+                    name: name.to_string(),
+                    failed: report.failed,
+                    rollback_failed: rollback.failed,
+                })
+            }
+        }
+
+        /// Apply the changes of a scene in order, as a unit.
+        ///
+        /// Each change is read back after writing it. If a change fails, the
+        /// remaining ones are skipped and the applied ones are undone in reverse
+        /// order. The report holds the outcome of every change.
+        pub $($async)? fn apply_scene(&self, scene: &crate::scene::Scene) -> crate::scene::SceneReport {
+            use crate::scene::ChangeOutcome;
+
+            let mut outcomes = Vec::new();
+            let mut undo = Vec::new();
+
+            for change in scene.changes() {
+                if let Some((_, ChangeOutcome::Failed(_))) = outcomes.last() {
+                    outcomes.push((change.clone(), ChangeOutcome::Skipped));
+                    continue;
+                }
+                match self.apply_scene_change(change)$($await)* {
+                    Ok(previous) => {
+                        undo.push(previous);
+                        outcomes.push((change.clone(), ChangeOutcome::Applied));
+                    }
+                    Err(error) => outcomes.push((change.clone(), ChangeOutcome::Failed(error))),
+                }
+            }
+
+            if outcomes.iter().any(|(_, outcome)| matches!(outcome, ChangeOutcome::Failed(_))) {
+                for (index, previous) in undo.iter().enumerate().rev() {
+                    outcomes[index].1 = match self.write_scene_change(previous)$($await)* {
+                        Ok(()) => ChangeOutcome::RolledBack,
+                        Err(error) => ChangeOutcome::RollbackFailed(error),
+                    };
+                }
+            }
+            crate::scene::SceneReport { outcomes }
+        }
+
+        /// Write and verify a change, returning the change that undoes it.
+        $($async)? fn apply_scene_change(&self, change: &crate::scene::SceneChange) -> crate::error::Result<crate::scene::SceneChange> {
+            let previous = self.read_scene_change(change)$($await)*?;
+            self.write_scene_change(change)$($await)*?;
+            if !change.is_applied(&self.read_scene_change(change)$($await)*?) {
+                return Err(crate::error::SonarError::NotApplied(change.to_string()));
+            }
+            Ok(previous)
+        }
+
+        /// The current value of the setting a change targets.
+        pub(crate) $($async)? fn read_scene_change(&self, change: &crate::scene::SceneChange) -> crate::error::Result<crate::scene::SceneChange> {
+            use crate::scene::SceneChange;
+
+            Ok(match change {
+                SceneChange::Mode(_) => SceneChange::Mode(self.get_mode()$($await)*?),
+                SceneChange::Volume { channel, slider, .. } => SceneChange::Volume {
+                    channel: channel.clone(),
+                    volume: self.get_channel_state(channel, *slider)$($await)*?.volume,
+                    slider: *slider,
+                },
+                SceneChange::Mute { channel, slider, .. } => SceneChange::Mute {
+                    channel: channel.clone(),
+                    muted: self.get_channel_state(channel, *slider)$($await)*?.muted,
+                    slider: *slider,
+                },
+                SceneChange::ChatMix(_) => {
+                    SceneChange::ChatMix(self.get_chat_mix_data()$($await)*?["balance"].as_f64().unwrap_or_default())
+                }
+            })
+        }
+
+        pub(crate) $($async)? fn write_scene_change(&self, change: &crate::scene::SceneChange) -> crate::error::Result<()> {
+            use crate::scene::SceneChange;
+
+            match change {
+                SceneChange::Mode(mode) => {
+                    self.set_mode(*mode)$($await)*?;
+                }
+                SceneChange::Volume { channel, volume, slider } => {
+                    self.set_volume(channel, *volume, *slider)$($await)*?;
+                }
+                SceneChange::Mute { channel, muted, slider } => {
+                    self.mute_channel(channel, *muted, *slider)$($await)*?;
+                }
+                SceneChange::ChatMix(balance) => {
+                    self.set_chat_mix(*balance)$($await)*?;
+                }
+            }
+            Ok(())
+        }
+
+        $($async)? fn selected_config(&self, channel: &str) -> crate::error::Result<crate::configs::SonarConfig> {
+            let channel = self.core.resolve_channel(channel)?;
+
+            self.get_selected_configs()$($await)*?
+                .into_iter()
+                .find(|config| config.channel == channel)
+                .ok_or_else(|| crate::error::SonarError::ConfigNotFound(channel.to_string()))
+        }
+
+        /// Resolve the server addresses again, returning whether they changed.
+        /// Fixed addresses are never refreshed.
+        $($async)? fn refresh_addresses(&self) -> crate::error::Result<bool> {
+            let Some(core_props_path) = &self.core.core_props_path else {
+                return Ok(false);
+            };
+            let addresses = Self::resolve_addresses(&self.client, core_props_path)$($await)*?;
+            Ok(self.core.update_addresses(addresses))
+        }
+
+        /// Read coreProps.json and ask the Engine for the Sonar web server
+        /// address.
+        #[cfg_attr(
+            feature = "tracing",
+            tracing::instrument(
+                name = "sonar.discover",
+                level = "debug",
+                skip_all,
+                fields(core_props = %core_props_path.display()),
+                err(Display)
+            )
+        )]
+        pub(crate) $($async)? fn resolve_addresses(client: &$client, core_props_path: &std::path::Path) -> crate::error::Result<crate::sonar::ServerAddresses> {
+            let base_url = Self::load_base_url(core_props_path)$($await)*?;
+            let web_server_address = Self::load_server_address(client, &base_url)$($await)*?;
+            #[cfg(feature = "tracing")]
+            tracing::debug!(base_url, web_server_address, "resolved server addresses");
+            Ok(crate::sonar::ServerAddresses {
+                base_url: Some(base_url),
+                web_server_address,
+            })
+        }
+
+        /// Resolve the server addresses as configured: launching GG, waiting for
+        /// it to start and enabling the Sonar sub-app if requested.
+        $($async)? fn discover(
+            client: &$client,
+            core_props_path: &std::path::Path,
+            options: &crate::builder::ClientOptions,
+        ) -> crate::error::Result<crate::sonar::ServerAddresses> {
+            use crate::health::STARTUP_TIMEOUT;
+
+            let launched = options.launch_engine && crate::launch::launch_if_not_running()?;
+            let ready_timeout = options.ready_timeout.or(launched.then_some(STARTUP_TIMEOUT));
+            let result = match ready_timeout {
+                Some(timeout) => Self::resolve_when_ready(client, core_props_path, timeout)$($await)*,
+                None => Self::resolve_addresses(client, core_props_path)$($await)*,
+            };
+            match result {
+                Err(crate::error::SonarError::SonarNotEnabled) if options.enable_sonar => {
+                    Self::enable_sub_app(client, core_props_path)$($await)*?;
+                    Self::resolve_when_ready(client, core_props_path, ready_timeout.unwrap_or(STARTUP_TIMEOUT))$($await)*
+                }
+                result => result,
+            }
+        }
+
+        /// Ask the Engine to enable the Sonar sub-app.
+        $($async)? fn enable_sub_app(client: &$client, core_props_path: &std::path::Path) -> crate::error::Result<()> {
+            let base_url = Self::load_base_url(core_props_path)$($await)*?;
+            #[cfg(feature = "tracing")]
+            tracing::info!("enabling the Sonar sub-app");
+            Self::send_raw(client, $put, &format!("{}{}", base_url, crate::sonar::ENABLE_SONAR_PATH))$($await)*?;
+            Ok(())
+        }
+
+        /// Resolve the server addresses, trying again while SteelSeries GG is
+        /// still starting.
+        $($async)? fn resolve_when_ready(
+            client: &$client,
+            core_props_path: &std::path::Path,
+            timeout: std::time::Duration,
+        ) -> crate::error::Result<crate::sonar::ServerAddresses> {
+            let deadline = <$instant>::now() + timeout;
+            loop {
+                match Self::resolve_addresses(client, core_props_path)$($await)* {
+                    Err(error) if crate::health::is_starting_up(&error) && <$instant>::now() < deadline => {
+                        #[cfg(feature = "tracing")]
+                        tracing::debug!(error = %error, "waiting for Sonar to start");
+                        $sleep(crate::health::READY_POLL_INTERVAL.min(deadline.saturating_duration_since(<$instant>::now())))$($await)*;
+                    }
+                    result => return result,
+                }
+            }
+        }
+
+        #[cfg_attr(feature = "tracing", tracing::instrument(name = "sonar.core_props", level = "debug", skip_all))]
+        $($async)? fn load_base_url(app_data_path: &std::path::Path) -> crate::error::Result<String> {
+            if !app_data_path.exists() {
+                return Err(crate::error::SonarError::EnginePathNotFound);
+            }
+
+            let content = $read_to_string(app_data_path)$($await)*?;
+            Ok(serde_json::from_str::<crate::sonar::CoreProps>(&content)?.base_url())
+        }
+
+        /// Fetch and parse the Engine's `/subApps`.
+        $($async)? fn load_sub_apps<T: serde::de::DeserializeOwned>(client: &$client, base_url: &str) -> crate::error::Result<T> {
+            let response = Self::send_raw(client, $get, &format!("{}/subApps", base_url))$($await)*?;
+            let sub_apps = response.json()$($await)*?;
+            Ok(sub_apps)
+        }
+
+        /// The Engine's `/version` response, `None` if GG does not have one.
+        $($async)? fn load_engine_version(client: &$client, base_url: &str) -> Option<serde_json::Value> {
+            let response = Self::send_raw(client, $get, &format!("{}/version", base_url))$($await)*.ok()?;
+            response.json()$($await)*.ok()
+        }
+
+        /// The Engine's view of the Sonar sub-app.
+        $($async)? fn load_sub_app(client: &$client, base_url: &str) -> crate::error::Result<crate::sonar::SubApp> {
+            let sub_apps_response: crate::sonar::SubAppsResponse = Self::load_sub_apps(client, base_url)$($await)*?;
+            Ok(sub_apps_response.sub_apps.sonar)
+        }
+
+        #[cfg_attr(feature = "tracing", tracing::instrument(name = "sonar.sub_apps", level = "debug", skip(client)))]
+        $($async)? fn load_server_address(client: &$client, base_url: &str) -> crate::error::Result<String> {
+            Self::load_sub_app(client, base_url)$($await)*?.web_server_address()
+        }
+    };
+}
+
+pub(crate) use client_orchestration;
+
+/// Runs the requests one after another, returning a tuple of their results
+/// or the first error; the blocking counterpart of `tokio::try_join!`.
+#[cfg(any(feature = "blocking", feature = "ureq"))]
+macro_rules! try_join_in_turn {
+    ($($request:expr),+ $(,)?) => {
+        crate::error::Result::Ok(($($request?,)+))
+    };
+}
+
+#[cfg(any(feature = "blocking", feature = "ureq"))]
+pub(crate) use try_join_in_turn;
@@ -0,0 +1,87 @@
+//! Lookup of running processes by executable name.
+
+use crate::routing::ChannelApps;
+
+/// Ids of the processes running the executable `name`, compared
+/// case-insensitively.
+///
+/// The processes with an audio session Sonar knows of are found everywhere.
+/// With the `windows-audio` feature, every running process is searched as
+/// well, so an app can be routed before it plays anything.
+pub(crate) fn find_processes(name: &str, routed_apps: &[ChannelApps]) -> Vec<u32> {
+    let mut process_ids: Vec<u32> = routed_apps
+        .iter()
+        .flat_map(|channel_apps| &channel_apps.apps)
+        .filter(|app| app.process_name.eq_ignore_ascii_case(name))
+        .map(|app| app.process_id)
+        .collect();
+    for process_id in running_processes(name) {
+        if !process_ids.contains(&process_id) {
+            process_ids.push(process_id);
+        }
+    }
+    process_ids
+}
+
+#[cfg(all(windows, feature = "windows-audio"))]
+fn running_processes(name: &str) -> Vec<u32> {
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W, TH32CS_SNAPPROCESS,
+    };
+
+    let mut process_ids = Vec::new();
+    // SAFETY: the snapshot handle is closed before returning, and `entry`
+    // has its size set as the API requires.
+    unsafe {
+        let Ok(snapshot) = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) else {
+            return process_ids;
+        };
+        let mut entry = PROCESSENTRY32W {
+            dwSize: size_of::<PROCESSENTRY32W>() as u32,
+            ..Default::default()
+        };
+        let mut found = Process32FirstW(snapshot, &mut entry).is_ok();
+        while found {
+            let len = entry.szExeFile.iter().position(|&c| c == 0).unwrap_or(entry.szExeFile.len());
+            if String::from_utf16_lossy(&entry.szExeFile[..len]).eq_ignore_ascii_case(name) {
+                process_ids.push(entry.th32ProcessID);
+            }
+            found = Process32NextW(snapshot, &mut entry).is_ok();
+        }
+        let _ = CloseHandle(snapshot);
+    }
+    process_ids
+}
+
+#[cfg(not(all(windows, feature = "windows-audio")))]
+fn running_processes(_name: &str) -> Vec<u32> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_find_processes() {
+        let routed_apps: Vec<ChannelApps> = serde_json::from_value(json!([
+            {
+                "role": "game",
+                "audioSessions": [{ "id": "a", "processId": 10, "processName": "Spotify.exe" }]
+            },
+            {
+                "role": "media",
+                "audioSessions": [
+                    { "id": "b", "processId": 20, "processName": "firefox.exe" },
+                    { "id": "c", "processId": 30, "processName": "spotify.exe" }
+                ]
+            }
+        ]))
+        .unwrap();
+
+        assert_eq!(find_processes("SPOTIFY.EXE", &routed_apps), [10, 30]);
+        assert!(find_processes("no-such-app.exe", &routed_apps).is_empty());
+    }
+}
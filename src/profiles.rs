@@ -0,0 +1,170 @@
+//! Named mixer profiles stored on disk.
+//!
+//! A profile is a [`SonarSnapshot`] saved under a name, by default in
+//! `steelseries-sonar/profiles` inside the platform config directory
+//! (e.g. `%APPDATA%` on Windows). The clients' `save_profile` and
+//! `load_profile` use that default [`ProfileStore`].
+
+use crate::error::{Result, SonarError};
+use crate::snapshot::SonarSnapshot;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+const PROFILE_EXTENSION: &str = "json";
+
+/// A directory of named [`SonarSnapshot`]s.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileStore {
+    dir: PathBuf,
+}
+
+impl ProfileStore {
+    /// A store keeping its profiles in `dir`, which is created on first save.
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    /// The store in the platform config directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the platform has no config directory.
+    pub fn open_default() -> Result<Self> {
+        let config_dir = dirs::config_dir()
+            .ok_or_else(|| std::io::Error::new(ErrorKind::NotFound, "no config directory on this platform"))?;
+        Ok(Self::new(config_dir.join("steelseries-sonar").join("profiles")))
+    }
+
+    /// The directory the profiles are stored in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Save a snapshot under `name`, replacing an existing profile.
+    ///
+    /// The file is written next to its destination and then renamed, so a
+    /// crash never leaves a truncated profile behind.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is invalid or the file cannot be written.
+    pub fn save(&self, name: &str, snapshot: &SonarSnapshot) -> Result<()> {
+        let path = self.path(name)?;
+        std::fs::create_dir_all(&self.dir)?;
+        let temp_path = path.with_extension("json.tmp");
+        snapshot.save(&temp_path)?;
+        Ok(std::fs::rename(temp_path, path)?)
+    }
+
+    /// Load the profile saved under `name`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SonarError::ProfileNotFound`] if there is no such profile.
+    pub fn load(&self, name: &str) -> Result<SonarSnapshot> {
+        let path = self.path(name)?;
+        if !path.exists() {
+            return Err(SonarError::ProfileNotFound(name.to_string()));
+        }
+        SonarSnapshot::load(path)
+    }
+
+    /// Delete the profile saved under `name`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SonarError::ProfileNotFound`] if there is no such profile.
+    pub fn delete(&self, name: &str) -> Result<()> {
+        match std::fs::remove_file(self.path(name)?) {
+            Err(error) if error.kind() == ErrorKind::NotFound => Err(SonarError::ProfileNotFound(name.to_string())),
+            result => Ok(result?),
+        }
+    }
+
+    /// Names of all saved profiles, sorted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory exists but cannot be read.
+    pub fn list(&self) -> Result<Vec<String>> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => return Err(error.into()),
+        };
+
+        let mut names = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_some_and(|extension| extension == PROFILE_EXTENSION)
+                && let Some(name) = path.file_stem().and_then(|stem| stem.to_str())
+            {
+                names.push(name.to_string());
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    fn path(&self, name: &str) -> Result<PathBuf> {
+        let valid = !name.is_empty()
+            && !name.starts_with('.')
+            && !name.contains(['/', '\\', ':'])
+            && name.trim() == name;
+        if !valid {
+            return Err(SonarError::InvalidProfileName(name.to_string()));
+        }
+        Ok(self.dir.join(format!("{}.{}", name, PROFILE_EXTENSION)))
+    }
+}
+
+/// Names of the profiles in the [default store](ProfileStore::open_default).
+///
+/// # Errors
+///
+/// Returns an error if the profile directory cannot be read.
+pub fn list_profiles() -> Result<Vec<String>> {
+    ProfileStore::open_default()?.list()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn snapshot(chat_mix: f64) -> SonarSnapshot {
+        SonarSnapshot {
+            streamer_mode: false,
+            volumes: BTreeMap::new(),
+            chat_mix,
+            redirections: Vec::new(),
+            stream_redirections: Vec::new(),
+            selected_configs: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_profile_store() {
+        let dir = std::env::temp_dir().join(format!("sonar-profiles-{}", std::process::id()));
+        let store = ProfileStore::new(&dir);
+        assert!(store.list().unwrap().is_empty());
+
+        store.save("streaming", &snapshot(0.5)).unwrap();
+        store.save("late-night", &snapshot(-0.5)).unwrap();
+        store.save("streaming", &snapshot(0.25)).unwrap();
+        assert_eq!(store.list().unwrap(), vec!["late-night", "streaming"]);
+        assert_eq!(store.load("streaming").unwrap().chat_mix, 0.25);
+
+        store.delete("late-night").unwrap();
+        assert!(matches!(store.load("late-night"), Err(SonarError::ProfileNotFound(_))));
+        assert!(matches!(store.delete("late-night"), Err(SonarError::ProfileNotFound(_))));
+        assert!(matches!(
+            store.save("../escape", &snapshot(0.0)),
+            Err(SonarError::InvalidProfileName(_))
+        ));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,227 @@
+//! Push-to-talk on top of Sonar.
+//!
+//! [`Sonar::push_to_talk`](crate::Sonar::push_to_talk) mutes the microphone
+//! channel and unmutes it only while a global hotkey is held, which
+//! SteelSeries GG cannot do on its own. Releases shorter than
+//! [`PushToTalkOptions::debounce`] are ignored, so a bouncing key does not
+//! cut words off, and the channel stays open for at least
+//! [`PushToTalkOptions::min_open`] after a tap.
+//!
+//! Hotkeys are global on Windows and on Linux with X11. macOS only delivers
+//! them to the main thread, so push-to-talk does not work there.
+
+use crate::cancel::CancellationToken;
+use crate::error::Result;
+use crate::hotkey_listener::{listen, parse_hotkey};
+use crate::sonar::{Channel, Sonar, StreamerSlider};
+use global_hotkey::{GlobalHotKeyEvent, HotKeyState};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+const EVENT_BUFFER: usize = 16;
+
+/// Options of [`Sonar::push_to_talk`](crate::Sonar::push_to_talk).
+///
+/// ```
+/// use steelseries_sonar::PushToTalkOptions;
+///
+/// let options = PushToTalkOptions::new("Ctrl+Shift+KeyT");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PushToTalkOptions {
+    /// The key to hold, e.g. `"F13"` or `"Ctrl+Shift+KeyT"`.
+    pub hotkey: String,
+    /// The channel unmuted while the key is held.
+    pub channel: Channel,
+    /// Streamer slider of the channel in streamer mode.
+    pub slider: Option<StreamerSlider>,
+    /// Releases shorter than this are ignored.
+    pub debounce: Duration,
+    /// The shortest time the channel is unmuted for.
+    pub min_open: Duration,
+}
+
+impl PushToTalkOptions {
+    /// Unmute the microphone (`chatCapture`) while `hotkey` is held.
+    pub fn new(hotkey: impl Into<String>) -> Self {
+        Self {
+            hotkey: hotkey.into(),
+            channel: Channel::ChatCapture,
+            slider: None,
+            debounce: Duration::from_millis(30),
+            min_open: Duration::from_millis(250),
+        }
+    }
+}
+
+/// A change made by [`PushToTalk`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PttEvent {
+    /// The channel was unmuted.
+    Opened,
+    /// The channel was muted again.
+    Closed,
+}
+
+/// Handle to running push-to-talk.
+///
+/// Stopping or dropping it mutes the channel and unregisters the hotkey.
+#[derive(Debug)]
+pub struct PushToTalk {
+    receiver: mpsc::Receiver<Result<PttEvent>>,
+    token: CancellationToken,
+    task: Option<JoinHandle<()>>,
+}
+
+impl PushToTalk {
+    pub(crate) fn spawn(sonar: Sonar, options: PushToTalkOptions) -> Result<Self> {
+        let hotkey = parse_hotkey(&options.hotkey)?;
+        let (keys, _thread) = listen(vec![hotkey])?;
+        let (sender, receiver) = mpsc::channel(EVENT_BUFFER);
+        let token = CancellationToken::new();
+        let task = tokio::spawn(run_loop(sonar, options, keys, token.clone(), sender));
+        Ok(Self {
+            receiver,
+            token,
+            task: Some(task),
+        })
+    }
+
+    /// Wait for the next change, or the error that prevented one.
+    ///
+    /// Returns `None` once push-to-talk has stopped.
+    pub async fn recv(&mut self) -> Option<Result<PttEvent>> {
+        self.receiver.recv().await
+    }
+
+    /// Stop push-to-talk, waiting until the channel is muted.
+    pub async fn stop(mut self) {
+        self.token.cancel();
+        if let Some(task) = self.task.take() {
+            let _ = task.await;
+        }
+    }
+}
+
+impl Drop for PushToTalk {
+    fn drop(&mut self) {
+        // The task mutes the channel and exits on its own.
+        self.token.cancel();
+    }
+}
+
+/// Whether the channel is open, and when it is to be closed.
+#[derive(Debug, Default)]
+struct PttState {
+    open: bool,
+    opened_at: Option<Instant>,
+    close_at: Option<Instant>,
+}
+
+impl PttState {
+    /// The key went down; returns whether to open the channel.
+    fn press(&mut self, now: Instant) -> bool {
+        self.close_at = None;
+        if self.open {
+            return false;
+        }
+        self.open = true;
+        self.opened_at = Some(now);
+        true
+    }
+
+    /// The key went up; the channel is closed once the debounce time and
+    /// the minimum open time are over, unless the key goes down again.
+    fn release(&mut self, now: Instant, options: &PushToTalkOptions) {
+        if let Some(opened_at) = self.opened_at.filter(|_| self.open) {
+            self.close_at = Some((now + options.debounce).max(opened_at + options.min_open));
+        }
+    }
+
+    /// Returns whether to close the channel now.
+    fn poll(&mut self, now: Instant) -> bool {
+        if self.close_at.is_some_and(|close_at| close_at <= now) {
+            self.open = false;
+            self.close_at = None;
+            return true;
+        }
+        false
+    }
+}
+
+async fn run_loop(
+    sonar: Sonar,
+    options: PushToTalkOptions,
+    mut keys: mpsc::UnboundedReceiver<GlobalHotKeyEvent>,
+    token: CancellationToken,
+    sender: mpsc::Sender<Result<PttEvent>>,
+) {
+    let channel = options.channel.as_str();
+    if let Err(error) = sonar.mute_channel(channel, true, options.slider).await {
+        let _ = sender.try_send(Err(error));
+    }
+
+    let mut state = PttState::default();
+    loop {
+        let close_at = state.close_at;
+        let event = tokio::select! {
+            () = token.cancelled() => break,
+            key = keys.recv() => {
+                let Some(key) = key else { break };
+                match key.state {
+                    HotKeyState::Pressed if state.press(Instant::now()) => Some(PttEvent::Opened),
+                    HotKeyState::Pressed => None,
+                    HotKeyState::Released => {
+                        state.release(Instant::now(), &options);
+                        None
+                    }
+                }
+            }
+            () = tokio::time::sleep_until(close_at.unwrap_or_else(Instant::now)), if close_at.is_some() => {
+                state.poll(Instant::now()).then_some(PttEvent::Closed)
+            }
+        };
+        if let Some(event) = event {
+            let muted = event == PttEvent::Closed;
+            // Nobody listening is fine, the channel is switched anyway.
+            let _ = sender.try_send(sonar.mute_channel(channel, muted, options.slider).await.map(|_| event));
+        }
+    }
+
+    if state.open {
+        let _ = sender.try_send(sonar.mute_channel(channel, true, options.slider).await.map(|_| PttEvent::Closed));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ptt_state() {
+        let options = PushToTalkOptions::new("F13");
+        let start = Instant::now();
+        let mut state = PttState::default();
+
+        assert!(state.press(start));
+        assert!(!state.press(start));
+        // A tap stays open for the minimum time
+        state.release(start + Duration::from_millis(10), &options);
+        assert!(!state.poll(start + Duration::from_millis(100)));
+        assert!(state.poll(start + options.min_open));
+        assert!(!state.open);
+
+        // Bouncing releases are ignored
+        let start = start + Duration::from_secs(1);
+        assert!(state.press(start));
+        state.release(start + Duration::from_secs(1), &options);
+        assert!(!state.press(start + Duration::from_secs(1) + Duration::from_millis(5)));
+        assert!(!state.poll(start + Duration::from_secs(2)));
+
+        state.release(start + Duration::from_secs(2), &options);
+        assert!(!state.poll(start + Duration::from_secs(2) + Duration::from_millis(10)));
+        assert!(state.poll(start + Duration::from_secs(2) + options.debounce));
+    }
+}
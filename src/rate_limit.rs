@@ -0,0 +1,114 @@
+//! Opt-in client-side rate limiting.
+//!
+//! The SteelSeries GG web server becomes unresponsive under request storms,
+//! e.g. from automation scripts. A [`RateLimit`] set via
+//! [`SonarBuilder::rate_limit`](crate::SonarBuilder::rate_limit) delays
+//! requests that exceed it instead of sending them right away.
+
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Token bucket limit for outgoing requests.
+///
+/// Up to `burst` requests are sent without delay; after that, requests are
+/// spaced to `requests_per_second` on average. Retries and reconnects count
+/// as requests. Clones of a client share their limit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    /// Average number of requests per second. Values that are not positive
+    /// disable the limit.
+    pub requests_per_second: f64,
+    /// Number of requests that may be sent back-to-back.
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// Allow `requests_per_second` requests per second, with a burst of the
+    /// same size.
+    pub fn per_second(requests_per_second: u32) -> Self {
+        Self {
+            requests_per_second: f64::from(requests_per_second),
+            burst: requests_per_second.max(1),
+        }
+    }
+
+    /// Set the number of requests that may be sent back-to-back.
+    pub fn burst(mut self, burst: u32) -> Self {
+        self.burst = burst;
+        self
+    }
+}
+
+/// The shared state of a [`RateLimit`].
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    rate: f64,
+    burst: f64,
+    bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    /// Negative while requests are waiting for their turn.
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    /// `None` if the limit is disabled.
+    pub fn new(limit: RateLimit) -> Option<Self> {
+        if !(limit.requests_per_second.is_finite() && limit.requests_per_second > 0.0) {
+            return None;
+        }
+        let burst = f64::from(limit.burst.max(1));
+        Some(Self {
+            rate: limit.requests_per_second,
+            burst,
+            bucket: Mutex::new(Bucket {
+                tokens: burst,
+                updated: Instant::now(),
+            }),
+        })
+    }
+
+    /// Reserve a request and return how long to wait before sending it.
+    pub fn acquire(&self) -> Duration {
+        self.acquire_at(Instant::now())
+    }
+
+    fn acquire_at(&self, now: Instant) -> Duration {
+        let mut bucket = self.bucket.lock().unwrap_or_else(PoisonError::into_inner);
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.burst) - 1.0;
+        bucket.updated = now;
+
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / self.rate)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket() {
+        let limiter = RateLimiter::new(RateLimit::per_second(10).burst(2)).unwrap();
+        let start = Instant::now();
+        assert_eq!(limiter.acquire_at(start), Duration::ZERO);
+        assert_eq!(limiter.acquire_at(start), Duration::ZERO);
+        assert_eq!(limiter.acquire_at(start), Duration::from_millis(100));
+        assert_eq!(limiter.acquire_at(start), Duration::from_millis(200));
+
+        // The queue drains, then the bucket refills up to the burst size
+        let later = start + Duration::from_secs(10);
+        assert_eq!(limiter.acquire_at(later), Duration::ZERO);
+        assert_eq!(limiter.acquire_at(later), Duration::ZERO);
+        assert!(limiter.acquire_at(later) > Duration::ZERO);
+
+        assert!(RateLimiter::new(RateLimit::per_second(0)).is_none());
+    }
+}
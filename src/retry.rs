@@ -0,0 +1,153 @@
+//! Opt-in retries for transient request failures.
+//!
+//! The Sonar web server briefly refuses connections or answers `503` while
+//! the SteelSeries Engine is starting or reloading. A [`RetryPolicy`] set via
+//! [`SonarBuilder::retry_policy`](crate::SonarBuilder::retry_policy) retries
+//! such requests with exponential backoff.
+
+use crate::error::SonarError;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// How failed requests are retried.
+///
+/// `POST` requests (such as [`create_config`](crate::Sonar::create_config))
+/// are never retried, since they are not idempotent.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one.
+    pub max_attempts: u32,
+    /// Delay before the first retry.
+    pub initial_backoff: Duration,
+    /// Upper bound for the delay between attempts.
+    pub max_backoff: Duration,
+    /// Factor the delay grows by after each attempt.
+    pub multiplier: f64,
+    /// Randomize each delay between half and the full backoff.
+    pub jitter: bool,
+    /// Decides which errors are retried, [`is_transient`] by default.
+    pub retry_on: fn(&SonarError) -> bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(2),
+            multiplier: 2.0,
+            jitter: true,
+            retry_on: is_transient,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Set the total number of attempts, including the first one.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Set the initial and maximum backoff.
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Set the factor the backoff grows by after each attempt.
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Enable or disable jitter.
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Use a custom classification of retryable errors.
+    pub fn retry_on(mut self, retry_on: fn(&SonarError) -> bool) -> Self {
+        self.retry_on = retry_on;
+        self
+    }
+
+    /// Whether a request that failed with `error` on the given (1-based)
+    /// attempt should be retried.
+    pub(crate) fn should_retry(&self, error: &SonarError, attempt: u32) -> bool {
+        attempt < self.max_attempts && (self.retry_on)(error)
+    }
+
+    /// Delay to wait after the given (1-based) failed attempt.
+    pub(crate) fn delay(&self, attempt: u32) -> Duration {
+        let exponent = i32::try_from(attempt.saturating_sub(1)).unwrap_or(i32::MAX);
+        let backoff = self.initial_backoff.as_secs_f64() * self.multiplier.max(1.0).powi(exponent);
+        let backoff = backoff.min(self.max_backoff.as_secs_f64());
+        let backoff = if self.jitter {
+            backoff * (0.5 + random_fraction() / 2.0)
+        } else {
+            backoff
+        };
+        Duration::try_from_secs_f64(backoff).unwrap_or(self.max_backoff)
+    }
+}
+
+/// Default retry classification, see [`SonarError::is_retryable`].
+pub fn is_transient(error: &SonarError) -> bool {
+    error.is_retryable()
+}
+
+/// A random number in `0.0..1.0`, seeded from the std hasher's random keys.
+fn random_fraction() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(0);
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy::default()
+            .backoff(Duration::from_millis(100), Duration::from_millis(350))
+            .jitter(false);
+
+        assert_eq!(policy.delay(1), Duration::from_millis(100));
+        assert_eq!(policy.delay(2), Duration::from_millis(200));
+        assert_eq!(policy.delay(3), Duration::from_millis(350));
+        assert_eq!(policy.delay(10), Duration::from_millis(350));
+    }
+
+    #[test]
+    fn test_jitter_bounds() {
+        let policy = RetryPolicy::default();
+        for _ in 0..100 {
+            let delay = policy.delay(2);
+            assert!(delay >= Duration::from_millis(100) && delay <= Duration::from_millis(200));
+        }
+    }
+
+    #[test]
+    fn test_should_retry() {
+        let policy = RetryPolicy::default();
+
+        assert!(policy.should_retry(&SonarError::ServerNotAccessible(503), 1));
+        assert!(policy.should_retry(&SonarError::Timeout, 2));
+        assert!(!policy.should_retry(&SonarError::Timeout, 3));
+        assert!(!policy.should_retry(&SonarError::ServerNotAccessible(404), 1));
+        assert!(!RetryPolicy::none().should_retry(&SonarError::Timeout, 1));
+    }
+}
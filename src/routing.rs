@@ -0,0 +1,189 @@
+//! Channel-to-device routing ("redirections").
+
+use crate::devices::AudioDevice;
+use crate::error::{Result, SonarError};
+use crate::sonar::StreamerSlider;
+use serde::{Deserialize, Serialize};
+
+/// Output (or input) device assigned to a channel in classic mode.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClassicRedirection {
+    /// The channel name, e.g. `"media"`.
+    #[serde(rename = "id")]
+    pub channel: String,
+    /// Id of the audio device the channel is routed to.
+    #[serde(rename = "deviceId")]
+    pub device_id: String,
+    #[serde(rename = "isRunning", default)]
+    pub is_running: bool,
+}
+
+/// Output device assigned to a streamer-mode mix.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StreamRedirection {
+    /// The mix this redirection belongs to.
+    #[serde(rename = "streamRedirectionId")]
+    pub slider: StreamerSlider,
+    /// Id of the audio device the mix is routed to.
+    #[serde(rename = "deviceId")]
+    pub device_id: String,
+    /// Per-channel state of this mix.
+    #[serde(default)]
+    pub redirections: Vec<ChannelRedirection>,
+}
+
+impl StreamRedirection {
+    /// Whether a channel is muted in this mix, independently of the channel's own mute.
+    ///
+    /// Returns `None` if the channel is not part of the mix.
+    pub fn is_channel_muted(&self, channel: &str) -> Option<bool> {
+        self.redirections
+            .iter()
+            .find(|redirection| redirection.channel == channel)
+            .map(ChannelRedirection::is_muted)
+    }
+}
+
+/// State of a single channel within a streamer-mode mix.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelRedirection {
+    #[serde(rename = "id")]
+    pub channel: String,
+    /// `false` when the channel is muted in this mix.
+    #[serde(rename = "isEnabled", default = "default_enabled")]
+    pub is_enabled: bool,
+}
+
+impl ChannelRedirection {
+    /// Whether the channel is muted in this mix.
+    pub fn is_muted(&self) -> bool {
+        !self.is_enabled
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// The channel fed by the microphone.
+pub(crate) const CAPTURE_CHANNEL: &str = "chatCapture";
+
+/// Check that `device_id` is among `devices` and records audio.
+pub(crate) fn check_capture_device(devices: &[AudioDevice], device_id: &str) -> Result<()> {
+    if devices.iter().any(|device| device.id == device_id && device.is_capture()) {
+        Ok(())
+    } else {
+        Err(SonarError::CaptureDeviceNotFound(device_id.to_string()))
+    }
+}
+
+/// The device `channel` is routed to in classic mode, if it is among `devices`.
+pub(crate) fn channel_device(redirections: &[ClassicRedirection], devices: Vec<AudioDevice>, channel: &str) -> Option<AudioDevice> {
+    let device_id = &redirections.iter().find(|redirection| redirection.channel == channel)?.device_id;
+    devices.into_iter().find(|device| device.id == *device_id)
+}
+
+/// Applications (audio sessions) Sonar has assigned to a channel.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelApps {
+    /// The channel name, e.g. `"media"`.
+    #[serde(rename = "role")]
+    pub channel: String,
+    /// Id of the Sonar virtual device backing the channel.
+    #[serde(rename = "deviceId", default)]
+    pub device_id: String,
+    #[serde(rename = "audioSessions", default)]
+    pub apps: Vec<RoutedApp>,
+}
+
+/// An application audio session routed through Sonar.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoutedApp {
+    /// Sonar's identifier of the audio session.
+    pub id: String,
+    #[serde(rename = "processId")]
+    pub process_id: u32,
+    /// Executable name, e.g. `"Spotify.exe"`.
+    #[serde(rename = "processName")]
+    pub process_name: String,
+    #[serde(rename = "displayName", default)]
+    pub display_name: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_classic_redirections() {
+        let redirections: Vec<ClassicRedirection> = serde_json::from_value(json!([
+            { "id": "game", "deviceId": "{0.0.0.00000000}.{a}", "isRunning": true },
+            { "id": "media", "deviceId": "{0.0.0.00000000}.{b}" }
+        ]))
+        .unwrap();
+
+        assert_eq!(redirections[0].channel, "game");
+        assert!(redirections[0].is_running);
+        assert_eq!(redirections[1].device_id, "{0.0.0.00000000}.{b}");
+        assert!(!redirections[1].is_running);
+    }
+
+    #[test]
+    fn test_parse_stream_redirections() {
+        let redirections: Vec<StreamRedirection> = serde_json::from_value(json!([
+            { "streamRedirectionId": "streaming", "deviceId": "{0.0.0.00000000}.{cable}" },
+            { "streamRedirectionId": "monitoring", "deviceId": "{0.0.0.00000000}.{headset}" }
+        ]))
+        .unwrap();
+
+        assert_eq!(redirections[0].slider, StreamerSlider::Streaming);
+        assert_eq!(redirections[1].slider, StreamerSlider::Monitoring);
+        assert_eq!(redirections[1].device_id, "{0.0.0.00000000}.{headset}");
+        assert!(redirections[0].redirections.is_empty());
+    }
+
+    #[test]
+    fn test_redirection_mute_state() {
+        let redirection: StreamRedirection = serde_json::from_value(json!({
+            "streamRedirectionId": "streaming",
+            "deviceId": "{0.0.0.00000000}.{cable}",
+            "redirections": [
+                { "id": "game", "isEnabled": true },
+                { "id": "chatRender", "isEnabled": false },
+                { "id": "media" }
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(redirection.is_channel_muted("game"), Some(false));
+        assert_eq!(redirection.is_channel_muted("chatRender"), Some(true));
+        assert_eq!(redirection.is_channel_muted("media"), Some(false));
+        assert_eq!(redirection.is_channel_muted("aux"), None);
+    }
+
+    #[test]
+    fn test_parse_routed_apps() {
+        let routing: Vec<ChannelApps> = serde_json::from_value(json!([
+            {
+                "role": "media",
+                "deviceId": "{0.0.0.00000000}.{sonar-media}",
+                "audioSessions": [
+                    {
+                        "id": "session-1",
+                        "processId": 4242,
+                        "processName": "Spotify.exe",
+                        "displayName": "Spotify"
+                    }
+                ]
+            },
+            { "role": "aux" }
+        ]))
+        .unwrap();
+
+        assert_eq!(routing[0].channel, "media");
+        assert_eq!(routing[0].apps[0].process_id, 4242);
+        assert_eq!(routing[0].apps[0].process_name, "Spotify.exe");
+        assert!(routing[1].apps.is_empty());
+    }
+}
@@ -0,0 +1,203 @@
+//! Scenes: sets of mixer changes applied as a unit.
+
+use crate::error::SonarError;
+use crate::sonar::{Mode, StreamerSlider};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Volumes read back within this distance of the requested value count as applied.
+const VOLUME_TOLERANCE: f64 = 1e-3;
+
+/// A sequence of mixer changes, applied in order by
+/// [`Sonar::apply_scene`](crate::Sonar::apply_scene).
+///
+/// ```
+/// use steelseries_sonar::Scene;
+///
+/// let scene = Scene::new()
+///     .volume("game", 0.3)
+///     .mute("media", true)
+///     .chat_mix(0.5);
+/// assert_eq!(scene.changes().len(), 3);
+/// ```
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Scene {
+    changes: Vec<SceneChange>,
+}
+
+impl Scene {
+    /// Create an empty scene.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Switch to classic or streamer mode.
+    pub fn mode(self, mode: Mode) -> Self {
+        self.change(SceneChange::Mode(mode))
+    }
+
+    /// Set the volume of a channel in the current mode's default slider.
+    pub fn volume(self, channel: &str, volume: f64) -> Self {
+        self.change(SceneChange::Volume {
+            channel: channel.to_string(),
+            volume,
+            slider: None,
+        })
+    }
+
+    /// Set the volume of a channel on a streamer slider.
+    pub fn slider_volume(self, channel: &str, slider: StreamerSlider, volume: f64) -> Self {
+        self.change(SceneChange::Volume {
+            channel: channel.to_string(),
+            volume,
+            slider: Some(slider),
+        })
+    }
+
+    /// Mute or unmute a channel in the current mode's default slider.
+    pub fn mute(self, channel: &str, muted: bool) -> Self {
+        self.change(SceneChange::Mute {
+            channel: channel.to_string(),
+            muted,
+            slider: None,
+        })
+    }
+
+    /// Mute or unmute a channel on a streamer slider.
+    pub fn slider_mute(self, channel: &str, slider: StreamerSlider, muted: bool) -> Self {
+        self.change(SceneChange::Mute {
+            channel: channel.to_string(),
+            muted,
+            slider: Some(slider),
+        })
+    }
+
+    /// Set the chat mix balance.
+    pub fn chat_mix(self, balance: f64) -> Self {
+        self.change(SceneChange::ChatMix(balance))
+    }
+
+    /// Append a change.
+    pub fn change(mut self, change: SceneChange) -> Self {
+        self.changes.push(change);
+        self
+    }
+
+    /// The changes of this scene, in order.
+    pub fn changes(&self) -> &[SceneChange] {
+        &self.changes
+    }
+}
+
+/// A single change of a [`Scene`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SceneChange {
+    Mode(Mode),
+    Volume {
+        channel: String,
+        volume: f64,
+        slider: Option<StreamerSlider>,
+    },
+    Mute {
+        channel: String,
+        muted: bool,
+        slider: Option<StreamerSlider>,
+    },
+    ChatMix(f64),
+}
+
+impl SceneChange {
+    /// Whether `actual`, read back from the server, shows this change as applied.
+    pub(crate) fn is_applied(&self, actual: &SceneChange) -> bool {
+        match (self, actual) {
+            (SceneChange::Volume { volume, .. }, SceneChange::Volume { volume: actual, .. })
+            | (SceneChange::ChatMix(volume), SceneChange::ChatMix(actual)) => (volume - actual).abs() < VOLUME_TOLERANCE,
+            _ => self == actual,
+        }
+    }
+}
+
+impl fmt::Display for SceneChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let on_slider = |slider: &Option<StreamerSlider>| slider.map(|slider| format!(" ({})", slider)).unwrap_or_default();
+        match self {
+            SceneChange::Mode(Mode::Streamer) => f.write_str("streamer mode"),
+            SceneChange::Mode(Mode::Classic) => f.write_str("classic mode"),
+            SceneChange::Volume { channel, volume, slider } => {
+                write!(f, "volume of {}{} to {}", channel, on_slider(slider), volume)
+            }
+            SceneChange::Mute { channel, muted, slider } => {
+                let action = if *muted { "mute" } else { "unmute" };
+                write!(f, "{} {}{}", action, channel, on_slider(slider))
+            }
+            SceneChange::ChatMix(balance) => write!(f, "chat mix to {}", balance),
+        }
+    }
+}
+
+/// What happened to a change when its scene was applied.
+#[derive(Debug)]
+pub enum ChangeOutcome {
+    /// Written and verified.
+    Applied,
+    /// Could not be written or verified; the changes before it were rolled back.
+    Failed(SonarError),
+    /// Applied, then undone because a later change failed.
+    RolledBack,
+    /// Applied, but undoing it failed, so it is still in effect.
+    RollbackFailed(SonarError),
+    /// Not attempted because an earlier change failed.
+    Skipped,
+}
+
+/// Outcome of [`Sonar::apply_scene`](crate::Sonar::apply_scene), one entry per change.
+#[derive(Debug, Default)]
+pub struct SceneReport {
+    pub outcomes: Vec<(SceneChange, ChangeOutcome)>,
+}
+
+impl SceneReport {
+    /// Whether every change of the scene was applied.
+    pub fn is_success(&self) -> bool {
+        self.outcomes
+            .iter()
+            .all(|(_, outcome)| matches!(outcome, ChangeOutcome::Applied))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scene_builder() {
+        let scene = Scene::new()
+            .mode(Mode::Streamer)
+            .slider_volume("game", StreamerSlider::Monitoring, 0.5)
+            .mute("chatCapture", true)
+            .chat_mix(-0.2);
+
+        assert_eq!(scene.changes().len(), 4);
+        assert_eq!(scene.changes()[0], SceneChange::Mode(Mode::Streamer));
+        assert_eq!(scene.changes()[1].to_string(), "volume of game (monitoring) to 0.5");
+        assert_eq!(scene.changes()[2].to_string(), "mute chatCapture");
+    }
+
+    #[test]
+    fn test_is_applied() {
+        let change = SceneChange::Volume {
+            channel: "game".to_string(),
+            volume: 0.3,
+            slider: None,
+        };
+        let read_back = SceneChange::Volume {
+            channel: "game".to_string(),
+            volume: 0.300_000_011_920_928_96,
+            slider: None,
+        };
+        assert!(change.is_applied(&read_back));
+        assert!(!change.is_applied(&SceneChange::ChatMix(0.3)));
+        assert!(!SceneChange::Mode(Mode::Streamer).is_applied(&SceneChange::Mode(Mode::Classic)));
+    }
+}
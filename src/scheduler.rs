@@ -0,0 +1,355 @@
+//! Time-based automation of the mixer.
+//!
+//! A [`Schedule`] is a list of [`ScheduleRule`]s, each applying a [`Scene`]
+//! at a time of day on some weekdays. [`Sonar::run_schedule`](crate::Sonar::run_schedule)
+//! executes a schedule in a background task. Schedules serialize to JSON, by
+//! default to `steelseries-sonar/schedule.json` inside the platform config
+//! directory.
+//!
+//! ```
+//! use steelseries_sonar::{Scene, Schedule, ScheduleRule, Trigger};
+//!
+//! let mut schedule = Schedule::new();
+//! schedule.add(ScheduleRule::new(
+//!     "quiet evening",
+//!     Trigger::at(22, 0)?,
+//!     Scene::new().volume("master", 0.3),
+//! ));
+//! schedule.add(ScheduleRule::new(
+//!     "work",
+//!     Trigger::at(9, 0)?.weekdays(),
+//!     Scene::new().mute("chatCapture", false),
+//! ));
+//! # Ok::<(), steelseries_sonar::SonarError>(())
+//! ```
+
+use crate::error::{Result, SonarError};
+use crate::scene::{Scene, SceneReport};
+use crate::sonar::Sonar;
+use chrono::{DateTime, Datelike, Days, Local, NaiveTime, TimeZone, Weekday};
+use serde::{Deserialize, Serialize};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+const EVENT_BUFFER: usize = 16;
+
+/// Longest single sleep of the scheduler task. Waking up regularly keeps the
+/// schedule on time when the system clock changes or the computer sleeps.
+const MAX_SLEEP: Duration = Duration::from_secs(60);
+
+/// Runs missed by more than this, e.g. while the computer was asleep, are skipped.
+const MISSED_RUN_GRACE: Duration = Duration::from_secs(5 * 60);
+
+/// When a [`ScheduleRule`] runs: a local time of day, on some or all weekdays.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Trigger {
+    pub time: NaiveTime,
+    /// Days the rule runs on; every day if empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub days: Vec<Weekday>,
+}
+
+impl Trigger {
+    /// Every day at `hour:minute`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SonarError::InvalidScheduleTime`] if the time does not exist.
+    pub fn at(hour: u32, minute: u32) -> Result<Self> {
+        let time = NaiveTime::from_hms_opt(hour, minute, 0).ok_or(SonarError::InvalidScheduleTime { hour, minute })?;
+        Ok(Self { time, days: Vec::new() })
+    }
+
+    /// Only run on `days`.
+    pub fn on(mut self, days: &[Weekday]) -> Self {
+        self.days = days.to_vec();
+        self
+    }
+
+    /// Only run Monday to Friday.
+    pub fn weekdays(self) -> Self {
+        self.on(&[Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri])
+    }
+
+    /// Only run on Saturday and Sunday.
+    pub fn weekends(self) -> Self {
+        self.on(&[Weekday::Sat, Weekday::Sun])
+    }
+
+    /// The first time the trigger fires strictly after `after`.
+    ///
+    /// Days on which the time of day does not exist, because of a daylight
+    /// saving time change, are skipped.
+    pub fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let timezone = after.timezone();
+        let today = after.date_naive();
+        (0..=7)
+            .filter_map(|offset| today.checked_add_days(Days::new(offset)))
+            .filter(|date| self.days.is_empty() || self.days.contains(&date.weekday()))
+            .filter_map(|date| timezone.from_local_datetime(&date.and_time(self.time)).earliest())
+            .find(|time| time > after)
+    }
+}
+
+/// A named [`Scene`] applied whenever its [`Trigger`] fires.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleRule {
+    pub name: String,
+    pub trigger: Trigger,
+    pub scene: Scene,
+}
+
+impl ScheduleRule {
+    pub fn new(name: &str, trigger: Trigger, scene: Scene) -> Self {
+        Self {
+            name: name.to_string(),
+            trigger,
+            scene,
+        }
+    }
+}
+
+/// A list of [`ScheduleRule`]s.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Schedule {
+    pub rules: Vec<ScheduleRule>,
+}
+
+impl Schedule {
+    /// Create an empty schedule.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a rule, replacing an existing rule of the same name.
+    pub fn add(&mut self, rule: ScheduleRule) -> &mut Self {
+        match self.rules.iter_mut().find(|existing| existing.name == rule.name) {
+            Some(existing) => *existing = rule,
+            None => self.rules.push(rule),
+        }
+        self
+    }
+
+    /// Remove the rule called `name`, returning it if there was one.
+    pub fn remove(&mut self, name: &str) -> Option<ScheduleRule> {
+        let index = self.rules.iter().position(|rule| rule.name == name)?;
+        Some(self.rules.remove(index))
+    }
+
+    /// The next time any rule fires strictly after `after`, with the rules
+    /// firing at that time.
+    pub fn next_run<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<(DateTime<Tz>, Vec<&ScheduleRule>)> {
+        let runs: Vec<_> = self
+            .rules
+            .iter()
+            .filter_map(|rule| Some((rule.trigger.next_after(after)?, rule)))
+            .collect();
+        let next = runs.iter().map(|(time, _)| time).min()?.clone();
+        let rules = runs
+            .into_iter()
+            .filter(|(time, _)| *time == next)
+            .map(|(_, rule)| rule)
+            .collect();
+        Some((next, rules))
+    }
+
+    /// Where [`load_default`](Self::load_default) and
+    /// [`save_default`](Self::save_default) keep the schedule.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the platform has no config directory.
+    pub fn default_path() -> Result<PathBuf> {
+        let config_dir = dirs::config_dir()
+            .ok_or_else(|| std::io::Error::new(ErrorKind::NotFound, "no config directory on this platform"))?;
+        Ok(config_dir.join("steelseries-sonar").join("schedule.json"))
+    }
+
+    /// Read a schedule from a JSON file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not a schedule.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+    }
+
+    /// Write the schedule to a JSON file, creating its directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        Ok(std::fs::write(path, serde_json::to_vec_pretty(self)?)?)
+    }
+
+    /// Read the schedule from the [default path](Self::default_path), or an
+    /// empty schedule if none was saved yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read.
+    pub fn load_default() -> Result<Self> {
+        match Self::load(Self::default_path()?) {
+            Err(SonarError::Io(error)) if error.kind() == ErrorKind::NotFound => Ok(Self::new()),
+            result => result,
+        }
+    }
+
+    /// Write the schedule to the [default path](Self::default_path).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn save_default(&self) -> Result<()> {
+        self.save(Self::default_path()?)
+    }
+}
+
+/// A rule executed by a [`Scheduler`].
+///
+/// Runs missed by more than five minutes, e.g. while the computer was asleep,
+/// are skipped and not reported.
+#[derive(Debug)]
+pub struct ScheduleEvent {
+    pub rule: String,
+    /// When the rule was due.
+    pub due: DateTime<Local>,
+    pub report: SceneReport,
+}
+
+/// Handle to a running schedule.
+///
+/// The background task is stopped when the scheduler is dropped.
+#[derive(Debug)]
+pub struct Scheduler {
+    receiver: mpsc::Receiver<ScheduleEvent>,
+    task: JoinHandle<()>,
+}
+
+impl Scheduler {
+    pub(crate) fn spawn(sonar: Sonar, schedule: Schedule) -> Self {
+        let (sender, receiver) = mpsc::channel(EVENT_BUFFER);
+        let task = tokio::spawn(run_loop(sonar, schedule, sender));
+        Self { receiver, task }
+    }
+
+    /// Wait for the next executed rule.
+    ///
+    /// Returns `None` once the schedule has no more runs.
+    pub async fn recv(&mut self) -> Option<ScheduleEvent> {
+        self.receiver.recv().await
+    }
+
+    /// Stop the schedule.
+    pub fn stop(self) {
+        // Dropping aborts the task.
+    }
+}
+
+impl Drop for Scheduler {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn run_loop(sonar: Sonar, schedule: Schedule, sender: mpsc::Sender<ScheduleEvent>) {
+    let mut checked = Local::now();
+    while let Some((due, rules)) = schedule.next_run(&checked) {
+        let now = Local::now();
+        if due > now {
+            let wait = (due - now).to_std().unwrap_or_default().min(MAX_SLEEP);
+            tokio::time::sleep(wait).await;
+            continue;
+        }
+
+        let missed = (now - due).to_std().unwrap_or_default() > MISSED_RUN_GRACE;
+        for rule in rules.into_iter().filter(|_| !missed) {
+            let report = sonar.apply_scene(&rule.scene).await;
+            let event = ScheduleEvent {
+                rule: rule.name.clone(),
+                due,
+                report,
+            };
+            // Nobody listening is fine, the rules still run.
+            let _ = sender.try_send(event);
+        }
+        checked = due;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn utc(date: &str) -> DateTime<Utc> {
+        date.parse().unwrap()
+    }
+
+    #[test]
+    fn test_trigger_next_after() {
+        // 2024-01-05 is a Friday.
+        let friday_noon = utc("2024-01-05T12:00:00Z");
+
+        let daily = Trigger::at(22, 0).unwrap();
+        assert_eq!(daily.next_after(&friday_noon), Some(utc("2024-01-05T22:00:00Z")));
+        assert_eq!(daily.next_after(&utc("2024-01-05T22:00:00Z")), Some(utc("2024-01-06T22:00:00Z")));
+
+        let weekdays = Trigger::at(9, 0).unwrap().weekdays();
+        assert_eq!(weekdays.next_after(&friday_noon), Some(utc("2024-01-08T09:00:00Z")));
+
+        let sundays = Trigger::at(9, 0).unwrap().on(&[Weekday::Sun]);
+        assert_eq!(sundays.next_after(&utc("2024-01-07T10:00:00Z")), Some(utc("2024-01-14T09:00:00Z")));
+
+        assert!(matches!(Trigger::at(24, 0), Err(SonarError::InvalidScheduleTime { hour: 24, .. })));
+    }
+
+    #[test]
+    fn test_schedule_next_run() {
+        let mut schedule = Schedule::new();
+        assert!(schedule.next_run(&utc("2024-01-05T12:00:00Z")).is_none());
+
+        schedule
+            .add(ScheduleRule::new("evening", Trigger::at(22, 0).unwrap(), Scene::new().volume("master", 0.3)))
+            .add(ScheduleRule::new("chat", Trigger::at(22, 0).unwrap(), Scene::new().chat_mix(0.5)))
+            .add(ScheduleRule::new("morning", Trigger::at(8, 0).unwrap(), Scene::new().volume("master", 0.8)));
+
+        let (due, rules) = schedule.next_run(&utc("2024-01-05T12:00:00Z")).unwrap();
+        assert_eq!(due, utc("2024-01-05T22:00:00Z"));
+        assert_eq!(rules.iter().map(|rule| rule.name.as_str()).collect::<Vec<_>>(), ["evening", "chat"]);
+
+        assert!(schedule.remove("chat").is_some());
+        assert!(schedule.remove("chat").is_none());
+        let (due, rules) = schedule.next_run(&due).unwrap();
+        assert_eq!(due, utc("2024-01-06T08:00:00Z"));
+        assert_eq!(rules[0].name, "morning");
+    }
+
+    #[test]
+    fn test_schedule_round_trip() {
+        let mut schedule = Schedule::new();
+        schedule.add(ScheduleRule::new(
+            "work",
+            Trigger::at(9, 30).unwrap().weekdays(),
+            Scene::new().mute("chatCapture", false),
+        ));
+
+        let path = std::env::temp_dir()
+            .join(format!("sonar-schedule-{}", std::process::id()))
+            .join("schedule.json");
+        schedule.save(&path).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(json["rules"][0]["trigger"]["time"], "09:30:00");
+        assert_eq!(Schedule::load(&path).unwrap(), schedule);
+
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
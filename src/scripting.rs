@@ -0,0 +1,357 @@
+//! Automation rules written as [Rhai](https://rhai.rs) scripts.
+//!
+//! [`Sonar::run_script`](crate::Sonar::run_script) watches the mixer and runs
+//! a [`Script`] for every [`SonarEvent`], so users can automate Sonar without
+//! recompiling Rust. The event is available to the script as `event`, a map
+//! with a `kind` (`"volume_changed"`, `"mute_changed"`, `"chat_mix_changed"`,
+//! `"mode_changed"` or `"reconnected"`) and the fields of the event:
+//!
+//! ```text
+//! if event.kind == "chat_mix_changed" && event.balance < -0.8 {
+//!     unmute("chatRender");
+//! }
+//! ```
+//!
+//! Scripts change the mixer with `set_volume(channel, volume)`,
+//! `mute(channel)`, `unmute(channel)`, `set_chat_mix(balance)`,
+//! `set_mode(mode)` and `load_profile(name)`. The channel functions take the
+//! streamer slider as an optional last argument, e.g.
+//! `mute("media", "streaming")`. The actions are collected while the script
+//! runs and performed afterwards; if the script fails, none are. Run by a
+//! client, scripts may also name channels by the client's channel aliases.
+
+use crate::builder::ClientOptions;
+use crate::error::{Result, SonarError};
+use crate::sonar::{Channel, Mode, Sonar, StreamerSlider};
+use crate::watch::SonarEvent;
+use rhai::{Dynamic, Engine, EvalAltResult, Map, Scope, AST};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+const EVENT_BUFFER: usize = 16;
+
+/// Operations a script may perform per event, so an endless loop cannot
+/// stall the runner.
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// A change a script asked for.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptAction {
+    SetVolume { channel: Channel, volume: f64, slider: Option<StreamerSlider> },
+    SetMute { channel: Channel, muted: bool, slider: Option<StreamerSlider> },
+    SetChatMix(f64),
+    SetMode(Mode),
+    LoadProfile(String),
+}
+
+type Actions = Arc<Mutex<Vec<ScriptAction>>>;
+
+/// The options channel names are resolved with, replaced by the client's
+/// when the script is run by one.
+type Options = Arc<Mutex<Arc<ClientOptions>>>;
+
+/// A compiled automation script.
+///
+/// ```
+/// use steelseries_sonar::{Channel, Script, ScriptAction, SonarEvent};
+///
+/// let script = Script::compile(r#"
+///     if event.kind == "chat_mix_changed" && event.balance < -0.8 {
+///         unmute("chatRender");
+///     }
+/// "#)?;
+/// let actions = script.run(&SonarEvent::ChatMixChanged { balance: -0.9 })?;
+/// assert_eq!(actions, [ScriptAction::SetMute { channel: Channel::ChatRender, muted: false, slider: None }]);
+/// # Ok::<(), steelseries_sonar::SonarError>(())
+/// ```
+#[derive(Debug)]
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+    actions: Actions,
+    options: Options,
+}
+
+impl Script {
+    /// Compile a script.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SonarError::Script`] if the script has a syntax error.
+    pub fn compile(source: &str) -> Result<Self> {
+        let actions = Actions::default();
+        let options = Options::default();
+        let engine = engine(&actions, &options);
+        let ast = engine.compile(source).map_err(|error| SonarError::Script(error.to_string()))?;
+        Ok(Self { engine, ast, actions, options })
+    }
+
+    /// Resolve channel names with `options`, e.g. those of the client
+    /// running the script.
+    pub(crate) fn set_options(&self, options: Arc<ClientOptions>) {
+        *self.options.lock().unwrap_or_else(PoisonError::into_inner) = options;
+    }
+
+    /// Read and compile a script file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, or
+    /// [`SonarError::Script`] if the script has a syntax error.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::compile(&std::fs::read_to_string(path)?)
+    }
+
+    /// Run the script for `event`, returning the actions it asked for
+    /// without performing them.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SonarError::Script`] if the script fails, e.g. on an unknown
+    /// channel name.
+    pub fn run(&self, event: &SonarEvent) -> Result<Vec<ScriptAction>> {
+        let mut scope = Scope::new();
+        scope.push_constant("event", event_map(event));
+        let result = self.engine.run_ast_with_scope(&mut scope, &self.ast);
+        let actions = std::mem::take(&mut *self.actions.lock().unwrap_or_else(PoisonError::into_inner));
+        result.map_err(|error| SonarError::Script(error.to_string()))?;
+        Ok(actions)
+    }
+}
+
+/// An action performed by a [`ScriptRunner`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptEvent {
+    /// The event the script ran for.
+    pub trigger: SonarEvent,
+    pub action: ScriptAction,
+}
+
+/// Handle to a running script.
+///
+/// The background task is stopped when the runner is dropped.
+#[derive(Debug)]
+pub struct ScriptRunner {
+    receiver: mpsc::Receiver<Result<ScriptEvent>>,
+    task: JoinHandle<()>,
+}
+
+impl ScriptRunner {
+    pub(crate) fn spawn(sonar: Sonar, script: Script, interval: Duration) -> Self {
+        script.set_options(Arc::clone(&sonar.core.options));
+        let (sender, receiver) = mpsc::channel(EVENT_BUFFER);
+        let task = tokio::spawn(run_loop(sonar, script, interval, sender));
+        Self { receiver, task }
+    }
+
+    /// Wait for the next performed action, or the error of a failed script
+    /// run or action.
+    ///
+    /// Returns `None` once the runner has stopped.
+    pub async fn recv(&mut self) -> Option<Result<ScriptEvent>> {
+        self.receiver.recv().await
+    }
+
+    /// Stop running the script.
+    pub fn stop(self) {
+        // Dropping aborts the task.
+    }
+}
+
+impl Drop for ScriptRunner {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn run_loop(sonar: Sonar, script: Script, interval: Duration, sender: mpsc::Sender<Result<ScriptEvent>>) {
+    let mut watcher = sonar.watch(interval);
+    while let Some(trigger) = watcher.recv().await {
+        let actions = match script.run(&trigger) {
+            Ok(actions) => actions,
+            Err(error) => {
+                let _ = sender.try_send(Err(error));
+                continue;
+            }
+        };
+        for action in actions {
+            let result = run_action(&sonar, &action).await;
+            // Nobody listening is fine, the actions are performed anyway.
+            let _ = sender.try_send(result.map(|()| ScriptEvent { trigger: trigger.clone(), action }));
+        }
+    }
+}
+
+async fn run_action(sonar: &Sonar, action: &ScriptAction) -> Result<()> {
+    match action {
+        ScriptAction::SetVolume { channel, volume, slider } => {
+            sonar.set_volume(channel.as_str(), *volume, *slider).await?;
+        }
+        ScriptAction::SetMute { channel, muted, slider } => {
+            sonar.mute_channel(channel.as_str(), *muted, *slider).await?;
+        }
+        ScriptAction::SetChatMix(balance) => {
+            sonar.set_chat_mix(*balance).await?;
+        }
+        ScriptAction::SetMode(mode) => {
+            sonar.set_mode(*mode).await?;
+        }
+        ScriptAction::LoadProfile(name) => sonar.load_profile(name).await?,
+    }
+    Ok(())
+}
+
+/// An engine with the mixer functions registered, collecting the actions
+/// into `actions` and resolving channel names with `options`.
+fn engine(actions: &Actions, options: &Options) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+
+    let push = |actions: &Actions| {
+        let actions = Arc::clone(actions);
+        move |action: ScriptAction| actions.lock().unwrap_or_else(PoisonError::into_inner).push(action)
+    };
+
+    let (add, channels) = (push(actions), Arc::clone(options));
+    engine.register_fn("set_volume", move |channel: &str, volume: f64| {
+        add(ScriptAction::SetVolume { channel: parse_channel(&channels, channel)?, volume, slider: None });
+        Ok::<_, Box<EvalAltResult>>(())
+    });
+    let (add, channels) = (push(actions), Arc::clone(options));
+    engine.register_fn("set_volume", move |channel: &str, volume: f64, slider: &str| {
+        add(ScriptAction::SetVolume { channel: parse_channel(&channels, channel)?, volume, slider: Some(parse(slider)?) });
+        Ok::<_, Box<EvalAltResult>>(())
+    });
+    for (name, muted) in [("mute", true), ("unmute", false)] {
+        let (add, channels) = (push(actions), Arc::clone(options));
+        engine.register_fn(name, move |channel: &str| {
+            add(ScriptAction::SetMute { channel: parse_channel(&channels, channel)?, muted, slider: None });
+            Ok::<_, Box<EvalAltResult>>(())
+        });
+        let (add, channels) = (push(actions), Arc::clone(options));
+        engine.register_fn(name, move |channel: &str, slider: &str| {
+            add(ScriptAction::SetMute { channel: parse_channel(&channels, channel)?, muted, slider: Some(parse(slider)?) });
+            Ok::<_, Box<EvalAltResult>>(())
+        });
+    }
+    let add = push(actions);
+    engine.register_fn("set_chat_mix", move |balance: f64| add(ScriptAction::SetChatMix(balance)));
+    let add = push(actions);
+    engine.register_fn("set_mode", move |mode: &str| {
+        add(ScriptAction::SetMode(parse(mode)?));
+        Ok::<_, Box<EvalAltResult>>(())
+    });
+    let add = push(actions);
+    engine.register_fn("load_profile", move |name: &str| add(ScriptAction::LoadProfile(name.to_string())));
+    engine
+}
+
+/// Parse a name passed by a script, failing the script if it is unknown.
+fn parse<T: FromStr<Err = SonarError>>(name: &str) -> std::result::Result<T, Box<EvalAltResult>> {
+    name.parse().map_err(|error: SonarError| error.to_string().into())
+}
+
+/// Resolve a channel name or alias passed by a script, failing the script if
+/// it is unknown.
+fn parse_channel(options: &Options, name: &str) -> std::result::Result<Channel, Box<EvalAltResult>> {
+    let options = Arc::clone(&options.lock().unwrap_or_else(PoisonError::into_inner));
+    let channel = options.resolve_channel(name).map_err(|error| error.to_string())?;
+    parse(channel)
+}
+
+/// The `event` map seen by scripts.
+fn event_map(event: &SonarEvent) -> Map {
+    let slider = |slider: &Option<StreamerSlider>| slider.map_or(Dynamic::UNIT, |slider| slider.as_str().into());
+    let fields: Vec<(&str, Dynamic)> = match event {
+        SonarEvent::VolumeChanged { channel, slider: s, volume } => vec![
+            ("kind", "volume_changed".into()),
+            ("channel", channel.clone().into()),
+            ("slider", slider(s)),
+            ("volume", (*volume).into()),
+        ],
+        SonarEvent::MuteChanged { channel, slider: s, muted } => vec![
+            ("kind", "mute_changed".into()),
+            ("channel", channel.clone().into()),
+            ("slider", slider(s)),
+            ("muted", (*muted).into()),
+        ],
+        SonarEvent::ChatMixChanged { balance } => vec![("kind", "chat_mix_changed".into()), ("balance", (*balance).into())],
+        SonarEvent::ModeChanged { streamer_mode } => {
+            vec![("kind", "mode_changed".into()), ("streamer_mode", (*streamer_mode).into())]
+        }
+        SonarEvent::Reconnected { web_server_address } => vec![
+            ("kind", "reconnected".into()),
+            ("web_server_address", web_server_address.clone().into()),
+        ],
+    };
+    fields.into_iter().map(|(key, value)| (key.into(), value)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_script_run() {
+        let script = Script::compile(
+            r#"
+            if event.kind == "volume_changed" && event.channel == "game" && event.slider == () {
+                set_volume("media", 1.0 - event.volume);
+                mute("aux", "monitoring");
+            }
+            if event.kind == "mode_changed" && event.streamer_mode {
+                load_profile("streaming");
+            }
+            "#,
+        )
+        .unwrap();
+
+        let event = SonarEvent::VolumeChanged { channel: "game".to_string(), slider: None, volume: 0.75 };
+        assert_eq!(
+            script.run(&event).unwrap(),
+            [
+                ScriptAction::SetVolume { channel: Channel::Media, volume: 0.25, slider: None },
+                ScriptAction::SetMute { channel: Channel::Aux, muted: true, slider: Some(StreamerSlider::Monitoring) },
+            ]
+        );
+        let event = SonarEvent::VolumeChanged { channel: "game".to_string(), slider: Some(StreamerSlider::Streaming), volume: 0.75 };
+        assert!(script.run(&event).unwrap().is_empty());
+        assert_eq!(
+            script.run(&SonarEvent::ModeChanged { streamer_mode: true }).unwrap(),
+            [ScriptAction::LoadProfile("streaming".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_script_errors() {
+        assert!(matches!(Script::compile("if {"), Err(SonarError::Script(_))));
+
+        // A failing script performs none of its actions
+        let script = Script::compile(r#"unmute("master"); if event.balance > 0.5 { mute("nope"); }"#).unwrap();
+        assert!(matches!(script.run(&SonarEvent::ChatMixChanged { balance: 0.9 }), Err(SonarError::Script(_))));
+        let event = SonarEvent::ChatMixChanged { balance: 0.0 };
+        assert_eq!(script.run(&event).unwrap(), [ScriptAction::SetMute { channel: Channel::Master, muted: false, slider: None }]);
+
+        let endless = Script::compile("loop {}").unwrap();
+        assert!(matches!(endless.run(&event), Err(SonarError::Script(_))));
+    }
+
+    #[test]
+    fn test_script_aliases() {
+        let script = Script::compile(r#"mute("voice");"#).unwrap();
+        let event = SonarEvent::ChatMixChanged { balance: 0.0 };
+        assert!(matches!(script.run(&event), Err(SonarError::Script(_))));
+
+        let options = ClientOptions {
+            channel_aliases: HashMap::from([("voice".to_string(), Channel::ChatCapture)]),
+            ..ClientOptions::default()
+        };
+        script.set_options(Arc::new(options));
+        assert_eq!(script.run(&event).unwrap(), [ScriptAction::SetMute { channel: Channel::ChatCapture, muted: true, slider: None }]);
+    }
+}
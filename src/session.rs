@@ -0,0 +1,153 @@
+//! Undo and redo for changes made through the crate.
+
+use crate::error::Result;
+use crate::scene::SceneChange;
+use crate::sonar::{Mode, Sonar, StreamerSlider};
+
+/// A change made through a [`Session`], with the change that undoes it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionEntry {
+    pub change: SceneChange,
+    /// The setting as it was before the change.
+    pub previous: SceneChange,
+}
+
+/// Wraps a [`Sonar`] client and records every change made through it, so
+/// that interactive tools can let users back out of them.
+///
+/// Each change reads the prior value first. [`undo`](Session::undo) writes
+/// it back and moves the change to the redo stack; a new change clears the
+/// redo stack. Changes made around the session, e.g. in the SteelSeries GG
+/// app, are not recorded and may be overwritten by an undo.
+///
+/// Created by [`Sonar::session`](crate::Sonar::session).
+///
+/// ```no_run
+/// # async fn example(sonar: steelseries_sonar::Sonar) -> steelseries_sonar::Result<()> {
+/// let mut session = sonar.session();
+/// session.set_volume("game", 0.3, None).await?;
+/// session.mute_channel("media", true, None).await?;
+/// session.undo().await?; // media unmuted again
+/// session.redo().await?; // and muted
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Session {
+    sonar: Sonar,
+    undo: Vec<SessionEntry>,
+    redo: Vec<SessionEntry>,
+}
+
+impl Session {
+    /// Start a session with empty undo and redo stacks.
+    pub fn new(sonar: Sonar) -> Self {
+        Self {
+            sonar,
+            undo: Vec::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    /// The wrapped client, for everything that should not be recorded.
+    pub fn sonar(&self) -> &Sonar {
+        &self.sonar
+    }
+
+    /// Set the volume of a channel, see [`Sonar::set_volume`].
+    pub async fn set_volume(&mut self, channel: &str, volume: f64, streamer_slider: Option<StreamerSlider>) -> Result<()> {
+        self.apply(SceneChange::Volume { channel: channel.to_string(), volume, slider: streamer_slider }).await
+    }
+
+    /// Mute or unmute a channel, see [`Sonar::mute_channel`].
+    pub async fn mute_channel(&mut self, channel: &str, muted: bool, streamer_slider: Option<StreamerSlider>) -> Result<()> {
+        self.apply(SceneChange::Mute { channel: channel.to_string(), muted, slider: streamer_slider }).await
+    }
+
+    /// Set the chat mix balance, see [`Sonar::set_chat_mix`].
+    pub async fn set_chat_mix(&mut self, mix_volume: f64) -> Result<()> {
+        self.apply(SceneChange::ChatMix(mix_volume)).await
+    }
+
+    /// Switch between classic and streamer mode, see [`Sonar::set_mode`].
+    pub async fn set_mode(&mut self, mode: Mode) -> Result<()> {
+        self.apply(SceneChange::Mode(mode)).await
+    }
+
+    /// Make a change and record it, clearing the redo stack.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the prior value cannot be read or the change
+    /// fails; nothing is recorded then.
+    pub async fn apply(&mut self, change: SceneChange) -> Result<()> {
+        let previous = self.sonar.read_scene_change(&change).await?;
+        self.sonar.write_scene_change(&change).await?;
+        self.undo.push(SessionEntry { change, previous });
+        self.redo.clear();
+        Ok(())
+    }
+
+    /// Revert the latest change, returning it, or `None` if there is nothing
+    /// to undo.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the prior value cannot be written; the change
+    /// stays on the undo stack then.
+    pub async fn undo(&mut self) -> Result<Option<SceneChange>> {
+        let Some(entry) = self.undo.pop() else {
+            return Ok(None);
+        };
+        if let Err(error) = self.sonar.write_scene_change(&entry.previous).await {
+            self.undo.push(entry);
+            return Err(error);
+        }
+        let change = entry.change.clone();
+        self.redo.push(entry);
+        Ok(Some(change))
+    }
+
+    /// Make the latest undone change again, returning it, or `None` if there
+    /// is nothing to redo.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the change fails; it stays on the redo stack then.
+    pub async fn redo(&mut self) -> Result<Option<SceneChange>> {
+        let Some(entry) = self.redo.pop() else {
+            return Ok(None);
+        };
+        if let Err(error) = self.sonar.write_scene_change(&entry.change).await {
+            self.redo.push(entry);
+            return Err(error);
+        }
+        let change = entry.change.clone();
+        self.undo.push(entry);
+        Ok(Some(change))
+    }
+
+    /// The recorded changes, oldest first.
+    pub fn undo_stack(&self) -> &[SessionEntry] {
+        &self.undo
+    }
+
+    /// The undone changes, the next one to redo last.
+    pub fn redo_stack(&self) -> &[SessionEntry] {
+        &self.redo
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Forget all recorded changes, keeping them in effect.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
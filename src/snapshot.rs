@@ -0,0 +1,446 @@
+//! Snapshots of the complete mixer state.
+
+use crate::configs::SonarConfig;
+use crate::error::{Result, SonarError};
+use crate::routing::{ChannelApps, ClassicRedirection, StreamRedirection};
+use crate::sonar::{Mode, StreamerSlider};
+use crate::volume::{ChannelVolumes, VolumeSettings};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+/// The mixer state of Sonar at one point in time, as returned by
+/// [`Sonar::export_state`](crate::Sonar::export_state).
+///
+/// Serializes to JSON, e.g. to back up a mixer setup. Applications routed to
+/// channels are not included, as they are tied to running processes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SonarSnapshot {
+    pub streamer_mode: bool,
+    /// Volume states by channel name, `"master"` included. Only the states of
+    /// the mode the snapshot was taken in are meaningful.
+    pub volumes: BTreeMap<String, ChannelVolumes>,
+    pub chat_mix: f64,
+    pub redirections: Vec<ClassicRedirection>,
+    pub stream_redirections: Vec<StreamRedirection>,
+    pub selected_configs: Vec<SonarConfig>,
+}
+
+impl SonarSnapshot {
+    /// Read a snapshot from a JSON file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not a snapshot.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+    }
+
+    /// Write the snapshot to a JSON file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        Ok(std::fs::write(path, serde_json::to_vec_pretty(self)?)?)
+    }
+
+    /// The changes that lead from this snapshot to `other`, in the order
+    /// [`Sonar::apply_state`](crate::Sonar::apply_state) writes them.
+    ///
+    /// Volumes and mutes are compared in the mode of `other`. Channels,
+    /// mixes and selected configs missing from either snapshot are skipped.
+    pub fn diff(&self, other: &SonarSnapshot) -> Vec<StateChange> {
+        let mut changes = Vec::new();
+        if self.streamer_mode != other.streamer_mode {
+            changes.push(StateChange::Mode {
+                from: Mode::from_streamer_mode(self.streamer_mode),
+                to: Mode::from_streamer_mode(other.streamer_mode),
+            });
+        }
+
+        let sliders: Vec<Option<StreamerSlider>> = if other.streamer_mode {
+            StreamerSlider::ALL.into_iter().map(Some).collect()
+        } else {
+            vec![None]
+        };
+        for (channel, volumes) in &other.volumes {
+            let Some(old_volumes) = self.volumes.get(channel) else {
+                continue;
+            };
+            for &slider in &sliders {
+                let old = old_volumes.state(other.streamer_mode, slider);
+                let new = volumes.state(other.streamer_mode, slider);
+                if old.volume != new.volume {
+                    changes.push(StateChange::Volume {
+                        channel: channel.clone(),
+                        slider,
+                        from: old.volume,
+                        to: new.volume,
+                    });
+                }
+                if old.muted != new.muted {
+                    changes.push(StateChange::Mute { channel: channel.clone(), slider, muted: new.muted });
+                }
+            }
+        }
+
+        if self.chat_mix != other.chat_mix {
+            changes.push(StateChange::ChatMix { from: self.chat_mix, to: other.chat_mix });
+        }
+
+        for redirection in &other.redirections {
+            if let Some(old) = self.redirections.iter().find(|old| old.channel == redirection.channel)
+                && old.device_id != redirection.device_id
+            {
+                changes.push(StateChange::Redirection {
+                    channel: redirection.channel.clone(),
+                    from: old.device_id.clone(),
+                    to: redirection.device_id.clone(),
+                });
+            }
+        }
+        for stream_redirection in &other.stream_redirections {
+            let slider = stream_redirection.slider;
+            let Some(old) = self.stream_redirections.iter().find(|old| old.slider == slider) else {
+                continue;
+            };
+            if old.device_id != stream_redirection.device_id {
+                changes.push(StateChange::StreamRedirection {
+                    slider,
+                    from: old.device_id.clone(),
+                    to: stream_redirection.device_id.clone(),
+                });
+            }
+            for redirection in &stream_redirection.redirections {
+                if old.is_channel_muted(&redirection.channel).is_some_and(|muted| muted != redirection.is_muted()) {
+                    changes.push(StateChange::RedirectionMute {
+                        channel: redirection.channel.clone(),
+                        slider,
+                        muted: redirection.is_muted(),
+                    });
+                }
+            }
+        }
+
+        for config in &other.selected_configs {
+            if let Some(old) = self.selected_configs.iter().find(|old| old.channel == config.channel)
+                && old.id != config.id
+            {
+                changes.push(StateChange::SelectedConfig { from: old.clone(), to: config.clone() });
+            }
+        }
+        changes
+    }
+}
+
+/// A single difference between two snapshots, as returned by
+/// [`SonarSnapshot::diff`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StateChange {
+    Mode { from: Mode, to: Mode },
+    /// `slider` is `None` for the classic volume.
+    Volume {
+        channel: String,
+        slider: Option<StreamerSlider>,
+        from: f64,
+        to: f64,
+    },
+    Mute {
+        channel: String,
+        slider: Option<StreamerSlider>,
+        muted: bool,
+    },
+    ChatMix { from: f64, to: f64 },
+    /// `from` and `to` are device ids.
+    Redirection { channel: String, from: String, to: String },
+    /// `from` and `to` are device ids.
+    StreamRedirection { slider: StreamerSlider, from: String, to: String },
+    RedirectionMute {
+        channel: String,
+        slider: StreamerSlider,
+        muted: bool,
+    },
+    /// The selected config of `to.channel` changed.
+    SelectedConfig { from: SonarConfig, to: SonarConfig },
+}
+
+impl StateChange {
+    /// The setting that changed.
+    pub fn item(&self) -> SnapshotItem {
+        match self {
+            StateChange::Mode { .. } => SnapshotItem::Mode,
+            StateChange::Volume { channel, slider, .. } => SnapshotItem::Volume { channel: channel.clone(), slider: *slider },
+            StateChange::Mute { channel, slider, .. } => SnapshotItem::Mute { channel: channel.clone(), slider: *slider },
+            StateChange::ChatMix { .. } => SnapshotItem::ChatMix,
+            StateChange::Redirection { channel, .. } => SnapshotItem::Redirection { channel: channel.clone() },
+            StateChange::StreamRedirection { slider, .. } => SnapshotItem::StreamRedirection { slider: *slider },
+            StateChange::RedirectionMute { channel, slider, .. } => {
+                SnapshotItem::RedirectionMute { channel: channel.clone(), slider: *slider }
+            }
+            StateChange::SelectedConfig { to, .. } => SnapshotItem::SelectedConfig { channel: to.channel.clone() },
+        }
+    }
+}
+
+impl fmt::Display for StateChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let item = self.item();
+        match self {
+            StateChange::Mode { from, to } => write!(f, "{}: {} -> {}", item, from, to),
+            StateChange::Volume { from, to, .. } | StateChange::ChatMix { from, to } => {
+                write!(f, "{}: {} -> {}", item, from, to)
+            }
+            StateChange::Mute { muted, .. } | StateChange::RedirectionMute { muted, .. } => {
+                write!(f, "{}: {}", item, if *muted { "muted" } else { "unmuted" })
+            }
+            StateChange::Redirection { from, to, .. } | StateChange::StreamRedirection { from, to, .. } => {
+                write!(f, "{}: {} -> {}", item, from, to)
+            }
+            StateChange::SelectedConfig { from, to } => write!(f, "{}: {} -> {}", item, from.name, to.name),
+        }
+    }
+}
+
+/// Everything Sonar reports about its current state, as returned by
+/// [`Sonar::get_full_state`](crate::Sonar::get_full_state).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FullState {
+    pub mode: Mode,
+    /// Volume settings of `mode`.
+    pub volumes: VolumeSettings,
+    pub chat_mix: f64,
+    /// Whether a ChatMix-capable headset is connected and enabled.
+    pub chat_mix_available: bool,
+    pub redirections: Vec<ClassicRedirection>,
+    pub stream_redirections: Vec<StreamRedirection>,
+    pub routed_apps: Vec<ChannelApps>,
+    pub selected_configs: Vec<SonarConfig>,
+}
+
+impl FullState {
+    /// The part of the state that can be restored with
+    /// [`Sonar::apply_state`](crate::Sonar::apply_state).
+    pub fn snapshot(&self) -> SonarSnapshot {
+        SonarSnapshot {
+            streamer_mode: self.mode.is_streamer(),
+            volumes: self
+                .volumes
+                .channels()
+                .map(|(channel, volumes)| (channel.to_string(), *volumes))
+                .collect(),
+            chat_mix: self.chat_mix,
+            redirections: self.redirections.clone(),
+            stream_redirections: self.stream_redirections.clone(),
+            selected_configs: self.selected_configs.clone(),
+        }
+    }
+
+    /// Render the mode, chat mix and a table of the channel volumes, as
+    /// [`Display`](fmt::Display) does.
+    pub fn to_table(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for FullState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Mode:        {}", self.mode)?;
+        let unavailable = if self.chat_mix_available { "" } else { " (unavailable)" };
+        writeln!(f, "Chat mix:    {:.2}{}", self.chat_mix, unavailable)?;
+        writeln!(f)?;
+        f.write_str(&self.volumes.to_table(self.mode))
+    }
+}
+
+/// A single setting written by [`Sonar::apply_state`](crate::Sonar::apply_state).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotItem {
+    Mode,
+    /// `slider` is `None` for the classic volume.
+    Volume { channel: String, slider: Option<StreamerSlider> },
+    Mute { channel: String, slider: Option<StreamerSlider> },
+    ChatMix,
+    Redirection { channel: String },
+    StreamRedirection { slider: StreamerSlider },
+    RedirectionMute { channel: String, slider: StreamerSlider },
+    SelectedConfig { channel: String },
+}
+
+impl fmt::Display for SnapshotItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotItem::Mode => f.write_str("mode"),
+            SnapshotItem::Volume { channel, slider: None } => write!(f, "volume of {}", channel),
+            SnapshotItem::Volume { channel, slider: Some(slider) } => write!(f, "{} volume of {}", slider, channel),
+            SnapshotItem::Mute { channel, slider: None } => write!(f, "mute of {}", channel),
+            SnapshotItem::Mute { channel, slider: Some(slider) } => write!(f, "{} mute of {}", slider, channel),
+            SnapshotItem::ChatMix => f.write_str("chat mix"),
+            SnapshotItem::Redirection { channel } => write!(f, "device of {}", channel),
+            SnapshotItem::StreamRedirection { slider } => write!(f, "device of the {} mix", slider),
+            SnapshotItem::RedirectionMute { channel, slider } => write!(f, "mute of {} in the {} mix", channel, slider),
+            SnapshotItem::SelectedConfig { channel } => write!(f, "selected config of {}", channel),
+        }
+    }
+}
+
+/// Outcome of applying a [`SonarSnapshot`].
+///
+/// Applying continues past failed items, so the report lists every item
+/// that could not be restored.
+#[derive(Debug, Default)]
+pub struct ApplyReport {
+    pub applied: Vec<SnapshotItem>,
+    pub failed: Vec<(SnapshotItem, SonarError)>,
+}
+
+impl ApplyReport {
+    /// Whether every item was restored.
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    pub(crate) fn record<T>(&mut self, item: SnapshotItem, result: Result<T>) {
+        match result {
+            Ok(_) => self.applied.push(item),
+            Err(error) => self.failed.push((item, error)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::volume::VolumeState;
+    use serde_json::json;
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let mut volumes = BTreeMap::new();
+        volumes.insert(
+            "game".to_string(),
+            ChannelVolumes {
+                classic: VolumeState { volume: 0.5, muted: true },
+                ..ChannelVolumes::default()
+            },
+        );
+        let snapshot = SonarSnapshot {
+            streamer_mode: false,
+            volumes,
+            chat_mix: -0.25,
+            redirections: vec![ClassicRedirection {
+                channel: "game".to_string(),
+                device_id: "{speakers}".to_string(),
+                is_running: true,
+            }],
+            stream_redirections: vec![StreamRedirection {
+                slider: StreamerSlider::Monitoring,
+                device_id: "{headset}".to_string(),
+                redirections: Vec::new(),
+            }],
+            selected_configs: vec![SonarConfig {
+                id: "abc".to_string(),
+                name: "FPS".to_string(),
+                channel: "game".to_string(),
+                settings: json!({ "enabled": true }),
+            }],
+        };
+
+        let json = serde_json::to_value(&snapshot).unwrap();
+        assert_eq!(json["stream_redirections"][0]["streamRedirectionId"], "monitoring");
+        assert_eq!(serde_json::from_value::<SonarSnapshot>(json).unwrap(), snapshot);
+    }
+
+    #[test]
+    fn test_snapshot_diff() {
+        let game = |volume, muted| ChannelVolumes {
+            classic: VolumeState { volume, muted },
+            ..ChannelVolumes::default()
+        };
+        let config = |id: &str| SonarConfig {
+            id: id.to_string(),
+            name: id.to_uppercase(),
+            channel: "game".to_string(),
+            settings: json!({}),
+        };
+        let snapshot = |volumes: ChannelVolumes, chat_mix, config_id| SonarSnapshot {
+            streamer_mode: false,
+            volumes: BTreeMap::from([("game".to_string(), volumes)]),
+            chat_mix,
+            redirections: Vec::new(),
+            stream_redirections: Vec::new(),
+            selected_configs: vec![config(config_id)],
+        };
+
+        let before = snapshot(game(0.5, false), 0.0, "fps");
+        assert!(before.diff(&before).is_empty());
+
+        let after = snapshot(game(0.7, true), 0.0, "rpg");
+        let changes = before.diff(&after);
+        assert_eq!(
+            changes,
+            vec![
+                StateChange::Volume { channel: "game".to_string(), slider: None, from: 0.5, to: 0.7 },
+                StateChange::Mute { channel: "game".to_string(), slider: None, muted: true },
+                StateChange::SelectedConfig { from: config("fps"), to: config("rpg") },
+            ]
+        );
+        assert_eq!(changes[0].to_string(), "volume of game: 0.5 -> 0.7");
+        assert_eq!(changes[1].to_string(), "mute of game: muted");
+        assert_eq!(changes[2].to_string(), "selected config of game: FPS -> RPG");
+
+        // Streamer volumes are only compared in streamer mode
+        let mut streamer = before.clone();
+        streamer.streamer_mode = true;
+        if let Some(volumes) = streamer.volumes.get_mut("game") {
+            volumes.stream.monitoring.volume = 0.1;
+        }
+        assert_eq!(before.diff(&streamer).len(), 2);
+        assert_eq!(streamer.diff(&before), vec![StateChange::Mode { from: Mode::Streamer, to: Mode::Classic }]);
+    }
+
+    #[test]
+    fn test_full_state_serde() {
+        let volumes = json!({"masters": {"classic": {"volume": 0.8, "muted": false}}, "devices": {}, "extra": 1});
+        let state = FullState {
+            mode: Mode::Classic,
+            volumes: VolumeSettings::from_value(volumes.clone()).unwrap(),
+            chat_mix: 0.2,
+            chat_mix_available: true,
+            redirections: Vec::new(),
+            stream_redirections: Vec::new(),
+            routed_apps: Vec::new(),
+            selected_configs: Vec::new(),
+        };
+        let json = serde_json::to_value(&state).unwrap();
+        // Volume settings keep the server's document, unknown fields included
+        assert_eq!(json["volumes"], volumes);
+        assert_eq!(serde_json::from_value::<FullState>(json).unwrap(), state);
+        assert_eq!(state.to_table(), "Mode:        classic\nChat mix:    0.20\n\nChannel      Volume\nmaster         80%\n");
+
+        let change = StateChange::ChatMix { from: 0.0, to: 0.5 };
+        let json = serde_json::to_string(&change).unwrap();
+        assert_eq!(json, r#"{"chat_mix":{"from":0.0,"to":0.5}}"#);
+        assert_eq!(serde_json::from_str::<StateChange>(&json).unwrap(), change);
+    }
+
+    #[test]
+    fn test_apply_report() {
+        let mut report = ApplyReport::default();
+        report.record(SnapshotItem::ChatMix, Ok(()));
+        assert!(report.is_complete());
+
+        let item = SnapshotItem::Volume {
+            channel: "game".to_string(),
+            slider: Some(StreamerSlider::Monitoring),
+        };
+        report.record::<()>(item.clone(), Err(SonarError::InvalidVolume(2.0)));
+        assert!(!report.is_complete());
+        assert_eq!(report.applied, vec![SnapshotItem::ChatMix]);
+        assert_eq!(report.failed[0].0, item);
+        assert_eq!(item.to_string(), "monitoring volume of game");
+    }
+}
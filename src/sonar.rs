@@ -1,10 +1,17 @@
-//! SteelSeries Sonar API client.
+//! SteelSeries Sonar API client and the types shared by both clients.
 
 use crate::error::{Result, SonarError};
-use reqwest::Client;
-use serde::{Deserialize};
-use serde_json::Value;
-use std::path::Path;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+
+#[cfg(feature = "async")]
+mod client;
+
+#[cfg(feature = "async")]
+pub use client::Sonar;
 
 /// Valid audio channel names in SteelSeries Sonar.
 pub const CHANNEL_NAMES: &[&str] = &["master", "game", "chatRender", "media", "aux", "chatCapture"];
@@ -12,327 +19,270 @@ pub const CHANNEL_NAMES: &[&str] = &["master", "game", "chatRender", "media", "a
 /// Valid streamer slider names.
 pub const STREAMER_SLIDER_NAMES: &[&str] = &["streaming", "monitoring"];
 
-/// Core properties structure from SteelSeries Engine.
-#[derive(Debug, Deserialize)]
-pub struct CoreProps {
-    #[serde(rename = "ggEncryptedAddress")]
-    pub gg_encrypted_address: String,
-}
+/// `state` of the chat mix data while a ChatMix-capable device is in use.
+pub(crate) const CHAT_MIX_ENABLED: &str = "enabled";
 
-/// Sub-application information structure.
-#[derive(Debug, Deserialize)]
-pub struct SubApp {
-    #[serde(rename = "isEnabled")]
-    pub is_enabled: bool,
-    #[serde(rename = "isReady")]
-    pub is_ready: bool,
-    #[serde(rename = "isRunning")]
-    pub is_running: bool,
-    pub metadata: SubAppMetadata,
+/// Whether chat mix data reports ChatMix as usable. Data without a `state`
+/// comes from Sonar versions that did not report it and is assumed usable.
+pub(crate) fn chat_mix_enabled(data: &serde_json::Value) -> bool {
+    data.get("state")
+        .and_then(serde_json::Value::as_str)
+        .is_none_or(|state| state == CHAT_MIX_ENABLED)
 }
 
-/// Sub-application metadata.
-#[derive(Debug, Deserialize)]
-pub struct SubAppMetadata {
-    #[serde(rename = "webServerAddress")]
-    pub web_server_address: String,
+/// The operating mode of Sonar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+pub enum Mode {
+    /// A single mix per channel.
+    #[default]
+    #[serde(rename = "classic")]
+    Classic,
+    /// Separate streaming and monitoring mixes, see [`StreamerSlider`].
+    #[serde(rename = "stream")]
+    Streamer,
 }
 
-/// Response from the /subApps endpoint.
-#[derive(Debug, Deserialize)]
-pub struct SubAppsResponse {
-    #[serde(rename = "subApps")]
-    pub sub_apps: SubApps,
-}
-
-/// Sub-applications container.
-#[derive(Debug, Deserialize)]
-pub struct SubApps {
-    pub sonar: SubApp,
-}
+impl Mode {
+    /// Both modes.
+    pub const ALL: [Mode; 2] = [Mode::Classic, Mode::Streamer];
 
-/// Main SteelSeries Sonar API client.
-#[derive(Debug)]
-pub struct Sonar {
-    client: Client,
-    #[allow(dead_code)]
-    base_url: String,
-    web_server_address: String,
-    streamer_mode: bool,
-    volume_path: String,
-}
-
-impl Sonar {
-    /// Create a new Sonar client with default settings.
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if the SteelSeries Engine is not found or accessible.
-    pub async fn new() -> Result<Self> {
-        Self::with_config(None, None).await
+    /// The mode name as used by the Sonar API.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Mode::Classic => "classic",
+            Mode::Streamer => "stream",
+        }
     }
 
-    /// Create a new Sonar client with custom configuration.
-    ///
-    /// # Arguments
-    ///
-    /// * `app_data_path` - Custom path to the coreProps.json file
-    /// * `streamer_mode` - Whether to use streamer mode (if None, will be auto-detected)
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if the SteelSeries Engine is not found or accessible.
-    pub async fn with_config(app_data_path: Option<&Path>, streamer_mode: Option<bool>) -> Result<Self> {
-        let client = Client::builder()
-            .danger_accept_invalid_certs(true)
-            .build()?;
-
-        let app_data_path = app_data_path.unwrap_or_else(|| {
-            #[cfg(target_os = "windows")]
-            {
-                Path::new("C:\\ProgramData\\SteelSeries\\SteelSeries Engine 3\\coreProps.json")
-            }
-            #[cfg(not(target_os = "windows"))]
-            {
-                // For non-Windows systems, this would need to be adapted based on where
-                // SteelSeries Engine might be installed
-                Path::new("/tmp/coreProps.json") // Placeholder
-            }
-        });
-
-        let base_url = Self::load_base_url(app_data_path).await?;
-        let web_server_address = Self::load_server_address(&client, &base_url).await?;
-
-        let detected_streamer_mode = match streamer_mode {
-            Some(mode) => mode,
-            None => Self::is_streamer_mode_internal(&client, &web_server_address).await?,
-        };
-
-        let volume_path = if detected_streamer_mode {
-            "/volumeSettings/streamer".to_string()
-        } else {
-            "/volumeSettings/classic".to_string()
-        };
-
-        Ok(Self {
-            client,
-            base_url,
-            web_server_address,
-            streamer_mode: detected_streamer_mode,
-            volume_path,
-        })
+    /// Whether this is streamer mode.
+    pub fn is_streamer(self) -> bool {
+        self == Mode::Streamer
     }
 
-    /// Check if streamer mode is currently enabled.
-    pub async fn is_streamer_mode(&self) -> Result<bool> {
-        Self::is_streamer_mode_internal(&self.client, &self.web_server_address).await
+    /// [`Mode::Streamer`] if `streamer_mode` is set, [`Mode::Classic`] otherwise.
+    pub fn from_streamer_mode(streamer_mode: bool) -> Self {
+        if streamer_mode { Mode::Streamer } else { Mode::Classic }
     }
 
-    async fn is_streamer_mode_internal(client: &Client, web_server_address: &str) -> Result<bool> {
-        let url = format!("{}/mode/", web_server_address);
-        let response = client.get(&url).send().await?;
-        
-        if !response.status().is_success() {
-            return Err(SonarError::ServerNotAccessible(response.status().as_u16()));
+    /// The path of the volume settings in this mode.
+    pub(crate) fn volume_path(self) -> &'static str {
+        match self {
+            Mode::Classic => "/volumeSettings/classic",
+            Mode::Streamer => "/volumeSettings/streamer",
         }
-
-        let mode: String = response.json().await?;
-        Ok(mode == "stream")
     }
+}
 
-    /// Set streamer mode on or off.
-    ///
-    /// # Arguments
-    ///
-    /// * `streamer_mode` - Whether to enable streamer mode
-    ///
-    /// # Returns
-    ///
-    /// Returns the new streamer mode state.
-    pub async fn set_streamer_mode(&mut self, streamer_mode: bool) -> Result<bool> {
-        let mode = if streamer_mode { "stream" } else { "classic" };
-        let url = format!("{}/mode/{}", self.web_server_address, mode);
-        
-        let response = self.client.put(&url).send().await?;
-        
-        if !response.status().is_success() {
-            return Err(SonarError::ServerNotAccessible(response.status().as_u16()));
-        }
-
-        let new_mode: String = response.json().await?;
-        self.streamer_mode = new_mode == "stream";
-        
-        self.volume_path = if self.streamer_mode {
-            "/volumeSettings/streamer".to_string()
-        } else {
-            "/volumeSettings/classic".to_string()
-        };
-
-        Ok(self.streamer_mode)
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
     }
+}
 
-    /// Get volume data for all channels.
-    pub async fn get_volume_data(&self) -> Result<Value> {
-        let url = format!("{}{}", self.web_server_address, self.volume_path);
-        let response = self.client.get(&url).send().await?;
-        
-        if !response.status().is_success() {
-            return Err(SonarError::ServerNotAccessible(response.status().as_u16()));
-        }
+impl FromStr for Mode {
+    type Err = SonarError;
 
-        let volume_data: Value = response.json().await?;
-        Ok(volume_data)
+    fn from_str(s: &str) -> Result<Self> {
+        Mode::ALL
+            .into_iter()
+            .find(|mode| mode.as_str() == s)
+            .ok_or_else(|| SonarError::ModeNotFound(s.to_string()))
     }
+}
 
-    /// Set the volume for a specific channel.
-    ///
-    /// # Arguments
-    ///
-    /// * `channel` - The audio channel name
-    /// * `volume` - Volume level (0.0 to 1.0)
-    /// * `streamer_slider` - Streamer slider to use in streamer mode
-    pub async fn set_volume(&self, channel: &str, volume: f64, streamer_slider: Option<&str>) -> Result<Value> {
-        if !CHANNEL_NAMES.contains(&channel) {
-            return Err(SonarError::ChannelNotFound(channel.to_string()));
-        }
+/// An audio channel of the mixer, see [`CHANNEL_NAMES`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Channel {
+    Master,
+    Game,
+    /// Chat playback, e.g. Discord voices.
+    ChatRender,
+    Media,
+    Aux,
+    /// The microphone.
+    ChatCapture,
+}
 
-        if !(0.0..=1.0).contains(&volume) {
-            return Err(SonarError::InvalidVolume(volume));
+impl Channel {
+    /// All channels, in the order of [`CHANNEL_NAMES`].
+    pub const ALL: [Channel; 6] = [
+        Channel::Master,
+        Channel::Game,
+        Channel::ChatRender,
+        Channel::Media,
+        Channel::Aux,
+        Channel::ChatCapture,
+    ];
+
+    /// The channel name as used by the Sonar API.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Channel::Master => "master",
+            Channel::Game => "game",
+            Channel::ChatRender => "chatRender",
+            Channel::Media => "media",
+            Channel::Aux => "aux",
+            Channel::ChatCapture => "chatCapture",
         }
+    }
 
-        let streamer_slider = streamer_slider.unwrap_or("streaming");
-        if self.streamer_mode && !STREAMER_SLIDER_NAMES.contains(&streamer_slider) {
-            return Err(SonarError::SliderNotFound(streamer_slider.to_string()));
-        }
+    /// Descriptions of all channels, in the order of [`CHANNEL_NAMES`], e.g.
+    /// to list them in a UI.
+    pub fn all() -> impl Iterator<Item = ChannelInfo> {
+        Channel::ALL.into_iter().map(Channel::info)
+    }
 
-        let full_volume_path = if self.streamer_mode {
-            format!("{}/{}", self.volume_path, streamer_slider)
-        } else {
-            self.volume_path.clone()
-        };
-
-        let url = format!("{}{}/{}/Volume/{}", 
-            self.web_server_address, full_volume_path, channel, serde_json::to_string(&volume)?);
-        
-        let response = self.client.put(&url).send().await?;
-        
-        if !response.status().is_success() {
-            return Err(SonarError::ServerNotAccessible(response.status().as_u16()));
+    /// Description of the channel.
+    pub fn info(self) -> ChannelInfo {
+        ChannelInfo {
+            channel: self,
+            name: self.as_str(),
+            label: self.label(),
+            is_capture: self.is_capture(),
         }
-
-        let result: Value = response.json().await?;
-        Ok(result)
     }
 
-    /// Mute or unmute a specific channel.
-    ///
-    /// # Arguments
-    ///
-    /// * `channel` - The audio channel name
-    /// * `muted` - Whether to mute the channel
-    /// * `streamer_slider` - Streamer slider to use in streamer mode
-    pub async fn mute_channel(&self, channel: &str, muted: bool, streamer_slider: Option<&str>) -> Result<Value> {
-        if !CHANNEL_NAMES.contains(&channel) {
-            return Err(SonarError::ChannelNotFound(channel.to_string()));
+    /// Human-readable name, e.g. "Chat (render)".
+    pub fn label(self) -> &'static str {
+        match self {
+            Channel::Master => "Master",
+            Channel::Game => "Game",
+            Channel::ChatRender => "Chat (render)",
+            Channel::Media => "Media",
+            Channel::Aux => "Aux",
+            Channel::ChatCapture => "Chat (capture)",
         }
+    }
 
-        let streamer_slider = streamer_slider.unwrap_or("streaming");
-        if self.streamer_mode && !STREAMER_SLIDER_NAMES.contains(&streamer_slider) {
-            return Err(SonarError::SliderNotFound(streamer_slider.to_string()));
-        }
+    /// Whether the channel records (the microphone) rather than plays audio.
+    pub fn is_capture(self) -> bool {
+        self == Channel::ChatCapture
+    }
+}
 
-        let full_volume_path = if self.streamer_mode {
-            format!("{}/{}", self.volume_path, streamer_slider)
-        } else {
-            self.volume_path.clone()
-        };
-
-        let mute_keyword = if self.streamer_mode { "isMuted" } else { "Mute" };
-
-        let url = format!("{}{}/{}/{}/{}", 
-            self.web_server_address, full_volume_path, channel, mute_keyword, serde_json::to_string(&muted)?);
-        
-        let response = self.client.put(&url).send().await?;
-        
-        if !response.status().is_success() {
-            return Err(SonarError::ServerNotAccessible(response.status().as_u16()));
-        }
+/// Description of a [`Channel`], see [`Channel::all`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelInfo {
+    pub channel: Channel,
+    /// The name used by the Sonar API, e.g. `chatRender`.
+    pub name: &'static str,
+    /// Human-readable name, e.g. "Chat (render)".
+    pub label: &'static str,
+    /// Whether the channel records rather than plays audio.
+    pub is_capture: bool,
+}
 
-        let result: Value = response.json().await?;
-        Ok(result)
+impl fmt::Display for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
     }
+}
 
-    /// Get chat mix data.
-    pub async fn get_chat_mix_data(&self) -> Result<Value> {
-        let url = format!("{}/chatMix", self.web_server_address);
-        let response = self.client.get(&url).send().await?;
-        
-        if !response.status().is_success() {
-            return Err(SonarError::ServerNotAccessible(response.status().as_u16()));
-        }
+impl FromStr for Channel {
+    type Err = SonarError;
 
-        let chat_mix_data: Value = response.json().await?;
-        Ok(chat_mix_data)
+    fn from_str(s: &str) -> Result<Self> {
+        Channel::ALL
+            .into_iter()
+            .find(|channel| channel.as_str() == s)
+            .ok_or_else(|| SonarError::ChannelNotFound(s.to_string()))
     }
+}
 
-    /// Set the chat mix volume.
-    ///
-    /// # Arguments
-    ///
-    /// * `mix_volume` - Mix volume level (-1.0 to 1.0)
-    pub async fn set_chat_mix(&self, mix_volume: f64) -> Result<Value> {
-        if !(-1.0..=1.0).contains(&mix_volume) {
-            return Err(SonarError::InvalidMixVolume(mix_volume));
-        }
+/// A slider (mix) available in streamer mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[serde(rename_all = "lowercase")]
+pub enum StreamerSlider {
+    /// The mix sent to the stream output.
+    #[default]
+    Streaming,
+    /// The mix you hear yourself.
+    Monitoring,
+}
 
-        let url = format!("{}/chatMix?balance={}", 
-            self.web_server_address, serde_json::to_string(&mix_volume)?);
-        
-        let response = self.client.put(&url).send().await?;
-        
-        if !response.status().is_success() {
-            return Err(SonarError::ServerNotAccessible(response.status().as_u16()));
+impl StreamerSlider {
+    /// All streamer sliders, in API order.
+    pub const ALL: [StreamerSlider; 2] = [StreamerSlider::Streaming, StreamerSlider::Monitoring];
+
+    /// The slider name as used by the Sonar API.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StreamerSlider::Streaming => "streaming",
+            StreamerSlider::Monitoring => "monitoring",
         }
+    }
+}
 
-        let result: Value = response.json().await?;
-        Ok(result)
+impl fmt::Display for StreamerSlider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
     }
+}
 
-    async fn load_base_url(app_data_path: &Path) -> Result<String> {
-        if !app_data_path.exists() {
-            return Err(SonarError::EnginePathNotFound);
-        }
+impl FromStr for StreamerSlider {
+    type Err = SonarError;
 
-        let content = tokio::fs::read_to_string(app_data_path).await?;
-        let core_props: CoreProps = serde_json::from_str(&content)?;
-        
-        Ok(format!("https://{}", core_props.gg_encrypted_address))
+    fn from_str(s: &str) -> Result<Self> {
+        StreamerSlider::ALL
+            .into_iter()
+            .find(|slider| slider.as_str() == s)
+            .ok_or_else(|| SonarError::SliderNotFound(s.to_string()))
     }
+}
+
+/// Engine endpoint enabling the Sonar sub-app, as toggled in the GG settings.
+pub(crate) const ENABLE_SONAR_PATH: &str = "/subApps/sonar/isEnabled/true";
 
-    async fn load_server_address(client: &Client, base_url: &str) -> Result<String> {
-        let url = format!("{}/subApps", base_url);
-        let response = client.get(&url).send().await?;
-        
-        if !response.status().is_success() {
-            return Err(SonarError::ServerNotAccessible(response.status().as_u16()));
+/// Core properties structure from SteelSeries Engine.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CoreProps {
+    #[serde(rename = "ggEncryptedAddress")]
+    pub gg_encrypted_address: String,
+}
+
+impl CoreProps {
+    /// The URL of the SteelSeries Engine. GG writes a bare `host:port`,
+    /// served over HTTPS; an address with a scheme is used as is.
+    pub(crate) fn base_url(&self) -> String {
+        if self.gg_encrypted_address.contains("://") {
+            return self.gg_encrypted_address.clone();
         }
+        format!("https://{}", self.gg_encrypted_address)
+    }
+}
 
-        let sub_apps_response: SubAppsResponse = response.json().await?;
-        let sonar = &sub_apps_response.sub_apps.sonar;
+/// Sub-application information structure.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubApp {
+    #[serde(rename = "isEnabled")]
+    pub is_enabled: bool,
+    #[serde(rename = "isReady")]
+    pub is_ready: bool,
+    #[serde(rename = "isRunning")]
+    pub is_running: bool,
+    pub metadata: SubAppMetadata,
+}
 
-        if !sonar.is_enabled {
+impl SubApp {
+    /// The address of the Sonar web server, if Sonar is enabled and running.
+    pub(crate) fn web_server_address(&self) -> Result<String> {
+        if !self.is_enabled {
             return Err(SonarError::SonarNotEnabled);
         }
 
-        if !sonar.is_ready {
+        if !self.is_ready {
             return Err(SonarError::ServerNotReady);
         }
 
-        if !sonar.is_running {
+        if !self.is_running {
             return Err(SonarError::ServerNotRunning);
         }
 
-        let web_server_address = &sonar.metadata.web_server_address;
+        let web_server_address = &self.metadata.web_server_address;
         if web_server_address.is_empty() || web_server_address == "null" {
             return Err(SonarError::WebServerAddressNotFound);
         }
@@ -341,6 +291,111 @@ impl Sonar {
     }
 }
 
+/// Sub-application metadata.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubAppMetadata {
+    #[serde(rename = "webServerAddress")]
+    pub web_server_address: String,
+}
+
+/// Response from the /subApps endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubAppsResponse {
+    #[serde(rename = "subApps")]
+    pub sub_apps: SubApps,
+}
+
+/// Sub-applications container.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubApps {
+    pub sonar: SubApp,
+}
+
+/// The sub-apps of SteelSeries GG and their status, as reported by the
+/// Engine's `/subApps` endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EngineInfo {
+    /// Sub-apps by name, e.g. `"sonar"` or `"moments"`.
+    #[serde(rename = "subApps")]
+    pub sub_apps: BTreeMap<String, SubAppStatus>,
+}
+
+impl EngineInfo {
+    /// The Sonar sub-app, `None` if this GG version does not have it.
+    pub fn sonar(&self) -> Option<&SubAppStatus> {
+        self.sub_apps.get("sonar")
+    }
+}
+
+/// Status of a single sub-app in [`EngineInfo`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubAppStatus {
+    #[serde(rename = "isEnabled", default)]
+    pub is_enabled: bool,
+    #[serde(rename = "isReady", default)]
+    pub is_ready: bool,
+    #[serde(rename = "isRunning", default)]
+    pub is_running: bool,
+    /// Sub-app specific details, such as the address of its web server.
+    #[serde(default)]
+    pub metadata: serde_json::Value,
+}
+
+impl SubAppStatus {
+    /// The address of the sub-app's web server, if it has one and it is running.
+    pub fn web_server_address(&self) -> Option<&str> {
+        self.metadata
+            .get("webServerAddress")
+            .and_then(serde_json::Value::as_str)
+            .filter(|address| !address.is_empty() && *address != "null")
+    }
+
+    /// The version of the sub-app, if the Engine reports it.
+    pub fn version(&self) -> Option<&str> {
+        self.metadata.get("version").and_then(serde_json::Value::as_str)
+    }
+}
+
+/// Versions of SteelSeries GG and Sonar, returned by `version_info`.
+///
+/// Either is `None` when the installed GG release does not report it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionInfo {
+    /// Version of the SteelSeries Engine, from its `/version` endpoint.
+    pub engine: Option<String>,
+    /// Version of the Sonar sub-app, from the Engine's `/subApps`.
+    pub sonar: Option<String>,
+}
+
+impl VersionInfo {
+    /// Combine the Engine's `/version` response, which is either a string or
+    /// an object with a `version` field, with its sub-app statuses.
+    pub(crate) fn new(engine_version: Option<serde_json::Value>, engine_info: &EngineInfo) -> Self {
+        let engine = engine_version.and_then(|version| match version {
+            serde_json::Value::String(version) => Some(version),
+            version => version.get("version").and_then(serde_json::Value::as_str).map(str::to_string),
+        });
+        Self {
+            engine,
+            sonar: engine_info.sonar().and_then(SubAppStatus::version).map(str::to_string),
+        }
+    }
+}
+
+/// Addresses of the SteelSeries Engine and the Sonar web server.
+///
+/// Both change whenever SteelSeries GG restarts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ServerAddresses {
+    /// `None` when the web server address was given directly.
+    pub base_url: Option<String>,
+    pub web_server_address: String,
+}
+
+/// Server addresses shared between a client and its background tasks.
+pub(crate) type SharedAddresses = Arc<RwLock<ServerAddresses>>;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -360,4 +415,90 @@ mod tests {
         assert!(STREAMER_SLIDER_NAMES.contains(&"streaming"));
         assert!(STREAMER_SLIDER_NAMES.contains(&"monitoring"));
     }
+
+    #[test]
+    fn test_chat_mix_enabled() {
+        assert!(chat_mix_enabled(&serde_json::json!({ "balance": 0.0, "state": "enabled" })));
+        assert!(!chat_mix_enabled(&serde_json::json!({ "balance": 0.0, "state": "differentDeviceSelected" })));
+        assert!(chat_mix_enabled(&serde_json::json!({ "balance": 0.0 })));
+    }
+
+    #[test]
+    fn test_engine_info() {
+        let info: EngineInfo = serde_json::from_value(serde_json::json!({
+            "subApps": {
+                "sonar": {
+                    "isEnabled": false,
+                    "isReady": false,
+                    "isRunning": false,
+                    "metadata": { "webServerAddress": "null" },
+                },
+                "moments": { "isEnabled": true, "isReady": true, "isRunning": true },
+            }
+        }))
+        .unwrap();
+
+        let sonar = info.sonar().unwrap();
+        assert!(!sonar.is_enabled);
+        assert_eq!(sonar.web_server_address(), None);
+        assert!(info.sub_apps["moments"].is_running);
+        assert_eq!(VersionInfo::new(None, &info), VersionInfo::default());
+    }
+
+    #[test]
+    fn test_version_info() {
+        let info: EngineInfo = serde_json::from_value(serde_json::json!({
+            "subApps": { "sonar": { "isEnabled": true, "metadata": { "version": "1.2.3" } } }
+        }))
+        .unwrap();
+
+        let versions = VersionInfo::new(Some(serde_json::json!({ "version": "75.0.0" })), &info);
+        assert_eq!(versions.engine.as_deref(), Some("75.0.0"));
+        assert_eq!(versions.sonar.as_deref(), Some("1.2.3"));
+        assert_eq!(VersionInfo::new(Some(serde_json::json!("76.1.0")), &info).engine.as_deref(), Some("76.1.0"));
+    }
+
+    #[test]
+    fn test_mode_round_trip() {
+        for mode in Mode::ALL {
+            assert_eq!(mode.as_str().parse::<Mode>().unwrap(), mode);
+            assert_eq!(serde_json::to_value(mode).unwrap(), mode.as_str());
+        }
+        assert_eq!(Mode::from_streamer_mode(true), Mode::Streamer);
+        assert!(!Mode::default().is_streamer());
+        assert!(matches!("streamer".parse::<Mode>(), Err(SonarError::ModeNotFound(_))));
+    }
+
+    #[test]
+    fn test_channel_round_trip() {
+        let names: Vec<&str> = Channel::ALL.iter().map(|channel| channel.as_str()).collect();
+        assert_eq!(names, CHANNEL_NAMES);
+        for channel in Channel::ALL {
+            assert_eq!(channel.as_str().parse::<Channel>().unwrap(), channel);
+            assert_eq!(serde_json::to_value(channel).unwrap(), channel.as_str());
+        }
+        assert!(matches!("chat".parse::<Channel>(), Err(SonarError::ChannelNotFound(_))));
+    }
+
+    #[test]
+    fn test_channel_info() {
+        let infos: Vec<ChannelInfo> = Channel::all().collect();
+        assert_eq!(infos.len(), CHANNEL_NAMES.len());
+        assert!(infos.iter().zip(CHANNEL_NAMES).all(|(info, name)| info.name == *name));
+        assert_eq!(Channel::ChatRender.label(), "Chat (render)");
+        assert_eq!(infos.iter().filter(|info| info.is_capture).map(|info| info.channel).collect::<Vec<_>>(), [Channel::ChatCapture]);
+    }
+
+    #[test]
+    fn test_streamer_slider_round_trip() {
+        for slider in StreamerSlider::ALL {
+            assert!(STREAMER_SLIDER_NAMES.contains(&slider.as_str()));
+            assert_eq!(slider.as_str().parse::<StreamerSlider>().unwrap(), slider);
+        }
+        assert_eq!(StreamerSlider::default(), StreamerSlider::Streaming);
+        assert!(matches!(
+            "invalid".parse::<StreamerSlider>(),
+            Err(SonarError::SliderNotFound(_))
+        ));
+    }
 }
@@ -0,0 +1,1379 @@
+//! The async [`Sonar`] client.
+
+use super::{chat_mix_enabled, Channel, Mode, StreamerSlider};
+use crate::batch::Batch;
+use crate::channel::ChannelOps;
+use crate::builder::{ClientOptions, SonarBuilder};
+use crate::capabilities::{Capabilities, PROBE_PATHS};
+use crate::capture::CapturedRequest;
+use crate::cassette::Interaction;
+use crate::client_core::{check_mix_volume, check_volume, raw_body, raw_path, ClientCore};
+use crate::configs::{NewConfig, SonarConfig};
+use crate::debounce::DebouncedSonar;
+use crate::devices::AudioDevice;
+#[cfg(feature = "windows-audio")]
+use crate::ducking::{AutoDucker, DuckingOptions};
+use crate::endpoint::{api_path, api_path_with_query};
+use crate::eq::{apply_band_update, EqBandUpdate, Equalizer};
+use crate::error::{api_error, Result, SonarError};
+use crate::fade::{crossfade_tracks, curved_fade_steps, FadeOptions};
+use crate::group::ChannelGroup;
+use crate::guard::{MuteGuard, RestoreGuard, SavedState, TimedMute};
+#[cfg(feature = "hotkeys")]
+use crate::hotkeys::{HotkeyBindings, Hotkeys};
+#[cfg(feature = "obs")]
+use crate::obs::{ObsFollower, ObsOptions};
+use crate::orchestration::client_orchestration;
+use crate::processes::find_processes;
+#[cfg(feature = "push-to-talk")]
+use crate::push_to_talk::{PushToTalk, PushToTalkOptions};
+#[cfg(feature = "scheduler")]
+use crate::scheduler::{Schedule, Scheduler};
+#[cfg(feature = "scripting")]
+use crate::scripting::{Script, ScriptRunner};
+use crate::routing::{channel_device, check_capture_device, ChannelApps, ClassicRedirection, StreamRedirection, CAPTURE_CHANNEL};
+use crate::session::Session;
+use crate::snapshot::SonarSnapshot;
+use crate::state_cache::Setting;
+#[cfg(feature = "metrics")]
+use crate::telemetry;
+use crate::volume::{percent_to_volume, volume_to_percent, VolumeSettings, VolumeState};
+use crate::watch::SonarWatcher;
+use futures_util::future::try_join_all;
+use reqwest::{Client, Method, Response};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::future::Future;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+
+/// Main SteelSeries Sonar API client.
+///
+/// Cloning is cheap: clones share the HTTP connection pool, the resolved
+/// server address and the current mode, so a mode switched through one
+/// clone applies to all of them.
+#[derive(Debug, Clone)]
+pub struct Sonar {
+    client: Client,
+    pub(crate) core: ClientCore,
+    /// Initialized once discovery and mode detection succeeded.
+    connected: Arc<OnceCell<()>>,
+}
+
+impl Sonar {
+    /// Create a new Sonar client with default settings.
+    ///
+    /// The `STEELSERIES_SONAR_COREPROPS` and `STEELSERIES_SONAR_ADDRESS`
+    /// environment variables override discovery, see
+    /// [`SonarBuilder::from_env`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the SteelSeries Engine is not found or accessible.
+    pub async fn new() -> Result<Self> {
+        SonarBuilder::from_env().build().await
+    }
+
+    /// Create a builder to configure a new Sonar client.
+    pub fn builder() -> SonarBuilder {
+        SonarBuilder::new()
+    }
+
+    /// Connect directly to a known Sonar web server address, skipping
+    /// coreProps.json and Engine discovery.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - Web server address, e.g. `http://127.0.0.1:51234`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the streamer mode cannot be detected.
+    pub async fn connect_to(address: impl AsRef<str>) -> Result<Self> {
+        Self::builder().web_server_address(address).build().await
+    }
+
+    /// Create a new Sonar client with custom configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `app_data_path` - Custom path to the coreProps.json file
+    /// * `streamer_mode` - Whether to use streamer mode (if None, will be auto-detected)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the SteelSeries Engine is not found or accessible.
+    #[deprecated(note = "use `Sonar::builder()` instead")]
+    pub async fn with_config(app_data_path: Option<&Path>, streamer_mode: Option<bool>) -> Result<Self> {
+        Self::from_options(ClientOptions {
+            core_props_path: app_data_path.map(Path::to_path_buf),
+            streamer_mode,
+            ..ClientOptions::default()
+        }, None)
+        .await
+    }
+
+    /// Create a client without any I/O. Discovery and mode detection happen
+    /// on the first request, so the client can be created before SteelSeries
+    /// GG is running.
+    ///
+    /// Background tasks such as [`watch`](Self::watch) only reach the server
+    /// once a request has connected the client.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP client cannot be created.
+    pub fn lazy() -> Result<Self> {
+        SonarBuilder::from_env().build_lazy()
+    }
+
+    pub(crate) async fn from_options(options: ClientOptions, client: Option<Client>) -> Result<Self> {
+        let sonar = Self::unconnected(options, client)?;
+        sonar.ensure_connected().await?;
+        Ok(sonar)
+    }
+
+    /// Set up a client that connects on its first request.
+    pub(crate) fn unconnected(mut options: ClientOptions, client: Option<Client>) -> Result<Self> {
+        options.load_config_file()?;
+        let client = match client {
+            Some(client) => client,
+            None => {
+                let mut client_builder = Client::builder().danger_accept_invalid_certs(true);
+                if let Some(timeout) = options.timeout {
+                    client_builder = client_builder.timeout(timeout);
+                }
+                if let Some(timeout) = options.connect_timeout {
+                    client_builder = client_builder.connect_timeout(timeout);
+                }
+                if let Some(timeout) = options.pool_idle_timeout {
+                    client_builder = client_builder.pool_idle_timeout(timeout);
+                }
+                if let Some(connections) = options.pool_max_idle {
+                    client_builder = client_builder.pool_max_idle_per_host(connections);
+                }
+                client_builder.build()?
+            }
+        };
+
+        Ok(Self {
+            client,
+            core: ClientCore::new(options)?,
+            connected: Arc::default(),
+        })
+    }
+
+    /// Connect unless already connected.
+    async fn ensure_connected(&self) -> Result<()> {
+        self.connected.get_or_try_init(|| self.establish()).await?;
+        Ok(())
+    }
+
+    /// Discover the server addresses and detect the mode.
+    #[cfg_attr(feature = "tracing", tracing::instrument(name = "sonar.connect", level = "debug", skip_all, err(Display)))]
+    async fn establish(&self) -> Result<()> {
+        if let Some(core_props_path) = &self.core.core_props_path {
+            let addresses = Self::discover(&self.client, core_props_path, &self.core.options).await?;
+            self.core.set_addresses(addresses);
+        }
+
+        let detected_streamer_mode = match self.core.options.streamer_mode {
+            Some(mode) => mode,
+            None => {
+                let mode: String = self.send_connected(Method::GET, "/mode/", None).await?.json().await?;
+                mode.parse::<Mode>()?.is_streamer()
+            }
+        };
+        self.core.use_mode(detected_streamer_mode);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            web_server_address = %self.web_server_address(),
+            streamer_mode = detected_streamer_mode,
+            "connected to Sonar"
+        );
+        Ok(())
+    }
+
+    /// Connect now unless already connected, so that a
+    /// [lazy](Self::lazy) client reports a missing SteelSeries GG up front.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the SteelSeries Engine is not found or accessible.
+    pub async fn connect(&self) -> Result<()> {
+        self.ensure_connected().await
+    }
+
+    /// Connect and open a connection to the Sonar web server, so that the
+    /// first real request, e.g. from a mute hotkey, does not wait for the
+    /// TLS handshake. Sends one cheap request.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the SteelSeries Engine or the Sonar web server is
+    /// not accessible.
+    pub async fn warm_up(&self) -> Result<()> {
+        self.ensure_connected().await?;
+        let _: String = self.get_json("/mode/").await?;
+        Ok(())
+    }
+
+    /// Run discovery and mode detection again, e.g. after SteelSeries GG
+    /// restarted.
+    ///
+    /// Unlike [`reconnect`](Self::reconnect), this resolves the Sonar
+    /// sub-app the way building the client did, including the configured
+    /// [`enable_sonar`](crate::SonarBuilder::enable_sonar) and ready timeout, and
+    /// re-detects the mode unless it was set on the builder. Options, clones
+    /// and watchers are kept.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the SteelSeries Engine is not found or accessible.
+    pub async fn refresh(&self) -> Result<()> {
+        self.establish().await?;
+        self.core.clear_cache();
+        // Lazy clients count as connected from now on. A concurrent first
+        // request may have connected them already.
+        let _ = self.connected.set(());
+        Ok(())
+    }
+
+    /// Re-read coreProps.json and re-resolve the Sonar web server address.
+    ///
+    /// SteelSeries GG picks a new port for the web server whenever it
+    /// restarts. Requests that fail to connect already trigger this
+    /// automatically unless disabled with
+    /// [`auto_reconnect`](crate::SonarBuilder::auto_reconnect).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the SteelSeries Engine is not found or accessible.
+    pub async fn reconnect(&self) -> Result<()> {
+        self.refresh_addresses().await?;
+        Ok(())
+    }
+
+    /// Run a single operation with a deadline.
+    ///
+    /// Returns [`SonarError::Timeout`] if `operation` does not complete within
+    /// `deadline`; the operation is cancelled in that case.
+    ///
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # async fn example(sonar: steelseries_sonar::Sonar) -> steelseries_sonar::Result<()> {
+    /// sonar
+    ///     .with_deadline(Duration::from_millis(250), sonar.set_volume("game", 0.5, None))
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn with_deadline<T>(&self, deadline: Duration, operation: impl Future<Output = Result<T>>) -> Result<T> {
+        tokio::time::timeout(deadline, operation)
+            .await
+            .unwrap_or(Err(SonarError::Timeout))
+    }
+
+    /// Get the current mode.
+    pub async fn get_mode(&self) -> Result<Mode> {
+        let mode: String = self.get_json("/mode/").await?;
+        mode.parse()
+    }
+
+    /// Switch between classic and streamer mode.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - The mode to switch to
+    ///
+    /// # Returns
+    ///
+    /// Returns the new mode as reported by the server.
+    pub async fn set_mode(&self, mode: Mode) -> Result<Mode> {
+        let new_mode: String = self.put_json(&format!("/mode/{}", mode)).await?;
+        let new_mode: Mode = new_mode.parse()?;
+        self.core.use_mode(new_mode.is_streamer());
+        Ok(new_mode)
+    }
+
+    /// Check if streamer mode is currently enabled.
+    #[deprecated(note = "use `get_mode` instead")]
+    pub async fn is_streamer_mode(&self) -> Result<bool> {
+        Ok(self.get_mode().await?.is_streamer())
+    }
+
+    /// Set streamer mode on or off, returning the new streamer mode state.
+    #[deprecated(note = "use `set_mode` instead")]
+    pub async fn set_streamer_mode(&self, streamer_mode: bool) -> Result<bool> {
+        Ok(self.set_mode(Mode::from_streamer_mode(streamer_mode)).await?.is_streamer())
+    }
+
+    pub(crate) fn streamer_mode(&self) -> bool {
+        self.core.streamer_mode()
+    }
+
+    /// Send a volume or mute change in `mode`, unless the state cache knows
+    /// it to be in effect already.
+    async fn write_setting(&self, mode: Mode, channel: &str, streamer_slider: Option<StreamerSlider>, setting: Setting) -> Result<Value> {
+        let (setting, path) = self.core.setting_path(mode, channel, streamer_slider, setting)?;
+        let Some(path) = path else {
+            return Ok(Value::Null);
+        };
+        let result = self.put_json(&path).await;
+        self.core.record_write(mode, channel, streamer_slider, setting, result.is_ok());
+        result
+    }
+
+    /// The volume settings of `mode`, which need not be the current one.
+    pub(crate) async fn get_volumes_in(&self, mode: Mode) -> Result<VolumeSettings> {
+        VolumeSettings::from_value(self.get_json(mode.volume_path()).await?)
+    }
+
+    /// Get volume data for all channels.
+    pub async fn get_volume_data(&self) -> Result<Value> {
+        self.ensure_connected().await?;
+        self.get_json(self.core.mode().volume_path()).await
+    }
+
+    /// Get typed volume settings for all channels.
+    ///
+    /// Use [`VolumeSettings::raw`] to access the underlying JSON.
+    pub async fn get_volumes(&self) -> Result<VolumeSettings> {
+        self.core.volumes(self.get_volume_data().await?)
+    }
+
+    /// Set the volume for a specific channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The audio channel name
+    /// * `volume` - Volume level (0.0 to 1.0)
+    /// * `streamer_slider` - Streamer slider to use in streamer mode
+    pub async fn set_volume(&self, channel: &str, volume: f64, streamer_slider: Option<StreamerSlider>) -> Result<Value> {
+        let channel = self.core.resolve_channel(channel)?;
+
+        check_volume(volume)?;
+
+        self.ensure_connected().await?;
+        self.write_setting(self.core.mode(), channel, streamer_slider, Setting::Volume(volume)).await
+    }
+
+    /// Change the volume of a channel relative to its current value.
+    ///
+    /// `delta` moves along the client's [`VolumeCurve`](crate::VolumeCurve) and
+    /// the result is clamped to 0.0..=1.0. Returns the new volume.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The audio channel name
+    /// * `delta` - Amount to add to the current volume, e.g. `0.05` or `-0.05`
+    /// * `streamer_slider` - Streamer slider to use in streamer mode
+    pub async fn adjust_volume(&self, channel: &str, delta: f64, streamer_slider: Option<StreamerSlider>) -> Result<f64> {
+        let current = self.get_channel_state(channel, streamer_slider).await?;
+        let position = self.core.volume_curve.to_position(current.volume) + delta;
+        let volume = self.core.volume_curve.to_volume(position);
+
+        self.set_volume(channel, volume, streamer_slider).await?;
+        Ok(volume)
+    }
+
+    /// Set the volume for a specific channel as a percentage.
+    ///
+    /// The percentage is a position on the client's [`VolumeCurve`](crate::VolumeCurve).
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The audio channel name
+    /// * `percent` - Volume level (0 to 100)
+    /// * `streamer_slider` - Streamer slider to use in streamer mode
+    pub async fn set_volume_percent(&self, channel: &str, percent: u8, streamer_slider: Option<StreamerSlider>) -> Result<Value> {
+        let volume = self.core.volume_curve.to_volume(percent_to_volume(percent)?);
+        self.set_volume(channel, volume, streamer_slider).await
+    }
+
+    /// Get the volume of a channel as a percentage, rounded to the nearest integer.
+    ///
+    /// The percentage is a position on the client's [`VolumeCurve`](crate::VolumeCurve).
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The audio channel name
+    /// * `streamer_slider` - Streamer slider to read in streamer mode
+    pub async fn get_volume_percent(&self, channel: &str, streamer_slider: Option<StreamerSlider>) -> Result<u8> {
+        let volume = self.get_channel_state(channel, streamer_slider).await?.volume;
+        Ok(volume_to_percent(self.core.volume_curve.to_position(volume)))
+    }
+
+    /// Invert the mute state of a channel.
+    ///
+    /// Returns the new mute state.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The audio channel name
+    /// * `streamer_slider` - Streamer slider to use in streamer mode
+    pub async fn toggle_mute(&self, channel: &str, streamer_slider: Option<StreamerSlider>) -> Result<bool> {
+        let muted = !self.get_channel_state(channel, streamer_slider).await?.muted;
+
+        self.mute_channel(channel, muted, streamer_slider).await?;
+        Ok(muted)
+    }
+
+    /// Get the volume and mute state of a single channel.
+    ///
+    /// The state is read from the current mode (and `streamer_slider` in streamer mode).
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The audio channel name
+    /// * `streamer_slider` - Streamer slider to read in streamer mode
+    pub async fn get_channel_state(&self, channel: &str, streamer_slider: Option<StreamerSlider>) -> Result<VolumeState> {
+        let channel = self.core.resolve_channel(channel)?;
+
+        self.core.channel_state(&self.get_volumes().await?, channel, streamer_slider)
+    }
+
+    /// Gradually change the volume of a channel over `duration`.
+    ///
+    /// Uses [`FadeOptions::default`]; see [`fade_volume_with`](Self::fade_volume_with)
+    /// to control the number of steps and the easing curve.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The audio channel name
+    /// * `target` - Final volume level (0.0 to 1.0)
+    /// * `duration` - Total duration of the fade
+    /// * `streamer_slider` - Streamer slider to use in streamer mode
+    pub async fn fade_volume(&self, channel: &str, target: f64, duration: Duration, streamer_slider: Option<StreamerSlider>) -> Result<()> {
+        self.fade_volume_with(channel, target, duration, streamer_slider, FadeOptions::default()).await
+    }
+
+    /// Gradually change the volume of a channel over `duration` with custom fade options.
+    ///
+    /// The fade moves evenly along the client's [`VolumeCurve`](crate::VolumeCurve).
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The audio channel name
+    /// * `target` - Final volume level (0.0 to 1.0)
+    /// * `duration` - Total duration of the fade
+    /// * `streamer_slider` - Streamer slider to use in streamer mode
+    /// * `options` - Step count and easing curve
+    pub async fn fade_volume_with(&self, channel: &str, target: f64, duration: Duration, streamer_slider: Option<StreamerSlider>, options: FadeOptions) -> Result<()> {
+        check_volume(target)?;
+
+        let start = self.get_channel_state(channel, streamer_slider).await?.volume;
+        let steps = curved_fade_steps(start, target, options, &self.core.volume_curve);
+        let step_duration = duration / steps.len().max(1) as u32;
+
+        for volume in steps {
+            tokio::time::sleep(step_duration).await;
+            self.set_volume(channel, volume, streamer_slider).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Gradually move every channel volume from one snapshot to another over
+    /// `duration`, e.g. from a gaming mix to a podcast mix.
+    ///
+    /// Uses [`FadeOptions::default`]; see [`crossfade_with`](Self::crossfade_with).
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The snapshot the fade starts at
+    /// * `to` - The snapshot the fade ends at
+    /// * `duration` - Total duration of the crossfade
+    pub async fn crossfade(&self, from: &SonarSnapshot, to: &SonarSnapshot, duration: Duration) -> Result<()> {
+        self.crossfade_with(from, to, duration, FadeOptions::default()).await
+    }
+
+    /// Gradually move every channel volume from one snapshot to another over
+    /// `duration` with custom fade options.
+    ///
+    /// Sonar is switched to the mode of `to` first. Only the volumes of
+    /// channels in both snapshots are faded; mutes, routing and configs are
+    /// left alone, use [`apply_state`](Self::apply_state) for those. The
+    /// volumes of each step are sent concurrently.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The snapshot the fade starts at
+    /// * `to` - The snapshot the fade ends at
+    /// * `duration` - Total duration of the crossfade
+    /// * `options` - Step count and easing curve
+    pub async fn crossfade_with(&self, from: &SonarSnapshot, to: &SonarSnapshot, duration: Duration, options: FadeOptions) -> Result<()> {
+        if self.streamer_mode() != to.streamer_mode {
+            self.set_mode(Mode::from_streamer_mode(to.streamer_mode)).await?;
+        }
+
+        let tracks = crossfade_tracks(from, to, options, &self.core.volume_curve);
+        let steps = tracks.first().map_or(0, |track| track.steps.len());
+        let step_duration = duration / steps.max(1) as u32;
+
+        for step in 0..steps {
+            tokio::time::sleep(step_duration).await;
+            try_join_all(
+                tracks
+                    .iter()
+                    .map(|track| self.set_volume(&track.channel, track.steps[step], track.slider)),
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Mute or unmute a specific channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The audio channel name
+    /// * `muted` - Whether to mute the channel
+    /// * `streamer_slider` - Streamer slider to use in streamer mode
+    pub async fn mute_channel(&self, channel: &str, muted: bool, streamer_slider: Option<StreamerSlider>) -> Result<Value> {
+        let channel = self.core.resolve_channel(channel)?;
+
+        self.ensure_connected().await?;
+        self.write_setting(self.core.mode(), channel, streamer_slider, Setting::Muted(muted)).await
+    }
+
+    /// Set the volume of a channel in both streamer-mode mixes, sending the
+    /// two requests concurrently.
+    ///
+    /// The streamer mixes are changed even while classic mode is active.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The audio channel name
+    /// * `streaming` - Volume level in the streaming mix (0.0 to 1.0)
+    /// * `monitoring` - Volume level in the monitoring mix (0.0 to 1.0)
+    pub async fn set_volume_both(&self, channel: &str, streaming: f64, monitoring: f64) -> Result<()> {
+        let channel = self.core.resolve_channel(channel)?;
+
+        check_volume(streaming)?;
+        check_volume(monitoring)?;
+
+        self.ensure_connected().await?;
+        tokio::try_join!(
+            self.write_setting(Mode::Streamer, channel, Some(StreamerSlider::Streaming), Setting::Volume(streaming)),
+            self.write_setting(Mode::Streamer, channel, Some(StreamerSlider::Monitoring), Setting::Volume(monitoring)),
+        )?;
+        Ok(())
+    }
+
+    /// Mute or unmute a channel in both streamer-mode mixes, sending the two
+    /// requests concurrently.
+    ///
+    /// The streamer mixes are changed even while classic mode is active.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The audio channel name
+    /// * `muted` - Whether to mute the channel
+    pub async fn mute_both(&self, channel: &str, muted: bool) -> Result<()> {
+        let channel = self.core.resolve_channel(channel)?;
+
+        self.ensure_connected().await?;
+        tokio::try_join!(
+            self.write_setting(Mode::Streamer, channel, Some(StreamerSlider::Streaming), Setting::Muted(muted)),
+            self.write_setting(Mode::Streamer, channel, Some(StreamerSlider::Monitoring), Setting::Muted(muted)),
+        )?;
+        Ok(())
+    }
+
+    /// Mute every playback channel except `channel`, and unmute `channel`.
+    ///
+    /// The previous mute states are saved and restored by
+    /// [`unsolo`](Self::unsolo). Soloing again while soloed stacks: each
+    /// `unsolo` undoes the latest `solo` only. Master and the microphone
+    /// are left alone; soloing them fails with [`SonarError::NotSoloable`].
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The audio channel name
+    /// * `streamer_slider` - Streamer slider to use in streamer mode
+    ///
+    /// ```no_run
+    /// # async fn example(sonar: steelseries_sonar::Sonar) -> steelseries_sonar::Result<()> {
+    /// sonar.solo("chatRender", None).await?;
+    /// // only chat is audible
+    /// sonar.unsolo().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn solo(&self, channel: &str, streamer_slider: Option<StreamerSlider>) -> Result<()> {
+        let channel = self.core.resolve_channel(channel)?;
+
+        let volumes = self.get_volumes().await?;
+        let mutes = self.core.push_solo(&volumes, channel, streamer_slider)?;
+        let mode = self.core.mode();
+        self.write_settings(mode, mutes.into_iter().map(|(channel, muted)| (channel, streamer_slider, Setting::Muted(muted)))).await
+    }
+
+    /// Restore the mute states saved by the latest [`solo`](Self::solo), in
+    /// the mode they were saved in. Returns `false` if nothing is soloed.
+    pub async fn unsolo(&self) -> Result<bool> {
+        self.ensure_connected().await?;
+        let Some(state) = self.core.pop_solo() else {
+            return Ok(false);
+        };
+        let mutes = state.muted.into_iter().map(|(channel, muted)| (channel, state.slider, Setting::Muted(muted)));
+        self.write_settings(state.mode, mutes).await?;
+        Ok(true)
+    }
+
+    /// Write several settings in `mode`, carrying on past failures, and
+    /// return the first error.
+    async fn write_settings(&self, mode: Mode, settings: impl IntoIterator<Item = (&'static str, Option<StreamerSlider>, Setting)>) -> Result<()> {
+        let mut result = Ok(());
+        for (channel, streamer_slider, setting) in settings {
+            if let Err(error) = self.write_setting(mode, channel, streamer_slider, setting).await {
+                result = result.and(Err(error));
+            }
+        }
+        result
+    }
+
+    /// Get the volume of a channel group: that of its loudest member.
+    pub async fn get_group_volume(&self, group: &ChannelGroup) -> Result<f64> {
+        Ok(group.volume(&self.get_volumes().await?, self.streamer_mode()))
+    }
+
+    /// Set the volume of a channel group, see [`ChannelGroup`].
+    ///
+    /// Every member is scaled by the same factor, keeping their relative
+    /// balance. The member changes are sent concurrently.
+    ///
+    /// # Arguments
+    ///
+    /// * `group` - The channels to change
+    /// * `volume` - New volume of the loudest member (0.0 to 1.0)
+    pub async fn set_group_volume(&self, group: &ChannelGroup, volume: f64) -> Result<()> {
+        check_volume(volume)?;
+
+        let volumes = group.scaled_volumes(&self.get_volumes().await?, self.streamer_mode(), volume);
+        try_join_all(
+            volumes
+                .iter()
+                .map(|(channel, volume)| self.set_volume(channel.as_str(), *volume, group.slider())),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Mute or unmute every channel of a group, concurrently.
+    pub async fn mute_group(&self, group: &ChannelGroup, muted: bool) -> Result<()> {
+        try_join_all(
+            group
+                .channels()
+                .iter()
+                .map(|channel| self.mute_channel(channel.as_str(), muted, group.slider())),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Mute a channel until the returned guard is dropped or released.
+    ///
+    /// Useful for push-to-mute style hooks, e.g. muting media while a screen
+    /// share is active.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The audio channel name
+    /// * `streamer_slider` - Streamer slider to use in streamer mode
+    pub async fn mute_guard(&self, channel: &str, streamer_slider: Option<StreamerSlider>) -> Result<MuteGuard> {
+        let channel = self.core.resolve_channel(channel)?;
+
+        self.ensure_connected().await?;
+        let mode = self.core.mode();
+        self.write_setting(mode, channel, streamer_slider, Setting::Muted(true)).await?;
+        Ok(MuteGuard::new(self.clone(), mode, channel, streamer_slider))
+    }
+
+    /// Unmute a channel muted by [`mute_guard`](Self::mute_guard) in the mode
+    /// it was muted in.
+    pub(crate) async fn end_mute_guard(&self, mode: Mode, channel: &str, streamer_slider: Option<StreamerSlider>) -> Result<Value> {
+        self.write_setting(mode, channel, streamer_slider, Setting::Muted(false)).await
+    }
+
+    /// Mute a channel for `duration`, then unmute it again.
+    ///
+    /// The returned handle can extend or cancel the timer. A channel that was
+    /// muted already, or is unmuted by hand before the time is up, is left
+    /// alone when it runs out.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The audio channel name
+    /// * `duration` - How long to keep the channel muted
+    /// * `streamer_slider` - Streamer slider to use in streamer mode
+    pub async fn mute_for(&self, channel: &str, duration: Duration, streamer_slider: Option<StreamerSlider>) -> Result<TimedMute> {
+        let channel = self.core.resolve_channel(channel)?;
+        let was_muted = self.get_channel_state(channel, streamer_slider).await?.muted;
+        let mode = self.core.mode();
+        self.write_setting(mode, channel, streamer_slider, Setting::Muted(true)).await?;
+        Ok(TimedMute::start(self.clone(), mode, channel, streamer_slider, duration, was_muted))
+    }
+
+    /// Unmute a channel muted by [`mute_for`](Self::mute_for), unless it has
+    /// been unmuted in the meantime. Returns whether it was unmuted.
+    pub(crate) async fn end_timed_mute(&self, mode: Mode, channel: &str, streamer_slider: Option<StreamerSlider>) -> Result<bool> {
+        // The state can only be read in the current mode.
+        if self.core.mode() == mode && !self.get_channel_state(channel, streamer_slider).await?.muted {
+            return Ok(false);
+        }
+        self.write_setting(mode, channel, streamer_slider, Setting::Muted(false)).await?;
+        Ok(true)
+    }
+
+    /// Save the volume and mute state of `channels` and restore it when the
+    /// returned guard is dropped or [restored](RestoreGuard::restore).
+    ///
+    /// In streamer mode both sliders of each channel are saved. The state is
+    /// restored in the mode it was saved in.
+    ///
+    /// ```no_run
+    /// # async fn example(sonar: steelseries_sonar::Sonar) -> steelseries_sonar::Result<()> {
+    /// let guard = sonar.scoped(&["media"]).await?;
+    /// sonar.mute_channel("media", true, None).await?;
+    /// // play an announcement
+    /// guard.restore().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn scoped(&self, channels: &[&str]) -> Result<RestoreGuard> {
+        self.ensure_connected().await?;
+        let mode = self.core.mode();
+        let sliders: &[Option<StreamerSlider>] = if mode.is_streamer() {
+            &[Some(StreamerSlider::Streaming), Some(StreamerSlider::Monitoring)]
+        } else {
+            &[None]
+        };
+
+        let volumes = self.get_volumes().await?;
+        let mut states = Vec::new();
+        for channel in channels {
+            let channel = self.core.resolve_channel(channel)?;
+            for &slider in sliders {
+                states.push((channel, slider, self.core.channel_state(&volumes, channel, slider)?));
+            }
+        }
+        Ok(RestoreGuard::new(self.clone(), mode, states))
+    }
+
+    /// Write saved channel states back in `mode`, returning the first error.
+    pub(crate) async fn restore_states(&self, mode: Mode, states: &[SavedState]) -> Result<()> {
+        let settings: Vec<_> = states
+            .iter()
+            .flat_map(|&(channel, slider, state)| [(channel, slider, Setting::Volume(state.volume)), (channel, slider, Setting::Muted(state.muted))])
+            .collect();
+        self.write_settings(mode, settings).await
+    }
+
+    /// Watch for changes made to the mixer, e.g. from the SteelSeries GG app.
+    ///
+    /// Spawns a background task that polls the server every `interval` and
+    /// reports differences as [`SonarEvent`](crate::SonarEvent)s. The polls go
+    /// through the client like any other request, so they follow reconnects
+    /// and are subject to its retry policy, rate limit and request capture.
+    /// The task runs until the returned watcher is dropped. Must be called
+    /// from within a tokio runtime.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - Time between two polls
+    pub fn watch(&self, interval: Duration) -> SonarWatcher {
+        SonarWatcher::spawn(self.clone(), interval)
+    }
+
+    /// Watch coreProps.json and refresh the server address as soon as
+    /// SteelSeries GG restarts, instead of on the next failed request.
+    ///
+    /// The returned watcher emits a [`SonarEvent::Reconnected`](crate::SonarEvent::Reconnected)
+    /// whenever the address changed and stops watching when dropped. Must be
+    /// called from within a tokio runtime.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be watched, or
+    /// [`SonarError::EnginePathNotFound`] if the client was created with a
+    /// fixed web server address.
+    #[cfg(feature = "core-props-watcher")]
+    pub fn watch_core_props(&self) -> Result<SonarWatcher> {
+        let core_props_path = self.core.core_props_path.clone().ok_or(SonarError::EnginePathNotFound)?;
+        crate::core_props::spawn(self.client.clone(), core_props_path, Arc::clone(&self.core.addresses))
+    }
+
+    /// Get chat mix data.
+    pub async fn get_chat_mix_data(&self) -> Result<Value> {
+        self.get_json("/chatMix").await
+    }
+
+    /// Set the chat mix volume.
+    ///
+    /// # Arguments
+    ///
+    /// * `mix_volume` - Mix volume level (-1.0 to 1.0)
+    pub async fn set_chat_mix(&self, mix_volume: f64) -> Result<Value> {
+        check_mix_volume(mix_volume)?;
+
+        let result: Result<Value> = self.put_json(&api_path_with_query(&["chatMix"], &[("balance", &serde_json::to_string(&mix_volume)?)])?).await;
+        match result {
+            Ok(data) if !chat_mix_enabled(&data) => Err(SonarError::ChatMixUnavailable),
+            Err(error) if error.status().is_some() => match self.chat_mix_supported().await {
+                Ok(false) => Err(SonarError::ChatMixUnavailable),
+                _ => Err(error),
+            },
+            result => result,
+        }
+    }
+
+    /// Check whether a ChatMix-capable headset is connected and enabled.
+    ///
+    /// Without one, [`set_chat_mix`](Self::set_chat_mix) fails with
+    /// [`SonarError::ChatMixUnavailable`].
+    pub async fn chat_mix_supported(&self) -> Result<bool> {
+        Ok(chat_mix_enabled(&self.get_chat_mix_data().await?))
+    }
+
+    /// List the audio devices known to Sonar.
+    ///
+    /// Sonar's own virtual devices are not included.
+    pub async fn list_audio_devices(&self) -> Result<Vec<AudioDevice>> {
+        self.get_json("/audioDevices?removeSteelSeriesVAD=true").await
+    }
+
+    /// Get the classic-mode device routing of every channel.
+    pub async fn get_redirections(&self) -> Result<Vec<ClassicRedirection>> {
+        self.get_json("/classicRedirections").await
+    }
+
+    /// Get the audio device a channel is routed to: the output device, or
+    /// the microphone for `chatCapture`.
+    ///
+    /// Returns `None` if the channel has no device of its own, like
+    /// `master`, or the device is not connected. Streamer-mode mixes have
+    /// their own devices, see `get_stream_redirections`.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The audio channel name
+    pub async fn get_channel_device(&self, channel: &str) -> Result<Option<AudioDevice>> {
+        let channel = self.core.resolve_channel(channel)?;
+
+        let (redirections, devices) = tokio::try_join!(self.get_redirections(), self.list_audio_devices())?;
+        Ok(channel_device(&redirections, devices, channel))
+    }
+
+    /// Route a channel to a different audio device in classic mode.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The audio channel name
+    /// * `device_id` - Id of the target device, see `list_audio_devices`
+    pub async fn set_redirection(&self, channel: &str, device_id: &str) -> Result<Value> {
+        let channel = self.core.resolve_channel(channel)?;
+
+        self.put_json(&api_path(&["classicRedirections", channel, "deviceId", device_id])?).await
+    }
+
+    /// Switch the microphone feeding the `chatCapture` channel, e.g. between
+    /// a headset and a desk mic.
+    ///
+    /// # Arguments
+    ///
+    /// * `device_id` - Id of a capture device, see `list_audio_devices`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SonarError::CaptureDeviceNotFound`] if no connected input
+    /// device has this id.
+    pub async fn set_capture_device(&self, device_id: &str) -> Result<Value> {
+        check_capture_device(&self.list_audio_devices().await?, device_id)?;
+        self.set_redirection(CAPTURE_CHANNEL, device_id).await
+    }
+
+    /// Get the output device of each streamer-mode mix.
+    pub async fn get_stream_redirections(&self) -> Result<Vec<StreamRedirection>> {
+        self.get_json("/streamRedirections").await
+    }
+
+    /// Route a streamer-mode mix (streaming or monitoring) to a different audio device.
+    ///
+    /// # Arguments
+    ///
+    /// * `slider` - The streamer mix to re-route
+    /// * `device_id` - Id of the target device, see `list_audio_devices`
+    pub async fn set_stream_redirection(&self, slider: StreamerSlider, device_id: &str) -> Result<Value> {
+        self.put_json(&api_path(&["streamRedirections", slider.as_str(), "deviceId", device_id])?).await
+    }
+
+    /// Mute or unmute a channel in one streamer-mode mix only.
+    ///
+    /// Unlike [`mute_channel`](Self::mute_channel), this toggles whether the
+    /// channel is sent to the mix's output device at all.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The audio channel name
+    /// * `slider` - The streamer mix to mute the channel in
+    /// * `muted` - Whether to mute the channel in that mix
+    pub async fn mute_redirection(&self, channel: &str, slider: StreamerSlider, muted: bool) -> Result<Value> {
+        let channel = self.core.resolve_channel(channel)?;
+
+        self.put_json(&api_path(&["streamRedirections", slider.as_str(), "redirections", channel, "isEnabled", 
+            &serde_json::to_string(&!muted)?])?).await
+    }
+
+    /// Get the applications Sonar has assigned to each channel.
+    pub async fn get_routed_apps(&self) -> Result<Vec<ChannelApps>> {
+        self.get_json("/AudioDeviceRouting").await
+    }
+
+    /// Move an application's audio to another channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `process_id` - Id of the process, see [`RoutedApp::process_id`](crate::RoutedApp::process_id)
+    /// * `channel` - The audio channel name
+    pub async fn route_app(&self, process_id: u32, channel: &str) -> Result<Value> {
+        let channel = self.core.resolve_channel(channel)?;
+
+        self.put_json(&api_path(&["AudioDeviceRouting", channel, &process_id.to_string()])?).await
+    }
+
+    /// Move every process of an application, found by its executable name,
+    /// to another channel.
+    ///
+    /// Returns the ids of the moved processes. Processes are looked up among
+    /// the audio sessions Sonar knows of and, with the `windows-audio`
+    /// feature, among all running processes.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Executable name, e.g. `"spotify.exe"`, compared case-insensitively
+    /// * `channel` - The audio channel
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SonarError::AppNotFound`] if no process of `name` is running.
+    pub async fn route_app_by_name(&self, name: &str, channel: Channel) -> Result<Vec<u32>> {
+        let process_ids = find_processes(name, &self.get_routed_apps().await?);
+        if process_ids.is_empty() {
+            return Err(SonarError::AppNotFound(name.to_string()));
+        }
+
+        try_join_all(process_ids.iter().map(|&process_id| self.route_app(process_id, channel.as_str()))).await?;
+        Ok(process_ids)
+    }
+
+    /// List the audio configs (presets) available for a channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The audio channel name
+    pub async fn list_configs(&self, channel: &str) -> Result<Vec<SonarConfig>> {
+        let channel = self.core.resolve_channel(channel)?;
+
+        self.get_json(&api_path_with_query(&["configs"], &[("vad", channel)])?).await
+    }
+
+    /// Get the currently selected config of every channel.
+    pub async fn get_selected_configs(&self) -> Result<Vec<SonarConfig>> {
+        self.get_json("/configs/selected").await
+    }
+
+    /// Select (activate) a config for its channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `config_id` - Id of the config, see [`SonarConfig::id`](crate::SonarConfig::id)
+    pub async fn select_config(&self, config_id: &str) -> Result<Value> {
+        self.put_json(&api_path(&["configs", config_id, "select"])?).await
+    }
+
+    /// Create a new config for a channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The audio channel name
+    /// * `name` - Display name of the new config
+    pub async fn create_config(&self, channel: &str, name: &str) -> Result<SonarConfig> {
+        let channel = self.core.resolve_channel(channel)?;
+
+        self.post_json("/configs", &NewConfig { name, channel }).await
+    }
+
+    /// Rename a config.
+    ///
+    /// # Arguments
+    ///
+    /// * `config_id` - Id of the config to rename
+    /// * `name` - The new display name
+    pub async fn rename_config(&self, config_id: &str, name: &str) -> Result<Value> {
+        self.put_json(&api_path(&["configs", config_id, "name", name])?).await
+    }
+
+    /// Delete a config.
+    ///
+    /// # Arguments
+    ///
+    /// * `config_id` - Id of the config to delete
+    pub async fn delete_config(&self, config_id: &str) -> Result<()> {
+        self.delete(&api_path(&["configs", config_id])?).await
+    }
+
+    /// Get the parametric EQ of the config currently selected for a channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The audio channel name
+    pub async fn get_eq(&self, channel: &str) -> Result<Equalizer> {
+        Ok(Equalizer::from_config(&self.selected_config(channel).await?))
+    }
+
+    /// Change one band of the parametric EQ of the config selected for a channel.
+    ///
+    /// Returns the updated EQ.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel` - The audio channel name
+    /// * `band` - Zero-based index of the band
+    /// * `update` - The band parameters to change
+    pub async fn set_eq_band(&self, channel: &str, band: usize, update: EqBandUpdate) -> Result<Equalizer> {
+        let mut config = self.selected_config(channel).await?;
+        apply_band_update(&mut config.settings, band, update)?;
+
+        let _: Value = self.put_body(&api_path(&["configs", &config.id])?, &config).await?;
+        Ok(Equalizer::from_config(&config))
+    }
+
+    /// Probe which optional endpoints the running Sonar version has, so
+    /// features it lacks can be hidden instead of failing.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the web server cannot be reached or fails for
+    /// another reason than a missing endpoint.
+    pub async fn capabilities(&self) -> Result<Capabilities> {
+        let [streamer_mode, chat_mix, device_routing, app_routing, configs] = PROBE_PATHS;
+        Capabilities::from_probes([
+            self.probe(streamer_mode).await,
+            self.probe(chat_mix).await,
+            self.probe(device_routing).await,
+            self.probe(app_routing).await,
+            self.probe(configs).await,
+        ])
+    }
+
+    async fn probe(&self, path: &str) -> Result<()> {
+        self.send(Method::GET, path, None).await?;
+        Ok(())
+    }
+
+    /// Send a `GET` request to an endpoint of the Sonar web server this crate
+    /// does not wrap yet, e.g. `/mode/`, returning the parsed JSON response.
+    ///
+    /// The request goes through the same discovery, retries and capture as
+    /// every other call. An empty response body is returned as `null`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SonarError::Api`] if the server answers with an error status.
+    pub async fn raw_get(&self, path: &str) -> Result<Value> {
+        let response = self.send(Method::GET, &raw_path(path), None).await?;
+        raw_body(&response.bytes().await?)
+    }
+
+    /// Send a `PUT` request with an optional JSON body to an endpoint of the
+    /// Sonar web server, see [`raw_get`](Self::raw_get).
+    pub async fn raw_put(&self, path: &str, body: Option<&Value>) -> Result<Value> {
+        let response = self.send(Method::PUT, &raw_path(path), body).await?;
+        raw_body(&response.bytes().await?)
+    }
+
+    /// Chain changes to one channel and send them together, see [`ChannelOps`].
+    pub fn channel(&self, channel: Channel) -> ChannelOps<'_> {
+        ChannelOps::new(self, channel)
+    }
+
+    /// Queue several operations to send them concurrently, see [`Batch`].
+    pub fn batch(&self) -> Batch<'_> {
+        Batch::new(self)
+    }
+
+    /// A wrapper coalescing bursts of volume changes into one request per
+    /// channel, sent after `quiet_period` without changes, see [`DebouncedSonar`].
+    pub fn debounced(&self, quiet_period: Duration) -> DebouncedSonar {
+        DebouncedSonar::new(self.clone(), quiet_period)
+    }
+
+    /// A wrapper recording every change made through it, with undo and
+    /// redo, see [`Session`].
+    pub fn session(&self) -> Session {
+        Session::new(self.clone())
+    }
+
+    /// Execute a [`Schedule`] in a background task.
+    ///
+    /// The scheduler owns the client and applies each rule's scene when its
+    /// trigger fires, until the returned [`Scheduler`] is dropped. Must be
+    /// called from within a tokio runtime.
+    #[cfg(feature = "scheduler")]
+    pub fn run_schedule(self, schedule: Schedule) -> Scheduler {
+        Scheduler::spawn(self, schedule)
+    }
+
+    /// Run a [`Script`] for every change to the mixer, in a background task.
+    ///
+    /// The runner owns the client, watches the mixer like
+    /// [`watch`](Self::watch) with the given `interval` and performs the
+    /// actions the script asks for, until the returned [`ScriptRunner`] is
+    /// dropped. Must be called from within a tokio runtime.
+    #[cfg(feature = "scripting")]
+    pub fn run_script(self, script: Script, interval: Duration) -> ScriptRunner {
+        ScriptRunner::spawn(self, script, interval)
+    }
+
+    /// Duck channels while chosen applications play audio, in a background
+    /// task. See [`ducking`](crate::ducking).
+    ///
+    /// The ducker owns the client and restores the ducked volumes when the
+    /// returned [`AutoDucker`] is stopped or dropped. Must be called from
+    /// within a tokio runtime.
+    #[cfg(feature = "windows-audio")]
+    pub fn auto_duck(self, options: DuckingOptions) -> AutoDucker {
+        AutoDucker::spawn(self, options)
+    }
+
+    /// Switch to streamer mode and apply a profile while OBS Studio is
+    /// streaming, see [`obs`](crate::obs).
+    ///
+    /// The follower owns the client and restores the previous mixer state
+    /// when OBS stops streaming, and when the returned [`ObsFollower`] is
+    /// stopped or dropped. Must be called from within a tokio runtime.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SonarError::Obs`] if obs-websocket cannot be connected to.
+    #[cfg(feature = "obs")]
+    pub async fn follow_obs(self, options: ObsOptions) -> Result<ObsFollower> {
+        ObsFollower::connect(self, options).await
+    }
+
+    /// Run mixer actions on global hotkeys, see [`hotkeys`](crate::hotkeys).
+    ///
+    /// The hotkeys own the client and stay registered until the returned
+    /// [`Hotkeys`] is stopped or dropped. Must be called from within a tokio
+    /// runtime.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SonarError::InvalidHotkey`] if a hotkey cannot be parsed or
+    /// is bound twice, or an I/O error if one cannot be registered.
+    #[cfg(feature = "hotkeys")]
+    pub fn hotkeys(self, bindings: HotkeyBindings) -> Result<Hotkeys> {
+        Hotkeys::spawn(self, bindings)
+    }
+
+    /// Unmute a channel, the microphone by default, only while a global
+    /// hotkey is held. See [`push_to_talk`](crate::push_to_talk).
+    ///
+    /// The channel is muted right away. Push-to-talk owns the client and
+    /// runs until the returned [`PushToTalk`] is stopped or dropped, which
+    /// mutes the channel again. Must be called from within a tokio runtime.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SonarError::InvalidHotkey`] if the hotkey cannot be parsed,
+    /// or an I/O error if it cannot be registered, e.g. because another
+    /// application has taken it.
+    #[cfg(feature = "push-to-talk")]
+    pub fn push_to_talk(self, options: PushToTalkOptions) -> Result<PushToTalk> {
+        PushToTalk::spawn(self, options)
+    }
+
+    async fn get_json<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        Ok(self.send(Method::GET, path, None).await?.json().await?)
+    }
+
+    async fn put_json<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        Ok(self.send(Method::PUT, path, None).await?.json().await?)
+    }
+
+    async fn put_body<B: Serialize + ?Sized, T: DeserializeOwned>(&self, path: &str, body: &B) -> Result<T> {
+        let body = serde_json::to_value(body)?;
+        Ok(self.send(Method::PUT, path, Some(&body)).await?.json().await?)
+    }
+
+    async fn post_json<B: Serialize + ?Sized, T: DeserializeOwned>(&self, path: &str, body: &B) -> Result<T> {
+        let body = serde_json::to_value(body)?;
+        Ok(self.send(Method::POST, path, Some(&body)).await?.json().await?)
+    }
+
+    async fn delete(&self, path: &str) -> Result<()> {
+        self.send(Method::DELETE, path, None).await?;
+        Ok(())
+    }
+
+    /// The Sonar web server address currently in use, e.g. to pass to
+    /// [`connect_to`](Self::connect_to) later.
+    pub fn web_server_address(&self) -> String {
+        self.core.web_server_address()
+    }
+
+    /// The last requests to the web server, oldest first, if enabled with
+    /// [`SonarBuilder::capture_requests`].
+    pub fn last_requests(&self) -> Vec<CapturedRequest> {
+        self.core.last_requests()
+    }
+
+    /// Send a request to the web server, connecting first if needed.
+    async fn send(&self, method: Method, path: &str, body: Option<&Value>) -> Result<Response> {
+        self.ensure_connected().await?;
+        self.send_connected(method, path, body).await
+    }
+
+    /// Send a request to the web server, retrying transient failures
+    /// according to the retry policy. `POST` requests are only resent after
+    /// a reconnect.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "sonar.request",
+            level = "debug",
+            skip(self, method, body),
+            fields(method = method.as_str()),
+            err(Display)
+        )
+    )]
+    async fn send_connected(&self, method: Method, path: &str, body: Option<&Value>) -> Result<Response> {
+        if let Some(interaction) = self.core.replay(method.as_str(), path, body) {
+            return replayed_response(path, interaction?);
+        }
+
+        let mut attempt = 1;
+        let mut reconnected = false;
+
+        loop {
+            if let Some(wait) = self.core.rate_limit_wait() {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(?wait, "rate limited");
+                tokio::time::sleep(wait).await;
+            }
+
+            let url = self.core.url(path);
+            let started = Instant::now();
+            let mut request = self.client.request(method.clone(), &url);
+            if let Some(body) = body {
+                request = request.json(body);
+            }
+
+            let error = match request.send().await {
+                Ok(response) => {
+                    let response = self.record(&method, path, &url, body, started, response).await?;
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(attempt, status = response.status().as_u16(), elapsed = ?started.elapsed(), "response received");
+                    #[cfg(feature = "metrics")]
+                    telemetry::record_response(method.as_str(), path, response.status().as_u16(), started.elapsed());
+                    if response.status().is_success() {
+                        return Ok(response);
+                    }
+                    api_error(response).await
+                }
+                Err(error) => {
+                    let error = SonarError::from(error);
+                    self.core.record_failure(method.as_str(), &url, body, started, &error);
+                    error
+                }
+            };
+
+            #[cfg(feature = "metrics")]
+            telemetry::record_error(method.as_str(), path, &error);
+
+            // A refused connection usually means GG restarted on a new port;
+            // the request never reached the server, so it is safe to resend.
+            if self.core.auto_reconnect && !reconnected && error.is_connect() {
+                reconnected = true;
+                #[cfg(feature = "tracing")]
+                tracing::info!(error = %error, "connection failed, re-resolving the server address");
+                #[cfg(feature = "metrics")]
+                telemetry::record_reconnect();
+                if let Ok(true) = self.refresh_addresses().await {
+                    continue;
+                }
+            }
+
+            if method == Method::POST || !self.core.retry_policy.should_retry(&error, attempt) {
+                return Err(error);
+            }
+            #[cfg(feature = "tracing")]
+            tracing::warn!(attempt, error = %error, "request failed, retrying");
+            #[cfg(feature = "metrics")]
+            telemetry::record_retry(method.as_str(), path);
+            tokio::time::sleep(self.core.retry_policy.delay(attempt)).await;
+            attempt += 1;
+        }
+    }
+
+    /// Write the response to the cassette and the request log, if enabled.
+    async fn record(&self, method: &Method, path: &str, url: &str, body: Option<&Value>, started: Instant, response: Response) -> Result<Response> {
+        if !self.core.records_responses() {
+            return Ok(response);
+        }
+
+        let status = response.status();
+        let bytes = response.bytes().await?;
+        self.core.record_response(method.as_str(), path, url, body, started, status.as_u16(), &bytes)?;
+
+        let mut response = http::Response::new(bytes);
+        *response.status_mut() = status;
+        Ok(response.into())
+    }
+
+    /// Send a single request, e.g. to the Engine, without retries, reconnects
+    /// or capture.
+    async fn send_raw(client: &Client, method: Method, url: &str) -> Result<Response> {
+        let response = client.request(method, url).send().await?;
+        if !response.status().is_success() {
+            return Err(SonarError::ServerNotAccessible(response.status().as_u16()));
+        }
+        Ok(response)
+    }
+
+    client_orchestration! {
+        builder: SonarBuilder,
+        async: [async],
+        await: [.await],
+        join: [tokio::try_join],
+        client: Client,
+        get: Method::GET,
+        put: Method::PUT,
+        instant: tokio::time::Instant,
+        sleep: tokio::time::sleep,
+        read_to_string: tokio::fs::read_to_string,
+    }
+}
+
+/// Turn a recorded interaction back into a response, failing like the
+/// original request did.
+fn replayed_response(path: &str, interaction: Interaction) -> Result<Response> {
+    let status = reqwest::StatusCode::from_u16(interaction.status)
+        .ok()
+        .filter(reqwest::StatusCode::is_success)
+        .ok_or_else(|| SonarError::Api {
+            status: interaction.status,
+            endpoint: path.to_string(),
+            body: interaction.response_body.clone(),
+        })?;
+
+    let mut response = http::Response::new(interaction.response_body);
+    *response.status_mut() = status;
+    Ok(response.into())
+}
@@ -0,0 +1,120 @@
+//! Opt-in cache of the last known channel state.
+//!
+//! With [`SonarBuilder::state_cache`](crate::SonarBuilder::state_cache), the
+//! clients remember the volumes and mute states they wrote or read, and skip
+//! `set_volume` and `mute_channel` requests that would not change anything,
+//! e.g. from tools syncing hardware faders by polling.
+
+use crate::sonar::{Mode, StreamerSlider};
+use crate::volume::VolumeSettings;
+use std::collections::HashMap;
+use std::mem::Discriminant;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// A cached value of a channel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Setting {
+    Volume(f64),
+    Muted(bool),
+}
+
+/// Mode, channel, slider (streamer mode only) and kind of setting.
+type Key = (Mode, String, Option<StreamerSlider>, Discriminant<Setting>);
+
+/// Last known settings, shared by clones of a client.
+#[derive(Debug)]
+pub(crate) struct StateCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<Key, (Setting, Instant)>>,
+}
+
+impl StateCache {
+    /// Cache settings for `ttl` after they were written or read.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether `setting` is known to be in effect already.
+    pub fn contains(&self, mode: Mode, channel: &str, slider: Option<StreamerSlider>, setting: Setting) -> bool {
+        self.lock()
+            .get(&key(mode, channel, slider, setting))
+            .is_some_and(|(cached, updated)| *cached == setting && updated.elapsed() < self.ttl)
+    }
+
+    /// Remember the outcome of writing `setting`. A failed write leaves the
+    /// setting unknown.
+    pub fn record(&self, mode: Mode, channel: &str, slider: Option<StreamerSlider>, setting: Setting, written: bool) {
+        let key = key(mode, channel, slider, setting);
+        let mut entries = self.lock();
+        if written {
+            entries.insert(key, (setting, Instant::now()));
+        } else {
+            entries.remove(&key);
+        }
+    }
+
+    /// Remember all channel states read from the server.
+    pub fn store(&self, volumes: &VolumeSettings) {
+        let now = Instant::now();
+        let mut entries = self.lock();
+        for (channel, channel_volumes) in volumes.channels() {
+            let states = std::iter::once((Mode::Classic, None, channel_volumes.classic)).chain(
+                StreamerSlider::ALL
+                    .into_iter()
+                    .map(|slider| (Mode::Streamer, Some(slider), channel_volumes.stream.slider(slider))),
+            );
+            for (mode, slider, state) in states {
+                for setting in [Setting::Volume(state.volume), Setting::Muted(state.muted)] {
+                    entries.insert(key(mode, channel, slider, setting), (setting, now));
+                }
+            }
+        }
+    }
+
+    /// Forget everything, e.g. after reconnecting.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<Key, (Setting, Instant)>> {
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn key(mode: Mode, channel: &str, slider: Option<StreamerSlider>, setting: Setting) -> Key {
+    let slider = mode.is_streamer().then(|| slider.unwrap_or_default());
+    (mode, channel.to_string(), slider, std::mem::discriminant(&setting))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_cache() {
+        let cache = StateCache::new(Duration::from_secs(60));
+        let volume = Setting::Volume(0.5);
+        assert!(!cache.contains(Mode::Classic, "game", None, volume));
+
+        cache.record(Mode::Classic, "game", None, volume, true);
+        assert!(cache.contains(Mode::Classic, "game", Some(StreamerSlider::Monitoring), volume));
+        assert!(!cache.contains(Mode::Classic, "game", None, Setting::Volume(0.6)));
+        assert!(!cache.contains(Mode::Classic, "game", None, Setting::Muted(false)));
+        assert!(!cache.contains(Mode::Streamer, "game", None, volume));
+
+        cache.record(Mode::Streamer, "media", None, Setting::Muted(true), true);
+        assert!(cache.contains(Mode::Streamer, "media", Some(StreamerSlider::Streaming), Setting::Muted(true)));
+        assert!(!cache.contains(Mode::Streamer, "media", Some(StreamerSlider::Monitoring), Setting::Muted(true)));
+
+        cache.record(Mode::Classic, "game", None, volume, false);
+        assert!(!cache.contains(Mode::Classic, "game", None, volume));
+
+        let expired = StateCache::new(Duration::ZERO);
+        expired.record(Mode::Classic, "game", None, volume, true);
+        assert!(!expired.contains(Mode::Classic, "game", None, volume));
+    }
+}
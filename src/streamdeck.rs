@@ -0,0 +1,202 @@
+//! Ready-made actions for Elgato Stream Deck plugins.
+//!
+//! A plugin registers each visible key with its [`KeyAction`], runs the
+//! action on `keyDown` and polls [`StreamDeck::poll`] to keep the key states
+//! and titles in sync with changes made elsewhere, e.g. in the GG app.
+
+use crate::error::Result;
+use crate::sonar::{Sonar, StreamerSlider};
+use crate::volume::{VolumeSettings, VolumeState};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// What a key does, deserialized from the key's settings in the plugin, e.g.
+/// `{"action": "toggleMute", "channel": "game"}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "camelCase")]
+pub enum KeyAction {
+    /// Toggle the mute state of a channel.
+    #[serde(rename_all = "camelCase")]
+    ToggleMute {
+        channel: String,
+        #[serde(default)]
+        streamer_slider: Option<StreamerSlider>,
+    },
+    /// Change the volume of a channel by `step` along the client's volume
+    /// curve, e.g. `0.05` or `-0.05`.
+    #[serde(rename_all = "camelCase")]
+    VolumeStep {
+        channel: String,
+        step: f64,
+        #[serde(default)]
+        streamer_slider: Option<StreamerSlider>,
+    },
+    /// Apply a saved profile, see [`Sonar::load_profile`].
+    ApplyProfile { name: String },
+}
+
+/// What a key should show.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyFeedback {
+    /// State index for `setState`: 1 for a muted channel, 0 otherwise.
+    pub state: u8,
+    /// Title for `setTitle`: the channel volume in percent, or the profile
+    /// name.
+    pub title: String,
+}
+
+impl KeyFeedback {
+    fn new(action: &KeyAction, volume: Option<VolumeState>) -> Self {
+        match (action, volume) {
+            (KeyAction::ApplyProfile { name }, _) => Self {
+                state: 0,
+                title: name.clone(),
+            },
+            (_, Some(volume)) => Self {
+                state: u8::from(volume.muted),
+                title: format!("{}%", volume.percent()),
+            },
+            (_, None) => Self {
+                state: 0,
+                title: String::new(),
+            },
+        }
+    }
+}
+
+impl KeyAction {
+    fn channel(&self) -> Option<(&str, Option<StreamerSlider>)> {
+        match self {
+            KeyAction::ToggleMute { channel, streamer_slider } | KeyAction::VolumeStep { channel, streamer_slider, .. } => {
+                Some((channel, *streamer_slider))
+            }
+            KeyAction::ApplyProfile { .. } => None,
+        }
+    }
+
+    fn feedback(&self, volumes: &VolumeSettings, streamer_mode: bool) -> KeyFeedback {
+        let volume = self
+            .channel()
+            .and_then(|(channel, slider)| Some(volumes.channel(channel)?.state(streamer_mode, slider)));
+        KeyFeedback::new(self, volume)
+    }
+}
+
+/// The keys of a Stream Deck plugin and the feedback they last showed.
+#[derive(Debug, Clone)]
+pub struct StreamDeck {
+    sonar: Sonar,
+    keys: HashMap<String, Key>,
+}
+
+#[derive(Debug, Clone)]
+struct Key {
+    action: KeyAction,
+    shown: Option<KeyFeedback>,
+}
+
+impl StreamDeck {
+    pub fn new(sonar: Sonar) -> Self {
+        Self {
+            sonar,
+            keys: HashMap::new(),
+        }
+    }
+
+    pub fn sonar(&self) -> &Sonar {
+        &self.sonar
+    }
+
+    /// Register a key, e.g. on `willAppear` or `didReceiveSettings`. `context`
+    /// is the Stream Deck's identifier of the key.
+    pub fn add_key(&mut self, context: impl Into<String>, action: KeyAction) {
+        self.keys.insert(context.into(), Key { action, shown: None });
+    }
+
+    /// Forget a key, e.g. on `willDisappear`.
+    pub fn remove_key(&mut self, context: &str) {
+        self.keys.remove(context);
+    }
+
+    /// Run the action of a registered key, e.g. on `keyDown`. Returns the
+    /// feedback to show, `None` for unknown keys.
+    pub async fn key_down(&mut self, context: &str) -> Result<Option<KeyFeedback>> {
+        let Some(action) = self.keys.get(context).map(|key| key.action.clone()) else {
+            return Ok(None);
+        };
+        let feedback = self.run(&action).await?;
+        if let Some(key) = self.keys.get_mut(context) {
+            key.shown = Some(feedback.clone());
+        }
+        Ok(Some(feedback))
+    }
+
+    /// Run an action and return the feedback to show afterwards.
+    pub async fn run(&self, action: &KeyAction) -> Result<KeyFeedback> {
+        let volume = match action {
+            KeyAction::ToggleMute { channel, streamer_slider } => {
+                self.sonar.toggle_mute(channel, *streamer_slider).await?;
+                Some(self.sonar.get_channel_state(channel, *streamer_slider).await?)
+            }
+            KeyAction::VolumeStep { channel, step, streamer_slider } => {
+                self.sonar.adjust_volume(channel, *step, *streamer_slider).await?;
+                Some(self.sonar.get_channel_state(channel, *streamer_slider).await?)
+            }
+            KeyAction::ApplyProfile { name } => {
+                self.sonar.load_profile(name).await?;
+                None
+            }
+        };
+        Ok(KeyFeedback::new(action, volume))
+    }
+
+    /// Read the current state with a single request and return the feedback
+    /// of keys whose state or title changed since it was last returned.
+    /// Newly added keys are always returned.
+    pub async fn poll(&mut self) -> Result<Vec<(String, KeyFeedback)>> {
+        let volumes = self.sonar.get_volumes().await?;
+        let streamer_mode = self.sonar.streamer_mode();
+
+        let mut changed = Vec::new();
+        for (context, key) in &mut self.keys {
+            let feedback = key.action.feedback(&volumes, streamer_mode);
+            if key.shown.as_ref() != Some(&feedback) {
+                key.shown = Some(feedback.clone());
+                changed.push((context.clone(), feedback));
+            }
+        }
+        Ok(changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_action_settings() {
+        let action: KeyAction = serde_json::from_str(r#"{"action": "volumeStep", "channel": "game", "step": -0.05}"#).unwrap();
+        assert_eq!(
+            action,
+            KeyAction::VolumeStep {
+                channel: "game".to_string(),
+                step: -0.05,
+                streamer_slider: None,
+            }
+        );
+        let action: KeyAction =
+            serde_json::from_str(r#"{"action": "toggleMute", "channel": "aux", "streamerSlider": "monitoring"}"#).unwrap();
+        assert_eq!(action.channel(), Some(("aux", Some(StreamerSlider::Monitoring))));
+
+        let muted = VolumeState { volume: 0.456, muted: true };
+        assert_eq!(
+            KeyFeedback::new(&action, Some(muted)),
+            KeyFeedback {
+                state: 1,
+                title: "46%".to_string()
+            }
+        );
+        let profile = KeyAction::ApplyProfile { name: "Gaming".to_string() };
+        assert_eq!(KeyFeedback::new(&profile, None).title, "Gaming");
+    }
+}
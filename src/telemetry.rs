@@ -0,0 +1,108 @@
+//! Request metrics emitted through the [`metrics`] facade.
+//!
+//! Nothing is recorded unless the application installs a recorder, e.g. a
+//! Prometheus exporter.
+
+use crate::error::SonarError;
+use std::time::Duration;
+
+/// Counter of responses, labelled with `method`, `endpoint` and `status`.
+pub(crate) const REQUESTS: &str = "sonar_requests_total";
+/// Histogram of response latencies in seconds, labelled with `method` and `endpoint`.
+pub(crate) const REQUEST_DURATION: &str = "sonar_request_duration_seconds";
+/// Counter of failed attempts, labelled with `method`, `endpoint` and `class`.
+pub(crate) const ERRORS: &str = "sonar_request_errors_total";
+/// Counter of resent requests, labelled with `method` and `endpoint`.
+pub(crate) const RETRIES: &str = "sonar_request_retries_total";
+/// Counter of web server address re-resolutions after a refused connection.
+pub(crate) const RECONNECTS: &str = "sonar_reconnects_total";
+
+/// Record a response of the web server, successful or not.
+pub(crate) fn record_response(method: &str, path: &str, status: u16, elapsed: Duration) {
+    let endpoint = endpoint(path);
+    metrics::counter!(
+        REQUESTS,
+        "method" => method.to_string(),
+        "endpoint" => endpoint,
+        "status" => status.to_string()
+    )
+    .increment(1);
+    metrics::histogram!(REQUEST_DURATION, "method" => method.to_string(), "endpoint" => endpoint).record(elapsed);
+}
+
+/// Record a failed attempt.
+pub(crate) fn record_error(method: &str, path: &str, error: &SonarError) {
+    metrics::counter!(
+        ERRORS,
+        "method" => method.to_string(),
+        "endpoint" => endpoint(path),
+        "class" => error_class(error)
+    )
+    .increment(1);
+}
+
+/// Record that a request is sent again.
+pub(crate) fn record_retry(method: &str, path: &str) {
+    metrics::counter!(RETRIES, "method" => method.to_string(), "endpoint" => endpoint(path)).increment(1);
+}
+
+/// Record a reconnect.
+pub(crate) fn record_reconnect() {
+    metrics::counter!(RECONNECTS).increment(1);
+}
+
+/// The first segment of a request path. Later segments hold channel names,
+/// ids and values, which would make the label unbounded.
+fn endpoint(path: &str) -> &'static str {
+    const ENDPOINTS: [&str; 9] = [
+        "mode",
+        "volumeSettings",
+        "chatMix",
+        "audioDevices",
+        "classicRedirections",
+        "streamRedirections",
+        "AudioDeviceRouting",
+        "configs",
+        "subApps",
+    ];
+    let segment = path.trim_start_matches('/').split(['/', '?']).next().unwrap_or_default();
+    ENDPOINTS
+        .into_iter()
+        .find(|endpoint| *endpoint == segment)
+        .unwrap_or("other")
+}
+
+fn error_class(error: &SonarError) -> &'static str {
+    match error {
+        SonarError::Timeout => "timeout",
+        error if error.status().is_some_and(|status| status >= 500) => "server_error",
+        error if error.status().is_some() => "client_error",
+        SonarError::Json(_) => "decode",
+        error if error.is_connect() => "connect",
+        _ => "other",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_labels() {
+        assert_eq!(endpoint("/volumeSettings/classic/game/Volume/0.5"), "volumeSettings");
+        assert_eq!(endpoint("/chatMix?balance=0.5"), "chatMix");
+        assert_eq!(endpoint("/mode/"), "mode");
+        assert_eq!(endpoint("/unknown/path"), "other");
+
+        assert_eq!(error_class(&SonarError::Timeout), "timeout");
+        assert_eq!(error_class(&SonarError::ServerNotAccessible(503)), "server_error");
+        assert_eq!(error_class(&SonarError::ServerNotAccessible(404)), "client_error");
+        let api_error = SonarError::Api {
+            status: 502,
+            endpoint: "/mode/".to_string(),
+            body: String::new(),
+        };
+        assert_eq!(error_class(&api_error), "server_error");
+        assert_eq!(error_class(&SonarError::SonarNotEnabled), "other");
+    }
+}
@@ -0,0 +1,332 @@
+//! A local HTTP server emulating the Sonar web server, for tests.
+//!
+//! [`TestSonarServer`] serves the `/subApps`, `/mode`, `/volumeSettings` and
+//! `/chatMix` endpoints, the audio devices, the device routing and config selection from an
+//! in-memory [`MockState`]. Unlike
+//! [`MockSonar`](crate::MockSonar), requests go through the real clients, so
+//! URL building, response parsing and error handling are exercised too.
+//!
+//! ```no_run
+//! # async fn example() -> steelseries_sonar::Result<()> {
+//! use steelseries_sonar::test_util::TestSonarServer;
+//! use steelseries_sonar::Sonar;
+//!
+//! let server = TestSonarServer::start()?;
+//! let sonar = Sonar::connect_to(server.web_server_address()).await?;
+//! sonar.set_volume("game", 0.25, None).await?;
+//! assert_eq!(server.state().volumes["game"].classic.volume, 0.25);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{Result, SonarError};
+use crate::mock::MockState;
+use crate::sonar::StreamerSlider;
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use percent_encoding::percent_decode_str;
+use serde_json::{json, Value};
+use std::borrow::Cow;
+use std::convert::Infallible;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread::JoinHandle;
+use tokio::sync::oneshot;
+
+/// An emulated Sonar web server listening on a random local port.
+///
+/// The server runs on its own thread and runtime, so it can be used from
+/// both async and blocking tests. It shuts down when dropped.
+#[derive(Debug)]
+pub struct TestSonarServer {
+    web_server_address: String,
+    state: Arc<Mutex<MockState>>,
+    shutdown: Option<oneshot::Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl TestSonarServer {
+    /// Start a server with the [default state](MockState::default).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no local port can be bound.
+    pub fn start() -> Result<Self> {
+        Self::with_state(MockState::default())
+    }
+
+    /// Start a server with the given initial state.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no local port can be bound.
+    pub fn with_state(state: MockState) -> Result<Self> {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        listener.set_nonblocking(true)?;
+        let web_server_address = format!("http://{}", listener.local_addr()?);
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+
+        let state = Arc::new(Mutex::new(state));
+        let (shutdown, shutdown_receiver) = oneshot::channel();
+        let server = Server {
+            web_server_address: web_server_address.clone(),
+            state: Arc::clone(&state),
+        };
+        let thread = std::thread::spawn(move || {
+            runtime.block_on(server.run(listener, shutdown_receiver));
+        });
+
+        Ok(Self {
+            web_server_address,
+            state,
+            shutdown: Some(shutdown),
+            thread: Some(thread),
+        })
+    }
+
+    /// The address to pass to [`Sonar::connect_to`](crate::Sonar::connect_to),
+    /// e.g. `http://127.0.0.1:49152`.
+    pub fn web_server_address(&self) -> &str {
+        &self.web_server_address
+    }
+
+    /// A snapshot of the current state.
+    pub fn state(&self) -> MockState {
+        lock(&self.state).clone()
+    }
+
+    /// Modify the state, e.g. to simulate changes made in the GG app.
+    pub fn update<R>(&self, update: impl FnOnce(&mut MockState) -> R) -> R {
+        update(&mut lock(&self.state))
+    }
+
+    /// Write a coreProps.json pointing at this server, for clients that
+    /// discover it like a real SteelSeries GG, e.g. through
+    /// [`SonarBuilder::core_props_path`](crate::SonarBuilder::core_props_path).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn write_core_props(&self, path: impl AsRef<Path>) -> Result<()> {
+        let core_props = json!({ "ggEncryptedAddress": self.web_server_address });
+        std::fs::write(path, serde_json::to_vec(&core_props)?)?;
+        Ok(())
+    }
+}
+
+impl Drop for TestSonarServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn lock(state: &Mutex<MockState>) -> MutexGuard<'_, MockState> {
+    state.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[derive(Clone)]
+struct Server {
+    web_server_address: String,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl Server {
+    async fn run(self, listener: std::net::TcpListener, mut shutdown: oneshot::Receiver<()>) {
+        let Ok(listener) = tokio::net::TcpListener::from_std(listener) else {
+            return;
+        };
+
+        loop {
+            let stream = tokio::select! {
+                _ = &mut shutdown => return,
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, _)) => stream,
+                    Err(_) => continue,
+                },
+            };
+
+            let server = self.clone();
+            tokio::spawn(async move {
+                let service = service_fn(move |request| {
+                    let server = server.clone();
+                    async move { Ok::<_, Infallible>(server.handle(request)) }
+                });
+                let _ = http1::Builder::new().serve_connection(TokioIo::new(stream), service).await;
+            });
+        }
+    }
+
+    fn handle(&self, request: Request<Incoming>) -> Response<Full<Bytes>> {
+        let segments: Vec<Cow<'_, str>> = request
+            .uri()
+            .path()
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| percent_decode_str(segment).decode_utf8_lossy())
+            .collect();
+        let segments: Vec<&str> = segments.iter().map(AsRef::as_ref).collect();
+        let result = self.route(request.method(), &segments, request.uri().query());
+
+        let (status, body) = match result {
+            Ok(body) => (StatusCode::OK, body),
+            Err(status) => (status, json!({ "error": status.canonical_reason() })),
+        };
+        let mut response = Response::new(Full::new(Bytes::from(body.to_string())));
+        *response.status_mut() = status;
+        response
+    }
+
+    fn route(&self, method: &Method, segments: &[&str], query: Option<&str>) -> std::result::Result<Value, StatusCode> {
+        let mut state = lock(&self.state);
+
+        match (method, segments) {
+            (&Method::GET, ["subApps"]) => Ok(json!({
+                "subApps": {
+                    "sonar": {
+                        "isEnabled": true,
+                        "isReady": true,
+                        "isRunning": true,
+                        "metadata": { "webServerAddress": self.web_server_address },
+                    }
+                }
+            })),
+            (&Method::GET, ["mode"]) => Ok(json!(if state.streamer_mode { "stream" } else { "classic" })),
+            (&Method::PUT, ["mode", mode @ ("classic" | "stream")]) => {
+                state.streamer_mode = *mode == "stream";
+                Ok(json!(mode))
+            }
+            (&Method::GET, ["volumeSettings", "classic" | "streamer"]) => Ok(state.volume_data()),
+            (&Method::PUT, ["volumeSettings", "classic", channel, action, value]) => {
+                set_volume_state(&mut state, channel, None, action, value)
+            }
+            (&Method::PUT, ["volumeSettings", "streamer", slider, channel, action, value]) => {
+                let slider: StreamerSlider = slider.parse().map_err(|_| StatusCode::NOT_FOUND)?;
+                set_volume_state(&mut state, channel, Some(slider), action, value)
+            }
+            (&Method::GET, ["chatMix"]) => Ok(state.chat_mix_data()),
+            (&Method::PUT, ["chatMix"]) => {
+                let balance = query
+                    .into_iter()
+                    .flat_map(|query| query.split('&'))
+                    .find_map(|pair| pair.strip_prefix("balance="))
+                    .and_then(|balance| balance.parse().ok())
+                    .ok_or(StatusCode::BAD_REQUEST)?;
+                state.set_chat_mix(balance).map_err(error_status)
+            }
+            (&Method::GET, ["audioDevices"]) => to_json(&state.devices),
+            (&Method::GET, ["classicRedirections"]) => to_json(&state.redirections),
+            (&Method::GET, ["streamRedirections"]) => to_json(&state.stream_redirections),
+            (&Method::GET, ["AudioDeviceRouting"]) => to_json(&state.routed_apps),
+            (&Method::GET, ["configs", "selected"]) => to_json(&state.selected_configs()),
+            (&Method::PUT, ["classicRedirections", channel, "deviceId", device_id]) => {
+                state.set_redirection(channel, device_id).map_err(error_status)
+            }
+            (&Method::PUT, ["streamRedirections", slider, "deviceId", device_id]) => {
+                let slider: StreamerSlider = slider.parse().map_err(|_| StatusCode::NOT_FOUND)?;
+                state.set_stream_redirection(slider, device_id).map_err(error_status)
+            }
+            (&Method::PUT, ["streamRedirections", slider, "redirections", channel, "isEnabled", enabled]) => {
+                let slider: StreamerSlider = slider.parse().map_err(|_| StatusCode::NOT_FOUND)?;
+                let enabled: bool = enabled.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+                state.mute_redirection(channel, slider, !enabled).map_err(error_status)
+            }
+            (&Method::PUT, ["AudioDeviceRouting", channel, process_id]) => {
+                let process_id: u32 = process_id.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+                state.route_app(process_id, channel).map_err(error_status)
+            }
+            (&Method::PUT, ["configs", config_id, "select"]) => state.select_config(config_id).map_err(error_status),
+            _ => Err(StatusCode::NOT_FOUND),
+        }
+    }
+}
+
+/// The status the server answers a rejected request with.
+fn error_status(error: SonarError) -> StatusCode {
+    match error {
+        SonarError::ServerNotAccessible(status) => {
+            StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+        SonarError::ChannelNotFound(_) => StatusCode::NOT_FOUND,
+        _ => StatusCode::BAD_REQUEST,
+    }
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> std::result::Result<Value, StatusCode> {
+    serde_json::to_value(value).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// Handle `.../{channel}/Volume/{volume}` and the mute endpoints, which are
+/// `.../{channel}/Mute/{muted}` in classic and `.../{channel}/isMuted/{muted}`
+/// in streamer mode.
+fn set_volume_state(
+    state: &mut MockState,
+    channel: &str,
+    slider: Option<StreamerSlider>,
+    action: &str,
+    value: &str,
+) -> std::result::Result<Value, StatusCode> {
+    let volume_state = state.volume_state_mut(channel, slider).map_err(|_| StatusCode::NOT_FOUND)?;
+
+    match (action, slider) {
+        ("Volume", _) => {
+            let volume: f64 = value.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+            if !(0.0..=1.0).contains(&volume) {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+            volume_state.volume = volume;
+        }
+        ("Mute", None) | ("isMuted", Some(_)) => {
+            volume_state.muted = value.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+        }
+        _ => return Err(StatusCode::NOT_FOUND),
+    }
+    Ok(state.volume_data())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_server_routes() {
+        let server = TestSonarServer::start().unwrap();
+        let client = reqwest::Client::new();
+        let url = |path: &str| format!("{}{}", server.web_server_address(), path);
+
+        let sub_apps: crate::sonar::SubAppsResponse =
+            client.get(url("/subApps")).send().await.unwrap().json().await.unwrap();
+        assert_eq!(sub_apps.sub_apps.sonar.metadata.web_server_address, server.web_server_address());
+
+        let mode: String = client.put(url("/mode/stream")).send().await.unwrap().json().await.unwrap();
+        assert_eq!(mode, "stream");
+        client
+            .put(url("/volumeSettings/streamer/monitoring/game/isMuted/true"))
+            .send()
+            .await
+            .unwrap();
+        client.put(url("/chatMix?balance=0.5")).send().await.unwrap();
+
+        let state = server.state();
+        assert!(state.streamer_mode);
+        assert!(state.volumes["game"].stream.monitoring.muted);
+        assert!(!state.volumes["game"].classic.muted);
+        assert_eq!(state.chat_mix, 0.5);
+
+        let response = client.get(url("/audioDevices")).send().await.unwrap();
+        assert_eq!(response.status(), 200);
+        let response = client.get(url("/unknown")).send().await.unwrap();
+        assert_eq!(response.status(), 404);
+        let response = client.put(url("/volumeSettings/classic/game/Volume/2")).send().await.unwrap();
+        assert_eq!(response.status(), 400);
+    }
+}
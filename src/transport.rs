@@ -0,0 +1,204 @@
+//! HTTP backends of the blocking client.
+//!
+//! [`BlockingSonar`](crate::BlockingSonar) runs on reqwest's blocking client
+//! (`blocking` feature) or on ureq (`ureq` feature), which does not depend on
+//! tokio. Both are hidden behind [`BlockingTransport`].
+
+use crate::builder::ClientOptions;
+use crate::error::{Result, SonarError};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// HTTP methods used by the Sonar API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Method {
+    Get,
+    Put,
+    Post,
+    Delete,
+}
+
+impl Method {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Method::Get => "GET",
+            Method::Put => "PUT",
+            Method::Post => "POST",
+            Method::Delete => "DELETE",
+        }
+    }
+}
+
+/// A fully read response.
+#[derive(Debug)]
+pub(crate) struct TransportResponse {
+    pub status: u16,
+    pub body: Vec<u8>,
+}
+
+impl TransportResponse {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// The [`SonarError::Api`] error for this unsuccessful response to `path`.
+    pub fn into_api_error(self, path: &str) -> SonarError {
+        SonarError::Api {
+            status: self.status,
+            endpoint: path.to_string(),
+            body: String::from_utf8_lossy(&self.body).into_owned(),
+        }
+    }
+
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T> {
+        Ok(serde_json::from_slice(&self.body)?)
+    }
+}
+
+/// The HTTP backend selected for a blocking client.
+///
+/// `None` fields use a client built from the [`ClientOptions`].
+#[derive(Debug, Clone)]
+pub(crate) enum Backend {
+    #[cfg(feature = "blocking")]
+    Reqwest(Option<reqwest::blocking::Client>),
+    #[cfg(feature = "ureq")]
+    Ureq(Option<ureq::Agent>),
+}
+
+impl Default for Backend {
+    /// reqwest when the `blocking` feature is enabled, ureq otherwise.
+    fn default() -> Self {
+        #[cfg(feature = "blocking")]
+        {
+            Backend::Reqwest(None)
+        }
+        #[cfg(not(feature = "blocking"))]
+        {
+            Backend::Ureq(None)
+        }
+    }
+}
+
+/// An HTTP client of one of the blocking backends.
+#[derive(Debug, Clone)]
+pub(crate) enum BlockingTransport {
+    #[cfg(feature = "blocking")]
+    Reqwest(reqwest::blocking::Client),
+    #[cfg(feature = "ureq")]
+    Ureq(ureq::Agent),
+}
+
+impl BlockingTransport {
+    pub fn new(backend: Backend, options: &ClientOptions) -> Result<Self> {
+        match backend {
+            #[cfg(feature = "blocking")]
+            Backend::Reqwest(Some(client)) => Ok(Self::Reqwest(client)),
+            #[cfg(feature = "blocking")]
+            Backend::Reqwest(None) => {
+                let mut builder = reqwest::blocking::Client::builder().danger_accept_invalid_certs(true);
+                if let Some(timeout) = options.timeout {
+                    builder = builder.timeout(timeout);
+                }
+                if let Some(timeout) = options.connect_timeout {
+                    builder = builder.connect_timeout(timeout);
+                }
+                if let Some(timeout) = options.pool_idle_timeout {
+                    builder = builder.pool_idle_timeout(timeout);
+                }
+                if let Some(connections) = options.pool_max_idle {
+                    builder = builder.pool_max_idle_per_host(connections);
+                }
+                Ok(Self::Reqwest(builder.build()?))
+            }
+            #[cfg(feature = "ureq")]
+            Backend::Ureq(Some(agent)) => Ok(Self::Ureq(agent)),
+            #[cfg(feature = "ureq")]
+            Backend::Ureq(None) => Ok(Self::Ureq(ureq_agent(options))),
+        }
+    }
+
+    /// Send a request and read the whole response.
+    ///
+    /// Non-success statuses are returned as responses, not errors.
+    pub fn send(&self, method: Method, url: &str, body: Option<&Value>) -> Result<TransportResponse> {
+        match self {
+            #[cfg(feature = "blocking")]
+            Self::Reqwest(client) => {
+                let method = match method {
+                    Method::Get => reqwest::Method::GET,
+                    Method::Put => reqwest::Method::PUT,
+                    Method::Post => reqwest::Method::POST,
+                    Method::Delete => reqwest::Method::DELETE,
+                };
+                let mut request = client.request(method, url);
+                if let Some(body) = body {
+                    request = request.json(body);
+                }
+                let response = request.send()?;
+                Ok(TransportResponse {
+                    status: response.status().as_u16(),
+                    body: response.bytes()?.to_vec(),
+                })
+            }
+            #[cfg(feature = "ureq")]
+            Self::Ureq(agent) => {
+                let response = match (method, body) {
+                    (Method::Get, _) => agent.get(url).call(),
+                    (Method::Delete, _) => agent.delete(url).call(),
+                    (Method::Put, Some(body)) => agent.put(url).send_json(body),
+                    (Method::Put, None) => agent.put(url).send_empty(),
+                    (Method::Post, Some(body)) => agent.post(url).send_json(body),
+                    (Method::Post, None) => agent.post(url).send_empty(),
+                };
+                let mut response = response?;
+                Ok(TransportResponse {
+                    status: response.status().as_u16(),
+                    body: response.body_mut().read_to_vec()?,
+                })
+            }
+        }
+    }
+}
+
+#[cfg(feature = "ureq")]
+fn ureq_agent(options: &ClientOptions) -> ureq::Agent {
+    let tls = ureq::tls::TlsConfig::builder().disable_verification(true);
+    #[cfg(feature = "native-tls")]
+    let tls = tls.provider(ureq::tls::TlsProvider::NativeTls);
+
+    let mut config = ureq::Agent::config_builder()
+        .tls_config(tls.build())
+        .http_status_as_error(false)
+        .timeout_global(options.timeout)
+        .timeout_connect(options.connect_timeout);
+    if let Some(timeout) = options.pool_idle_timeout {
+        config = config.max_idle_age(timeout);
+    }
+    if let Some(connections) = options.pool_max_idle {
+        config = config.max_idle_connections(connections).max_idle_connections_per_host(connections);
+    }
+    config.build().new_agent()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transport_response() {
+        let response = TransportResponse {
+            status: 200,
+            body: br#"{"balance": 0.5}"#.to_vec(),
+        };
+        assert!(response.is_success());
+        let value: Value = response.json().unwrap();
+        assert_eq!(value["balance"], 0.5);
+
+        let response = TransportResponse {
+            status: 503,
+            body: Vec::new(),
+        };
+        assert!(!response.is_success());
+    }
+}
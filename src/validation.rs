@@ -0,0 +1,52 @@
+//! Handling of input that is valid but has no effect.
+//!
+//! Some arguments are accepted but ignored, such as a streamer slider passed
+//! while Sonar is in classic mode. [`Validation`], set via
+//! [`SonarBuilder::validation`](crate::SonarBuilder::validation), decides
+//! whether such input is an error, a warning or silently ignored.
+
+use crate::error::{Result, SonarError};
+use serde::Deserialize;
+
+/// How input that has no effect is handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Validation {
+    /// Fail with [`SonarError::IgnoredInput`].
+    Strict,
+    /// Log a warning with the `tracing` feature, then go on.
+    Warn,
+    /// Silently ignore the input, as earlier versions did.
+    #[default]
+    Lenient,
+}
+
+impl Validation {
+    /// Report input that has no effect, described by `message`.
+    pub(crate) fn report(self, message: impl FnOnce() -> String) -> Result<()> {
+        match self {
+            Validation::Strict => Err(SonarError::IgnoredInput(message())),
+            Validation::Warn => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!("ignored input: {}", message());
+                #[cfg(not(feature = "tracing"))]
+                let _ = message;
+                Ok(())
+            }
+            Validation::Lenient => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let message = || "the monitoring slider only applies in streamer mode".to_string();
+        assert!(matches!(Validation::Strict.report(message), Err(SonarError::IgnoredInput(_))));
+        assert!(Validation::Warn.report(message).is_ok());
+        assert!(Validation::Lenient.report(message).is_ok());
+    }
+}
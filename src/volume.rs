@@ -0,0 +1,420 @@
+//! Typed model of the Sonar volume settings.
+
+use crate::error::{Result, SonarError};
+use crate::sonar::{Mode, StreamerSlider, CHANNEL_NAMES};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Growth rate of [`VolumeCurve::Logarithmic`], giving it a 40 dB range.
+const LOGARITHMIC_GROWTH: f64 = std::f64::consts::LN_10 * 2.0;
+
+/// Iterations used to invert a [`VolumeCurve::Custom`] mapping.
+const INVERSION_STEPS: u32 = 40;
+
+/// Volume level and mute flag of a single channel/slider.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+pub struct VolumeState {
+    #[serde(default)]
+    pub volume: f64,
+    #[serde(default)]
+    pub muted: bool,
+}
+
+impl VolumeState {
+    /// The volume as a percentage, rounded to the nearest integer.
+    pub fn percent(&self) -> u8 {
+        volume_to_percent(self.volume)
+    }
+}
+
+/// Mapping between positions of a control (0.0 to 1.0) and Sonar volume levels.
+///
+/// Used by the convenience setters ([`set_volume_percent`](crate::Sonar::set_volume_percent),
+/// [`adjust_volume`](crate::Sonar::adjust_volume)) and fades, so that equal
+/// steps of a hardware knob or UI slider change the perceived loudness evenly.
+/// [`set_volume`](crate::Sonar::set_volume) always takes raw volume levels.
+#[derive(Clone, Default)]
+pub enum VolumeCurve {
+    /// Positions are volume levels.
+    #[default]
+    Linear,
+    /// Exponential mapping over a 40 dB range: the lower half of the control
+    /// covers the quiet levels where the ear is most sensitive.
+    Logarithmic,
+    /// A custom mapping from position to volume level. It must be increasing,
+    /// so that it can be inverted to read positions back.
+    Custom(Arc<dyn Fn(f64) -> f64 + Send + Sync>),
+}
+
+impl VolumeCurve {
+    /// A custom curve mapping a position to a volume level.
+    ///
+    /// ```
+    /// use steelseries_sonar::VolumeCurve;
+    ///
+    /// let curve = VolumeCurve::custom(|position| position * position);
+    /// assert_eq!(curve.to_volume(0.5), 0.25);
+    /// ```
+    pub fn custom(curve: impl Fn(f64) -> f64 + Send + Sync + 'static) -> Self {
+        VolumeCurve::Custom(Arc::new(curve))
+    }
+
+    /// The volume level of a control position, both clamped to 0.0..=1.0.
+    pub fn to_volume(&self, position: f64) -> f64 {
+        let position = position.clamp(0.0, 1.0);
+        let volume = match self {
+            VolumeCurve::Linear => position,
+            VolumeCurve::Logarithmic => (LOGARITHMIC_GROWTH * position).exp_m1() / LOGARITHMIC_GROWTH.exp_m1(),
+            VolumeCurve::Custom(curve) => curve(position),
+        };
+        volume.clamp(0.0, 1.0)
+    }
+
+    /// The control position of a volume level, inverting [`to_volume`](Self::to_volume).
+    pub fn to_position(&self, volume: f64) -> f64 {
+        let volume = volume.clamp(0.0, 1.0);
+        match self {
+            VolumeCurve::Linear => volume,
+            VolumeCurve::Logarithmic => (volume * LOGARITHMIC_GROWTH.exp_m1()).ln_1p() / LOGARITHMIC_GROWTH,
+            VolumeCurve::Custom(_) => {
+                let (mut low, mut high) = (0.0, 1.0);
+                for _ in 0..INVERSION_STEPS {
+                    let middle = (low + high) / 2.0;
+                    if self.to_volume(middle) < volume {
+                        low = middle;
+                    } else {
+                        high = middle;
+                    }
+                }
+                (low + high) / 2.0
+            }
+        }
+    }
+}
+
+impl fmt::Debug for VolumeCurve {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VolumeCurve::Linear => f.write_str("Linear"),
+            VolumeCurve::Logarithmic => f.write_str("Logarithmic"),
+            VolumeCurve::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+/// Convert a percentage (0 to 100) to a volume level (0.0 to 1.0).
+///
+/// # Errors
+///
+/// Returns [`SonarError::InvalidVolumePercent`] if `percent` is above 100.
+pub fn percent_to_volume(percent: u8) -> Result<f64> {
+    if percent > 100 {
+        return Err(SonarError::InvalidVolumePercent(percent));
+    }
+    Ok(f64::from(percent) / 100.0)
+}
+
+/// Convert a volume level to a percentage, rounded to the nearest integer.
+///
+/// Levels outside 0.0..=1.0 are clamped.
+pub fn volume_to_percent(volume: f64) -> u8 {
+    // In range after clamping, so the cast cannot truncate.
+    (volume.clamp(0.0, 1.0) * 100.0).round() as u8
+}
+
+/// Width of the columns of [`VolumeSettings::to_table`].
+const TABLE_COLUMN_WIDTH: usize = 13;
+
+/// A column of a volume table: its header and the state it shows.
+type TableColumn = (&'static str, fn(&ChannelVolumes) -> VolumeState);
+
+const CLASSIC_COLUMNS: &[TableColumn] = &[("Volume", |volumes| volumes.classic)];
+const STREAMER_COLUMNS: &[TableColumn] = &[
+    ("Streaming", |volumes| volumes.stream.streaming),
+    ("Monitoring", |volumes| volumes.stream.monitoring),
+];
+const ALL_COLUMNS: &[TableColumn] = &[
+    ("Classic", |volumes| volumes.classic),
+    ("Streaming", |volumes| volumes.stream.streaming),
+    ("Monitoring", |volumes| volumes.stream.monitoring),
+];
+
+/// Per-slider volume states used in streamer mode.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct StreamerVolumes {
+    #[serde(default)]
+    pub streaming: VolumeState,
+    #[serde(default)]
+    pub monitoring: VolumeState,
+}
+
+impl StreamerVolumes {
+    /// Get the volume state of a specific slider.
+    pub fn slider(&self, slider: StreamerSlider) -> VolumeState {
+        match slider {
+            StreamerSlider::Streaming => self.streaming,
+            StreamerSlider::Monitoring => self.monitoring,
+        }
+    }
+}
+
+/// Volume states of a channel in both classic and streamer mode.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct ChannelVolumes {
+    #[serde(default)]
+    pub classic: VolumeState,
+    #[serde(default)]
+    pub stream: StreamerVolumes,
+}
+
+impl ChannelVolumes {
+    /// Get the volume state that applies in the given mode.
+    ///
+    /// `streamer_slider` is only used in streamer mode and defaults to
+    /// [`StreamerSlider::Streaming`].
+    pub fn state(&self, streamer_mode: bool, streamer_slider: Option<StreamerSlider>) -> VolumeState {
+        if streamer_mode {
+            self.stream.slider(streamer_slider.unwrap_or_default())
+        } else {
+            self.classic
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RawVolumeSettings {
+    #[serde(default)]
+    masters: ChannelVolumes,
+    #[serde(default)]
+    devices: BTreeMap<String, ChannelVolumes>,
+}
+
+/// Volume settings for all channels, as returned by `/volumeSettings`.
+///
+/// Serializes to the JSON document it was parsed from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(into = "Value", try_from = "Value")]
+pub struct VolumeSettings {
+    masters: ChannelVolumes,
+    devices: BTreeMap<String, ChannelVolumes>,
+    raw: Value,
+}
+
+impl VolumeSettings {
+    /// Parse volume settings from the raw JSON returned by the server.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JSON does not have the expected shape.
+    pub fn from_value(value: Value) -> Result<Self> {
+        let parsed = RawVolumeSettings::deserialize(&value)?;
+        Ok(Self {
+            masters: parsed.masters,
+            devices: parsed.devices,
+            raw: value,
+        })
+    }
+
+    /// Volume states of the master channel.
+    pub fn master(&self) -> &ChannelVolumes {
+        &self.masters
+    }
+
+    /// Volume states of a channel by name (`"master"` included).
+    pub fn channel(&self, channel: &str) -> Option<&ChannelVolumes> {
+        if channel == "master" {
+            Some(&self.masters)
+        } else {
+            self.devices.get(channel)
+        }
+    }
+
+    /// Iterate over all known channels, in [`CHANNEL_NAMES`] order.
+    pub fn channels(&self) -> impl Iterator<Item = (&'static str, &ChannelVolumes)> {
+        CHANNEL_NAMES
+            .iter()
+            .filter_map(move |name| self.channel(name).map(|volumes| (*name, volumes)))
+    }
+
+    /// The untyped JSON document these settings were parsed from.
+    pub fn raw(&self) -> &Value {
+        &self.raw
+    }
+
+    /// Consume the settings and return the untyped JSON document.
+    pub fn into_raw(self) -> Value {
+        self.raw
+    }
+
+    /// Render the volumes and mute flags of `mode` as an aligned text table
+    /// with one line per channel.
+    ///
+    /// ```
+    /// use serde_json::json;
+    /// use steelseries_sonar::{Mode, VolumeSettings};
+    ///
+    /// let volumes = VolumeSettings::from_value(json!({
+    ///     "masters": {"classic": {"volume": 0.8, "muted": false}},
+    ///     "devices": {"game": {"classic": {"volume": 0.5, "muted": true}}},
+    /// }))?;
+    /// assert_eq!(
+    ///     volumes.to_table(Mode::Classic),
+    ///     "Channel      Volume\nmaster         80%\ngame           50% muted\n",
+    /// );
+    /// # Ok::<(), steelseries_sonar::SonarError>(())
+    /// ```
+    pub fn to_table(&self, mode: Mode) -> String {
+        let columns = if mode.is_streamer() { STREAMER_COLUMNS } else { CLASSIC_COLUMNS };
+        let mut table = String::new();
+        // Writing to a String cannot fail.
+        let _ = self.write_table(&mut table, columns);
+        table
+    }
+
+    fn write_table(&self, out: &mut impl fmt::Write, columns: &[TableColumn]) -> fmt::Result {
+        let mut line = format!("{:<width$}", "Channel", width = TABLE_COLUMN_WIDTH);
+        for (header, _) in columns {
+            line.push_str(&format!("{:<width$}", header, width = TABLE_COLUMN_WIDTH));
+        }
+        writeln!(out, "{}", line.trim_end())?;
+
+        for (channel, volumes) in self.channels() {
+            let mut line = format!("{:<width$}", channel, width = TABLE_COLUMN_WIDTH);
+            for (_, state) in columns {
+                let state = state(volumes);
+                let cell = format!("{:>4}%{}", state.percent(), if state.muted { " muted" } else { "" });
+                line.push_str(&format!("{:<width$}", cell, width = TABLE_COLUMN_WIDTH));
+            }
+            writeln!(out, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+/// Renders the classic and both streamer states of every channel, see
+/// [`VolumeSettings::to_table`] for the states of a single mode.
+impl fmt::Display for VolumeSettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_table(f, ALL_COLUMNS)
+    }
+}
+
+impl From<VolumeSettings> for Value {
+    fn from(settings: VolumeSettings) -> Self {
+        settings.raw
+    }
+}
+
+impl TryFrom<Value> for VolumeSettings {
+    type Error = SonarError;
+
+    fn try_from(value: Value) -> Result<Self> {
+        Self::from_value(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_volume_curves() {
+        let logarithmic = VolumeCurve::Logarithmic;
+        assert_eq!(logarithmic.to_volume(0.0), 0.0);
+        assert!((logarithmic.to_volume(1.0) - 1.0).abs() < 1e-12);
+        assert!(logarithmic.to_volume(0.5) < 0.1);
+        assert!((logarithmic.to_position(logarithmic.to_volume(0.3)) - 0.3).abs() < 1e-9);
+
+        let custom = VolumeCurve::custom(|position| position.powi(3));
+        assert!((custom.to_position(0.125) - 0.5).abs() < 1e-9);
+        assert_eq!(VolumeCurve::Linear.to_volume(1.5), 1.0);
+        assert_eq!(format!("{:?}", custom), "Custom(..)");
+    }
+
+    #[test]
+    fn test_percent_conversion() {
+        assert_eq!(percent_to_volume(29).unwrap(), 0.29);
+        assert_eq!(percent_to_volume(100).unwrap(), 1.0);
+        assert!(matches!(percent_to_volume(101), Err(SonarError::InvalidVolumePercent(101))));
+
+        assert_eq!(volume_to_percent(0.289_999_991_655_349_73), 29);
+        assert_eq!(volume_to_percent(1.5), 100);
+        assert_eq!(VolumeState { volume: 0.576, muted: false }.percent(), 58);
+    }
+
+    fn sample() -> Value {
+        json!({
+            "masters": {
+                "classic": { "volume": 0.8, "muted": false },
+                "stream": {
+                    "streaming": { "volume": 0.6, "muted": false },
+                    "monitoring": { "volume": 0.4, "muted": true }
+                }
+            },
+            "devices": {
+                "game": {
+                    "classic": { "volume": 0.5, "muted": true },
+                    "stream": {
+                        "streaming": { "volume": 0.7, "muted": false },
+                        "monitoring": { "volume": 0.3, "muted": false }
+                    }
+                },
+                "media": {
+                    "classic": { "volume": 1.0, "muted": false }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_parse_volume_settings() {
+        let settings = VolumeSettings::from_value(sample()).unwrap();
+
+        assert_eq!(settings.master().classic.volume, 0.8);
+        assert!(settings.master().stream.monitoring.muted);
+
+        let game = settings.channel("game").unwrap();
+        assert!(game.classic.muted);
+        assert_eq!(game.stream.slider(StreamerSlider::Streaming).volume, 0.7);
+        assert_eq!(game.stream.slider(StreamerSlider::Monitoring).volume, 0.3);
+
+        assert_eq!(game.state(false, None).volume, 0.5);
+        assert_eq!(game.state(true, None).volume, 0.7);
+        assert_eq!(game.state(true, Some(StreamerSlider::Monitoring)).volume, 0.3);
+
+        let media = settings.channel("media").unwrap();
+        assert_eq!(media.stream, StreamerVolumes::default());
+        assert!(settings.channel("aux").is_none());
+
+        assert_eq!(settings.raw(), &sample());
+    }
+
+    #[test]
+    fn test_volume_table() {
+        let volumes = VolumeSettings::from_value(json!({
+            "masters": {"classic": {"volume": 1.0}, "stream": {"monitoring": {"volume": 0.3, "muted": true}}},
+            "devices": {},
+        }))
+        .unwrap();
+        assert_eq!(
+            volumes.to_table(Mode::Streamer),
+            "Channel      Streaming    Monitoring\nmaster          0%          30% muted\n"
+        );
+        assert_eq!(
+            volumes.to_string(),
+            "Channel      Classic      Streaming    Monitoring\nmaster        100%           0%          30% muted\n"
+        );
+    }
+
+    #[test]
+    fn test_channels_order() {
+        let settings = VolumeSettings::from_value(sample()).unwrap();
+        let names: Vec<_> = settings.channels().map(|(name, _)| name).collect();
+        assert_eq!(names, ["master", "game", "media"]);
+    }
+}
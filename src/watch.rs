@@ -0,0 +1,325 @@
+//! Polling-based change watcher for the Sonar mixer.
+//!
+//! The Sonar API has no push notifications, so [`Sonar::watch`](crate::Sonar::watch)
+//! polls the mode, volume settings and chat mix endpoints and reports differences
+//! between consecutive polls as [`SonarEvent`]s. A watcher can be converted into a
+//! [`SonarEventStream`] to use it with `Stream` combinators.
+
+use crate::error::Result;
+use crate::sonar::{Mode, Sonar, StreamerSlider};
+use crate::volume::{VolumeSettings, VolumeState};
+use futures_core::Stream;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+
+const EVENT_BUFFER: usize = 64;
+
+/// A change observed by a [`SonarWatcher`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SonarEvent {
+    /// A channel volume changed. `slider` is `None` for the classic mix.
+    VolumeChanged {
+        channel: String,
+        slider: Option<StreamerSlider>,
+        volume: f64,
+    },
+    /// A channel was muted or unmuted. `slider` is `None` for the classic mix.
+    MuteChanged {
+        channel: String,
+        slider: Option<StreamerSlider>,
+        muted: bool,
+    },
+    /// The chat mix balance changed.
+    ChatMixChanged { balance: f64 },
+    /// Sonar switched between classic and streamer mode.
+    ModeChanged { streamer_mode: bool },
+    /// SteelSeries GG restarted and the client now uses a new web server address.
+    Reconnected { web_server_address: String },
+}
+
+impl fmt::Display for SonarEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SonarEvent::VolumeChanged { channel, slider: None, volume } => write!(f, "volume of {}: {:.2}", channel, volume),
+            SonarEvent::VolumeChanged { channel, slider: Some(slider), volume } => {
+                write!(f, "{} volume of {}: {:.2}", slider, channel, volume)
+            }
+            SonarEvent::MuteChanged { channel, slider, muted } => {
+                let state = if *muted { "muted" } else { "unmuted" };
+                match slider {
+                    None => write!(f, "mute of {}: {}", channel, state),
+                    Some(slider) => write!(f, "{} mute of {}: {}", slider, channel, state),
+                }
+            }
+            SonarEvent::ChatMixChanged { balance } => write!(f, "chat mix: {:.2}", balance),
+            SonarEvent::ModeChanged { streamer_mode } => write!(f, "mode: {}", Mode::from_streamer_mode(*streamer_mode)),
+            SonarEvent::Reconnected { web_server_address } => write!(f, "reconnected to {}", web_server_address),
+        }
+    }
+}
+
+/// Handle to a running watcher task.
+///
+/// Events are buffered in a `tokio::sync::mpsc` channel. The background task is
+/// stopped when the watcher is dropped.
+#[derive(Debug)]
+pub struct SonarWatcher {
+    receiver: mpsc::Receiver<SonarEvent>,
+    task: JoinHandle<()>,
+}
+
+impl SonarWatcher {
+    pub(crate) fn spawn(sonar: Sonar, interval: Duration) -> Self {
+        let (sender, receiver) = Self::channel();
+        let task = tokio::spawn(poll_loop(sonar, interval, sender));
+        Self::from_task(receiver, task)
+    }
+
+    /// Wrap a task that reports events through the sender of `receiver`.
+    pub(crate) fn from_task(receiver: mpsc::Receiver<SonarEvent>, task: JoinHandle<()>) -> Self {
+        Self { receiver, task }
+    }
+
+    /// Create the channel used by watcher tasks.
+    pub(crate) fn channel() -> (mpsc::Sender<SonarEvent>, mpsc::Receiver<SonarEvent>) {
+        mpsc::channel(EVENT_BUFFER)
+    }
+
+    /// Wait for the next event.
+    ///
+    /// Returns `None` once the watcher task has stopped.
+    pub async fn recv(&mut self) -> Option<SonarEvent> {
+        self.receiver.recv().await
+    }
+
+    /// Convert the watcher into a [`Stream`] of events.
+    pub fn into_stream(self) -> SonarEventStream {
+        SonarEventStream { watcher: self }
+    }
+
+    /// Stop the background polling task.
+    pub fn stop(self) {
+        // Dropping aborts the task.
+    }
+}
+
+impl Drop for SonarWatcher {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// [`Stream`] of [`SonarEvent`]s backed by a [`SonarWatcher`].
+///
+/// The stream ends when the watcher task stops, and dropping the stream stops
+/// the watcher.
+#[derive(Debug)]
+pub struct SonarEventStream {
+    watcher: SonarWatcher,
+}
+
+impl From<SonarWatcher> for SonarEventStream {
+    fn from(watcher: SonarWatcher) -> Self {
+        watcher.into_stream()
+    }
+}
+
+impl Stream for SonarEventStream {
+    type Item = SonarEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<SonarEvent>> {
+        self.get_mut().watcher.receiver.poll_recv(cx)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Observed {
+    mode: Option<Mode>,
+    volumes: Option<VolumeSettings>,
+    chat_mix: Option<f64>,
+}
+
+async fn poll_loop(sonar: Sonar, interval: Duration, sender: mpsc::Sender<SonarEvent>) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut observed = Observed::default();
+
+    loop {
+        ticker.tick().await;
+        if sender.is_closed() {
+            return;
+        }
+
+        // Polling errors are skipped; the next tick simply tries again.
+        let mut events = Vec::new();
+        if let Err(_error) = poll_once(&sonar, &mut observed, &mut events).await {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(error = %_error, "polling Sonar failed");
+            continue;
+        }
+
+        for event in events {
+            if sender.send(event).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg_attr(feature = "tracing", tracing::instrument(name = "sonar.poll", level = "trace", skip_all, err(Display)))]
+async fn poll_once(sonar: &Sonar, observed: &mut Observed, events: &mut Vec<SonarEvent>) -> Result<()> {
+    let mode = sonar.get_mode().await?;
+    if observed.mode.is_some_and(|previous| previous != mode) {
+        events.push(SonarEvent::ModeChanged { streamer_mode: mode.is_streamer() });
+    }
+    observed.mode = Some(mode);
+
+    let volumes = sonar.get_volumes_in(mode).await?;
+    if let Some(previous) = &observed.volumes {
+        diff_volumes(previous, &volumes, events);
+    }
+    observed.volumes = Some(volumes);
+
+    let chat_mix = sonar.get_chat_mix_data().await?;
+    if let Some(balance) = chat_mix.get("balance").and_then(Value::as_f64) {
+        if observed.chat_mix.is_some_and(|previous| previous != balance) {
+            events.push(SonarEvent::ChatMixChanged { balance });
+        }
+        observed.chat_mix = Some(balance);
+    }
+
+    Ok(())
+}
+
+fn diff_volumes(previous: &VolumeSettings, current: &VolumeSettings, events: &mut Vec<SonarEvent>) {
+    for (channel, volumes) in current.channels() {
+        let old = previous.channel(channel).copied().unwrap_or_default();
+
+        diff_state(channel, None, &old.classic, &volumes.classic, events);
+        for slider in StreamerSlider::ALL {
+            diff_state(
+                channel,
+                Some(slider),
+                &old.stream.slider(slider),
+                &volumes.stream.slider(slider),
+                events,
+            );
+        }
+    }
+}
+
+fn diff_state(
+    channel: &str,
+    slider: Option<StreamerSlider>,
+    previous: &VolumeState,
+    current: &VolumeState,
+    events: &mut Vec<SonarEvent>,
+) {
+    if previous.volume != current.volume {
+        events.push(SonarEvent::VolumeChanged {
+            channel: channel.to_string(),
+            slider,
+            volume: current.volume,
+        });
+    }
+
+    if previous.muted != current.muted {
+        events.push(SonarEvent::MuteChanged {
+            channel: channel.to_string(),
+            slider,
+            muted: current.muted,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_event_stream() {
+        let (sender, receiver) = mpsc::channel(EVENT_BUFFER);
+        let task = tokio::spawn(async move {
+            sender
+                .send(SonarEvent::ChatMixChanged { balance: 0.5 })
+                .await
+                .unwrap();
+        });
+        let mut stream = SonarWatcher { receiver, task }.into_stream();
+
+        let next = std::future::poll_fn(|cx| Pin::new(&mut stream).poll_next(cx));
+        assert_eq!(next.await, Some(SonarEvent::ChatMixChanged { balance: 0.5 }));
+
+        let next = std::future::poll_fn(|cx| Pin::new(&mut stream).poll_next(cx));
+        assert_eq!(next.await, None);
+    }
+
+    #[test]
+    fn test_event_display() {
+        let event = SonarEvent::VolumeChanged {
+            channel: "game".to_string(),
+            slider: Some(StreamerSlider::Monitoring),
+            volume: 0.5,
+        };
+        assert_eq!(event.to_string(), "monitoring volume of game: 0.50");
+        let event = SonarEvent::MuteChanged {
+            channel: "media".to_string(),
+            slider: None,
+            muted: true,
+        };
+        assert_eq!(event.to_string(), "mute of media: muted");
+        assert_eq!(SonarEvent::ModeChanged { streamer_mode: false }.to_string(), "mode: classic");
+    }
+
+    #[test]
+    fn test_diff_volumes() {
+        let previous = VolumeSettings::from_value(json!({
+            "masters": { "classic": { "volume": 1.0, "muted": false } },
+            "devices": {
+                "game": {
+                    "classic": { "volume": 0.5, "muted": false },
+                    "stream": { "monitoring": { "volume": 0.2, "muted": false } }
+                }
+            }
+        }))
+        .unwrap();
+        let current = VolumeSettings::from_value(json!({
+            "masters": { "classic": { "volume": 1.0, "muted": false } },
+            "devices": {
+                "game": {
+                    "classic": { "volume": 0.75, "muted": false },
+                    "stream": { "monitoring": { "volume": 0.2, "muted": true } }
+                }
+            }
+        }))
+        .unwrap();
+
+        let mut events = Vec::new();
+        diff_volumes(&previous, &current, &mut events);
+
+        assert_eq!(
+            events,
+            vec![
+                SonarEvent::VolumeChanged {
+                    channel: "game".to_string(),
+                    slider: None,
+                    volume: 0.75,
+                },
+                SonarEvent::MuteChanged {
+                    channel: "game".to_string(),
+                    slider: Some(StreamerSlider::Monitoring),
+                    muted: true,
+                },
+            ]
+        );
+    }
+}
@@ -1,20 +1,34 @@
 //! Integration tests for the SteelSeries Sonar API.
 //!
-//! These tests require a running SteelSeries Engine with Sonar enabled.
-//! They will be skipped if the engine is not available.
+//! These tests run against a running SteelSeries Engine with Sonar enabled.
+//! If the engine is not available, they fall back to a `TestSonarServer`.
 
-use steelseries_sonar::{Sonar, SonarError, CHANNEL_NAMES, STREAMER_SLIDER_NAMES};
+use std::sync::OnceLock;
+use std::time::Duration;
+use steelseries_sonar::test_util::TestSonarServer;
+use steelseries_sonar::{
+    ChangeOutcome, Mode, ProfileStore, Scene, SceneChange, Sonar, SonarError, SonarEvent, SonarSnapshot, StreamerSlider, CHANNEL_NAMES, STREAMER_SLIDER_NAMES,
+};
 
 async fn create_test_client() -> Result<Sonar, SonarError> {
     match Sonar::new().await {
         Ok(sonar) => Ok(sonar),
         Err(e) => {
-            println!("Skipping integration tests - SteelSeries Engine not available: {}", e);
-            Err(e)
+            println!("SteelSeries Engine not available ({}), using the test server", e);
+            match test_server() {
+                Some(server) => Sonar::connect_to(server.web_server_address()).await,
+                None => Err(e),
+            }
         }
     }
 }
 
+/// A server shared by all tests, started on first use.
+fn test_server() -> Option<&'static TestSonarServer> {
+    static SERVER: OnceLock<Option<TestSonarServer>> = OnceLock::new();
+    SERVER.get_or_init(|| TestSonarServer::start().ok()).as_ref()
+}
+
 #[tokio::test]
 async fn test_connection() {
     if let Ok(_sonar) = create_test_client().await {
@@ -32,12 +46,32 @@ async fn test_volume_data() {
     }
 }
 
+#[tokio::test]
+async fn test_typed_volumes() {
+    if let Ok(sonar) = create_test_client().await {
+        let volumes = sonar.get_volumes().await;
+        assert!(volumes.is_ok(), "Should be able to parse volume settings");
+        assert!(volumes.unwrap().channel("master").is_some());
+    }
+}
+
+#[tokio::test]
+async fn test_channel_state() {
+    if let Ok(sonar) = create_test_client().await {
+        let state = sonar.get_channel_state("master", None).await;
+        assert!(state.is_ok(), "Should be able to read the master channel state");
+
+        let result = sonar.get_channel_state("invalid_channel", None).await;
+        assert!(matches!(result, Err(SonarError::ChannelNotFound(_))));
+    }
+}
+
 #[tokio::test]
 async fn test_streamer_mode_detection() {
     if let Ok(sonar) = create_test_client().await {
-        let is_streamer_mode = sonar.is_streamer_mode().await;
-        assert!(is_streamer_mode.is_ok(), "Should be able to detect streamer mode");
-        println!("Streamer mode: {}", is_streamer_mode.unwrap());
+        let mode = sonar.get_mode().await;
+        assert!(mode.is_ok(), "Should be able to detect the mode");
+        println!("Mode: {}", mode.unwrap());
     }
 }
 
@@ -54,11 +88,9 @@ async fn test_chat_mix_data() {
 async fn test_volume_control() {
     if let Ok(sonar) = create_test_client().await {
         // Test setting volume for master channel
-        let result = sonar.set_volume("master", 0.5, None).await;
-        if result.is_ok() {
-            println!("✅ Successfully set master volume to 50%");
-        } else {
-            println!("❌ Failed to set volume: {}", result.unwrap_err());
+        match sonar.set_volume("master", 0.5, None).await {
+            Ok(_) => println!("✅ Successfully set master volume to 50%"),
+            Err(e) => println!("❌ Failed to set volume: {}", e),
         }
     }
 }
@@ -73,6 +105,9 @@ async fn test_invalid_volume() {
         // Test invalid volume (too low)
         let result = sonar.set_volume("master", -1.0, None).await;
         assert!(matches!(result, Err(SonarError::InvalidVolume(_))));
+
+        let result = sonar.set_volume_percent("master", 101, None).await;
+        assert!(matches!(result, Err(SonarError::InvalidVolumePercent(101))));
     }
 }
 
@@ -114,6 +149,902 @@ async fn test_mute_control() {
     }
 }
 
+#[tokio::test]
+async fn test_mute_guard() {
+    if let Ok(sonar) = create_test_client().await {
+        let guard = sonar.mute_guard("media", None).await;
+        if let Ok(guard) = guard {
+            println!("✅ Media channel muted by guard");
+
+            let result = guard.release().await;
+            assert!(result.is_ok(), "Should be able to release the mute guard");
+            println!("✅ Media channel unmuted on release");
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_deadline() {
+    if let Ok(sonar) = create_test_client().await {
+        let result = sonar
+            .with_deadline(Duration::from_secs(5), sonar.get_volume_data())
+            .await;
+        assert!(result.is_ok(), "Should complete within the deadline");
+
+        let result = sonar
+            .with_deadline(Duration::ZERO, std::future::pending::<steelseries_sonar::Result<()>>())
+            .await;
+        assert!(matches!(result, Err(SonarError::Timeout)));
+    }
+}
+
+#[tokio::test]
+async fn test_connect_to() {
+    if let Ok(sonar) = create_test_client().await {
+        let direct = Sonar::connect_to(sonar.web_server_address()).await;
+        assert!(direct.is_ok(), "Should connect to a known address");
+        if let Ok(direct) = direct {
+            assert_eq!(direct.web_server_address(), sonar.web_server_address());
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_lazy() {
+    let server = TestSonarServer::start().unwrap();
+    let sonar = Sonar::builder().web_server_address(server.web_server_address()).build_lazy().unwrap();
+
+    // The mode is detected on the first request, not when building
+    server.update(|state| state.streamer_mode = true);
+    sonar.set_volume("game", 0.4, Some(StreamerSlider::Monitoring)).await.unwrap();
+    assert_eq!(server.state().volumes["game"].stream.monitoring.volume, 0.4);
+}
+
+#[tokio::test]
+async fn test_lazy_ping() {
+    let server = TestSonarServer::start().unwrap();
+    let path = std::env::temp_dir().join(format!("sonar-core-props-{}.json", std::process::id()));
+    server.write_core_props(&path).unwrap();
+
+    // Nothing is resolved until the first request
+    let sonar = Sonar::builder().core_props_path(&path).build_lazy().unwrap();
+    assert!(sonar.ping().await.is_ready());
+    assert!(sonar.wait_until_ready(Duration::from_secs(5)).await.unwrap().is_ready());
+    assert_eq!(sonar.web_server_address(), server.web_server_address());
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_lazy_ping_blocking() {
+    let server = TestSonarServer::start().unwrap();
+    let path = std::env::temp_dir().join(format!("sonar-core-props-blocking-{}.json", std::process::id()));
+    server.write_core_props(&path).unwrap();
+
+    let sonar = steelseries_sonar::BlockingSonar::builder().core_props_path(&path).build_lazy().unwrap();
+    assert!(sonar.ping().is_ready());
+    assert!(sonar.wait_until_ready(Duration::from_secs(5)).unwrap().is_ready());
+    assert_eq!(sonar.web_server_address(), server.web_server_address());
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[tokio::test]
+async fn test_refresh() {
+    let server = TestSonarServer::start().unwrap();
+    let sonar = Sonar::builder().web_server_address(server.web_server_address()).build_lazy().unwrap();
+    sonar.connect().await.unwrap();
+
+    // Switched outside of the client, e.g. in the GG app
+    server.update(|state| state.streamer_mode = true);
+    sonar.refresh().await.unwrap();
+    sonar.set_volume("game", 0.4, Some(StreamerSlider::Monitoring)).await.unwrap();
+    assert_eq!(server.state().volumes["game"].stream.monitoring.volume, 0.4);
+
+    drop(server);
+    assert!(sonar.refresh().await.is_err());
+}
+
+#[tokio::test]
+async fn test_warm_up() {
+    let server = TestSonarServer::start().unwrap();
+    let sonar = Sonar::builder()
+        .web_server_address(server.web_server_address())
+        .pool_idle_timeout(Duration::from_secs(600))
+        .pool_max_idle(1)
+        .build_lazy()
+        .unwrap();
+    sonar.warm_up().await.unwrap();
+
+    drop(server);
+    assert!(sonar.warm_up().await.is_err());
+}
+
+#[cfg(feature = "ffi")]
+#[test]
+fn test_ffi() {
+    use std::ffi::CString;
+    use steelseries_sonar::ffi::*;
+
+    let server = TestSonarServer::start().unwrap();
+    let address = CString::new(server.web_server_address()).unwrap();
+    let game = c"game";
+    unsafe {
+        let mut sonar = std::ptr::null_mut();
+        assert_eq!(sonar_connect_to(address.as_ptr(), &mut sonar), SONAR_OK);
+
+        assert_eq!(sonar_set_volume(sonar, game.as_ptr(), 0.3, SONAR_SLIDER_DEFAULT), SONAR_OK);
+        let mut volume = 0.0;
+        assert_eq!(sonar_get_volume(sonar, game.as_ptr(), SONAR_SLIDER_DEFAULT, &mut volume), SONAR_OK);
+        assert_eq!(volume, 0.3);
+
+        assert_eq!(sonar_mute(sonar, game.as_ptr(), true, SONAR_SLIDER_DEFAULT), SONAR_OK);
+        let mut muted = false;
+        assert_eq!(sonar_is_muted(sonar, game.as_ptr(), SONAR_SLIDER_DEFAULT, &mut muted), SONAR_OK);
+        assert!(muted);
+
+        assert_eq!(sonar_set_volume(sonar, c"bogus".as_ptr(), 0.3, SONAR_SLIDER_DEFAULT), SONAR_ERROR_CHANNEL_NOT_FOUND);
+        assert_eq!(sonar_set_volume(sonar, game.as_ptr(), 1.5, SONAR_SLIDER_DEFAULT), SONAR_ERROR_INVALID_VOLUME);
+        assert!(!sonar_last_error().is_null());
+        sonar_free(sonar);
+    }
+}
+
+#[tokio::test]
+async fn test_stream_deck() {
+    use steelseries_sonar::{KeyAction, StreamDeck};
+
+    let server = TestSonarServer::start().unwrap();
+    let sonar = Sonar::connect_to(server.web_server_address()).await.unwrap();
+    let mut deck = StreamDeck::new(sonar);
+    let mute = KeyAction::ToggleMute { channel: "game".to_string(), streamer_slider: None };
+    deck.add_key("mute-game", mute);
+
+    // New keys are reported once
+    assert_eq!(deck.poll().await.unwrap().len(), 1);
+    assert!(deck.poll().await.unwrap().is_empty());
+
+    let feedback = deck.key_down("mute-game").await.unwrap().unwrap();
+    assert_eq!(feedback.state, 1);
+    assert!(server.state().volumes["game"].classic.muted);
+    assert!(deck.poll().await.unwrap().is_empty());
+
+    // Unmuted outside the plugin
+    server.update(|state| state.volumes.get_mut("game").unwrap().classic.muted = false);
+    let changed = deck.poll().await.unwrap();
+    assert_eq!(changed[0].0, "mute-game");
+    assert_eq!(changed[0].1.state, 0);
+    assert!(deck.key_down("unknown").await.unwrap().is_none());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_ipc() {
+    use steelseries_sonar::{IpcClient, IpcServer};
+
+    let server = TestSonarServer::start().unwrap();
+    let sonar = Sonar::connect_to(server.web_server_address()).await.unwrap();
+    let endpoint = std::env::temp_dir().join(format!("steelseries-sonar-test-{}.sock", std::process::id()));
+    let endpoint = endpoint.to_str().unwrap();
+    tokio::spawn(IpcServer::bind(sonar, endpoint).unwrap().run());
+
+    let mut client = IpcClient::connect(endpoint).await.unwrap();
+    client.apply(&Scene::new().volume("game", 0.35).mute("media", true)).await.unwrap();
+    assert_eq!(server.state().volumes["game"].classic.volume, 0.35);
+    assert!(client.snapshot().await.unwrap().volumes["media"].classic.muted);
+
+    // Other processes share the same connection to Sonar.
+    let mut other = IpcClient::connect(endpoint).await.unwrap();
+    assert_eq!(other.snapshot().await.unwrap().volumes["game"].classic.volume, 0.35);
+
+    let error = client.apply(&Scene::new().volume("game", 0.2).chat_mix(5.0)).await.unwrap_err();
+    assert!(matches!(error, SonarError::Daemon(_)));
+}
+
+#[tokio::test]
+async fn test_channel_device() {
+    use steelseries_sonar::{AudioDevice, DataFlow};
+
+    let server = TestSonarServer::start().unwrap();
+    server.update(|state| {
+        state.devices.push(AudioDevice {
+            id: "{0.0.1.00000000}.{desk mic}".to_string(),
+            friendly_name: "Desk Mic".to_string(),
+            data_flow: DataFlow::Capture,
+            is_default: false,
+            is_default_communication: false,
+        })
+    });
+    let sonar = Sonar::connect_to(server.web_server_address()).await.unwrap();
+
+    assert!(sonar.get_channel_device("chatCapture").await.unwrap().is_none());
+    assert!(matches!(
+        sonar.set_capture_device("headset-mic").await,
+        Err(SonarError::CaptureDeviceNotFound(_))
+    ));
+    sonar.set_capture_device("{0.0.1.00000000}.{desk mic}").await.unwrap();
+    let device = sonar.get_channel_device("chatCapture").await.unwrap().unwrap();
+    assert_eq!(device.friendly_name, "Desk Mic");
+    assert!(matches!(sonar.get_channel_device("bogus").await, Err(SonarError::ChannelNotFound(_))));
+}
+
+#[tokio::test]
+async fn test_state_cache() {
+    let server = TestSonarServer::start().unwrap();
+    let sonar = Sonar::builder()
+        .web_server_address(server.web_server_address())
+        .state_cache(Duration::from_secs(60))
+        .build()
+        .await
+        .unwrap();
+
+    sonar.set_volume("game", 0.5, None).await.unwrap();
+    sonar.mute_channel("game", true, None).await.unwrap();
+    server.update(|state| {
+        let game = &mut state.volumes.get_mut("game").unwrap().classic;
+        game.volume = 0.2;
+        game.muted = false;
+    });
+
+    // Both writes are skipped, the external change goes unnoticed.
+    assert_eq!(sonar.set_volume("game", 0.5, None).await.unwrap(), serde_json::Value::Null);
+    sonar.mute_channel("game", true, None).await.unwrap();
+    assert_eq!(server.state().volumes["game"].classic.volume, 0.2);
+    assert!(!server.state().volumes["game"].classic.muted);
+
+    // Reading the volumes updates the cache.
+    sonar.get_volumes().await.unwrap();
+    sonar.set_volume("game", 0.5, None).await.unwrap();
+    sonar.mute_channel("game", true, None).await.unwrap();
+    assert_eq!(server.state().volumes["game"].classic.volume, 0.5);
+    assert!(server.state().volumes["game"].classic.muted);
+}
+
+#[tokio::test]
+async fn test_api_error() {
+    let server = TestSonarServer::start().unwrap();
+    let sonar = Sonar::connect_to(server.web_server_address()).await.unwrap();
+
+    let error = sonar.select_config("missing").await.unwrap_err();
+    assert_eq!(error.status(), Some(404));
+    match error {
+        SonarError::Api { endpoint, body, .. } => {
+            assert_eq!(endpoint, "/configs/missing/select");
+            assert!(body.contains("Not Found"));
+        }
+        error => panic!("unexpected error {:?}", error),
+    }
+}
+
+#[tokio::test]
+async fn test_ping() {
+    let server = TestSonarServer::start().unwrap();
+    let sonar = Sonar::connect_to(server.web_server_address()).await.unwrap();
+
+    let status = sonar.ping().await;
+    assert!(status.is_ready());
+    assert!(status.round_trip.is_some());
+    assert!(sonar.wait_until_ready(Duration::from_secs(1)).await.unwrap().is_ready());
+
+    drop(server);
+    assert!(!sonar.ping().await.reachable);
+    assert!(matches!(
+        sonar.wait_until_ready(Duration::from_millis(300)).await,
+        Err(SonarError::Timeout)
+    ));
+}
+
+#[tokio::test]
+async fn test_export_state() {
+    if let Ok(sonar) = create_test_client().await {
+        let snapshot = sonar.export_state().await;
+        assert!(snapshot.is_ok(), "Should be able to export the mixer state");
+        let snapshot = snapshot.unwrap();
+        assert!(snapshot.volumes.contains_key("master"));
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert_eq!(serde_json::from_str::<SonarSnapshot>(&json).unwrap(), snapshot);
+    }
+}
+
+#[tokio::test]
+async fn test_full_state() {
+    let server = TestSonarServer::start().unwrap();
+    let sonar = Sonar::connect_to(server.web_server_address()).await.unwrap();
+    sonar.set_volume("game", 0.3, None).await.unwrap();
+
+    let state = sonar.get_full_state().await.unwrap();
+    assert_eq!(state.mode, Mode::Classic);
+    assert!(state.chat_mix_available);
+    assert_eq!(state.snapshot(), sonar.export_state().await.unwrap());
+
+    // Switched in the GG app, behind the client's back
+    server.update(|state| state.streamer_mode = true);
+    let state = sonar.get_full_state().await.unwrap();
+    assert_eq!(state.mode, Mode::Streamer);
+    assert_eq!(state.snapshot(), sonar.export_state().await.unwrap());
+}
+
+#[tokio::test]
+async fn test_apply_state() {
+    let server = TestSonarServer::start().unwrap();
+    let sonar = Sonar::connect_to(server.web_server_address()).await.unwrap();
+    sonar.set_volume("game", 0.3, None).await.unwrap();
+    sonar.set_chat_mix(0.4).await.unwrap();
+    let snapshot = sonar.export_state().await.unwrap();
+
+    sonar.set_volume("game", 1.0, None).await.unwrap();
+    sonar.mute_channel("media", true, None).await.unwrap();
+    sonar.set_chat_mix(0.0).await.unwrap();
+
+    let report = sonar.apply_state(&snapshot).await;
+    assert!(report.is_complete(), "Failed items: {:?}", report.failed);
+    assert_eq!(sonar.export_state().await.unwrap(), snapshot);
+}
+
+#[tokio::test]
+async fn test_apply_scene() {
+    let server = TestSonarServer::start().unwrap();
+    let sonar = Sonar::connect_to(server.web_server_address()).await.unwrap();
+
+    let report = sonar
+        .apply_scene(&Scene::new().volume("game", 0.2).mute("media", true).chat_mix(0.5))
+        .await;
+    assert!(report.is_success());
+    assert_eq!(server.state().chat_mix, 0.5);
+
+    let before = server.state();
+    let report = sonar
+        .apply_scene(&Scene::new().volume("game", 0.8).mute("media", false).volume("game", 2.0).chat_mix(0.0))
+        .await;
+    assert!(!report.is_success());
+    assert!(matches!(report.outcomes[0].1, ChangeOutcome::RolledBack));
+    assert!(matches!(report.outcomes[1].1, ChangeOutcome::RolledBack));
+    assert!(matches!(report.outcomes[2].1, ChangeOutcome::Failed(SonarError::InvalidVolume(_))));
+    assert!(matches!(report.outcomes[3].1, ChangeOutcome::Skipped));
+    assert_eq!(server.state(), before);
+}
+
+#[tokio::test]
+async fn test_batch() {
+    if let Ok(sonar) = create_test_client().await {
+        let results = sonar
+            .batch()
+            .set_volume("aux", 0.4, None)
+            .set_volume("invalid_channel", 0.5, None)
+            .mute_channel("aux", false, None)
+            .execute()
+            .await;
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok(), "Should be able to set the volume");
+        assert!(matches!(results[1], Err(SonarError::ChannelNotFound(_))));
+        assert!(results[2].is_ok(), "Should be able to unmute");
+    }
+}
+
+#[tokio::test]
+async fn test_channel_ops() {
+    use steelseries_sonar::{Channel, StreamerSlider};
+
+    let server = TestSonarServer::start().unwrap();
+    server.update(|state| state.streamer_mode = true);
+    let sonar = Sonar::connect_to(server.web_server_address()).await.unwrap();
+
+    sonar
+        .channel(Channel::Game)
+        .slider(StreamerSlider::Monitoring)
+        .volume(0.2)
+        .volume(0.8)
+        .mute(true)
+        .apply()
+        .await
+        .unwrap();
+    let game = &server.state().volumes["game"];
+    assert_eq!(game.stream.monitoring.volume, 0.8);
+    assert!(game.stream.monitoring.muted);
+    assert!(!game.stream.streaming.muted);
+
+    assert!(sonar.channel(Channel::Media).is_empty());
+    let result = sonar.channel(Channel::Media).volume(1.5).mute(true).apply().await;
+    assert!(matches!(result, Err(SonarError::InvalidVolume(_))));
+    assert!(!server.state().volumes["media"].stream.streaming.muted);
+}
+
+#[tokio::test]
+async fn test_volume_both() {
+    let server = TestSonarServer::start().unwrap();
+    let sonar = Sonar::connect_to(server.web_server_address()).await.unwrap();
+
+    sonar.set_volume_both("game", 0.3, 0.7).await.unwrap();
+    sonar.mute_both("game", true).await.unwrap();
+    let game = &server.state().volumes["game"];
+    assert_eq!(game.stream.streaming.volume, 0.3);
+    assert_eq!(game.stream.monitoring.volume, 0.7);
+    assert!(game.stream.streaming.muted && game.stream.monitoring.muted);
+    assert!(!game.classic.muted);
+
+    assert!(matches!(sonar.set_volume_both("game", 0.3, 1.5).await, Err(SonarError::InvalidVolume(_))));
+    assert!(matches!(sonar.mute_both("chat", true).await, Err(SonarError::ChannelNotFound(_))));
+}
+
+#[tokio::test]
+async fn test_channel_group() {
+    use steelseries_sonar::{Channel, ChannelGroup};
+
+    let server = TestSonarServer::start().unwrap();
+    let sonar = Sonar::connect_to(server.web_server_address()).await.unwrap();
+    let group = ChannelGroup::new([Channel::Game, Channel::Media]);
+
+    sonar.set_volume("game", 0.8, None).await.unwrap();
+    sonar.set_volume("media", 0.4, None).await.unwrap();
+    assert_eq!(sonar.get_group_volume(&group).await.unwrap(), 0.8);
+
+    sonar.set_group_volume(&group, 0.4).await.unwrap();
+    let state = server.state();
+    assert_eq!(state.volumes["game"].classic.volume, 0.4);
+    assert_eq!(state.volumes["media"].classic.volume, 0.2);
+
+    sonar.mute_group(&group, true).await.unwrap();
+    let state = server.state();
+    assert!(state.volumes["game"].classic.muted && state.volumes["media"].classic.muted);
+    assert!(!state.volumes["aux"].classic.muted);
+}
+
+#[tokio::test]
+async fn test_route_app_by_name() {
+    use steelseries_sonar::{Channel, ChannelApps, RoutedApp};
+
+    let server = TestSonarServer::start().unwrap();
+    server.update(|state| {
+        state.routed_apps = vec![ChannelApps {
+            channel: "game".to_string(),
+            device_id: String::new(),
+            apps: vec![RoutedApp {
+                id: "spotify".to_string(),
+                process_id: 4242,
+                process_name: "Spotify.exe".to_string(),
+                display_name: "Spotify".to_string(),
+            }],
+        }];
+    });
+    let sonar = Sonar::connect_to(server.web_server_address()).await.unwrap();
+
+    assert_eq!(sonar.route_app_by_name("spotify.exe", Channel::Media).await.unwrap(), [4242]);
+    let routed = sonar.get_routed_apps().await.unwrap();
+    let media = routed.iter().find(|channel_apps| channel_apps.channel == "media").unwrap();
+    assert_eq!(media.apps[0].process_id, 4242);
+
+    assert!(matches!(
+        sonar.route_app_by_name("no-such-app.exe", Channel::Media).await,
+        Err(SonarError::AppNotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_crossfade() {
+    let server = TestSonarServer::start().unwrap();
+    let sonar = Sonar::connect_to(server.web_server_address()).await.unwrap();
+
+    sonar.set_volume("game", 0.8, None).await.unwrap();
+    sonar.set_volume("media", 0.2, None).await.unwrap();
+    let gaming = sonar.export_state().await.unwrap();
+    sonar.set_volume("game", 0.3, None).await.unwrap();
+    sonar.set_volume("media", 0.9, None).await.unwrap();
+    let podcast = sonar.export_state().await.unwrap();
+
+    sonar.crossfade(&podcast, &gaming, Duration::from_millis(20)).await.unwrap();
+    let state = server.state();
+    assert_eq!(state.volumes["game"].classic.volume, 0.8);
+    assert_eq!(state.volumes["media"].classic.volume, 0.2);
+}
+
+#[tokio::test]
+async fn test_record_and_replay() {
+    let server = TestSonarServer::start().unwrap();
+    let path = std::env::temp_dir().join(format!("sonar-replay-{}.json", std::process::id()));
+
+    let sonar = Sonar::builder()
+        .web_server_address(server.web_server_address())
+        .record(&path)
+        .build()
+        .await
+        .unwrap();
+    sonar.set_volume("game", 0.25, None).await.unwrap();
+    let recorded = sonar.get_volumes().await.unwrap();
+    drop(server);
+
+    let replay = Sonar::builder().replay(&path).build().await.unwrap();
+    replay.set_volume("game", 0.25, None).await.unwrap();
+    assert_eq!(replay.get_volumes().await.unwrap(), recorded);
+    assert!(matches!(
+        replay.get_chat_mix_data().await,
+        Err(SonarError::CassetteMiss(_))
+    ));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[tokio::test]
+async fn test_config_file() {
+    use steelseries_sonar::Channel;
+
+    let server = TestSonarServer::start().unwrap();
+    let path = std::env::temp_dir().join(format!("sonar-config-{}.toml", std::process::id()));
+    std::fs::write(
+        &path,
+        format!(
+            "address = \"{}/\"\n\n[volume_caps]\nmaster = 0.5\n\n[aliases]\nvoice = \"chatRender\"\n",
+            server.web_server_address()
+        ),
+    )
+    .unwrap();
+
+    let sonar = Sonar::builder()
+        .config_file(&path)
+        .channel_alias("mic", Channel::ChatCapture)
+        .build()
+        .await
+        .unwrap();
+    sonar.set_volume("master", 0.9, None).await.unwrap();
+    sonar.set_volume("voice", 0.3, None).await.unwrap();
+    sonar.mute_channel("mic", true, None).await.unwrap();
+
+    // Aliases work in the wrappers and guards too
+    let guard = sonar.mute_guard("voice", None).await.unwrap();
+    assert!(server.state().volumes["chatRender"].classic.muted);
+    guard.release().await.unwrap();
+    assert!(!server.state().volumes["chatRender"].classic.muted);
+    let debounced = sonar.debounced(Duration::from_millis(10));
+    debounced.set_volume("voice", 0.3, None).unwrap();
+    debounced.flush().await.unwrap();
+
+    let state = server.state();
+    assert_eq!(state.volumes["master"].classic.volume, 0.5);
+    assert_eq!(state.volumes["chatRender"].classic.volume, 0.3);
+    assert!(state.volumes["chatCapture"].classic.muted);
+    assert!(matches!(
+        sonar.set_volume("nope", 0.3, None).await,
+        Err(SonarError::ChannelNotFound(_))
+    ));
+
+    std::fs::write(&path, "[aliases]\nvoice = \"chat\"\n").unwrap();
+    assert!(matches!(
+        Sonar::builder().config_file(&path).build_lazy(),
+        Err(SonarError::InvalidConfig(_))
+    ));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[tokio::test]
+async fn test_capture_requests() {
+    let server = TestSonarServer::start().unwrap();
+    let sonar = Sonar::builder()
+        .web_server_address(server.web_server_address())
+        .capture_requests(2)
+        .auto_reconnect(false)
+        .build()
+        .await
+        .unwrap();
+
+    sonar.set_volume("game", 0.25, None).await.unwrap();
+    let requests = sonar.last_requests();
+    let request = requests.last().unwrap();
+    assert_eq!(request.method, "PUT");
+    assert!(request.url.starts_with(server.web_server_address()));
+    assert!(request.url.contains("/game/Volume/0.25"));
+    assert_eq!(request.status, Some(200));
+
+    drop(server);
+    assert!(sonar.get_volumes().await.is_err());
+    let requests = sonar.last_requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[1].status, None);
+    assert!(requests[1].error.is_some());
+}
+
+#[tokio::test]
+async fn test_validation() {
+    use steelseries_sonar::{StreamerSlider, Validation};
+
+    let server = TestSonarServer::start().unwrap();
+    let lenient = Sonar::connect_to(server.web_server_address()).await.unwrap();
+    lenient.set_volume("game", 0.5, Some(StreamerSlider::Monitoring)).await.unwrap();
+    assert_eq!(server.state().volumes["game"].classic.volume, 0.5);
+
+    let strict = Sonar::builder()
+        .web_server_address(server.web_server_address())
+        .validation(Validation::Strict)
+        .build()
+        .await
+        .unwrap();
+    assert!(matches!(
+        strict.set_volume("game", 0.2, Some(StreamerSlider::Monitoring)).await,
+        Err(SonarError::IgnoredInput(_))
+    ));
+    assert!(matches!(
+        strict.get_channel_state("game", Some(StreamerSlider::Streaming)).await,
+        Err(SonarError::IgnoredInput(_))
+    ));
+    strict.set_volume("game", 0.2, None).await.unwrap();
+    assert_eq!(server.state().volumes["game"].classic.volume, 0.2);
+}
+
+#[tokio::test]
+async fn test_raw_requests() {
+    let server = TestSonarServer::start().unwrap();
+    let sonar = Sonar::connect_to(server.web_server_address()).await.unwrap();
+    assert_eq!(sonar.raw_get("/mode/").await.unwrap(), "classic");
+    assert_eq!(sonar.raw_put("mode/stream", None).await.unwrap(), "stream");
+    assert!(server.state().streamer_mode);
+
+    let error = sonar.raw_get("/unknown").await.unwrap_err();
+    assert_eq!(error.status(), Some(404));
+}
+
+#[tokio::test]
+async fn test_capabilities() {
+    let server = TestSonarServer::start().unwrap();
+    let sonar = Sonar::connect_to(server.web_server_address()).await.unwrap();
+    let capabilities = sonar.capabilities().await.unwrap();
+    assert!(capabilities.streamer_mode && capabilities.chat_mix && capabilities.app_routing && capabilities.configs);
+}
+
+#[tokio::test]
+async fn test_restore_guard() {
+    let server = TestSonarServer::start().unwrap();
+    let sonar = Sonar::connect_to(server.web_server_address()).await.unwrap();
+    sonar.set_volume("media", 0.6, None).await.unwrap();
+
+    let guard = sonar.scoped(&["media"]).await.unwrap();
+    assert_eq!(guard.saved("media", None).map(|state| state.volume), Some(0.6));
+    sonar.set_volume("media", 0.1, None).await.unwrap();
+    sonar.mute_channel("media", true, None).await.unwrap();
+    guard.restore().await.unwrap();
+    assert_eq!(server.state().volumes["media"].classic.volume, 0.6);
+    assert!(!server.state().volumes["media"].classic.muted);
+
+    sonar.set_mode(Mode::Streamer).await.unwrap();
+    let guard = sonar.scoped(&["media"]).await.unwrap();
+    sonar.mute_channel("media", true, Some(StreamerSlider::Monitoring)).await.unwrap();
+    drop(guard);
+    for _ in 0..50 {
+        if !server.state().volumes["media"].stream.monitoring.muted {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(!server.state().volumes["media"].stream.monitoring.muted);
+}
+
+#[tokio::test]
+async fn test_mute_for() {
+    let server = TestSonarServer::start().unwrap();
+    let sonar = Sonar::connect_to(server.web_server_address()).await.unwrap();
+
+    let mute = sonar.mute_for("media", Duration::from_millis(50), None).await.unwrap();
+    assert!(server.state().volumes["media"].classic.muted);
+    mute.extend(Duration::from_secs(60));
+    assert!(mute.remaining().unwrap() > Duration::from_secs(1));
+    assert!(mute.unmute_now().await.unwrap());
+    assert!(!server.state().volumes["media"].classic.muted);
+
+    // Unmuted by hand in the meantime
+    let mute = sonar.mute_for("media", Duration::from_millis(50), None).await.unwrap();
+    sonar.mute_channel("media", false, None).await.unwrap();
+    assert!(!mute.wait().await.unwrap());
+
+    let mute = sonar.mute_for("media", Duration::from_millis(10), None).await.unwrap();
+    mute.cancel();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(server.state().volumes["media"].classic.muted);
+
+    // Muted before, so it stays muted
+    let mute = sonar.mute_for("media", Duration::from_millis(10), None).await.unwrap();
+    assert!(!mute.wait().await.unwrap());
+    assert!(server.state().volumes["media"].classic.muted);
+}
+
+#[tokio::test]
+async fn test_solo() {
+    let server = TestSonarServer::start().unwrap();
+    let sonar = Sonar::connect_to(server.web_server_address()).await.unwrap();
+    sonar.mute_channel("media", true, None).await.unwrap();
+    let muted = |state: &steelseries_sonar::MockState| {
+        ["game", "chatRender", "media", "aux"].map(|channel| state.volumes[channel].classic.muted)
+    };
+
+    sonar.solo("media", None).await.unwrap();
+    assert_eq!(muted(&server.state()), [true, true, false, true]);
+    sonar.solo("game", None).await.unwrap();
+    assert_eq!(muted(&server.state()), [false, true, true, true]);
+    assert!(!server.state().volumes["master"].classic.muted);
+
+    assert!(sonar.unsolo().await.unwrap());
+    assert_eq!(muted(&server.state()), [true, true, false, true]);
+    assert!(sonar.unsolo().await.unwrap());
+    assert_eq!(muted(&server.state()), [false, false, true, false]);
+    assert!(!sonar.unsolo().await.unwrap());
+
+    assert!(matches!(sonar.solo("master", None).await, Err(SonarError::NotSoloable(_))));
+}
+
+#[tokio::test]
+async fn test_session() {
+    let server = TestSonarServer::start().unwrap();
+    let sonar = Sonar::connect_to(server.web_server_address()).await.unwrap();
+    sonar.set_volume("game", 0.5, None).await.unwrap();
+
+    let mut session = sonar.session();
+    session.set_volume("game", 0.2, None).await.unwrap();
+    session.mute_channel("media", true, None).await.unwrap();
+    session.set_chat_mix(-0.5).await.unwrap();
+    assert_eq!(session.undo_stack().len(), 3);
+
+    assert_eq!(session.undo().await.unwrap(), Some(SceneChange::ChatMix(-0.5)));
+    session.undo().await.unwrap();
+    assert!(!server.state().volumes["media"].classic.muted);
+    assert_eq!(server.state().chat_mix, 0.0);
+    assert!(session.can_redo());
+
+    session.redo().await.unwrap();
+    assert!(server.state().volumes["media"].classic.muted);
+
+    // A new change clears the redo stack
+    session.set_mode(Mode::Streamer).await.unwrap();
+    assert!(!session.can_redo());
+    assert!(session.redo().await.unwrap().is_none());
+
+    while session.undo().await.unwrap().is_some() {}
+    let state = server.state();
+    assert!(!state.streamer_mode);
+    assert!(!state.volumes["media"].classic.muted);
+    assert_eq!(state.volumes["game"].classic.volume, 0.5);
+    assert_eq!(session.redo_stack().len(), 3);
+}
+
+#[tokio::test]
+async fn test_watch() {
+    let server = TestSonarServer::start().unwrap();
+    let sonar = Sonar::builder()
+        .web_server_address(server.web_server_address())
+        .capture_requests(64)
+        .build()
+        .await
+        .unwrap();
+    let mut watcher = sonar.watch(Duration::from_millis(20));
+
+    // The polls go through the client, so they show up in its request log;
+    // once the chat mix was read, the first poll is complete.
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while !sonar.last_requests().iter().any(|request| request.url.ends_with("/chatMix")) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+
+    server.update(|state| {
+        state.volumes.get_mut("game").unwrap().classic.volume = 0.25;
+        state.volumes.get_mut("media").unwrap().classic.muted = true;
+        state.chat_mix = 0.5;
+    });
+    let mut events = Vec::new();
+    while events.len() < 3 {
+        let event = tokio::time::timeout(Duration::from_secs(5), watcher.recv()).await.unwrap().unwrap();
+        events.push(event);
+    }
+    // A poll running during the update may report part of it a tick early.
+    for expected in [
+        SonarEvent::VolumeChanged { channel: "game".to_string(), slider: None, volume: 0.25 },
+        SonarEvent::MuteChanged { channel: "media".to_string(), slider: None, muted: true },
+        SonarEvent::ChatMixChanged { balance: 0.5 },
+    ] {
+        assert!(events.contains(&expected), "{expected} missing from {events:?}");
+    }
+
+    server.update(|state| state.streamer_mode = true);
+    let mode_changed = tokio::time::timeout(Duration::from_secs(5), async {
+        while let Some(event) = watcher.recv().await {
+            if let SonarEvent::ModeChanged { streamer_mode } = event {
+                return streamer_mode;
+            }
+        }
+        false
+    })
+    .await
+    .unwrap();
+    assert!(mode_changed);
+}
+
+#[tokio::test]
+async fn test_load_profile_without_chat_mix() {
+    let server = TestSonarServer::start().unwrap();
+    server.update(|state| state.chat_mix_available = false);
+    let sonar = Sonar::connect_to(server.web_server_address()).await.unwrap();
+    let store = ProfileStore::new(std::env::temp_dir().join(format!("steelseries-sonar-profiles-{}", std::process::id())));
+
+    sonar.set_volume("game", 0.2, None).await.unwrap();
+    store.save("quiet", &sonar.export_state().await.unwrap()).unwrap();
+    sonar.set_volume("game", 0.8, None).await.unwrap();
+
+    sonar.load_profile_from(&store, "quiet").await.unwrap();
+    assert_eq!(server.state().volumes["game"].classic.volume, 0.2);
+    std::fs::remove_dir_all(store.dir()).unwrap();
+}
+
+#[tokio::test]
+async fn test_load_profile_rollback_failed() {
+    let server = TestSonarServer::start().unwrap();
+    let sonar = Sonar::connect_to(server.web_server_address()).await.unwrap();
+    let store = ProfileStore::new(std::env::temp_dir().join(format!("steelseries-sonar-rollback-{}", std::process::id())));
+
+    let mut profile = sonar.export_state().await.unwrap();
+    profile.chat_mix = 2.0;
+    store.save("broken", &profile).unwrap();
+    // A balance out of range cannot be written back either
+    server.update(|state| state.chat_mix = 2.0);
+
+    let error = sonar.load_profile_from(&store, "broken").await.unwrap_err();
+    assert!(
+        matches!(&error, SonarError::ProfileRollbackFailed { failed, rollback_failed, .. } if failed.len() == 1 && rollback_failed.len() == 1),
+        "{:?}",
+        error
+    );
+    std::fs::remove_dir_all(store.dir()).unwrap();
+}
+
+#[tokio::test]
+async fn test_chat_mix_unavailable() {
+    let server = TestSonarServer::start().unwrap();
+    let sonar = Sonar::connect_to(server.web_server_address()).await.unwrap();
+    assert!(sonar.chat_mix_supported().await.unwrap());
+
+    server.update(|state| state.chat_mix_available = false);
+    assert!(!sonar.chat_mix_supported().await.unwrap());
+    assert!(matches!(sonar.set_chat_mix(0.5).await, Err(SonarError::ChatMixUnavailable)));
+}
+
+#[tokio::test]
+async fn test_set_mode_shared() {
+    let server = TestSonarServer::start().unwrap();
+    let sonar = Sonar::connect_to(server.web_server_address()).await.unwrap();
+
+    let task = tokio::spawn({
+        let sonar = sonar.clone();
+        async move { sonar.set_mode(Mode::Streamer).await }
+    });
+    assert_eq!(task.await.unwrap().unwrap(), Mode::Streamer);
+    assert!(server.state().streamer_mode);
+
+    sonar.set_volume("game", 0.4, Some(StreamerSlider::Monitoring)).await.unwrap();
+    let state = sonar.get_channel_state("game", Some(StreamerSlider::Monitoring)).await.unwrap();
+    assert_eq!(state.volume, 0.4);
+}
+
+#[tokio::test]
+async fn test_debounced_volume() {
+    let server = TestSonarServer::start().unwrap();
+    let sonar = Sonar::connect_to(server.web_server_address()).await.unwrap();
+    let initial = sonar.get_channel_state("game", None).await.unwrap().volume;
+    let debounced = sonar.debounced(Duration::from_millis(50));
+
+    for step in 1..=10 {
+        debounced.set_volume("game", f64::from(step) / 20.0, None).unwrap();
+    }
+    assert!(debounced.set_volume("game", 2.0, None).is_err());
+    assert!(debounced.has_pending());
+    assert_eq!(sonar.get_channel_state("game", None).await.unwrap().volume, initial);
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(!debounced.has_pending());
+    assert_eq!(sonar.get_channel_state("game", None).await.unwrap().volume, 0.5);
+
+    debounced.set_volume("media", 0.2, None).unwrap();
+    debounced.flush().await.unwrap();
+    assert_eq!(sonar.get_channel_state("media", None).await.unwrap().volume, 0.2);
+}
+
 #[tokio::test]
 async fn test_constants() {
     // Test that constants are not empty